@@ -22,8 +22,9 @@ use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use serenity::Client;
 use serenity::all::{
-    ChannelId, Context, CreateMessage, EditMessage, EventHandler, GatewayIntents, Message,
-    MessageId, Ready,
+    ButtonStyle, ChannelId, Context, CreateActionRow, CreateButton, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateMessage, EditMessage, EventHandler, GatewayIntents,
+    Http, Interaction, Message, MessageId, Ready,
 };
 use serenity::async_trait;
 use std::collections::HashMap;
@@ -31,11 +32,14 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
 use tarpc::context;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, broadcast};
 use tracing::{debug, error, info, warn};
 
 use localgpt_bridge::connect;
-use localgpt_core::agent::{Agent, AgentConfig, StreamEvent, extract_tool_detail};
+use localgpt_core::agent::{
+    Agent, AgentConfig, ApprovalBroker, ApprovalDecision, ApprovalEvent, Cancelled, StreamEvent,
+    TurnCanceller, extract_tool_detail,
+};
 use localgpt_core::concurrency::TurnGate;
 use localgpt_core::config::Config;
 use localgpt_core::memory::MemoryManager;
@@ -101,6 +105,9 @@ struct BotState {
     turn_gate: TurnGate,
     paired_user: Mutex<Option<PairedUser>>,
     pending_pairing_code: Mutex<Option<String>>,
+    approvals: ApprovalBroker,
+    /// Turns in progress by channel, for /stop (a turn holds `sessions`)
+    running: Mutex<HashMap<u64, TurnCanceller>>,
 }
 
 // ── Event handler ─────────────────────────────────────────────────────────
@@ -166,6 +173,42 @@ impl EventHandler for Handler {
             self.handle_chat(&ctx, channel_id, &content).await;
         }
     }
+
+    /// Approve/deny buttons on an approval request
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let Interaction::Component(component) = interaction else {
+            return;
+        };
+
+        let authorized = self
+            .state
+            .paired_user
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|p| p.user_id == component.user.id.get());
+
+        let answer = if !authorized {
+            "Not authorized."
+        } else if let Some((id, decision)) = parse_approval_button(&component.data.custom_id) {
+            if self.state.approvals.resolve(id, decision) {
+                "Done."
+            } else {
+                "This request is no longer pending."
+            }
+        } else {
+            "Unknown action."
+        };
+
+        let response = CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(answer)
+                .ephemeral(true),
+        );
+        if let Err(e) = component.create_response(&ctx.http, response).await {
+            warn!("Failed to answer interaction: {}", e);
+        }
+    }
 }
 
 impl Handler {
@@ -250,6 +293,16 @@ impl Handler {
                 );
                 send_long_message(ctx, channel_id, None, &help).await;
             }
+            "/stop" => {
+                let reply = match self.state.running.lock().await.get(&channel_id.get()) {
+                    Some(canceller) => {
+                        canceller.cancel();
+                        "⏹ Stopping the current reply."
+                    }
+                    None => "Nothing to stop.",
+                };
+                let _ = channel_id.say(&ctx.http, reply).await;
+            }
             "/new" => {
                 self.state.sessions.lock().await.remove(&channel_id.get());
                 let _ = channel_id
@@ -335,6 +388,83 @@ impl Handler {
                     let _ = channel_id.say(&ctx.http, "No active session.").await;
                 }
             }
+            "/pin" => {
+                let mut sessions = self.state.sessions.lock().await;
+                if let Some(entry) = sessions.get_mut(&channel_id.get()) {
+                    entry.last_accessed = Instant::now();
+                    let reply = match entry.agent.pin((!args.is_empty()).then_some(args)) {
+                        Ok(pin) => format!("📌 Pinned: {}", truncate_str(&pin.content, 200)),
+                        Err(e) => format!("❌ Pin failed: {}", e),
+                    };
+                    let _ = channel_id.say(&ctx.http, reply).await;
+                } else {
+                    let _ = channel_id.say(&ctx.http, "No active session.").await;
+                }
+            }
+            "/unpin" => {
+                let mut sessions = self.state.sessions.lock().await;
+                if let Some(entry) = sessions.get_mut(&channel_id.get()) {
+                    entry.last_accessed = Instant::now();
+                    let pins = entry.agent.pinned_messages().to_vec();
+                    let reply = if args.is_empty() {
+                        if pins.is_empty() {
+                            "Nothing pinned. Use `/pin [text]` to pin something.".to_string()
+                        } else {
+                            let mut t = "**Pinned:**\n".to_string();
+                            for (i, pin) in pins.iter().enumerate() {
+                                t.push_str(&format!(
+                                    "{}. {}\n",
+                                    i + 1,
+                                    truncate_str(&pin.content, 200)
+                                ));
+                            }
+                            t.push_str("\nUse `/unpin <n>` to remove one.");
+                            t
+                        }
+                    } else {
+                        match args.parse::<usize>() {
+                            Ok(n) if n >= 1 && n <= pins.len() => {
+                                match entry.agent.unpin(&pins[n - 1].id) {
+                                    Ok(()) => "✅ Unpinned.".to_string(),
+                                    Err(e) => format!("❌ Unpin failed: {}", e),
+                                }
+                            }
+                            _ => "Usage: `/unpin <n>` (send `/unpin` for the list)".to_string(),
+                        }
+                    };
+                    let _ = channel_id.say(&ctx.http, reply).await;
+                } else {
+                    let _ = channel_id.say(&ctx.http, "No active session.").await;
+                }
+            }
+            "/instructions" => {
+                let mut sessions = self.state.sessions.lock().await;
+                if let Some(entry) = sessions.get_mut(&channel_id.get()) {
+                    entry.last_accessed = Instant::now();
+                    let reply = match args {
+                        "" => match entry.agent.session_instructions() {
+                            Some(instructions) => {
+                                format!("**Session instructions:**\n{}", instructions)
+                            }
+                            None => {
+                                "No session instructions. Use `/instructions <text>` to set them."
+                                    .to_string()
+                            }
+                        },
+                        "clear" => {
+                            entry.agent.set_session_instructions(None);
+                            "✅ Session instructions cleared.".to_string()
+                        }
+                        _ => {
+                            entry.agent.set_session_instructions(Some(args.to_string()));
+                            "✅ Session instructions set.".to_string()
+                        }
+                    };
+                    let _ = channel_id.say(&ctx.http, reply).await;
+                } else {
+                    let _ = channel_id.say(&ctx.http, "No active session.").await;
+                }
+            }
             "/memory" => {
                 if args.is_empty() {
                     let _ = channel_id
@@ -480,6 +610,10 @@ impl Handler {
             .await
             {
                 Ok(mut agent) => {
+                    agent.set_usage_scope(DISCORD_AGENT_ID, "discord");
+                    agent.set_approval_handler(
+                        self.state.approvals.handler(discord_origin(channel_id)),
+                    );
                     if let Err(err) = agent.new_session().await {
                         error!("Failed to create session: {}", err);
                         let _ = thinking_msg
@@ -520,6 +654,13 @@ impl Handler {
 
         let entry = sessions.get_mut(&channel_id.get()).unwrap();
         entry.last_accessed = Instant::now();
+        let canceller = entry.agent.canceller();
+        canceller.prepare_turn();
+        self.state
+            .running
+            .lock()
+            .await
+            .insert(channel_id.get(), canceller);
 
         let response = match entry.agent.chat_stream_with_tools(text, Vec::new()).await {
             Ok(event_stream) => {
@@ -530,6 +671,7 @@ impl Handler {
 
                 while let Some(event) = pinned_stream.next().await {
                     match event {
+                        Ok(StreamEvent::Thinking(_)) => {}
                         Ok(StreamEvent::Content(delta)) => {
                             full_response.push_str(&delta);
                             if last_edit.elapsed().as_secs() >= EDIT_DEBOUNCE_SECS {
@@ -587,6 +729,10 @@ impl Handler {
                             }
                         }
                         Ok(StreamEvent::Done) => break,
+                        Err(e) if Cancelled::is(&e) => {
+                            full_response.push_str("\n\n⏹ Stopped");
+                            break;
+                        }
                         Err(e) => {
                             error!("Stream error: {}", e);
                             full_response.push_str(&format!("\n\n❌ Error: {}", e));
@@ -603,6 +749,7 @@ impl Handler {
             }
             Err(e) => format!("❌ Error: {}", e),
         };
+        self.state.running.lock().await.remove(&channel_id.get());
 
         if let Err(e) = entry.agent.save_session_for_agent(DISCORD_AGENT_ID).await {
             debug!("Failed to save Discord session: {}", e);
@@ -617,6 +764,97 @@ impl Handler {
 
 // ── Helpers ────────────────────────────────────────────────────────────────
 
+// ── Tool approvals ────────────────────────────────────────────────────────
+
+/// Approval origin for a Discord channel
+fn discord_origin(channel_id: ChannelId) -> String {
+    format!("discord:{}", channel_id.get())
+}
+
+fn parse_approval_button(custom_id: &str) -> Option<(&str, ApprovalDecision)> {
+    let (action, id) = custom_id.split_once(':')?;
+    let decision = match action {
+        "approve" => ApprovalDecision::Approve,
+        "always" => ApprovalDecision::AlwaysAllow,
+        "deny" => ApprovalDecision::Deny,
+        _ => return None,
+    };
+    Some((id, decision))
+}
+
+/// Post approval requests from Discord channels with buttons, and record the
+/// outcome on the message once the request is settled.
+async fn forward_approvals(http: Arc<Http>, approvals: ApprovalBroker) {
+    let mut events = approvals.subscribe();
+    let mut prompts: HashMap<String, (MessageId, String)> = HashMap::new();
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        let Some(channel_id) = event
+            .origin()
+            .strip_prefix("discord:")
+            .and_then(|id| id.parse().ok())
+            .map(ChannelId::new)
+        else {
+            continue;
+        };
+
+        match event {
+            ApprovalEvent::Requested(request) => {
+                let text = match &request.detail {
+                    Some(d) => format!("🔐 Run `{}` ({})?", request.tool_name, d),
+                    None => format!("🔐 Run `{}`?", request.tool_name),
+                };
+                let buttons = CreateActionRow::Buttons(vec![
+                    CreateButton::new(format!("approve:{}", request.id))
+                        .label("Approve")
+                        .style(ButtonStyle::Success),
+                    CreateButton::new(format!("always:{}", request.id))
+                        .label("Always")
+                        .style(ButtonStyle::Primary),
+                    CreateButton::new(format!("deny:{}", request.id))
+                        .label("Deny")
+                        .style(ButtonStyle::Danger),
+                ]);
+                let message = CreateMessage::new()
+                    .content(truncate_str(&text, MAX_MESSAGE_LENGTH))
+                    .components(vec![buttons]);
+                match channel_id.send_message(&http, message).await {
+                    Ok(msg) => {
+                        prompts.insert(request.id, (msg.id, text));
+                    }
+                    Err(e) => warn!("Failed to send approval request: {}", e),
+                }
+            }
+            ApprovalEvent::Resolved {
+                id,
+                decision,
+                timed_out,
+                ..
+            } => {
+                let Some((msg_id, text)) = prompts.remove(&id) else {
+                    continue;
+                };
+                let outcome = match decision {
+                    _ if timed_out => "⌛ No answer, denied",
+                    ApprovalDecision::Approve => "✅ Approved",
+                    ApprovalDecision::AlwaysAllow => "✅ Allowed for this session",
+                    ApprovalDecision::Deny => "❌ Denied",
+                };
+                let content = format!("{}\n{}", text, outcome);
+                let edit = EditMessage::new()
+                    .content(truncate_str(&content, MAX_MESSAGE_LENGTH))
+                    .components(vec![]);
+                let _ = channel_id.edit_message(&http, msg_id, edit).await;
+            }
+        }
+    }
+}
+
 fn format_display(response: &str, tool_info: &str) -> String {
     let mut display = String::new();
     if !tool_info.is_empty() {
@@ -747,6 +985,7 @@ async fn main() -> Result<()> {
     let memory =
         MemoryManager::new_with_full_config(&config.memory, Some(&config), DISCORD_AGENT_ID)?;
     let turn_gate = TurnGate::new();
+    let approvals = ApprovalBroker::from_config(&config.tools);
 
     let paired_user = load_paired_user();
     if let Some(ref user) = paired_user {
@@ -762,6 +1001,8 @@ async fn main() -> Result<()> {
         turn_gate,
         paired_user: Mutex::new(paired_user),
         pending_pairing_code: Mutex::new(None),
+        approvals: approvals.clone(),
+        running: Mutex::new(HashMap::new()),
     });
 
     // 5. Start Discord bot
@@ -775,6 +1016,8 @@ async fn main() -> Result<()> {
         .await
         .map_err(|e| anyhow::anyhow!("Failed to create Discord client: {}", e))?;
 
+    tokio::spawn(forward_approvals(client.http.clone(), approvals));
+
     info!("Discord bot started. Listening for messages...");
 
     client
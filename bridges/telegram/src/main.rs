@@ -7,12 +7,17 @@ use std::sync::Arc;
 use std::time::Instant;
 use tarpc::context;
 use teloxide::prelude::*;
-use teloxide::types::{ChatAction, MessageId, ParseMode};
-use tokio::sync::Mutex;
+use teloxide::types::{
+    ChatAction, InlineKeyboardButton, InlineKeyboardMarkup, MessageId, ParseMode, UpdateKind,
+};
+use tokio::sync::{Mutex, broadcast};
 use tracing::{debug, error, info, warn};
 
 use localgpt_bridge::connect;
-use localgpt_core::agent::{Agent, AgentConfig, StreamEvent, extract_tool_detail};
+use localgpt_core::agent::{
+    Agent, AgentConfig, ApprovalBroker, ApprovalDecision, ApprovalEvent, Cancelled, StreamEvent,
+    TurnCanceller, extract_tool_detail,
+};
 use localgpt_core::concurrency::TurnGate;
 use localgpt_core::config::Config;
 use localgpt_core::memory::MemoryManager;
@@ -46,6 +51,9 @@ struct BotState {
     paired_user: Mutex<Option<PairedUser>>,
     pending_pairing_code: Mutex<Option<String>>,
     bot_info: teloxide::types::Me,
+    approvals: ApprovalBroker,
+    /// Turns in progress by chat, for /stop (a turn holds `sessions`)
+    running: Mutex<HashMap<i64, TurnCanceller>>,
 }
 
 fn pairing_file_path() -> Result<PathBuf> {
@@ -134,6 +142,7 @@ async fn main() -> Result<()> {
     let memory =
         MemoryManager::new_with_full_config(&config.memory, Some(&config), TELEGRAM_AGENT_ID)?;
     let turn_gate = TurnGate::new();
+    let approvals = ApprovalBroker::from_config(&config.tools);
 
     let paired_user = load_paired_user();
     if let Some(ref user) = paired_user {
@@ -154,6 +163,8 @@ async fn main() -> Result<()> {
         paired_user: Mutex::new(paired_user),
         pending_pairing_code: Mutex::new(None),
         bot_info,
+        approvals: approvals.clone(),
+        running: Mutex::new(HashMap::new()),
     });
 
     // 5. Register slash commands so clients show the "/" menu
@@ -167,11 +178,23 @@ async fn main() -> Result<()> {
         warn!("Failed to set bot commands: {}", e);
     }
 
+    tokio::spawn(forward_approvals(bot.clone(), approvals));
+
     info!("Telegram bot started.");
 
-    let handler = Update::filter_message().endpoint(handle_message);
+    let handler = dptree::entry()
+        .branch(Update::filter_message().endpoint(handle_message))
+        .branch(Update::filter_callback_query().endpoint(handle_callback));
 
     Dispatcher::builder(bot, handler)
+        // Button presses and /stop must not queue behind their chat: its
+        // turn may be the one waiting on the approval they carry, or the one
+        // to stop
+        .distribution_function(|upd| match upd.kind {
+            UpdateKind::CallbackQuery(_) => None,
+            UpdateKind::Message(ref msg) if msg.text().map(str::trim) == Some("/stop") => None,
+            _ => upd.chat().map(|chat| chat.id),
+        })
         .default_handler(|_upd| async {})
         .dependencies(dptree::deps![state])
         .enable_ctrlc_handler()
@@ -244,6 +267,118 @@ async fn handle_message(bot: Bot, msg: Message, state: Arc<BotState>) -> Respons
     handle_chat(&bot, chat_id, &state, &text).await
 }
 
+/// Approve/deny buttons on an approval request
+async fn handle_callback(
+    bot: Bot,
+    query: CallbackQuery,
+    state: Arc<BotState>,
+) -> ResponseResult<()> {
+    let authorized = state
+        .paired_user
+        .lock()
+        .await
+        .as_ref()
+        .is_some_and(|pu| pu.user_id == query.from.id.0);
+
+    let answer = if !authorized {
+        "Not authorized."
+    } else if let Some((id, decision)) = query.data.as_deref().and_then(parse_approval_callback) {
+        if state.approvals.resolve(id, decision) {
+            "Done."
+        } else {
+            "This request is no longer pending."
+        }
+    } else {
+        "Unknown action."
+    };
+
+    bot.answer_callback_query(query.id).text(answer).await?;
+    Ok(())
+}
+
+/// Approval origin for a Telegram chat
+fn telegram_origin(chat_id: ChatId) -> String {
+    format!("telegram:{}", chat_id.0)
+}
+
+fn parse_approval_callback(data: &str) -> Option<(&str, ApprovalDecision)> {
+    let (action, id) = data.split_once(':')?;
+    let decision = match action {
+        "approve" => ApprovalDecision::Approve,
+        "always" => ApprovalDecision::AlwaysAllow,
+        "deny" => ApprovalDecision::Deny,
+        _ => return None,
+    };
+    Some((id, decision))
+}
+
+/// Post approval requests from Telegram chats with inline buttons, and
+/// record the outcome on the message once the request is settled.
+async fn forward_approvals(bot: Bot, approvals: ApprovalBroker) {
+    let mut events = approvals.subscribe();
+    let mut prompts: HashMap<String, (MessageId, String)> = HashMap::new();
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        let Some(chat_id) = event
+            .origin()
+            .strip_prefix("telegram:")
+            .and_then(|id| id.parse().ok())
+            .map(ChatId)
+        else {
+            continue;
+        };
+
+        match event {
+            ApprovalEvent::Requested(request) => {
+                let text = match &request.detail {
+                    Some(d) => format!("🔐 Run {}({})?", request.tool_name, d),
+                    None => format!("🔐 Run {}?", request.tool_name),
+                };
+                let keyboard = InlineKeyboardMarkup::new([[
+                    InlineKeyboardButton::callback("Approve", format!("approve:{}", request.id)),
+                    InlineKeyboardButton::callback("Always", format!("always:{}", request.id)),
+                    InlineKeyboardButton::callback("Deny", format!("deny:{}", request.id)),
+                ]]);
+                match bot
+                    .send_message(chat_id, &text)
+                    .reply_markup(keyboard)
+                    .await
+                {
+                    Ok(msg) => {
+                        prompts.insert(request.id, (msg.id, text));
+                    }
+                    Err(e) => warn!("Failed to send approval request: {}", e),
+                }
+            }
+            ApprovalEvent::Resolved {
+                id,
+                decision,
+                timed_out,
+                ..
+            } => {
+                let Some((msg_id, text)) = prompts.remove(&id) else {
+                    continue;
+                };
+                let outcome = match decision {
+                    _ if timed_out => "⌛ No answer, denied",
+                    ApprovalDecision::Approve => "✅ Approved",
+                    ApprovalDecision::AlwaysAllow => "✅ Allowed for this session",
+                    ApprovalDecision::Deny => "❌ Denied",
+                };
+                // Editing the text drops the buttons
+                let _ = bot
+                    .edit_message_text(chat_id, msg_id, format!("{}\n{}", text, outcome))
+                    .await;
+            }
+        }
+    }
+}
+
 async fn handle_pairing(
     bot: Bot,
     chat_id: ChatId,
@@ -331,6 +466,16 @@ async fn handle_command(
             );
             bot.send_message(chat_id, &help).await?;
         }
+        "/stop" => {
+            let reply = match state.running.lock().await.get(&chat_id.0) {
+                Some(canceller) => {
+                    canceller.cancel();
+                    "Stopping the current reply."
+                }
+                None => "Nothing to stop.",
+            };
+            bot.send_message(chat_id, reply).await?;
+        }
         "/new" => {
             state.sessions.lock().await.remove(&chat_id.0);
             bot.send_message(
@@ -411,6 +556,79 @@ async fn handle_command(
                 bot.send_message(chat_id, "No active session.").await?;
             }
         }
+        "/pin" => {
+            let mut sessions = state.sessions.lock().await;
+            if let Some(entry) = sessions.get_mut(&chat_id.0) {
+                entry.last_accessed = Instant::now();
+                let reply = match entry.agent.pin((!args.is_empty()).then_some(args)) {
+                    Ok(pin) => format!("Pinned: {}", truncate_str(&pin.content, 200)),
+                    Err(e) => format!("Pin failed: {}", e),
+                };
+                bot.send_message(chat_id, reply).await?;
+            } else {
+                bot.send_message(chat_id, "No active session.").await?;
+            }
+        }
+        "/unpin" => {
+            let mut sessions = state.sessions.lock().await;
+            if let Some(entry) = sessions.get_mut(&chat_id.0) {
+                entry.last_accessed = Instant::now();
+                let pins = entry.agent.pinned_messages().to_vec();
+                let reply = if args.is_empty() {
+                    if pins.is_empty() {
+                        "Nothing pinned. Use /pin [text] to pin something.".to_string()
+                    } else {
+                        let mut text = "Pinned:\n".to_string();
+                        for (i, pin) in pins.iter().enumerate() {
+                            text.push_str(&format!(
+                                "{}. {}\n",
+                                i + 1,
+                                truncate_str(&pin.content, 200)
+                            ));
+                        }
+                        text.push_str("\nUse /unpin <n> to remove one.");
+                        text
+                    }
+                } else {
+                    match args.parse::<usize>() {
+                        Ok(n) if n >= 1 && n <= pins.len() => {
+                            match entry.agent.unpin(&pins[n - 1].id) {
+                                Ok(()) => "Unpinned.".to_string(),
+                                Err(e) => format!("Unpin failed: {}", e),
+                            }
+                        }
+                        _ => "Usage: /unpin <n> (send /unpin for the list)".to_string(),
+                    }
+                };
+                bot.send_message(chat_id, reply).await?;
+            } else {
+                bot.send_message(chat_id, "No active session.").await?;
+            }
+        }
+        "/instructions" => {
+            let mut sessions = state.sessions.lock().await;
+            if let Some(entry) = sessions.get_mut(&chat_id.0) {
+                entry.last_accessed = Instant::now();
+                let reply = match args {
+                    "" => match entry.agent.session_instructions() {
+                        Some(instructions) => format!("Session instructions:\n{}", instructions),
+                        None => "No session instructions. Use /instructions <text> to set them."
+                            .to_string(),
+                    },
+                    "clear" => {
+                        entry.agent.set_session_instructions(None);
+                        "Session instructions cleared.".to_string()
+                    }
+                    _ => {
+                        entry.agent.set_session_instructions(Some(args.to_string()));
+                        "Session instructions set.".to_string()
+                    }
+                };
+                bot.send_message(chat_id, reply).await?;
+            } else {
+                bot.send_message(chat_id, "No active session.").await?;
+            }
+        }
         "/memory" => {
             if args.is_empty() {
                 bot.send_message(chat_id, "Usage: /memory <search query>")
@@ -539,6 +757,8 @@ async fn handle_chat(
 
         match Agent::new(agent_config, &state.config, Arc::new(state.memory.clone())).await {
             Ok(mut agent) => {
+                agent.set_usage_scope(TELEGRAM_AGENT_ID, "telegram");
+                agent.set_approval_handler(state.approvals.handler(telegram_origin(chat_id)));
                 if let Err(err) = agent.new_session().await {
                     bot.send_message(chat_id, format!("Error: {}", err)).await?;
                     return Ok(());
@@ -566,6 +786,9 @@ async fn handle_chat(
 
     let entry = sessions.get_mut(&chat_id.0).unwrap();
     entry.last_accessed = Instant::now();
+    let canceller = entry.agent.canceller();
+    canceller.prepare_turn();
+    state.running.lock().await.insert(chat_id.0, canceller);
 
     let mut msg_id: Option<MessageId> = None;
 
@@ -585,6 +808,7 @@ async fn handle_chat(
                 }
 
                 match event {
+                    Ok(StreamEvent::Thinking(_)) => {}
                     Ok(StreamEvent::Content(delta)) => {
                         full_response.push_str(&delta);
                         if last_edit.elapsed().as_secs() >= EDIT_DEBOUNCE_SECS || msg_id.is_none() {
@@ -636,6 +860,10 @@ async fn handle_chat(
                         }
                     }
                     Ok(StreamEvent::Done) => break,
+                    Err(e) if Cancelled::is(&e) => {
+                        full_response.push_str("\n\n⏹ Stopped");
+                        break;
+                    }
                     Err(e) => {
                         error!("Stream error: {}", e);
                         full_response.push_str(&format!("\n\nError: {}", e));
@@ -652,6 +880,7 @@ async fn handle_chat(
         }
         Err(e) => format!("Error: {}", e),
     };
+    state.running.lock().await.remove(&chat_id.0);
 
     if let Err(e) = entry.agent.save_session_for_agent(TELEGRAM_AGENT_ID).await {
         debug!("Failed to save telegram session: {}", e);
@@ -7,6 +7,14 @@
 //!
 //! Architecture:
 //! [WhatsApp] <-> [Node.js Adapter] <-> [This Bridge] <-> [LocalGPT Core]
+//!
+//! Tool approvals: while a reply waits on a tool that needs approval, the
+//! adapter polls `GET /approvals` and relays each prompt to its chat. The
+//! user answers "approve", "always" or "deny", which the adapter posts to
+//! `/webhook` like any other message.
+//!
+//! Sending "/stop" while a reply is in progress cancels it; the pending
+//! webhook request then returns what was generated so far.
 
 use anyhow::Result;
 use axum::{
@@ -26,7 +34,9 @@ use tokio::sync::Mutex;
 use tracing::{error, info, warn};
 
 use localgpt_bridge::connect;
-use localgpt_core::agent::{Agent, AgentConfig, StreamEvent};
+use localgpt_core::agent::{
+    Agent, AgentConfig, ApprovalBroker, ApprovalDecision, Cancelled, StreamEvent, TurnCanceller,
+};
 use localgpt_core::concurrency::TurnGate;
 use localgpt_core::config::Config;
 use localgpt_core::memory::MemoryManager;
@@ -47,6 +57,14 @@ struct OutgoingMessage {
     reply: String,
 }
 
+/// A tool approval prompt for the adapter to relay
+#[derive(Debug, Serialize)]
+struct ApprovalPrompt {
+    id: String,
+    chat_id: String,
+    prompt: String,
+}
+
 struct SessionEntry {
     agent: Agent,
     last_accessed: Instant,
@@ -57,6 +75,9 @@ struct BridgeState {
     sessions: Mutex<HashMap<String, SessionEntry>>,
     memory: MemoryManager,
     turn_gate: TurnGate,
+    approvals: ApprovalBroker,
+    /// Turns in progress by chat, for /stop (a turn holds `sessions`)
+    running: Mutex<HashMap<String, TurnCanceller>>,
 }
 
 #[tokio::main]
@@ -106,12 +127,15 @@ async fn main() -> Result<()> {
         sessions: Mutex::new(HashMap::new()),
         memory,
         turn_gate: TurnGate::new(),
+        approvals: ApprovalBroker::from_config(&config.tools),
+        running: Mutex::new(HashMap::new()),
     });
 
     // 5. Start HTTP Relay Server
     let app = Router::new()
         .route("/health", get(|| async { "OK" }))
         .route("/webhook", post(handle_webhook))
+        .route("/approvals", get(list_approvals))
         .with_state(state);
 
     let addr = std::net::SocketAddr::from(([127, 0, 0, 1], RELAY_PORT));
@@ -133,6 +157,45 @@ async fn handle_webhook(
         payload.chat_id, payload.content
     );
 
+    // An answer to a pending approval resumes the waiting reply instead
+    if let Some(decision) = parse_approval_reply(&payload.content)
+        && let Some(request) = state
+            .approvals
+            .pending_for(&whatsapp_origin(&payload.chat_id))
+            .into_iter()
+            .next()
+    {
+        let reply = if !state.approvals.resolve(&request.id, decision) {
+            "That request is no longer pending."
+        } else if decision == ApprovalDecision::Deny {
+            "Denied."
+        } else {
+            "Approved."
+        };
+        return (
+            StatusCode::OK,
+            Json(OutgoingMessage {
+                reply: reply.to_string(),
+            }),
+        );
+    }
+
+    if payload.content.trim() == "/stop" {
+        let reply = match state.running.lock().await.get(&payload.chat_id) {
+            Some(canceller) => {
+                canceller.cancel();
+                "Stopping the current reply."
+            }
+            None => "Nothing to stop.",
+        };
+        return (
+            StatusCode::OK,
+            Json(OutgoingMessage {
+                reply: reply.to_string(),
+            }),
+        );
+    }
+
     // 1. Process with Agent
     let response_text = match process_message(state, payload.chat_id.clone(), payload.content).await
     {
@@ -153,6 +216,42 @@ async fn handle_webhook(
     )
 }
 
+/// Approval origin for a WhatsApp chat
+fn whatsapp_origin(chat_id: &str) -> String {
+    format!("whatsapp:{}", chat_id)
+}
+
+fn parse_approval_reply(text: &str) -> Option<ApprovalDecision> {
+    match text.trim().to_lowercase().as_str() {
+        "approve" | "yes" | "y" => Some(ApprovalDecision::Approve),
+        "always" => Some(ApprovalDecision::AlwaysAllow),
+        "deny" | "no" | "n" => Some(ApprovalDecision::Deny),
+        _ => None,
+    }
+}
+
+/// Approval prompts waiting to be relayed, oldest first
+async fn list_approvals(State(state): State<Arc<BridgeState>>) -> Json<Vec<ApprovalPrompt>> {
+    let prompts = state
+        .approvals
+        .pending()
+        .into_iter()
+        .filter_map(|request| {
+            let chat_id = request.origin.strip_prefix("whatsapp:")?.to_string();
+            let call = match &request.detail {
+                Some(d) => format!("{} ({})", request.tool_name, d),
+                None => request.tool_name.clone(),
+            };
+            Some(ApprovalPrompt {
+                id: request.id,
+                chat_id,
+                prompt: format!("Run {}? Reply approve, always or deny.", call),
+            })
+        })
+        .collect();
+    Json(prompts)
+}
+
 async fn process_message(state: Arc<BridgeState>, chat_id: String, text: String) -> Result<String> {
     // Acquire turn gate to limit concurrency if needed
     let _gate_permit = state.turn_gate.acquire().await;
@@ -169,6 +268,8 @@ async fn process_message(state: Arc<BridgeState>, chat_id: String, text: String)
 
         let mut agent =
             Agent::new(agent_config, &state.config, Arc::new(state.memory.clone())).await?;
+        agent.set_usage_scope(WHATSAPP_AGENT_ID, "whatsapp");
+        agent.set_approval_handler(state.approvals.handler(whatsapp_origin(&chat_id)));
         agent.new_session().await?;
 
         e.insert(SessionEntry {
@@ -180,6 +281,7 @@ async fn process_message(state: Arc<BridgeState>, chat_id: String, text: String)
 
     let entry = sessions.get_mut(&chat_id).unwrap();
     entry.last_accessed = Instant::now();
+    let canceller = entry.agent.canceller();
 
     // Chat with Agent
     // TODO: Support tools (pass in tools if needed)
@@ -187,15 +289,23 @@ async fn process_message(state: Arc<BridgeState>, chat_id: String, text: String)
         .agent
         .chat_stream_with_tools(&text, Vec::new())
         .await?;
+    state
+        .running
+        .lock()
+        .await
+        .insert(chat_id.clone(), canceller);
 
     let mut full_response = String::new();
     let mut pinned_stream = std::pin::pin!(event_stream);
 
     while let Some(event) = pinned_stream.next().await {
-        if let Ok(StreamEvent::Content(delta)) = event {
-            full_response.push_str(&delta);
+        match event {
+            Ok(StreamEvent::Content(delta)) => full_response.push_str(&delta),
+            Err(e) if Cancelled::is(&e) => full_response.push_str("\n\n(stopped)"),
+            _ => {}
         }
     }
+    state.running.lock().await.remove(&chat_id);
 
     Ok(full_response)
 }
@@ -7,7 +7,7 @@ use localgpt_core::agent::{
     Agent, AgentConfig, StreamEvent, create_spawn_agent_tool, extract_tool_detail,
 };
 use localgpt_core::concurrency::WorkspaceLock;
-use localgpt_core::config::Config;
+use localgpt_core::config::{Config, ReasoningEffort};
 use localgpt_core::memory::MemoryManager;
 use std::io::Write;
 
@@ -20,13 +20,24 @@ pub struct AskArgs {
     #[arg(short, long)]
     pub model: Option<String>,
 
+    /// Reasoning effort: off, low, medium or high (overrides config)
+    #[arg(long)]
+    pub reasoning: Option<ReasoningEffort>,
+
     /// Output format: text (default) or json
     #[arg(short, long, default_value = "text")]
     pub format: String,
+
+    /// Record every LLM exchange to a JSONL fixture (replay with --model replay/<FILE>)
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<String>,
 }
 
 pub async fn run(args: AskArgs, agent_id: &str) -> Result<()> {
-    let config = Config::load()?;
+    let mut config = Config::load()?;
+    if args.record.is_some() {
+        config.agent.record_fixture = args.record.clone();
+    }
     let memory = Arc::new(MemoryManager::new_with_full_config(
         &config.memory,
         Some(&config),
@@ -40,6 +51,10 @@ pub async fn run(args: AskArgs, agent_id: &str) -> Result<()> {
     };
 
     let mut agent = Agent::new(agent_config, &config, Arc::clone(&memory)).await?;
+    agent.set_usage_scope(agent_id, "cli");
+    if args.reasoning.is_some() {
+        agent.set_reasoning(args.reasoning)?;
+    }
     agent.extend_tools(crate::tools::create_cli_tools(&config)?);
     agent.extend_tools(vec![create_spawn_agent_tool(config.clone(), memory)]);
     agent.new_session().await?;
@@ -65,6 +80,10 @@ pub async fn run(args: AskArgs, agent_id: &str) -> Result<()> {
 
         while let Some(event) = pinned_stream.next().await {
             match event {
+                Ok(StreamEvent::Thinking(thinking)) => {
+                    // Keep reasoning off stdout so piped answers stay clean
+                    eprint!("\x1b[90m{}\x1b[0m", thinking);
+                }
                 Ok(StreamEvent::Content(content)) => {
                     print!("{}", content);
                     let _ = stdout.flush();
@@ -8,14 +8,63 @@ use std::sync::Arc;
 use tracing::debug;
 
 use localgpt_core::agent::{
-    Agent, AgentConfig, ImageAttachment, Skill, create_spawn_agent_tool, extract_tool_detail,
+    Agent, AgentConfig, ApprovalDecision, ApprovalHandler, Cancelled, ImageAttachment, Skill,
+    ToolCall, TurnCanceller, create_spawn_agent_tool, extract_tool_detail,
     get_last_session_id_for_agent, get_skills_summary, list_sessions_for_agent, load_skills,
     parse_skill_command, search_sessions_for_agent,
 };
 use localgpt_core::concurrency::WorkspaceLock;
-use localgpt_core::config::Config;
+use localgpt_core::config::{Config, ReasoningEffort};
 use localgpt_core::memory::MemoryManager;
 
+/// Asks on the terminal before running tools that need approval
+struct TerminalApproval;
+
+#[async_trait::async_trait]
+impl ApprovalHandler for TerminalApproval {
+    async fn decide(&self, call: &ToolCall) -> ApprovalDecision {
+        match extract_tool_detail(&call.name, &call.arguments) {
+            Some(detail) => print!("\nExecute {} ({})? [y/N/a=always]: ", call.name, detail),
+            None => print!("\nExecute {}? [y/N/a=always]: ", call.name),
+        }
+        let _ = io::stdout().flush();
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input).is_err() {
+            return ApprovalDecision::Deny;
+        }
+        match input.trim().to_lowercase().as_str() {
+            "y" | "yes" => ApprovalDecision::Approve,
+            "a" | "always" => ApprovalDecision::AlwaysAllow,
+            _ => {
+                println!("Skipped: {}", call.name);
+                ApprovalDecision::Deny
+            }
+        }
+    }
+}
+
+/// Stops the agent's turn when Ctrl-C is pressed, until dropped. Outside a
+/// turn Ctrl-C is handled by the line editor instead.
+struct CtrlCStopsTurn(tokio::task::JoinHandle<()>);
+
+impl CtrlCStopsTurn {
+    fn new(canceller: TurnCanceller) -> Self {
+        canceller.prepare_turn();
+        Self(tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                canceller.cancel();
+            }
+        }))
+    }
+}
+
+impl Drop for CtrlCStopsTurn {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
 /// Adjust a byte index to the nearest valid UTF-8 char boundary (searching forward).
 fn floor_char_boundary(s: &str, index: usize) -> usize {
     if index >= s.len() {
@@ -114,10 +163,17 @@ pub struct ChatArgs {
     /// Resume the most recent session
     #[arg(long)]
     pub resume: bool,
+
+    /// Record every LLM exchange to a JSONL fixture (replay with --model replay/<FILE>)
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<String>,
 }
 
 pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
-    let config = Config::load()?;
+    let mut config = Config::load()?;
+    if args.record.is_some() {
+        config.agent.record_fixture = args.record.clone();
+    }
     // Embedding provider is automatically created based on config.memory.embedding_provider
     let memory = Arc::new(MemoryManager::new_with_full_config(
         &config.memory,
@@ -132,6 +188,8 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
     };
 
     let mut agent = Agent::new(agent_config, &config, Arc::clone(&memory)).await?;
+    agent.set_usage_scope(agent_id, "cli");
+    agent.set_approval_handler(Arc::new(TerminalApproval));
     agent.extend_tools(crate::tools::create_cli_tools(&config)?);
     // Add spawn_agent tool for hierarchical delegation
     agent.extend_tools(vec![create_spawn_agent_tool(config.clone(), memory)]);
@@ -360,18 +418,31 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
             match handle_command(input, &mut agent, &agent_id, &skills).await {
                 CommandResult::Continue => continue,
                 CommandResult::Quit => break,
-                CommandResult::SendMessage(msg) => {
-                    // Skill invocation - send message to agent
+                result @ (CommandResult::SendMessage(_)
+                | CommandResult::Retry
+                | CommandResult::Edit { .. }) => {
+                    // Skill invocation or rerun of a turn - send to agent
                     print!("\nLocalGPT: ");
                     stdout.flush().ok();
                     let _lock_guard = workspace_lock.acquire()?;
-                    match agent.chat(&msg).await {
+                    let _ctrl_c = CtrlCStopsTurn::new(agent.canceller());
+                    let reply = match result {
+                        CommandResult::Retry => agent.regenerate().await,
+                        CommandResult::Edit {
+                            message_id,
+                            content,
+                        } => agent.edit_message(&message_id, &content).await,
+                        CommandResult::SendMessage(msg) => agent.chat(&msg).await,
+                        _ => unreachable!(),
+                    };
+                    match reply {
                         Ok(response) => {
                             println!("{}\n", response);
                             if let Err(e) = agent.auto_save_session() {
                                 eprintln!("Warning: Failed to auto-save session: {}", e);
                             }
                         }
+                        Err(e) if Cancelled::is(&e) => println!("[Stopped]\n"),
                         Err(e) => {
                             eprintln!("Error: {}\n", e);
                         }
@@ -418,22 +489,48 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
         stdout.flush()?;
 
         let _lock_guard = workspace_lock.acquire()?;
+        let _ctrl_c = CtrlCStopsTurn::new(agent.canceller());
         match agent.chat_stream_with_images(&message, images).await {
             Ok(mut stream) => {
                 let mut full_response = String::new();
                 let mut pending_tool_calls = None;
+                let mut stream_usage = None;
+                let mut thinking_blocks = Vec::new();
+                let mut in_thinking = false;
 
                 while let Some(result) = stream.next().await {
                     match result {
                         Ok(chunk) => {
+                            // Reasoning is shown dimmed, ahead of the answer
+                            if let Some(thinking) = &chunk.thinking {
+                                if !in_thinking {
+                                    print!("\x1b[90m");
+                                    in_thinking = true;
+                                }
+                                print!("{}", thinking);
+                            }
+                            if in_thinking && !chunk.delta.is_empty() {
+                                print!("\x1b[0m\n\n");
+                                in_thinking = false;
+                            }
                             print!("{}", chunk.delta);
                             stdout.flush()?;
                             full_response.push_str(&chunk.delta);
 
-                            // Capture tool calls from the final chunk
+                            // Capture tool calls, thinking and usage from the final chunk
                             if chunk.done && chunk.tool_calls.is_some() {
                                 pending_tool_calls = chunk.tool_calls;
                             }
+                            if let Some(blocks) = chunk.thinking_blocks {
+                                thinking_blocks = blocks;
+                            }
+                            if chunk.usage.is_some() {
+                                stream_usage = chunk.usage;
+                            }
+                        }
+                        Err(e) if Cancelled::is(&e) => {
+                            print!("\n[Stopped]");
+                            break;
                         }
                         Err(e) => {
                             eprintln!("\nStream error: {}", e);
@@ -441,87 +538,56 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
                         }
                     }
                 }
+                if in_thinking {
+                    print!("\x1b[0m");
+                }
+
+                agent.add_usage(stream_usage);
 
                 // Handle tool calls if any
                 if let Some(tool_calls) = pending_tool_calls {
-                    // Check for tools requiring approval
-                    let mut approved_calls = Vec::new();
-                    let mut any_denied = false;
-
-                    for tc in tool_calls {
-                        let detail = extract_tool_detail(&tc.name, &tc.arguments);
-                        if let Some(ref d) = detail {
-                            println!("\n[{}: {}]", tc.name, d);
-                        } else {
-                            println!("\n[{}]", tc.name);
-                        }
-
-                        if agent.requires_approval(&tc.name) {
-                            // Prompt for approval
-                            print!("Execute {}? [y/N]: ", tc.name);
-                            stdout.flush()?;
-
-                            let mut input = String::new();
-                            std::io::stdin().read_line(&mut input)?;
-                            let input = input.trim().to_lowercase();
-
-                            if input == "y" || input == "yes" {
-                                approved_calls.push(tc);
-                            } else {
-                                println!("Skipped: {}", tc.name);
-                                any_denied = true;
-                            }
-                        } else {
-                            approved_calls.push(tc);
-                        }
-                    }
-                    stdout.flush()?;
-
-                    if !approved_calls.is_empty() {
-                        match agent
-                            .execute_streaming_tool_calls(
-                                &full_response,
-                                approved_calls,
-                                |name, args| {
-                                    // Print tool call as it starts (including recursive calls)
-                                    let detail = extract_tool_detail(name, args);
-                                    if let Some(ref d) = detail {
-                                        print!("\n> Running tool: {} ({}) ... ", name, d);
-                                    } else {
-                                        print!("\n> Running tool: {} ... ", name);
-                                    }
-                                    let _ = std::io::stdout().flush();
-                                },
-                                |_name, result| {
-                                    match result {
-                                        Ok(_) => print!("Done."),
-                                        Err(e) => print!("Failed: {}", e),
-                                    }
-                                    let _ = std::io::stdout().flush();
-                                },
-                            )
-                            .await
-                        {
-                            Ok((follow_up, warnings)) => {
-                                for (tool_name, tool_warnings) in &warnings {
-                                    for w in tool_warnings {
-                                        eprintln!(
-                                            "  \u{26a0} Suspicious content in {} output: {}",
-                                            tool_name, w
-                                        );
-                                    }
+                    // Calls that need approval are confirmed by TerminalApproval before they run
+                    match agent
+                        .execute_streaming_tool_calls(
+                            &full_response,
+                            thinking_blocks,
+                            tool_calls,
+                            |name, args| {
+                                // Print tool call as it starts (including recursive calls)
+                                let detail = extract_tool_detail(name, args);
+                                if let Some(ref d) = detail {
+                                    print!("\n> Running tool: {} ({}) ... ", name, d);
+                                } else {
+                                    print!("\n> Running tool: {} ... ", name);
+                                }
+                                let _ = std::io::stdout().flush();
+                            },
+                            |_name, result| {
+                                match result {
+                                    Ok(_) => print!("Done."),
+                                    Err(e) => print!("Failed: {}", e),
+                                }
+                                let _ = std::io::stdout().flush();
+                            },
+                        )
+                        .await
+                    {
+                        Ok((follow_up, warnings)) => {
+                            for (tool_name, tool_warnings) in &warnings {
+                                for w in tool_warnings {
+                                    eprintln!(
+                                        "  \u{26a0} Suspicious content in {} output: {}",
+                                        tool_name, w
+                                    );
                                 }
-                                print!("\nLocalGPT: {}", follow_up);
-                                stdout.flush()?;
-                            }
-                            Err(e) => {
-                                eprintln!("Tool execution error: {}", e);
                             }
+                            print!("\nLocalGPT: {}", follow_up);
+                            stdout.flush()?;
+                        }
+                        Err(e) if Cancelled::is(&e) => print!("\n[Stopped]"),
+                        Err(e) => {
+                            eprintln!("Tool execution error: {}", e);
                         }
-                    } else if any_denied {
-                        // All tools were denied, just finish the stream
-                        agent.finish_chat_stream(&full_response);
-                        println!("\n(Tool execution skipped)");
                     }
                 } else {
                     // No tool calls - just finish the stream
@@ -533,6 +599,7 @@ pub async fn run(args: ChatArgs, agent_id: &str) -> Result<()> {
                 }
                 println!("\n");
             }
+            Err(e) if Cancelled::is(&e) => println!("[Stopped]\n"),
             Err(e) => {
                 eprintln!("Error: {}\n", e);
             }
@@ -547,6 +614,13 @@ enum CommandResult {
     Continue,
     Quit,
     SendMessage(String),
+    /// Regenerate the last reply
+    Retry,
+    /// Replace a user message and answer it on a new branch
+    Edit {
+        message_id: String,
+        content: String,
+    },
     Error(String),
 }
 
@@ -705,13 +779,47 @@ async fn handle_command(
             let model = parts[1];
             match agent.set_model(model) {
                 Ok(()) => {
-                    println!("\nSwitched to model: {}\n", model);
+                    let spec = agent.model_spec();
+                    println!(
+                        "\nSwitched to model: {} (context {} tokens, output {} tokens{}{})\n",
+                        model,
+                        agent.context_window(),
+                        spec.max_output_tokens,
+                        if spec.supports_tools {
+                            ""
+                        } else {
+                            ", no tools"
+                        },
+                        if spec.supports_images {
+                            ""
+                        } else {
+                            ", no images"
+                        },
+                    );
                     CommandResult::Continue
                 }
                 Err(e) => CommandResult::Error(format!("Failed to switch model: {}", e)),
             }
         }
 
+        "/think" => {
+            if parts.len() < 2 {
+                println!("\nReasoning effort: {}\n", agent.reasoning());
+                return CommandResult::Continue;
+            }
+            let effort = match parts[1].parse::<ReasoningEffort>() {
+                Ok(effort) => effort,
+                Err(e) => return CommandResult::Error(e.to_string()),
+            };
+            match agent.set_reasoning(Some(effort)) {
+                Ok(()) => {
+                    println!("\nReasoning effort set to: {}\n", agent.reasoning());
+                    CommandResult::Continue
+                }
+                Err(e) => CommandResult::Error(format!("Failed to set reasoning: {}", e)),
+            }
+        }
+
         "/compact" => match agent.compact_session().await {
             Ok((before, after)) => {
                 println!("\nSession compacted. Token count: {} → {}\n", before, after);
@@ -720,6 +828,122 @@ async fn handle_command(
             Err(e) => CommandResult::Error(format!("Failed to compact: {}", e)),
         },
 
+        "/retry" => CommandResult::Retry,
+
+        "/edit" => {
+            let content = input.strip_prefix("/edit").unwrap_or("").trim();
+            if content.is_empty() {
+                return CommandResult::Error("Usage: /edit <text>".into());
+            }
+            match agent.last_user_message_id() {
+                Some(id) => CommandResult::Edit {
+                    message_id: id.to_string(),
+                    content: content.to_string(),
+                },
+                None => CommandResult::Error("No message to edit".into()),
+            }
+        }
+
+        "/branch" => {
+            let branches = agent.branches();
+            let Some(target) = parts.get(1) else {
+                if branches.len() < 2 {
+                    println!("\nNo other branches. Use /retry or /edit to create one.\n");
+                    return CommandResult::Continue;
+                }
+                println!("\nBranches:");
+                for (i, branch) in branches.iter().enumerate() {
+                    let preview: String = branch.preview.chars().take(50).collect();
+                    println!(
+                        "{} {}. {} ({} messages) {}",
+                        if branch.active { "*" } else { " " },
+                        i + 1,
+                        branch.leaf_id,
+                        branch.message_count,
+                        preview
+                    );
+                }
+                println!("\nUse /branch <n> to switch.\n");
+                return CommandResult::Continue;
+            };
+
+            // Accept a list number or a message ID prefix
+            let message_id = match target.parse::<usize>() {
+                Ok(n) if n >= 1 && n <= branches.len() => branches[n - 1].leaf_id.clone(),
+                _ => target.to_string(),
+            };
+            match agent.switch_branch(&message_id) {
+                Ok(()) => {
+                    println!(
+                        "\nSwitched branch ({} messages).\n",
+                        agent.raw_session_messages().len()
+                    );
+                    CommandResult::Continue
+                }
+                Err(e) => CommandResult::Error(format!("Failed to switch branch: {}", e)),
+            }
+        }
+
+        "/pin" => {
+            let note = input.strip_prefix("/pin").unwrap_or("").trim();
+            match agent.pin((!note.is_empty()).then_some(note)) {
+                Ok(pin) => {
+                    let preview: String = pin.content.chars().take(60).collect();
+                    println!("\nPinned: {}\n", preview);
+                    CommandResult::Continue
+                }
+                Err(e) => CommandResult::Error(format!("Failed to pin: {}", e)),
+            }
+        }
+
+        "/unpin" => {
+            let pins = agent.pinned_messages().to_vec();
+            let Some(target) = parts.get(1) else {
+                if pins.is_empty() {
+                    println!("\nNothing pinned. Use /pin [text] to pin something.\n");
+                } else {
+                    println!("\nPinned:");
+                    for (i, pin) in pins.iter().enumerate() {
+                        let preview: String = pin.content.chars().take(60).collect();
+                        println!("  {}. {}", i + 1, preview);
+                    }
+                    println!("\nUse /unpin <n> to remove one.\n");
+                }
+                return CommandResult::Continue;
+            };
+            match target.parse::<usize>() {
+                Ok(n) if n >= 1 && n <= pins.len() => match agent.unpin(&pins[n - 1].id) {
+                    Ok(()) => {
+                        println!("\nUnpinned.\n");
+                        CommandResult::Continue
+                    }
+                    Err(e) => CommandResult::Error(format!("Failed to unpin: {}", e)),
+                },
+                _ => CommandResult::Error("Usage: /unpin <n> (see /unpin for the list)".into()),
+            }
+        }
+
+        "/instructions" => {
+            let text = input.strip_prefix("/instructions").unwrap_or("").trim();
+            match text {
+                "" => match agent.session_instructions() {
+                    Some(instructions) => println!("\nSession instructions:\n{}\n", instructions),
+                    None => println!(
+                        "\nNo session instructions. Use /instructions <text> to set them.\n"
+                    ),
+                },
+                "clear" => {
+                    agent.set_session_instructions(None);
+                    println!("\nSession instructions cleared.\n");
+                }
+                _ => {
+                    agent.set_session_instructions(Some(text.to_string()));
+                    println!("\nSession instructions set.\n");
+                }
+            }
+            CommandResult::Continue
+        }
+
         "/clear" => {
             agent.clear_session();
             println!("\nSession cleared.\n");
@@ -810,6 +1034,9 @@ async fn handle_command(
             println!("\nSession Status:");
             println!("  ID: {}", status.id);
             println!("  Model: {}", agent.model());
+            if agent.reasoning().is_enabled() {
+                println!("  Reasoning: {}", agent.reasoning());
+            }
             println!("  Messages: {}", status.message_count);
             println!("  Context tokens: ~{}", status.token_count);
             println!("  Compactions: {}", status.compaction_count);
@@ -867,6 +1094,18 @@ async fn handle_command(
             println!("  Usable: {} tokens", usable);
             println!("  Total: {} tokens", total);
             println!("  Reserve: {} tokens", total - usable);
+            let tokenizer = agent.tokenizer_info();
+            if tokenizer.samples > 0 {
+                println!(
+                    "  Tokenizer: {} (calibrated ×{:.2} from {} response(s))",
+                    tokenizer.name, tokenizer.calibration, tokenizer.samples
+                );
+            } else {
+                println!(
+                    "  Tokenizer: {} (estimate ×{:.2}, not yet reconciled)",
+                    tokenizer.name, tokenizer.calibration
+                );
+            }
 
             if pct > 80.0 {
                 println!("\n⚠ Context nearly full. Consider /compact or /new.");
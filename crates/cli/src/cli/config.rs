@@ -119,7 +119,7 @@ const DEFAULT_CONFIG_TEMPLATE: &str = r#"# LocalGPT Configuration
 [agent]
 # Default model: claude-cli/opus, anthropic/claude-sonnet-4-5, openai/gpt-4o, etc.
 default_model = "claude-cli/opus"
-context_window = 128000
+# context_window = 128000  # Override the model's context window (default: from model catalog)
 reserve_tokens = 8000
 
 # Anthropic API (for anthropic/* models)
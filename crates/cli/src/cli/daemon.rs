@@ -183,6 +183,9 @@ async fn run_daemon_services(
     // Create shared turn gate for heartbeat + HTTP concurrency control
     let turn_gate = TurnGate::new();
 
+    // Shared so tool approvals requested in Telegram can be answered over HTTP too
+    let approvals = localgpt_core::agent::ApprovalBroker::from_config(&config.tools);
+
     // Collect all running JoinHandles
     let mut handles = JoinSet::new();
 
@@ -275,6 +278,7 @@ async fn run_daemon_services(
     if config.telegram.as_ref().is_some_and(|t| t.enabled) {
         let tg_config = config.clone();
         let tg_gate = turn_gate.clone();
+        let tg_approvals = approvals.clone();
         println!("  Telegram: enabled");
         handles.spawn(async move {
             // Create tool factory that provides CLI tools to Telegram
@@ -286,6 +290,7 @@ async fn run_daemon_services(
             let bot = localgpt_server::telegram::run_telegram_bot(
                 &tg_config,
                 tg_gate,
+                tg_approvals,
                 Some(tool_factory),
             );
             tracing::info!("Telegram bot created");
@@ -327,12 +332,18 @@ async fn run_daemon_services(
         let server_config = config.clone();
         let server_gate = turn_gate.clone();
         let server_bridge_manager = bridge_manager.clone();
+        let server_approvals = approvals.clone();
         println!(
             "  Server: http://{}:{}",
             server_config.server.bind, server_config.server.port
         );
         handles.spawn(async move {
-            match Server::new_daemon(&server_config, server_gate, server_bridge_manager) {
+            match Server::new_daemon(
+                &server_config,
+                server_gate,
+                server_bridge_manager,
+                server_approvals,
+            ) {
                 Err(e) => {
                     tracing::error!("Failed to create HTTP server: {}", e);
                 }
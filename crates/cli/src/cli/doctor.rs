@@ -111,6 +111,15 @@ pub async fn run(args: DoctorArgs) -> Result<()> {
     // Check #12: Server port available (if enabled)
     results.push(check_server_port(config.as_ref()).await);
 
+    // Check #13: Provider circuit breakers closed
+    results.push(check_provider_health());
+
+    // Check #14: Azure OpenAI endpoint and credentials (if configured)
+    results.push(check_azure_openai(config.as_ref()));
+
+    // Check #15: AWS Bedrock credentials and region (if configured)
+    results.push(check_bedrock(config.as_ref()));
+
     // Output results
     if args.json {
         println!("{}", serde_json::to_string_pretty(&results)?);
@@ -316,10 +325,19 @@ async fn check_memory_database(
         Ok(mm) => {
             // Get stats
             match mm.stats() {
-                Ok(stats) => CheckResult::pass(
-                    "Memory database",
-                    format!("Memory database OK ({} chunks indexed)", stats.total_chunks),
-                ),
+                Ok(stats) => {
+                    let vector_search = match stats.sqlite_vec_version {
+                        Some(version) => format!("sqlite-vec {}", version),
+                        None => "in-memory scan".to_string(),
+                    };
+                    CheckResult::pass(
+                        "Memory database",
+                        format!(
+                            "Memory database OK ({} chunks indexed, vector search: {})",
+                            stats.total_chunks, vector_search
+                        ),
+                    )
+                }
                 Err(_) => CheckResult::pass("Memory database", "Memory database OK"),
             }
         }
@@ -741,3 +759,144 @@ async fn check_server_port(config: Option<&localgpt_core::config::Config>) -> Ch
         ),
     }
 }
+
+/// Check #13: No provider circuit breaker open (from persisted failover state)
+fn check_provider_health() -> CheckResult {
+    use localgpt_core::agent::failover::{CircuitState, ProviderHealthRegistry};
+
+    let paths = match localgpt_core::paths::Paths::resolve() {
+        Ok(p) => p,
+        Err(_) => return CheckResult::pass("Provider health", "Cannot check provider health"),
+    };
+
+    let health_file = paths.provider_health();
+    if !health_file.exists() {
+        return CheckResult::pass("Provider health", "No provider failures recorded");
+    }
+
+    let health = ProviderHealthRegistry::load(health_file).snapshot();
+    let unhealthy: Vec<String> = health
+        .iter()
+        .filter(|h| h.state != CircuitState::Closed)
+        .map(|h| match h.retry_in_secs {
+            Some(secs) => format!(
+                "{} {} after {} failure(s), retry in {}s",
+                h.provider, h.state, h.consecutive_failures, secs
+            ),
+            None => format!(
+                "{} {} after {} failure(s)",
+                h.provider, h.state, h.consecutive_failures
+            ),
+        })
+        .collect();
+
+    if unhealthy.is_empty() {
+        CheckResult::pass(
+            "Provider health",
+            format!("{} provider circuit(s) closed", health.len()),
+        )
+    } else {
+        let last_error = health
+            .iter()
+            .filter(|h| h.state != CircuitState::Closed)
+            .find_map(|h| h.last_error.clone())
+            .unwrap_or_default();
+        CheckResult::warn(
+            "Provider health",
+            format!("Provider circuit open: {}", unhealthy.join("; ")),
+            format!(
+                "Requests fail over to the next provider until the cooldown expires. Last error: {}",
+                last_error
+            ),
+        )
+    }
+}
+
+/// Whether a config value is set, treating unexpanded `${VAR}` placeholders as unset
+fn is_set(value: Option<&String>) -> bool {
+    value.is_some_and(|v| !v.is_empty() && !v.starts_with("${"))
+}
+
+/// Check #14: Azure OpenAI endpoint and credentials (if configured)
+fn check_azure_openai(config: Option<&localgpt_core::config::Config>) -> CheckResult {
+    let azure = match config.and_then(|c| c.providers.azure_openai.as_ref()) {
+        Some(a) => a,
+        None => return CheckResult::pass("Azure OpenAI", "Azure OpenAI not configured"),
+    };
+
+    if !azure.endpoint.starts_with("https://") && !azure.endpoint.starts_with("http://") {
+        return CheckResult::fail(
+            "Azure OpenAI",
+            format!("Invalid Azure OpenAI endpoint: '{}'", azure.endpoint),
+            "Set providers.azure_openai.endpoint to https://<resource>.openai.azure.com",
+        );
+    }
+
+    if !is_set(azure.api_key.as_ref()) && !is_set(azure.ad_token.as_ref()) {
+        return CheckResult::fail(
+            "Azure OpenAI",
+            "Azure OpenAI configured without api_key or ad_token",
+            "Set AZURE_OPENAI_API_KEY or providers.azure_openai.ad_token",
+        );
+    }
+
+    CheckResult::pass(
+        "Azure OpenAI",
+        format!(
+            "Azure OpenAI configured ({}, api-version {}, {} deployment mapping(s))",
+            azure.endpoint,
+            azure.api_version,
+            azure.deployments.len()
+        ),
+    )
+}
+
+/// Check #15: AWS Bedrock credentials and region (if configured or the default model)
+fn check_bedrock(config: Option<&localgpt_core::config::Config>) -> CheckResult {
+    use localgpt_core::agent::bedrock::{AwsCredentials, resolve_region};
+
+    let config = match config {
+        Some(c) => c,
+        None => return CheckResult::pass("AWS Bedrock", "AWS Bedrock not configured"),
+    };
+    let uses_bedrock = config.agent.default_model.starts_with("bedrock/");
+    let bedrock = match (&config.providers.bedrock, uses_bedrock) {
+        (Some(b), _) => b.clone(),
+        (None, true) => Default::default(),
+        (None, false) => return CheckResult::pass("AWS Bedrock", "AWS Bedrock not configured"),
+    };
+
+    let credentials = match AwsCredentials::resolve(&bedrock) {
+        Ok(c) => c,
+        Err(e) => {
+            return CheckResult::fail(
+                "AWS Bedrock",
+                "AWS credentials not found",
+                e.to_string().replace('\n', " "),
+            );
+        }
+    };
+    let region = match resolve_region(&bedrock) {
+        Ok(r) => r,
+        Err(e) => {
+            return CheckResult::fail(
+                "AWS Bedrock",
+                "AWS region not set",
+                e.to_string().replace('\n', " "),
+            );
+        }
+    };
+
+    CheckResult::pass(
+        "AWS Bedrock",
+        format!(
+            "AWS Bedrock configured (region {}, {} credentials)",
+            region,
+            if credentials.session_token.is_some() {
+                "temporary"
+            } else {
+                "long-term"
+            }
+        ),
+    )
+}
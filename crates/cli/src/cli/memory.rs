@@ -1,8 +1,9 @@
 use anyhow::Result;
+use chrono::NaiveDate;
 use clap::{Args, Subcommand};
 
 use localgpt_core::config::Config;
-use localgpt_core::memory::MemoryManager;
+use localgpt_core::memory::{MemoryManager, MemorySource, SearchFilter, parse_filter_date};
 
 #[derive(Args)]
 pub struct MemoryArgs {
@@ -20,6 +21,23 @@ pub enum MemoryCommands {
         /// Maximum number of results
         #[arg(short, long, default_value = "10")]
         limit: usize,
+
+        /// Only search paths with this prefix or matching this glob, e.g.
+        /// knowledge/finance/** (repeatable)
+        #[arg(long = "path", value_name = "PATH")]
+        paths: Vec<String>,
+
+        /// Only files dated on or after this day (YYYY-MM-DD)
+        #[arg(long, value_parser = parse_filter_date)]
+        after: Option<NaiveDate>,
+
+        /// Only files dated on or before this day (YYYY-MM-DD)
+        #[arg(long, value_parser = parse_filter_date)]
+        before: Option<NaiveDate>,
+
+        /// Only files from this source: memory, session or external (repeatable)
+        #[arg(long = "source", value_name = "SOURCE")]
+        sources: Vec<MemorySource>,
     },
 
     /// Reindex all memory files
@@ -45,15 +63,35 @@ pub async fn run(args: MemoryArgs, agent_id: &str) -> Result<()> {
     let memory = MemoryManager::new_with_full_config(&config.memory, Some(&config), agent_id)?;
 
     match args.command {
-        MemoryCommands::Search { query, limit } => search_memory(&memory, &query, limit).await,
+        MemoryCommands::Search {
+            query,
+            limit,
+            paths,
+            after,
+            before,
+            sources,
+        } => {
+            let filter = SearchFilter {
+                paths,
+                after,
+                before,
+                sources,
+            };
+            search_memory(&memory, &query, limit, &filter).await
+        }
         MemoryCommands::Reindex { force } => reindex_memory(&memory, force).await,
         MemoryCommands::Stats => show_stats(&memory).await,
         MemoryCommands::Recent { count } => show_recent(&memory, count).await,
     }
 }
 
-async fn search_memory(memory: &MemoryManager, query: &str, limit: usize) -> Result<()> {
-    let results = memory.search(query, limit)?;
+async fn search_memory(
+    memory: &MemoryManager,
+    query: &str,
+    limit: usize,
+    filter: &SearchFilter,
+) -> Result<()> {
+    let results = memory.search_filtered(query, limit, filter)?;
 
     if results.is_empty() {
         println!("No results found for '{}'", query);
@@ -70,6 +108,9 @@ async fn search_memory(memory: &MemoryManager, query: &str, limit: usize) -> Res
             result.line_start,
             result.line_end
         );
+        if !result.breadcrumb.is_empty() {
+            println!("   Section: {}", result.breadcrumb);
+        }
         println!("   Score: {:.3}", result.score);
 
         // Show preview (first 200 chars)
@@ -127,6 +168,34 @@ async fn show_stats(memory: &MemoryManager) -> Result<()> {
     println!("Total files: {}", stats.total_files);
     println!("Total chunks: {}", stats.total_chunks);
     println!("Index size: {} KB", stats.index_size_kb);
+    match &stats.sqlite_vec_version {
+        Some(version) => println!("Vector search: sqlite-vec {}", version),
+        None => println!("Vector search: in-memory scan (built without sqlite-vec)"),
+    }
+
+    if !stats.embeddings.is_empty() {
+        println!("\nEmbeddings:");
+        for model in &stats.embeddings {
+            let coverage = (model.embedded_chunks * 100)
+                .checked_div(stats.total_chunks)
+                .unwrap_or(0);
+            let active = if stats.embedding_model.as_deref() == Some(model.model.as_str()) {
+                " (active)"
+            } else {
+                ""
+            };
+            println!(
+                "  {}{}: {}/{} chunks ({}%), {} dims",
+                model.model,
+                active,
+                model.embedded_chunks,
+                stats.total_chunks,
+                coverage,
+                model.dims
+            );
+        }
+    }
+
     println!("\nFiles:");
     for file in &stats.files {
         println!(
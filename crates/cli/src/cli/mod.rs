@@ -15,6 +15,7 @@ pub mod memory;
 pub mod paths;
 pub mod sandbox;
 pub mod search;
+pub mod usage;
 
 use clap::{Parser, Subcommand};
 
@@ -86,6 +87,9 @@ pub enum Commands {
     /// Test and manage web search
     Search(search::SearchArgs),
 
+    /// Report token usage and spend from the usage ledger
+    Usage(usage::UsageArgs),
+
     /// Authenticate with providers (Gemini, etc.)
     Auth(auth::AuthArgs),
 
@@ -0,0 +1,188 @@
+use anyhow::Result;
+use chrono::{Datelike, Duration, Local, NaiveDate, TimeZone, Utc};
+use clap::{Args, ValueEnum};
+
+use localgpt_core::agent::{BudgetPeriod, UsageGroup, UsageLedger, UsageSummary, summarize};
+use localgpt_core::config::Config;
+
+#[derive(Args)]
+pub struct UsageArgs {
+    /// Time period to report on
+    #[arg(long, value_enum, default_value_t = Period::Month)]
+    pub period: Period,
+
+    /// Group totals by
+    #[arg(long, value_enum, default_value_t = GroupBy::Model)]
+    pub by: GroupBy,
+
+    /// Only include usage from this agent
+    #[arg(long = "only-agent")]
+    pub only_agent: Option<String>,
+
+    /// Print JSON instead of a table
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Period {
+    /// Since local midnight
+    Today,
+    /// The last 7 days
+    Week,
+    /// The current calendar month
+    Month,
+    /// The current calendar year
+    Year,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GroupBy {
+    Agent,
+    Channel,
+    Provider,
+    Model,
+    Day,
+}
+
+impl From<GroupBy> for UsageGroup {
+    fn from(by: GroupBy) -> Self {
+        match by {
+            GroupBy::Agent => UsageGroup::Agent,
+            GroupBy::Channel => UsageGroup::Channel,
+            GroupBy::Provider => UsageGroup::Provider,
+            GroupBy::Model => UsageGroup::Model,
+            GroupBy::Day => UsageGroup::Day,
+        }
+    }
+}
+
+pub fn run(args: UsageArgs) -> Result<()> {
+    let config = Config::load()?;
+    let ledger = UsageLedger::new(config.paths.usage_dir());
+    let now = Local::now();
+
+    let (since, label) = match args.period {
+        Period::Today => (BudgetPeriod::Day.start(now), "today"),
+        Period::Week => (
+            BudgetPeriod::Day.start(now - Duration::days(6)),
+            "in the last 7 days",
+        ),
+        Period::Month => (BudgetPeriod::Month.start(now), "this month"),
+        Period::Year => {
+            let jan1 = NaiveDate::from_ymd_opt(now.year(), 1, 1)
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .unwrap_or_default();
+            let since = Local
+                .from_local_datetime(&jan1)
+                .earliest()
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_else(|| Utc.from_utc_datetime(&jan1));
+            (since, "this year")
+        }
+    };
+
+    let mut entries = ledger.entries(since, Utc::now() + Duration::minutes(1))?;
+    if let Some(agent) = &args.only_agent {
+        entries.retain(|e| &e.agent_id == agent);
+    }
+    let rows = summarize(&entries, args.by.into());
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+
+    if rows.is_empty() {
+        println!("No usage recorded {}.", label);
+        return Ok(());
+    }
+
+    let heading = match args.by {
+        GroupBy::Agent => "AGENT",
+        GroupBy::Channel => "CHANNEL",
+        GroupBy::Provider => "PROVIDER",
+        GroupBy::Model => "MODEL",
+        GroupBy::Day => "DAY",
+    };
+    let width = rows
+        .iter()
+        .map(|r| r.key.len())
+        .max()
+        .unwrap_or(0)
+        .max(heading.len());
+
+    println!(
+        "Usage {} (since {}):\n",
+        label,
+        since.with_timezone(&Local).format("%Y-%m-%d")
+    );
+    println!(
+        "  {:<width$}  {:>8}  {:>12}  {:>12}  {:>10}  {:>10}",
+        heading, "REQUESTS", "INPUT", "CACHED", "OUTPUT", "COST"
+    );
+    for row in &rows {
+        print_row(row, width);
+    }
+
+    let total = rows.iter().fold(
+        UsageSummary {
+            key: "Total".to_string(),
+            ..Default::default()
+        },
+        |mut acc, r| {
+            acc.requests += r.requests;
+            acc.input_tokens += r.input_tokens;
+            acc.output_tokens += r.output_tokens;
+            acc.cache_read_tokens += r.cache_read_tokens;
+            acc.cost_usd += r.cost_usd;
+            acc
+        },
+    );
+    if rows.len() > 1 {
+        print_row(&total, width);
+    }
+
+    print_budget(&config, &ledger, now)?;
+    Ok(())
+}
+
+fn print_row(row: &UsageSummary, width: usize) {
+    println!(
+        "  {:<width$}  {:>8}  {:>12}  {:>12}  {:>10}  {:>10}",
+        row.key,
+        row.requests,
+        row.input_tokens,
+        row.cache_read_tokens,
+        row.output_tokens,
+        format!("${:.3}", row.cost_usd)
+    );
+}
+
+fn print_budget(config: &Config, ledger: &UsageLedger, now: chrono::DateTime<Local>) -> Result<()> {
+    let budget = &config.budget;
+    if budget.daily_usd.is_none() && budget.monthly_usd.is_none() {
+        return Ok(());
+    }
+
+    let month = ledger.entries(
+        BudgetPeriod::Month.start(now),
+        Utc::now() + Duration::minutes(1),
+    )?;
+    let day_start = BudgetPeriod::Day.start(now);
+    let spent_today: f64 = month
+        .iter()
+        .filter(|e| e.timestamp >= day_start)
+        .map(|e| e.cost_usd)
+        .sum();
+    let spent_month: f64 = month.iter().map(|e| e.cost_usd).sum();
+
+    println!();
+    if let Some(limit) = budget.daily_usd {
+        println!("Daily budget:   ${:.2} of ${:.2}", spent_today, limit);
+    }
+    if let Some(limit) = budget.monthly_usd {
+        println!("Monthly budget: ${:.2} of ${:.2}", spent_month, limit);
+    }
+    Ok(())
+}
@@ -13,6 +13,8 @@ pub enum UiMessage {
     ResumeSession(String),
     /// Approve pending tool calls
     ApproveTools(Vec<ToolCall>),
+    /// Approve pending tool calls and stop asking for these tools this session
+    AlwaysAllowTools(Vec<ToolCall>),
     /// Deny pending tool calls
     DenyTools,
     /// Request session list refresh
@@ -198,8 +200,8 @@ impl UiState {
                 }
             }
             WorkerMessage::ToolsPendingApproval(calls) => {
+                // Stays loading: the turn resumes once the user decides
                 self.pending_approval = Some(calls);
-                self.is_loading = false;
             }
             WorkerMessage::Done => {
                 // Finalize streaming content as assistant message
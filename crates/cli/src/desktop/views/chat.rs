@@ -1,6 +1,7 @@
 //! Chat view - message display and input
 
 use eframe::egui::{self, Color32, RichText, ScrollArea, TextEdit, Ui};
+use localgpt_core::agent::extract_tool_detail;
 
 use crate::desktop::state::{ChatMessage, MessageRole, Panel, ToolStatus, UiMessage, UiState};
 
@@ -70,13 +71,20 @@ impl ChatView {
                     ui.group(|ui| {
                         ui.label(RichText::new("Tools pending approval:").strong());
                         for tool in &tools {
-                            ui.label(format!("  - {}", tool.name));
+                            match extract_tool_detail(&tool.name, &tool.arguments) {
+                                Some(detail) => ui.label(format!("  - {}: {}", tool.name, detail)),
+                                None => ui.label(format!("  - {}", tool.name)),
+                            };
                         }
                         ui.horizontal(|ui| {
                             if ui.button("Approve").clicked() {
                                 message_to_send = Some(UiMessage::ApproveTools(tools.clone()));
                                 state.pending_approval = None;
                             }
+                            if ui.button("Always allow").clicked() {
+                                message_to_send = Some(UiMessage::AlwaysAllowTools(tools.clone()));
+                                state.pending_approval = None;
+                            }
                             if ui.button("Deny").clicked() {
                                 message_to_send = Some(UiMessage::DenyTools);
                                 state.pending_approval = None;
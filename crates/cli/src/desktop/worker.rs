@@ -4,16 +4,16 @@
 //! It receives commands from the UI and sends back status updates.
 
 use std::pin::pin;
-use std::sync::Arc;
 use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 
 use anyhow::Result;
 use futures::StreamExt;
 
 use localgpt_core::agent::{
-    Agent, AgentConfig, DEFAULT_AGENT_ID, StreamEvent, ToolCall, create_spawn_agent_tool,
-    extract_tool_detail, list_sessions_for_agent,
+    Agent, AgentConfig, ApprovalDecision, ApprovalHandler, DEFAULT_AGENT_ID, StreamEvent, ToolCall,
+    create_spawn_agent_tool, extract_tool_detail, list_sessions_for_agent,
 };
 use localgpt_core::config::Config;
 use localgpt_core::memory::MemoryManager;
@@ -71,11 +71,53 @@ impl WorkerHandle {
     }
 }
 
+/// Asks the UI about tool calls that need approval, while the agent waits.
+///
+/// The worker runs a current-thread runtime and the agent is suspended in the
+/// tool call, so blocking on the UI channel here is fine.
+struct DesktopApproval {
+    rx: Arc<Mutex<Receiver<UiMessage>>>,
+    tx: Sender<WorkerMessage>,
+}
+
+#[async_trait::async_trait]
+impl ApprovalHandler for DesktopApproval {
+    async fn decide(&self, call: &ToolCall) -> ApprovalDecision {
+        if self
+            .tx
+            .send(WorkerMessage::ToolsPendingApproval(vec![call.clone()]))
+            .is_err()
+        {
+            return ApprovalDecision::Deny;
+        }
+
+        let Ok(rx) = self.rx.lock() else {
+            return ApprovalDecision::Deny;
+        };
+        while let Ok(msg) = rx.recv() {
+            match msg {
+                UiMessage::ApproveTools(calls) if calls.iter().any(|c| c.id == call.id) => {
+                    return ApprovalDecision::Approve;
+                }
+                UiMessage::AlwaysAllowTools(calls) if calls.iter().any(|c| c.id == call.id) => {
+                    return ApprovalDecision::AlwaysAllow;
+                }
+                UiMessage::DenyTools => return ApprovalDecision::Deny,
+                // Stale decisions for other calls, or input sent while waiting
+                _ => {}
+            }
+        }
+        ApprovalDecision::Deny
+    }
+}
+
 async fn worker_loop(
     agent_id: String,
     rx: Receiver<UiMessage>,
     tx: Sender<WorkerMessage>,
 ) -> Result<()> {
+    let rx = Arc::new(Mutex::new(rx));
+
     // Initialize agent
     let config = Config::load()?;
     let memory = Arc::new(MemoryManager::new_with_full_config(
@@ -91,6 +133,11 @@ async fn worker_loop(
     };
 
     let mut agent = Agent::new(agent_config, &config, Arc::clone(&memory)).await?;
+    agent.set_usage_scope(&agent_id, "desktop");
+    agent.set_approval_handler(Arc::new(DesktopApproval {
+        rx: Arc::clone(&rx),
+        tx: tx.clone(),
+    }));
     agent.extend_tools(crate::tools::create_cli_tools(&config)?);
     agent.extend_tools(vec![create_spawn_agent_tool(config.clone(), memory)]);
     agent.new_session().await?;
@@ -118,11 +165,12 @@ async fn worker_loop(
         ));
     }
 
-    // Track tools requiring approval
-    let approval_tools: Vec<String> = agent.approval_required_tools().to_vec();
-
     // Main loop
-    while let Ok(msg) = rx.recv() {
+    loop {
+        // The lock is released before handling: DesktopApproval takes it mid-turn
+        let Ok(msg) = rx.lock().expect("UI channel lock poisoned").recv() else {
+            break;
+        };
         let mut should_auto_save = false;
 
         match msg {
@@ -131,7 +179,6 @@ async fn worker_loop(
                 match agent.chat_stream_with_tools(&message, Vec::new()).await {
                     Ok(stream) => {
                         let mut stream = pin!(stream);
-                        let mut pending_tools: Vec<ToolCall> = Vec::new();
 
                         while let Some(result) = stream.next().await {
                             match result {
@@ -139,27 +186,18 @@ async fn worker_loop(
                                     StreamEvent::Content(text) => {
                                         let _ = tx.send(WorkerMessage::ContentChunk(text));
                                     }
+                                    StreamEvent::Thinking(_) => {}
                                     StreamEvent::ToolCallStart {
                                         name,
                                         id,
                                         arguments,
                                     } => {
-                                        // Check if this tool requires approval
-                                        if approval_tools.contains(&name) {
-                                            // Collect for approval
-                                            pending_tools.push(ToolCall {
-                                                id,
-                                                name,
-                                                arguments: String::new(),
-                                            });
-                                        } else {
-                                            let detail = extract_tool_detail(&name, &arguments);
-                                            let _ = tx.send(WorkerMessage::ToolCallStart {
-                                                name,
-                                                id,
-                                                detail,
-                                            });
-                                        }
+                                        let detail = extract_tool_detail(&name, &arguments);
+                                        let _ = tx.send(WorkerMessage::ToolCallStart {
+                                            name,
+                                            id,
+                                            detail,
+                                        });
                                     }
                                     StreamEvent::ToolCallEnd {
                                         name,
@@ -175,14 +213,7 @@ async fn worker_loop(
                                         });
                                     }
                                     StreamEvent::Done => {
-                                        if !pending_tools.is_empty() {
-                                            let _ = tx.send(WorkerMessage::ToolsPendingApproval(
-                                                pending_tools.clone(),
-                                            ));
-                                            pending_tools.clear();
-                                        } else {
-                                            let _ = tx.send(WorkerMessage::Done);
-                                        }
+                                        let _ = tx.send(WorkerMessage::Done);
                                         should_auto_save = true;
                                     }
                                 },
@@ -224,13 +255,9 @@ async fn worker_loop(
                     let _ = tx.send(WorkerMessage::Error(e.to_string()));
                 }
             },
-            UiMessage::ApproveTools(_tools) => {
-                // Tool approval is handled in chat loop
-                // For now, just send done
-                let _ = tx.send(WorkerMessage::Done);
-            }
-            UiMessage::DenyTools => {
-                let _ = tx.send(WorkerMessage::Done);
+            UiMessage::ApproveTools(_) | UiMessage::AlwaysAllowTools(_) | UiMessage::DenyTools => {
+                // Decisions are consumed by DesktopApproval while a turn waits;
+                // one arriving here has nothing left to decide
             }
             UiMessage::RefreshSessions => {
                 if let Ok(sessions) = list_sessions_for_agent(&agent_id) {
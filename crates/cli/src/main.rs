@@ -102,6 +102,7 @@ async fn async_main(cli: Cli) -> Result<()> {
         Commands::Md(args) => crate::cli::md::run(args).await,
         Commands::Sandbox(args) => crate::cli::sandbox::run(args).await,
         Commands::Search(args) => crate::cli::search::run(args).await,
+        Commands::Usage(args) => crate::cli::usage::run(args),
         Commands::Auth(args) => crate::cli::auth::run(args).await,
         Commands::Init(args) => crate::cli::init::run(args),
         Commands::Bridge(args) => crate::cli::bridge::run(args).await,
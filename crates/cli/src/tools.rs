@@ -162,6 +162,11 @@ impl Tool for BashTool {
         "bash"
     }
 
+    // Commands share the workspace and may depend on each other's effects
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "bash".to_string(),
@@ -238,16 +243,14 @@ impl Tool for BashTool {
         }
 
         // Fallback: run command directly without sandbox
-        let timeout_duration = std::time::Duration::from_millis(timeout_ms);
-        let output = tokio::time::timeout(
-            timeout_duration,
-            tokio::process::Command::new("bash")
-                .arg("-c")
-                .arg(command)
-                .output(),
+        let mut child = tokio::process::Command::new("bash");
+        child.arg("-c").arg(command);
+        let output = localgpt_sandbox::output_with_timeout(
+            child,
+            std::time::Duration::from_millis(timeout_ms),
         )
-        .await
-        .map_err(|_| anyhow::anyhow!("Command timed out after {}ms", timeout_ms))??;
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Command timed out after {}ms", timeout_ms))?;
 
         let stdout = String::from_utf8_lossy(&output.stdout);
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -423,6 +426,11 @@ impl Tool for WriteFileTool {
         "write_file"
     }
 
+    // Writes must land in the order the model issued them
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "write_file".to_string(),
@@ -551,6 +559,11 @@ impl Tool for EditFileTool {
         "edit_file"
     }
 
+    // Edits must land in the order the model issued them
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "edit_file".to_string(),
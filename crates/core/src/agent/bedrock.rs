@@ -0,0 +1,1108 @@
+//! AWS Bedrock provider (Converse API).
+//!
+//! Bedrock is called over plain HTTPS rather than through the AWS SDK: requests
+//! are signed with Signature Version 4, and `converse-stream` responses are
+//! decoded from the AWS event-stream binary framing.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use super::provider_error::ProviderError;
+use super::providers::{
+    LLMProvider, LLMResponse, LLMResponseContent, Message, Role, StreamChunk, StreamResult,
+    ThinkingBlock, ToolCall, ToolSchema, Usage, apply_anthropic_thinking,
+};
+use crate::config::{BedrockConfig, ReasoningEffort};
+
+/// SigV4 service name for the Bedrock runtime
+const SIGNING_SERVICE: &str = "bedrock";
+
+/// AWS credentials used to sign requests
+#[derive(Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    /// Credentials from `[providers.bedrock]`, falling back to the standard AWS
+    /// environment variables
+    pub fn resolve(config: &BedrockConfig) -> Result<Self> {
+        let access_key_id = config_or_env(&config.access_key_id, &["AWS_ACCESS_KEY_ID"]);
+        let secret_access_key =
+            config_or_env(&config.secret_access_key, &["AWS_SECRET_ACCESS_KEY"]);
+
+        match (access_key_id, secret_access_key) {
+            (Some(access_key_id), Some(secret_access_key)) => Ok(Self {
+                access_key_id,
+                secret_access_key,
+                session_token: config_or_env(&config.session_token, &["AWS_SESSION_TOKEN"]),
+            }),
+            _ => anyhow::bail!(
+                "AWS credentials not found.\n\
+                Set AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY, or add access_key_id and \
+                secret_access_key to [providers.bedrock]"
+            ),
+        }
+    }
+}
+
+/// Region from `[providers.bedrock]`, falling back to AWS_REGION and AWS_DEFAULT_REGION
+pub fn resolve_region(config: &BedrockConfig) -> Result<String> {
+    config_or_env(&config.region, &["AWS_REGION", "AWS_DEFAULT_REGION"]).ok_or_else(|| {
+        anyhow::anyhow!(
+            "AWS region not set.\n\
+            Set AWS_REGION or add region = \"us-east-1\" to [providers.bedrock]"
+        )
+    })
+}
+
+/// A configured value, or the first set environment variable. Unexpanded
+/// `${VAR}` placeholders count as unset.
+fn config_or_env(value: &Option<String>, vars: &[&str]) -> Option<String> {
+    value
+        .clone()
+        .filter(|v| !v.is_empty() && !v.starts_with("${"))
+        .or_else(|| {
+            vars.iter()
+                .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        })
+}
+
+// SigV4 signing
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// RFC 3986 percent-encoding as SigV4 specifies it: everything but unreserved
+/// characters is encoded, with uppercase hex digits
+fn uri_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// The parts of a request covered by its signature
+struct SignableRequest<'a> {
+    method: &'a str,
+    url: &'a reqwest::Url,
+    /// Headers to sign besides `host`
+    headers: &'a [(&'a str, &'a str)],
+    payload: &'a [u8],
+}
+
+/// Headers that sign a request with AWS Signature Version 4: `x-amz-date`,
+/// `x-amz-security-token` for temporary credentials, and `authorization`.
+///
+/// Services other than S3 sign each path segment encoded a second time, so a model id sent as
+/// `anthropic.claude-v2%3A1` is signed as `anthropic.claude-v2%253A1`.
+fn sigv4_headers(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    request: &SignableRequest<'_>,
+    now: DateTime<Utc>,
+) -> Vec<(&'static str, String)> {
+    let url = request.url;
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = &amz_date[..8];
+
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+    let mut signed: Vec<(String, String)> = request
+        .headers
+        .iter()
+        .map(|(name, value)| (name.to_lowercase(), value.trim().to_string()))
+        .collect();
+    signed.push(("host".to_string(), host));
+    signed.push(("x-amz-date".to_string(), amz_date.clone()));
+    if let Some(ref token) = credentials.session_token {
+        signed.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    signed.sort();
+
+    let canonical_headers: String = signed
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    let signed_headers = signed
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_uri = url
+        .path()
+        .split('/')
+        .map(uri_encode)
+        .collect::<Vec<_>>()
+        .join("/");
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (uri_encode(&k), uri_encode(&v)))
+        .collect();
+    query.sort();
+    let canonical_query = query
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        request.method,
+        canonical_uri,
+        canonical_query,
+        canonical_headers,
+        signed_headers,
+        hex(&Sha256::digest(request.payload))
+    );
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let mut key = hmac_sha256(
+        format!("AWS4{}", credentials.secret_access_key).as_bytes(),
+        date.as_bytes(),
+    );
+    for part in [region, service, "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    let mut out = vec![("x-amz-date", amz_date)];
+    if let Some(ref token) = credentials.session_token {
+        out.push(("x-amz-security-token", token.clone()));
+    }
+    out.push((
+        "authorization",
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key_id, scope, signed_headers, signature
+        ),
+    ));
+    out
+}
+
+// AWS event-stream decoding
+
+/// One message of an `application/vnd.amazon.eventstream` response
+struct EventMessage {
+    /// String-valued headers (`:event-type`, `:message-type`, ...)
+    headers: Vec<(String, String)>,
+    payload: Vec<u8>,
+}
+
+impl EventMessage {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Incremental decoder for the AWS event-stream framing.
+///
+/// A message is `total_len | headers_len | prelude_crc | headers | payload |
+/// message_crc`, with big-endian u32 lengths. The CRCs are not checked; the
+/// stream already arrives over TLS.
+#[derive(Default)]
+struct EventStreamDecoder {
+    buffer: Vec<u8>,
+}
+
+impl EventStreamDecoder {
+    const PRELUDE_LEN: usize = 12;
+    const CRC_LEN: usize = 4;
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// The next complete message, or `None` until more bytes arrive
+    fn next_message(&mut self) -> Result<Option<EventMessage>> {
+        if self.buffer.len() < Self::PRELUDE_LEN {
+            return Ok(None);
+        }
+        let total_len = u32::from_be_bytes(self.buffer[0..4].try_into()?) as usize;
+        let headers_len = u32::from_be_bytes(self.buffer[4..8].try_into()?) as usize;
+        if total_len < Self::PRELUDE_LEN + headers_len + Self::CRC_LEN {
+            anyhow::bail!("Malformed event-stream message ({} bytes)", total_len);
+        }
+        if self.buffer.len() < total_len {
+            return Ok(None);
+        }
+
+        let frame: Vec<u8> = self.buffer.drain(..total_len).collect();
+        let headers_end = Self::PRELUDE_LEN + headers_len;
+        Ok(Some(EventMessage {
+            headers: parse_event_headers(&frame[Self::PRELUDE_LEN..headers_end])?,
+            payload: frame[headers_end..total_len - Self::CRC_LEN].to_vec(),
+        }))
+    }
+}
+
+/// Decode event-stream headers, keeping only string values
+fn parse_event_headers(mut bytes: &[u8]) -> Result<Vec<(String, String)>> {
+    fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+        if bytes.len() < n {
+            anyhow::bail!("Truncated event-stream header");
+        }
+        let (head, rest) = bytes.split_at(n);
+        *bytes = rest;
+        Ok(head)
+    }
+
+    let mut headers = Vec::new();
+    while !bytes.is_empty() {
+        let name_len = take(&mut bytes, 1)?[0] as usize;
+        let name = String::from_utf8_lossy(take(&mut bytes, name_len)?).into_owned();
+        let value_type = take(&mut bytes, 1)?[0];
+        let value_len = match value_type {
+            0 | 1 => 0, // bool true / false
+            2 => 1,     // byte
+            3 => 2,     // short
+            4 => 4,     // int
+            5 | 8 => 8, // long, timestamp
+            9 => 16,    // uuid
+            6 | 7 => u16::from_be_bytes(take(&mut bytes, 2)?.try_into()?) as usize,
+            other => anyhow::bail!("Unknown event-stream header type {}", other),
+        };
+        let value = take(&mut bytes, value_len)?;
+        if value_type == 7 {
+            headers.push((name, String::from_utf8_lossy(value).into_owned()));
+        }
+    }
+    Ok(headers)
+}
+
+/// Classify an exception message sent inside a `converse-stream` response
+fn stream_exception(kind: &str, payload: &Value) -> ProviderError {
+    let provider = "bedrock".to_string();
+    let message = payload["message"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| kind.to_string());
+
+    match kind {
+        "throttlingException" | "serviceQuotaExceededException" => ProviderError::RateLimited {
+            provider,
+            retry_after: None,
+            message,
+        },
+        "accessDeniedException" => ProviderError::Auth { provider, message },
+        "validationException" => ProviderError::from_error_body(
+            &provider,
+            &json!({ "type": "invalid_request", "message": message }),
+        ),
+        _ => ProviderError::Server {
+            provider,
+            status: 500,
+            message,
+        },
+    }
+}
+
+// Bedrock Provider
+
+pub struct BedrockProvider {
+    client: Client,
+    credentials: AwsCredentials,
+    region: String,
+    endpoint: String,
+    model: String,
+    max_tokens: usize,
+    reasoning: ReasoningEffort,
+}
+
+impl BedrockProvider {
+    pub fn new(
+        credentials: AwsCredentials,
+        region: &str,
+        model: &str,
+        max_tokens: usize,
+    ) -> Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            credentials,
+            region: region.to_string(),
+            endpoint: format!("https://bedrock-runtime.{}.amazonaws.com", region),
+            model: model.to_string(),
+            max_tokens,
+            reasoning: ReasoningEffort::default(),
+        })
+    }
+
+    /// Send requests to another runtime endpoint (VPC endpoints, local mocks)
+    pub fn with_endpoint(mut self, endpoint: Option<&str>) -> Self {
+        if let Some(endpoint) = endpoint {
+            self.endpoint = endpoint.trim_end_matches('/').to_string();
+        }
+        self
+    }
+
+    /// Enable extended thinking (Anthropic models only)
+    pub fn with_reasoning(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning = effort;
+        self
+    }
+
+    /// Converse `image.format` for a MIME type
+    fn image_format(media_type: &str) -> &str {
+        match media_type.strip_prefix("image/").unwrap_or(media_type) {
+            "jpg" => "jpeg",
+            other => other,
+        }
+    }
+
+    /// Signed reasoning to send back ahead of a turn's tool calls
+    fn reasoning_blocks(thinking: &[ThinkingBlock]) -> Vec<Value> {
+        thinking
+            .iter()
+            .filter_map(|t| match (&t.redacted, &t.signature) {
+                (Some(data), _) => Some(json!({ "reasoningContent": { "redactedContent": data } })),
+                (None, Some(signature)) => Some(json!({
+                    "reasoningContent": {
+                        "reasoningText": { "text": t.text, "signature": signature }
+                    }
+                })),
+                (None, None) => None,
+            })
+            .collect()
+    }
+
+    /// Split messages into Converse `system` blocks and `messages`.
+    ///
+    /// Converse requires strictly alternating roles, so tool results (sent as
+    /// user turns) are merged with any neighbouring user turn.
+    fn format_messages(messages: &[Message]) -> (Vec<Value>, Vec<Value>) {
+        let mut system = Vec::new();
+        let mut formatted: Vec<Value> = Vec::new();
+
+        for m in messages {
+            let (role, content) = match m.role {
+                Role::System => {
+                    if !m.content.is_empty() {
+                        system.push(json!({ "text": m.content }));
+                    }
+                    continue;
+                }
+                Role::User => {
+                    let mut content: Vec<Value> = m
+                        .images
+                        .iter()
+                        .map(|img| {
+                            json!({
+                                "image": {
+                                    "format": Self::image_format(&img.media_type),
+                                    "source": { "bytes": img.data }
+                                }
+                            })
+                        })
+                        .collect();
+                    if !m.content.is_empty() {
+                        content.push(json!({ "text": m.content }));
+                    }
+                    ("user", content)
+                }
+                Role::Assistant => {
+                    let mut content = Vec::new();
+                    if m.tool_calls.is_some() {
+                        content.extend(Self::reasoning_blocks(&m.thinking));
+                    }
+                    if !m.content.is_empty() {
+                        content.push(json!({ "text": m.content }));
+                    }
+                    for tc in m.tool_calls.iter().flatten() {
+                        content.push(json!({
+                            "toolUse": {
+                                "toolUseId": tc.id,
+                                "name": tc.name,
+                                "input": serde_json::from_str::<Value>(&tc.arguments)
+                                    .unwrap_or(json!({}))
+                            }
+                        }));
+                    }
+                    ("assistant", content)
+                }
+                Role::Tool => {
+                    // Converse rejects blank text blocks
+                    let output = if m.content.is_empty() {
+                        "(no output)"
+                    } else {
+                        m.content.as_str()
+                    };
+                    (
+                        "user",
+                        vec![json!({
+                            "toolResult": {
+                                "toolUseId": m.tool_call_id.clone().unwrap_or_default(),
+                                "content": [{ "text": output }]
+                            }
+                        })],
+                    )
+                }
+            };
+
+            if content.is_empty() {
+                continue;
+            }
+            match formatted.last_mut() {
+                Some(last) if last["role"] == role => {
+                    if let Some(blocks) = last["content"].as_array_mut() {
+                        blocks.extend(content);
+                    }
+                }
+                _ => formatted.push(json!({ "role": role, "content": content })),
+            }
+        }
+
+        (system, formatted)
+    }
+
+    fn format_tools(tools: &[ToolSchema]) -> Value {
+        let specs: Vec<Value> = tools
+            .iter()
+            .map(|t| {
+                json!({
+                    "toolSpec": {
+                        "name": t.name,
+                        "description": t.description,
+                        "inputSchema": { "json": t.parameters }
+                    }
+                })
+            })
+            .collect();
+        json!({ "tools": specs })
+    }
+
+    fn build_body(&self, messages: &[Message], tools: Option<&[ToolSchema]>) -> Value {
+        let (system, formatted) = Self::format_messages(messages);
+        let mut body = json!({
+            "messages": formatted,
+            "inferenceConfig": { "maxTokens": self.max_tokens }
+        });
+        if !system.is_empty() {
+            body["system"] = json!(system);
+        }
+        if let Some(tools) = tools
+            && !tools.is_empty()
+        {
+            body["toolConfig"] = Self::format_tools(tools);
+        }
+
+        // Claude takes Anthropic's thinking settings as model-specific fields
+        if self.model.contains("anthropic.") {
+            let mut fields = json!({});
+            apply_anthropic_thinking(&mut fields, self.reasoning, self.max_tokens);
+            if fields.get("thinking").is_some() {
+                body["additionalModelRequestFields"] = fields;
+            }
+        }
+
+        body
+    }
+
+    fn parse_usage(u: &Value) -> Usage {
+        Usage {
+            input_tokens: u["inputTokens"].as_u64().unwrap_or(0),
+            output_tokens: u["outputTokens"].as_u64().unwrap_or(0),
+            cache_read_tokens: u["cacheReadInputTokens"].as_u64().unwrap_or(0),
+            cache_write_tokens: u["cacheWriteInputTokens"].as_u64().unwrap_or(0),
+        }
+    }
+
+    /// Text, tool calls and reasoning from a Converse `content` array
+    fn parse_content(content: &[Value]) -> (String, Vec<ToolCall>, Vec<ThinkingBlock>) {
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        let mut thinking = Vec::new();
+
+        for block in content {
+            if let Some(t) = block["text"].as_str() {
+                text.push_str(t);
+            } else if let Some(tool) = block.get("toolUse") {
+                tool_calls.push(ToolCall {
+                    id: tool["toolUseId"].as_str().unwrap_or("").to_string(),
+                    name: tool["name"].as_str().unwrap_or("").to_string(),
+                    arguments: serde_json::to_string(&tool["input"]).unwrap_or("{}".to_string()),
+                    signature: None,
+                });
+            } else if let Some(reasoning) = block.get("reasoningContent") {
+                thinking.push(ThinkingBlock {
+                    text: reasoning["reasoningText"]["text"]
+                        .as_str()
+                        .unwrap_or("")
+                        .to_string(),
+                    signature: reasoning["reasoningText"]["signature"]
+                        .as_str()
+                        .map(str::to_string),
+                    redacted: reasoning["redactedContent"].as_str().map(str::to_string),
+                });
+            }
+        }
+
+        (text, tool_calls, thinking)
+    }
+
+    /// Sign and send a request to `/model/{model}/{action}`
+    async fn send(&self, action: &str, body: &Value) -> Result<reqwest::Response> {
+        let url = reqwest::Url::parse(&format!(
+            "{}/model/{}/{}",
+            self.endpoint,
+            uri_encode(&self.model),
+            action
+        ))?;
+        let payload = serde_json::to_vec(body)?;
+        let content_type = "application/json";
+        let signed = sigv4_headers(
+            &self.credentials,
+            &self.region,
+            SIGNING_SERVICE,
+            &SignableRequest {
+                method: "POST",
+                url: &url,
+                headers: &[("content-type", content_type)],
+                payload: &payload,
+            },
+            Utc::now(),
+        );
+
+        let mut request = self.client.post(url).header("Content-Type", content_type);
+        for (name, value) in signed {
+            request = request.header(name, value);
+        }
+
+        let response = request
+            .body(payload)
+            .send()
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        Ok(ProviderError::check_response(&self.name(), response).await?)
+    }
+
+    /// Convert a `converse-stream` event-stream body into `StreamChunk`s.
+    ///
+    /// Text and reasoning deltas are yielded as they arrive; tool input JSON is
+    /// accumulated per content block and returned on the final `done` chunk.
+    fn event_stream<S, B, E>(byte_stream: S) -> StreamResult
+    where
+        S: Stream<Item = std::result::Result<B, E>> + Send + 'static,
+        B: AsRef<[u8]> + Send,
+        E: std::fmt::Display + Send,
+    {
+        let stream = async_stream::stream! {
+            let mut byte_stream = Box::pin(byte_stream);
+            let mut decoder = EventStreamDecoder::default();
+            let mut tool_calls: Vec<ToolCall> = Vec::new();
+            let mut current_tool: Option<ToolCall> = None;
+            let mut thinking: Vec<ThinkingBlock> = Vec::new();
+            let mut current_thinking: Option<ThinkingBlock> = None;
+            let mut usage: Option<Usage> = None;
+
+            while let Some(chunk) = byte_stream.next().await {
+                match chunk {
+                    Ok(bytes) => decoder.push(bytes.as_ref()),
+                    Err(e) => {
+                        yield Err(ProviderError::Transport {
+                            provider: "bedrock".to_string(),
+                            message: format!("stream interrupted: {}", e),
+                        }
+                        .into());
+                        return;
+                    }
+                }
+
+                loop {
+                    let message = match decoder.next_message() {
+                        Ok(Some(message)) => message,
+                        Ok(None) => break,
+                        Err(e) => {
+                            yield Err(e);
+                            return;
+                        }
+                    };
+                    let payload: Value =
+                        serde_json::from_slice(&message.payload).unwrap_or(Value::Null);
+
+                    if message.header(":message-type") == Some("exception") {
+                        let kind = message.header(":exception-type").unwrap_or("exception");
+                        yield Err(stream_exception(kind, &payload).into());
+                        return;
+                    }
+
+                    match message.header(":event-type").unwrap_or("") {
+                        "contentBlockStart" => {
+                            if let Some(tool) = payload["start"].get("toolUse") {
+                                current_tool = Some(ToolCall {
+                                    id: tool["toolUseId"].as_str().unwrap_or("").to_string(),
+                                    name: tool["name"].as_str().unwrap_or("").to_string(),
+                                    arguments: String::new(),
+                                    signature: None,
+                                });
+                            }
+                        }
+                        "contentBlockDelta" => {
+                            let delta = &payload["delta"];
+                            if let Some(text) = delta["text"].as_str() {
+                                if !text.is_empty() {
+                                    yield Ok(StreamChunk {
+                                        delta: text.to_string(),
+                                        done: false,
+                                        tool_calls: None,
+                                        usage: None,
+                                        thinking: None,
+                                        thinking_blocks: None,
+                                    });
+                                }
+                            } else if let Some(input) = delta["toolUse"]["input"].as_str() {
+                                if let Some(tool) = current_tool.as_mut() {
+                                    tool.arguments.push_str(input);
+                                }
+                            } else if let Some(reasoning) = delta.get("reasoningContent") {
+                                let block = current_thinking.get_or_insert_with(ThinkingBlock::default);
+                                if let Some(signature) = reasoning["signature"].as_str() {
+                                    block.signature = Some(signature.to_string());
+                                }
+                                if let Some(data) = reasoning["redactedContent"].as_str() {
+                                    block.redacted = Some(data.to_string());
+                                }
+                                if let Some(text) = reasoning["text"].as_str()
+                                    && !text.is_empty()
+                                {
+                                    block.text.push_str(text);
+                                    yield Ok(StreamChunk {
+                                        delta: String::new(),
+                                        done: false,
+                                        tool_calls: None,
+                                        usage: None,
+                                        thinking: Some(text.to_string()),
+                                        thinking_blocks: None,
+                                    });
+                                }
+                            }
+                        }
+                        "contentBlockStop" => {
+                            if let Some(mut tool) = current_tool.take() {
+                                if tool.arguments.trim().is_empty() {
+                                    tool.arguments = "{}".to_string();
+                                }
+                                tool_calls.push(tool);
+                            }
+                            if let Some(block) = current_thinking.take() {
+                                thinking.push(block);
+                            }
+                        }
+                        "metadata" if payload["usage"].is_object() => {
+                            usage = Some(Self::parse_usage(&payload["usage"]));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            yield Ok(StreamChunk {
+                delta: String::new(),
+                done: true,
+                tool_calls: if tool_calls.is_empty() {
+                    None
+                } else {
+                    Some(tool_calls)
+                },
+                usage,
+                thinking: None,
+                thinking_blocks: (!thinking.is_empty()).then_some(thinking),
+            });
+        };
+
+        Box::pin(stream)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for BedrockProvider {
+    fn name(&self) -> String {
+        "bedrock".to_string()
+    }
+
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        let body = self.build_body(messages, tools);
+        debug!(
+            "Bedrock request to {}: {}",
+            self.model,
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let response = self.send("converse", &body).await?;
+        let response_body: Value = response.json().await?;
+        debug!(
+            "Bedrock response: {}",
+            serde_json::to_string_pretty(&response_body)?
+        );
+
+        let content = response_body["output"]["message"]["content"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let usage = response_body.get("usage").map(Self::parse_usage);
+        let (text, tool_calls, thinking) = Self::parse_content(&content);
+
+        if !tool_calls.is_empty() {
+            return Ok(LLMResponse {
+                content: LLMResponseContent::ToolCalls(tool_calls),
+                usage,
+                thinking,
+            });
+        }
+
+        Ok(LLMResponse {
+            content: LLMResponseContent::Text(text),
+            usage,
+            thinking,
+        })
+    }
+
+    async fn summarize(&self, text: &str) -> Result<String> {
+        let messages = vec![Message {
+            role: Role::User,
+            content: format!(
+                "Summarize the following conversation concisely, preserving key information and context:\n\n{}",
+                text
+            ),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: Vec::new(),
+        }];
+
+        match self.chat(&messages, None).await?.content {
+            LLMResponseContent::Text(summary) => Ok(summary),
+            _ => anyhow::bail!("Unexpected response type"),
+        }
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        let body = self.build_body(messages, tools);
+        debug!(
+            "Bedrock streaming request to {}: {}",
+            self.model,
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let response = self.send("converse-stream", &body).await?;
+        Ok(Self::event_stream(response.bytes_stream()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::mock_http;
+
+    fn credentials() -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        }
+    }
+
+    /// Encode one event-stream message with string headers (CRCs left zero)
+    fn encode_event(headers: &[(&str, &str)], payload: &Value) -> Vec<u8> {
+        let mut header_bytes = Vec::new();
+        for (name, value) in headers {
+            header_bytes.push(name.len() as u8);
+            header_bytes.extend_from_slice(name.as_bytes());
+            header_bytes.push(7);
+            header_bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            header_bytes.extend_from_slice(value.as_bytes());
+        }
+        let payload = serde_json::to_vec(payload).unwrap();
+        let total = 12 + header_bytes.len() + payload.len() + 4;
+
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&(total as u32).to_be_bytes());
+        frame.extend_from_slice(&(header_bytes.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&[0; 4]);
+        frame.extend_from_slice(&header_bytes);
+        frame.extend_from_slice(&payload);
+        frame.extend_from_slice(&[0; 4]);
+        frame
+    }
+
+    fn event(event_type: &str, payload: Value) -> Vec<u8> {
+        encode_event(
+            &[
+                (":event-type", event_type),
+                (":content-type", "application/json"),
+                (":message-type", "event"),
+            ],
+            &payload,
+        )
+    }
+
+    #[test]
+    fn test_sigv4_matches_aws_test_suite() {
+        // "get-vanilla" from the AWS Signature Version 4 test suite
+        let url = reqwest::Url::parse("https://example.amazonaws.com/").unwrap();
+        let now = DateTime::parse_from_rfc3339("2015-08-30T12:36:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let headers = sigv4_headers(
+            &credentials(),
+            "us-east-1",
+            "service",
+            &SignableRequest {
+                method: "GET",
+                url: &url,
+                headers: &[],
+                payload: b"",
+            },
+            now,
+        );
+
+        assert_eq!(headers[0], ("x-amz-date", "20150830T123600Z".to_string()));
+        assert_eq!(
+            headers[1].1,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn test_format_messages_merges_tool_results() {
+        let message = |role, content: &str| Message {
+            role,
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: Vec::new(),
+        };
+        let mut assistant = message(Role::Assistant, "");
+        assistant.tool_calls = Some(vec![ToolCall {
+            id: "tool_1".to_string(),
+            name: "bash".to_string(),
+            arguments: "{\"command\":\"ls\"}".to_string(),
+            signature: None,
+        }]);
+        assistant.thinking = vec![ThinkingBlock {
+            text: "List files.".to_string(),
+            signature: Some("sig".to_string()),
+            redacted: None,
+        }];
+        let mut result = message(Role::Tool, "");
+        result.tool_call_id = Some("tool_1".to_string());
+
+        let (system, formatted) = BedrockProvider::format_messages(&[
+            message(Role::System, "Be brief."),
+            message(Role::User, "What's here?"),
+            assistant,
+            result,
+            message(Role::User, "Thanks"),
+        ]);
+
+        assert_eq!(system, vec![json!({ "text": "Be brief." })]);
+        assert_eq!(formatted.len(), 3);
+        let assistant = formatted[1]["content"].as_array().unwrap();
+        assert_eq!(
+            assistant[0]["reasoningContent"]["reasoningText"]["signature"],
+            "sig"
+        );
+        assert_eq!(assistant[1]["toolUse"]["input"]["command"], "ls");
+        let user = formatted[2]["content"].as_array().unwrap();
+        assert_eq!(user.len(), 2);
+        assert_eq!(user[0]["toolResult"]["toolUseId"], "tool_1");
+        assert_eq!(user[0]["toolResult"]["content"][0]["text"], "(no output)");
+        assert_eq!(user[1]["text"], "Thanks");
+    }
+
+    #[test]
+    fn test_event_stream_decoder_handles_split_frames() {
+        let mut bytes = event("contentBlockDelta", json!({ "delta": { "text": "Hi" } }));
+        bytes.extend(event("messageStop", json!({ "stopReason": "end_turn" })));
+
+        let mut decoder = EventStreamDecoder::default();
+        decoder.push(&bytes[..10]);
+        assert!(decoder.next_message().unwrap().is_none());
+        decoder.push(&bytes[10..]);
+
+        let first = decoder.next_message().unwrap().unwrap();
+        assert_eq!(first.header(":event-type"), Some("contentBlockDelta"));
+        let payload: Value = serde_json::from_slice(&first.payload).unwrap();
+        assert_eq!(payload["delta"]["text"], "Hi");
+        let second = decoder.next_message().unwrap().unwrap();
+        assert_eq!(second.header(":event-type"), Some("messageStop"));
+        assert!(decoder.next_message().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_converse_against_mock_server() {
+        let response = json!({
+            "output": { "message": { "role": "assistant", "content": [
+                { "text": "Let me check." },
+                { "toolUse": { "toolUseId": "tool_1", "name": "bash", "input": { "command": "ls" } } }
+            ] } },
+            "stopReason": "tool_use",
+            "usage": { "inputTokens": 12, "outputTokens": 8, "totalTokens": 20 }
+        });
+        let (base_url, request) =
+            mock_http::serve_once(200, "application/json", response.to_string().into_bytes()).await;
+
+        let provider = BedrockProvider::new(
+            credentials(),
+            "us-east-1",
+            "anthropic.claude-3-haiku-20240307-v1:0",
+            1024,
+        )
+        .unwrap()
+        .with_endpoint(Some(&base_url));
+        let messages = vec![Message {
+            role: Role::User,
+            content: "What's here?".to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: Vec::new(),
+        }];
+        let resp = provider.chat(&messages, None).await.unwrap();
+
+        let LLMResponseContent::ToolCalls(calls) = resp.content else {
+            panic!("expected tool calls");
+        };
+        assert_eq!(calls[0].id, "tool_1");
+        assert_eq!(calls[0].arguments, "{\"command\":\"ls\"}");
+        assert_eq!(resp.usage.unwrap().output_tokens, 8);
+
+        let request = request.await.unwrap();
+        assert_eq!(
+            request.path,
+            "/model/anthropic.claude-3-haiku-20240307-v1%3A0/converse"
+        );
+        let authorization = request.header("authorization").unwrap();
+        assert!(authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"));
+        assert!(authorization.contains("/us-east-1/bedrock/aws4_request"));
+        assert!(authorization.contains("SignedHeaders=content-type;host;x-amz-date"));
+        let body: Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body["messages"][0]["content"][0]["text"], "What's here?");
+    }
+
+    #[tokio::test]
+    async fn test_converse_stream_against_mock_server() {
+        let mut body = Vec::new();
+        body.extend(event("messageStart", json!({ "role": "assistant" })));
+        body.extend(event(
+            "contentBlockDelta",
+            json!({ "contentBlockIndex": 0, "delta": { "reasoningContent": { "text": "Hmm" } } }),
+        ));
+        body.extend(event(
+            "contentBlockDelta",
+            json!({ "contentBlockIndex": 0, "delta": { "reasoningContent": { "signature": "sig" } } }),
+        ));
+        body.extend(event("contentBlockStop", json!({ "contentBlockIndex": 0 })));
+        body.extend(event(
+            "contentBlockDelta",
+            json!({ "contentBlockIndex": 1, "delta": { "text": "Checking" } }),
+        ));
+        body.extend(event("contentBlockStop", json!({ "contentBlockIndex": 1 })));
+        body.extend(event(
+            "contentBlockStart",
+            json!({ "contentBlockIndex": 2, "start": { "toolUse": { "toolUseId": "tool_1", "name": "bash" } } }),
+        ));
+        body.extend(event(
+            "contentBlockDelta",
+            json!({ "contentBlockIndex": 2, "delta": { "toolUse": { "input": "{\"command\":" } } }),
+        ));
+        body.extend(event(
+            "contentBlockDelta",
+            json!({ "contentBlockIndex": 2, "delta": { "toolUse": { "input": "\"ls\"}" } } }),
+        ));
+        body.extend(event("contentBlockStop", json!({ "contentBlockIndex": 2 })));
+        body.extend(event("messageStop", json!({ "stopReason": "tool_use" })));
+        body.extend(event(
+            "metadata",
+            json!({ "usage": { "inputTokens": 5, "outputTokens": 7 } }),
+        ));
+        let (base_url, request) =
+            mock_http::serve_once(200, "application/vnd.amazon.eventstream", body).await;
+
+        let provider =
+            BedrockProvider::new(credentials(), "eu-west-1", "amazon.nova-pro-v1:0", 1024)
+                .unwrap()
+                .with_endpoint(Some(&base_url));
+        let stream = provider.chat_stream(&[], None).await.unwrap();
+        let chunks: Vec<StreamChunk> = stream.map(|c| c.unwrap()).collect().await;
+
+        assert_eq!(chunks[0].thinking.as_deref(), Some("Hmm"));
+        assert_eq!(chunks[1].delta, "Checking");
+        let last = chunks.last().unwrap();
+        assert!(last.done);
+        let calls = last.tool_calls.as_ref().unwrap();
+        assert_eq!(calls[0].name, "bash");
+        assert_eq!(calls[0].arguments, "{\"command\":\"ls\"}");
+        let blocks = last.thinking_blocks.as_ref().unwrap();
+        assert_eq!(blocks[0].signature.as_deref(), Some("sig"));
+        assert_eq!(last.usage.as_ref().unwrap().output_tokens, 7);
+        assert!(request.await.unwrap().path.ends_with("/converse-stream"));
+    }
+
+    #[tokio::test]
+    async fn test_converse_stream_exception() {
+        let body = encode_event(
+            &[
+                (":message-type", "exception"),
+                (":exception-type", "throttlingException"),
+            ],
+            &json!({ "message": "Too many requests" }),
+        );
+        let (base_url, _request) =
+            mock_http::serve_once(200, "application/vnd.amazon.eventstream", body).await;
+
+        let provider =
+            BedrockProvider::new(credentials(), "us-east-1", "amazon.nova-pro-v1:0", 1024)
+                .unwrap()
+                .with_endpoint(Some(&base_url));
+        let mut stream = provider.chat_stream(&[], None).await.unwrap();
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(
+            ProviderError::from_anyhow(&err),
+            Some(ProviderError::RateLimited { .. })
+        ));
+    }
+}
@@ -0,0 +1,184 @@
+//! Cancellation of in-flight agent turns
+//!
+//! A turn holds `&mut Agent` (usually behind a mutex) until it finishes, so the
+//! code that wants to stop it cannot reach the agent. Instead it keeps a
+//! [`TurnCanceller`] cloned from the agent beforehand and calls
+//! [`TurnCanceller::cancel`]. The agent races provider requests, streams, tool
+//! approvals and tool execution against the turn's token; dropping a running
+//! tool future is what aborts an HTTP request or kills a bash child.
+
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use futures::{Stream, StreamExt};
+use tokio_util::sync::CancellationToken;
+
+/// Error returned by a turn that was cancelled before it finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Turn cancelled")]
+pub struct Cancelled;
+
+impl Cancelled {
+    /// Whether `err` (or anything it wraps) is a cancelled turn
+    pub fn is(err: &anyhow::Error) -> bool {
+        err.chain().any(|cause| cause.is::<Cancelled>())
+    }
+}
+
+/// Cancels the agent's in-flight turn from another task or thread.
+///
+/// Cloning is cheap and every clone controls the same agent. A cancel only
+/// affects the turn in progress; the next turn starts with a fresh token.
+#[derive(Clone, Default)]
+pub struct TurnCanceller {
+    current: Arc<Mutex<TurnToken>>,
+}
+
+#[derive(Default)]
+struct TurnToken {
+    token: CancellationToken,
+    /// Set by `prepare_turn`: the next `begin_turn` keeps the token as is
+    prepared: bool,
+}
+
+impl TurnCanceller {
+    /// Stop the turn in progress, if any
+    pub fn cancel(&self) {
+        self.token().cancel();
+    }
+
+    /// Token of the turn in progress (or of the last one)
+    pub fn token(&self) -> CancellationToken {
+        self.current
+            .lock()
+            .expect("turn canceller lock poisoned")
+            .token
+            .clone()
+    }
+
+    /// Give the next turn its token now. Call this before publishing the
+    /// canceller for a turn that is about to start, so a cancel arriving
+    /// before the agent begins the turn still stops it.
+    pub fn prepare_turn(&self) {
+        let mut current = self.current.lock().expect("turn canceller lock poisoned");
+        if current.token.is_cancelled() {
+            current.token = CancellationToken::new();
+        }
+        current.prepared = true;
+    }
+
+    /// Start a turn, replacing the token if the previous turn was cancelled
+    /// (unless the turn was prepared, in which case any cancel is for it)
+    pub(crate) fn begin_turn(&self) {
+        let mut current = self.current.lock().expect("turn canceller lock poisoned");
+        if !std::mem::take(&mut current.prepared) && current.token.is_cancelled() {
+            current.token = CancellationToken::new();
+        }
+    }
+
+    /// Run `fut` unless the turn is cancelled first. A cancelled `fut` is
+    /// dropped, which aborts whatever it was waiting on.
+    pub(crate) async fn run<T>(&self, fut: impl Future<Output = Result<T>>) -> Result<T> {
+        let token = self.token();
+        tokio::select! {
+            biased;
+            _ = token.cancelled() => Err(Cancelled.into()),
+            result = fut => result,
+        }
+    }
+
+    /// Pass `stream` through until the turn is cancelled, then end it with a
+    /// [`Cancelled`] error
+    pub(crate) fn stream<T: Send + 'static>(
+        &self,
+        mut stream: Pin<Box<dyn Stream<Item = Result<T>> + Send>>,
+    ) -> Pin<Box<dyn Stream<Item = Result<T>> + Send>> {
+        let token = self.token();
+        Box::pin(async_stream::stream! {
+            loop {
+                tokio::select! {
+                    biased;
+                    _ = token.cancelled() => {
+                        yield Err(Cancelled.into());
+                        break;
+                    }
+                    item = stream.next() => match item {
+                        Some(item) => yield item,
+                        None => break,
+                    },
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn cancel_stops_the_running_future() {
+        let canceller = TurnCanceller::default();
+        canceller.begin_turn();
+
+        let remote = canceller.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            remote.cancel();
+        });
+
+        let result = canceller
+            .run(async {
+                tokio::time::sleep(Duration::from_secs(30)).await;
+                Ok(())
+            })
+            .await;
+        assert!(Cancelled::is(&result.unwrap_err()));
+    }
+
+    #[tokio::test]
+    async fn next_turn_starts_uncancelled() {
+        let canceller = TurnCanceller::default();
+        canceller.begin_turn();
+        canceller.cancel();
+        assert!(canceller.run(async { Ok(()) }).await.is_err());
+
+        canceller.begin_turn();
+        assert_eq!(canceller.run(async { Ok(7) }).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn cancel_between_prepare_and_begin_is_kept() {
+        let canceller = TurnCanceller::default();
+        canceller.begin_turn();
+        canceller.cancel();
+
+        // The last turn's cancel is cleared, a new one carries into the turn
+        canceller.prepare_turn();
+        assert!(!canceller.token().is_cancelled());
+        canceller.cancel();
+        canceller.begin_turn();
+        assert!(canceller.run(async { Ok(()) }).await.is_err());
+
+        canceller.begin_turn();
+        assert_eq!(canceller.run(async { Ok(7) }).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn cancelled_stream_ends_with_error() {
+        let canceller = TurnCanceller::default();
+        canceller.begin_turn();
+
+        let items: Pin<Box<dyn Stream<Item = Result<u32>> + Send>> =
+            Box::pin(futures::stream::iter([Ok(1), Ok(2)]).chain(futures::stream::pending()));
+        let mut stream = canceller.stream(items);
+        assert_eq!(stream.next().await.unwrap().unwrap(), 1);
+        assert_eq!(stream.next().await.unwrap().unwrap(), 2);
+
+        canceller.cancel();
+        assert!(Cancelled::is(&stream.next().await.unwrap().unwrap_err()));
+        assert!(stream.next().await.is_none());
+    }
+}
@@ -2,78 +2,404 @@
 //!
 //! When a provider fails with a retryable error (rate limit, timeout, server error),
 //! the FailoverProvider automatically tries the next provider in the chain.
-
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Instant;
+//!
+//! Each chain entry sits behind a circuit breaker keyed by the model it serves
+//! (e.g. `openai/gpt-4o`), so two models of one provider fail over independently.
+//! A retryable failure opens the circuit for the provider's `Retry-After` (or an
+//! exponential backoff when none is given); once that expires the circuit is
+//! half-open and a single probe request is let through. Circuit state is shared
+//! process-wide through [`ProviderHealthRegistry::global`] and persisted in the
+//! state directory, so a restart does not immediately hammer a provider that is
+//! still rate limiting us.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::Result;
 use async_trait::async_trait;
-use tracing::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
 
+use super::provider_error::ProviderError;
 use super::providers::{LLMProvider, LLMResponse, Message, StreamResult, ToolSchema};
+use super::structured::{ResponseSchema, StructuredResponse};
+
+/// Cooldown after the first retryable failure; doubles with each consecutive failure
+const BASE_COOLDOWN_SECS: u64 = 30;
+
+/// Upper bound on any cooldown, including server-provided `Retry-After` values
+const MAX_COOLDOWN_SECS: u64 = 30 * 60;
+
+/// A half-open probe that has not reported back after this long is considered lost
+const PROBE_TIMEOUT_SECS: u64 = 120;
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Circuit-breaker state of a single provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Healthy; requests flow normally
+    Closed,
+    /// Cooling down after failures; requests skip this provider
+    Open,
+    /// Cooldown expired; one probe request is allowed through
+    HalfOpen,
+}
+
+impl std::fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CircuitState::Closed => write!(f, "closed"),
+            CircuitState::Open => write!(f, "open"),
+            CircuitState::HalfOpen => write!(f, "half-open"),
+        }
+    }
+}
+
+/// Persisted per-provider bookkeeping
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct HealthEntry {
+    consecutive_failures: u32,
+    /// Unix timestamp (seconds) at which the circuit half-opens
+    open_until: u64,
+    last_error: Option<String>,
+    last_failure_at: Option<u64>,
+    last_success_at: Option<u64>,
+    /// Set while a half-open probe is in flight (not persisted)
+    #[serde(skip)]
+    probe_started_at: Option<u64>,
+}
+
+impl HealthEntry {
+    fn state(&self, now: u64) -> CircuitState {
+        if self.consecutive_failures == 0 {
+            CircuitState::Closed
+        } else if now < self.open_until {
+            CircuitState::Open
+        } else {
+            CircuitState::HalfOpen
+        }
+    }
+}
+
+/// Point-in-time health of one provider, for `/api/status` and `localgpt doctor`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderHealth {
+    pub provider: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// Seconds until the circuit half-opens (only set while open)
+    pub retry_in_secs: Option<u64>,
+    pub last_error: Option<String>,
+    /// Unix timestamp of the last retryable failure
+    pub last_failure_at: Option<u64>,
+    /// Unix timestamp of the last successful request
+    pub last_success_at: Option<u64>,
+}
 
-/// Duration to cooldown a failed provider before retrying
-const COOLDOWN_SECS: u64 = 60;
+/// Shared circuit-breaker state for all providers, keyed by model (e.g. `openai/gpt-4o`).
+pub struct ProviderHealthRegistry {
+    entries: Mutex<HashMap<String, HealthEntry>>,
+    /// Where state is persisted; `None` keeps it in memory only
+    path: Option<PathBuf>,
+}
+
+static GLOBAL_REGISTRY: Lazy<Arc<ProviderHealthRegistry>> =
+    Lazy::new(|| match crate::paths::Paths::resolve() {
+        Ok(paths) => Arc::new(ProviderHealthRegistry::load(paths.provider_health())),
+        Err(e) => {
+            warn!(
+                "Cannot resolve state dir, provider health will not persist: {}",
+                e
+            );
+            Arc::new(ProviderHealthRegistry::in_memory())
+        }
+    });
+
+impl ProviderHealthRegistry {
+    /// Registry that is never written to disk
+    pub fn in_memory() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            path: None,
+        }
+    }
+
+    /// Load persisted state from `path` (missing or corrupt files start empty)
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let entries = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| match serde_json::from_str(&content) {
+                Ok(entries) => Some(entries),
+                Err(e) => {
+                    warn!("Ignoring corrupt provider health file {:?}: {}", path, e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self {
+            entries: Mutex::new(entries),
+            path: Some(path),
+        }
+    }
+
+    /// Process-wide registry backed by `state_dir/provider_health.json`
+    pub fn global() -> Arc<Self> {
+        Arc::clone(&GLOBAL_REGISTRY)
+    }
+
+    /// Health of every known provider, sorted by name
+    pub fn snapshot(&self) -> Vec<ProviderHealth> {
+        self.snapshot_at(now_secs())
+    }
+
+    fn snapshot_at(&self, now: u64) -> Vec<ProviderHealth> {
+        let entries = self.lock();
+        let mut health: Vec<_> = entries
+            .iter()
+            .map(|(name, entry)| Self::to_health(name, entry, now))
+            .collect();
+        health.sort_by(|a, b| a.provider.cmp(&b.provider));
+        health
+    }
+
+    /// Health of a single provider (closed if never seen)
+    pub fn get(&self, provider: &str) -> ProviderHealth {
+        let entries = self.lock();
+        let entry = entries.get(provider).cloned().unwrap_or_default();
+        Self::to_health(provider, &entry, now_secs())
+    }
+
+    fn to_health(name: &str, entry: &HealthEntry, now: u64) -> ProviderHealth {
+        let state = entry.state(now);
+        ProviderHealth {
+            provider: name.to_string(),
+            state,
+            consecutive_failures: entry.consecutive_failures,
+            retry_in_secs: (state == CircuitState::Open).then(|| entry.open_until - now),
+            last_error: entry.last_error.clone(),
+            last_failure_at: entry.last_failure_at,
+            last_success_at: entry.last_success_at,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, HealthEntry>> {
+        // State is plain bookkeeping; recover it even if a holder panicked
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Make a provider visible in snapshots before it has any history
+    fn register(&self, provider: &str) {
+        self.lock().entry(provider.to_string()).or_default();
+    }
+
+    /// Whether a request may be sent to `provider` now.
+    ///
+    /// In the half-open state only one caller gets through until it reports back.
+    fn try_acquire(&self, provider: &str, now: u64) -> bool {
+        let mut entries = self.lock();
+        let entry = entries.entry(provider.to_string()).or_default();
+        match entry.state(now) {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => {
+                let probe_active = entry
+                    .probe_started_at
+                    .is_some_and(|started| now < started + PROBE_TIMEOUT_SECS);
+                if probe_active {
+                    false
+                } else {
+                    entry.probe_started_at = Some(now);
+                    true
+                }
+            }
+        }
+    }
+
+    /// The provider answered; close its circuit.
+    ///
+    /// `success` is false when the provider responded with a non-retryable error
+    /// (e.g. a bad request): it is reachable, but that was not a successful call.
+    fn record_response(&self, provider: &str, now: u64, success: bool) {
+        let mut entries = self.lock();
+        let entry = entries.entry(provider.to_string()).or_default();
+        let was_failing = entry.consecutive_failures > 0;
+
+        entry.consecutive_failures = 0;
+        entry.open_until = 0;
+        entry.probe_started_at = None;
+        if success {
+            entry.last_success_at = Some(now);
+        }
+
+        if was_failing {
+            debug!("Provider {} recovered, circuit closed", provider);
+            self.save(&entries);
+        }
+    }
+
+    /// Record a retryable failure and open the circuit. Returns the cooldown applied.
+    fn record_failure(&self, provider: &str, err: &ProviderError, now: u64) -> Duration {
+        let mut entries = self.lock();
+        let entry = entries.entry(provider.to_string()).or_default();
+
+        entry.consecutive_failures = entry.consecutive_failures.saturating_add(1);
+        let cooldown = cooldown_for(entry.consecutive_failures, err.retry_after());
+        entry.open_until = now + cooldown.as_secs();
+        entry.last_error = Some(err.to_string());
+        entry.last_failure_at = Some(now);
+        entry.probe_started_at = None;
+
+        self.save(&entries);
+        cooldown
+    }
+
+    fn save(&self, entries: &HashMap<String, HealthEntry>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = (|| -> Result<()> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, serde_json::to_string_pretty(entries)?)?;
+            Ok(())
+        })();
+        if let Err(e) = result {
+            warn!("Failed to persist provider health to {:?}: {}", path, e);
+        }
+    }
+}
+
+/// Cooldown for the given failure count: the server's `Retry-After` if provided,
+/// otherwise exponential backoff from `BASE_COOLDOWN_SECS`, capped at `MAX_COOLDOWN_SECS`
+fn cooldown_for(consecutive_failures: u32, retry_after: Option<Duration>) -> Duration {
+    let secs = match retry_after {
+        // Round up so sub-second hints still produce a non-zero cooldown
+        Some(d) => d.as_secs() + u64::from(d.subsec_nanos() > 0),
+        None => {
+            let exponent = consecutive_failures.saturating_sub(1).min(16);
+            BASE_COOLDOWN_SECS.saturating_mul(1 << exponent)
+        }
+    };
+    Duration::from_secs(secs.clamp(1, MAX_COOLDOWN_SECS))
+}
 
 /// Provider that wraps multiple LLM providers and tries them in sequence on failure.
 ///
-/// Retryable errors include:
-/// - HTTP 429 (rate limit)
-/// - HTTP 500, 502, 503 (server errors)
-/// - Timeouts
-/// - Connection refused/reset
+/// Retryability is decided by the provider's [`ProviderError`]:
+/// - `RateLimited` (HTTP 429)
+/// - `Server` (HTTP 5xx, overloaded)
+/// - `Transport` (timeouts, connection refused/reset)
 ///
-/// Non-retryable errors (e.g., 401 unauthorized, 400 bad request) fail immediately.
+/// Non-retryable errors (auth, bad request, context overflow) and errors that
+/// carry no `ProviderError` fail immediately.
 pub struct FailoverProvider {
     providers: Vec<Box<dyn LLMProvider>>,
-    /// Cooldown expiry timestamps as seconds since start (AtomicU64 for thread safety)
-    /// 0 means not in cooldown
-    cooldowns: Vec<AtomicU64>,
-    /// Reference time for cooldown calculations
-    start_instant: Instant,
+    /// Circuit key of each provider: the model it was created for
+    models: Vec<String>,
+    health: Arc<ProviderHealthRegistry>,
 }
 
 impl FailoverProvider {
-    /// Create a new FailoverProvider with the given providers.
+    /// Create a new FailoverProvider from `(model, provider)` pairs.
     /// The first provider is the primary, followed by fallbacks in order.
-    pub fn new(providers: Vec<Box<dyn LLMProvider>>) -> Self {
-        let count = providers.len();
+    pub fn new(providers: Vec<(String, Box<dyn LLMProvider>)>) -> Self {
+        Self::with_registry(providers, ProviderHealthRegistry::global())
+    }
+
+    /// Create a FailoverProvider that tracks circuit state in `health`
+    pub fn with_registry(
+        providers: Vec<(String, Box<dyn LLMProvider>)>,
+        health: Arc<ProviderHealthRegistry>,
+    ) -> Self {
+        let (models, providers): (Vec<_>, Vec<_>) = providers.into_iter().unzip();
+        for model in &models {
+            health.register(model);
+        }
         Self {
             providers,
-            cooldowns: (0..count).map(|_| AtomicU64::new(0)).collect(),
-            start_instant: Instant::now(),
+            models,
+            health,
         }
     }
 
-    /// Check if an error is retryable (should try next provider)
-    fn is_retryable(err: &anyhow::Error) -> bool {
-        let msg = err.to_string().to_lowercase();
-        // Rate limits
-        msg.contains("429") || msg.contains("rate limit") || msg.contains("ratelimit") ||
-        // Server errors
-        msg.contains("500") || msg.contains("502") || msg.contains("503") ||
-        // Network issues
-        msg.contains("timeout") ||
-        msg.contains("connection refused") ||
-        msg.contains("connection reset") ||
-        msg.contains("connection closed") ||
-        msg.contains("timed out")
-    }
-
-    /// Check if a provider is in cooldown
-    fn is_in_cooldown(&self, index: usize) -> bool {
-        let expiry_secs = self.cooldowns[index].load(Ordering::Relaxed);
-        if expiry_secs == 0 {
-            return false;
+    /// Health of each provider in the chain, in failover order
+    pub fn health(&self) -> Vec<ProviderHealth> {
+        self.models.iter().map(|m| self.health.get(m)).collect()
+    }
+
+    /// Check whether the provider at `index` may be called, logging when it is skipped
+    fn acquire(&self, index: usize, name: &str) -> bool {
+        let allowed = self.health.try_acquire(name, now_secs());
+        if !allowed {
+            warn!("Provider {} ({}) circuit open, skipping", index, name);
+        }
+        allowed
+    }
+
+    /// Update circuit state for a failed call.
+    ///
+    /// Returns `Ok(err)` when the next provider should be tried and `Err(err)` when
+    /// the error must be returned to the caller immediately.
+    fn handle_error(
+        &self,
+        index: usize,
+        name: &str,
+        err: anyhow::Error,
+    ) -> std::result::Result<anyhow::Error, anyhow::Error> {
+        match ProviderError::classify(&err) {
+            Some(provider_err) if provider_err.is_retryable() => {
+                let cooldown = self.health.record_failure(name, &provider_err, now_secs());
+                warn!(
+                    "Provider {} ({}) failed (retryable): {}, cooling down for {}s, trying next",
+                    index,
+                    name,
+                    err,
+                    cooldown.as_secs()
+                );
+                Ok(err)
+            }
+            _ => {
+                // The provider answered, so it is reachable even though the request failed
+                self.health.record_response(name, now_secs(), false);
+                warn!(
+                    "Provider {} ({}) failed (non-retryable): {}",
+                    index, name, err
+                );
+                Err(err)
+            }
         }
-        let elapsed = self.start_instant.elapsed().as_secs();
-        elapsed < expiry_secs
     }
 
-    /// Put a provider into cooldown
-    fn set_cooldown(&self, index: usize) {
-        let expiry = self.start_instant.elapsed().as_secs() + COOLDOWN_SECS;
-        self.cooldowns[index].store(expiry, Ordering::Relaxed);
+    /// Error returned when no provider could be tried or all of them failed
+    fn exhausted(&self, last_err: Option<anyhow::Error>) -> anyhow::Error {
+        if let Some(err) = last_err {
+            return err;
+        }
+        let next_retry = self.health().iter().filter_map(|h| h.retry_in_secs).min();
+        match next_retry {
+            Some(secs) => anyhow::anyhow!(
+                "All {} providers in cooldown; next retry in {}s",
+                self.providers.len(),
+                secs
+            ),
+            None => anyhow::anyhow!(
+                "All {} providers in cooldown or unavailable",
+                self.providers.len()
+            ),
+        }
     }
 }
 
@@ -92,84 +418,67 @@ impl LLMProvider for FailoverProvider {
         let mut last_err = None;
 
         for (i, provider) in self.providers.iter().enumerate() {
-            // Skip if in cooldown
-            if self.is_in_cooldown(i) {
-                warn!("Provider {} ({}) in cooldown, skipping", i, provider.name());
+            let name = &self.models[i];
+            if !self.acquire(i, name) {
                 continue;
             }
 
             match provider.chat(messages, tools).await {
-                Ok(result) => return Ok(result),
-                Err(e) if Self::is_retryable(&e) => {
-                    warn!(
-                        "Provider {} ({}) failed (retryable): {}, trying next",
-                        i,
-                        provider.name(),
-                        e
-                    );
-                    self.set_cooldown(i);
-                    last_err = Some(e);
-                }
-                Err(e) => {
-                    // Non-retryable error, fail immediately
-                    warn!(
-                        "Provider {} ({}) failed (non-retryable): {}",
-                        i,
-                        provider.name(),
-                        e
-                    );
-                    return Err(e);
+                Ok(result) => {
+                    self.health.record_response(name, now_secs(), true);
+                    return Ok(result);
                 }
+                Err(e) => last_err = Some(self.handle_error(i, name, e)?),
             }
         }
 
-        Err(last_err.unwrap_or_else(|| {
-            anyhow::anyhow!(
-                "All {} providers in cooldown or unavailable",
-                self.providers.len()
-            )
-        }))
+        Err(self.exhausted(last_err))
     }
 
     async fn summarize(&self, text: &str) -> Result<String> {
         let mut last_err = None;
 
         for (i, provider) in self.providers.iter().enumerate() {
-            if self.is_in_cooldown(i) {
-                warn!("Provider {} ({}) in cooldown, skipping", i, provider.name());
+            let name = &self.models[i];
+            if !self.acquire(i, name) {
                 continue;
             }
 
             match provider.summarize(text).await {
-                Ok(result) => return Ok(result),
-                Err(e) if Self::is_retryable(&e) => {
-                    warn!(
-                        "Provider {} ({}) failed (retryable): {}, trying next",
-                        i,
-                        provider.name(),
-                        e
-                    );
-                    self.set_cooldown(i);
-                    last_err = Some(e);
+                Ok(result) => {
+                    self.health.record_response(name, now_secs(), true);
+                    return Ok(result);
                 }
-                Err(e) => {
-                    warn!(
-                        "Provider {} ({}) failed (non-retryable): {}",
-                        i,
-                        provider.name(),
-                        e
-                    );
-                    return Err(e);
+                Err(e) => last_err = Some(self.handle_error(i, name, e)?),
+            }
+        }
+
+        Err(self.exhausted(last_err))
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        schema: &ResponseSchema,
+    ) -> Result<StructuredResponse> {
+        let mut last_err = None;
+
+        for (i, provider) in self.providers.iter().enumerate() {
+            let name = &self.models[i];
+            if !self.acquire(i, name) {
+                continue;
+            }
+
+            match provider.chat_structured(messages, schema).await {
+                Ok(result) => {
+                    self.health.record_response(name, now_secs(), true);
+                    return Ok(result);
                 }
+                Err(e) => last_err = Some(self.handle_error(i, name, e)?),
             }
         }
 
-        Err(last_err.unwrap_or_else(|| {
-            anyhow::anyhow!(
-                "All {} providers in cooldown or unavailable",
-                self.providers.len()
-            )
-        }))
+        Err(self.exhausted(last_err))
     }
 
     async fn chat_stream(
@@ -180,41 +489,21 @@ impl LLMProvider for FailoverProvider {
         let mut last_err = None;
 
         for (i, provider) in self.providers.iter().enumerate() {
-            if self.is_in_cooldown(i) {
-                warn!("Provider {} ({}) in cooldown, skipping", i, provider.name());
+            let name = &self.models[i];
+            if !self.acquire(i, name) {
                 continue;
             }
 
             match provider.chat_stream(messages, tools).await {
-                Ok(result) => return Ok(result),
-                Err(e) if Self::is_retryable(&e) => {
-                    warn!(
-                        "Provider {} ({}) failed (retryable): {}, trying next",
-                        i,
-                        provider.name(),
-                        e
-                    );
-                    self.set_cooldown(i);
-                    last_err = Some(e);
-                }
-                Err(e) => {
-                    warn!(
-                        "Provider {} ({}) failed (non-retryable): {}",
-                        i,
-                        provider.name(),
-                        e
-                    );
-                    return Err(e);
+                Ok(result) => {
+                    self.health.record_response(name, now_secs(), true);
+                    return Ok(result);
                 }
+                Err(e) => last_err = Some(self.handle_error(i, name, e)?),
             }
         }
 
-        Err(last_err.unwrap_or_else(|| {
-            anyhow::anyhow!(
-                "All {} providers in cooldown or unavailable",
-                self.providers.len()
-            )
-        }))
+        Err(self.exhausted(last_err))
     }
 
     fn reset_session(&self) {
@@ -228,46 +517,281 @@ impl LLMProvider for FailoverProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::providers::LLMResponseContent;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Whether the failover loop would move on to the next provider
+    fn is_retryable(err: &anyhow::Error) -> bool {
+        ProviderError::classify(err).is_some_and(|e| e.is_retryable())
+    }
+
+    fn provider_err(err: ProviderError) -> anyhow::Error {
+        err.into()
+    }
+
+    fn rate_limited(retry_after: Option<Duration>) -> ProviderError {
+        ProviderError::RateLimited {
+            provider: "primary".to_string(),
+            retry_after,
+            message: "Too Many Requests".to_string(),
+        }
+    }
+
+    /// Provider that fails with a rate limit for its first `failures` calls
+    struct FlakyProvider {
+        name: &'static str,
+        failures: usize,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LLMProvider for FlakyProvider {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        async fn chat(
+            &self,
+            _messages: &[Message],
+            _tools: Option<&[ToolSchema]>,
+        ) -> Result<LLMResponse> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call < self.failures {
+                Err(rate_limited(Some(Duration::from_secs(45))).into())
+            } else {
+                Ok(LLMResponse::text(self.name.to_string()))
+            }
+        }
+
+        async fn summarize(&self, _text: &str) -> Result<String> {
+            Ok(String::new())
+        }
+    }
 
     #[test]
     fn test_is_retryable_rate_limit() {
-        let err = anyhow::anyhow!("Error: 429 Too Many Requests");
-        assert!(FailoverProvider::is_retryable(&err));
-
-        let err = anyhow::anyhow!("Rate limit exceeded");
-        assert!(FailoverProvider::is_retryable(&err));
+        let err = provider_err(rate_limited(Some(Duration::from_secs(30))));
+        assert!(is_retryable(&err));
     }
 
     #[test]
     fn test_is_retryable_server_error() {
-        let err = anyhow::anyhow!("Error: 500 Internal Server Error");
-        assert!(FailoverProvider::is_retryable(&err));
+        for status in [500, 502, 503, 529] {
+            let err = provider_err(ProviderError::Server {
+                provider: "anthropic".to_string(),
+                status,
+                message: "unavailable".to_string(),
+            });
+            assert!(is_retryable(&err));
+        }
+    }
 
-        let err = anyhow::anyhow!("Error: 502 Bad Gateway");
-        assert!(FailoverProvider::is_retryable(&err));
+    #[test]
+    fn test_is_retryable_network_error() {
+        let err = provider_err(ProviderError::Transport {
+            provider: "ollama".to_string(),
+            message: "connection failed: Connection refused".to_string(),
+        });
+        assert!(is_retryable(&err));
+
+        // Context added by callers must not hide the cause
+        let err = err.context("chat request failed");
+        assert!(is_retryable(&err));
+    }
 
-        let err = anyhow::anyhow!("Error: 503 Service Unavailable");
-        assert!(FailoverProvider::is_retryable(&err));
+    #[test]
+    fn test_is_not_retryable_auth_error() {
+        let err = provider_err(ProviderError::Auth {
+            provider: "openai".to_string(),
+            message: "Unauthorized".to_string(),
+        });
+        assert!(!is_retryable(&err));
+
+        let err = provider_err(ProviderError::BadRequest {
+            provider: "openai".to_string(),
+            message: "Bad Request".to_string(),
+        });
+        assert!(!is_retryable(&err));
+
+        let err = provider_err(ProviderError::ContextOverflow {
+            provider: "openai".to_string(),
+            message: "maximum context length is 128000 tokens".to_string(),
+        });
+        assert!(!is_retryable(&err));
     }
 
     #[test]
-    fn test_is_retryable_network_error() {
-        let err = anyhow::anyhow!("Connection refused");
-        assert!(FailoverProvider::is_retryable(&err));
+    fn test_untyped_errors_are_not_sniffed() {
+        // Messages that merely mention status codes or timeouts are not retryable
+        let err = anyhow::anyhow!("max_tokens must be at most 500");
+        assert!(!is_retryable(&err));
 
-        let err = anyhow::anyhow!("Request timed out");
-        assert!(FailoverProvider::is_retryable(&err));
+        let err = anyhow::anyhow!("Error: 429 Too Many Requests");
+        assert!(!is_retryable(&err));
 
-        let err = anyhow::anyhow!("Connection reset by peer");
-        assert!(FailoverProvider::is_retryable(&err));
+        let err = anyhow::anyhow!("tool 'bash' timed out");
+        assert!(!is_retryable(&err));
     }
 
     #[test]
-    fn test_is_not_retryable_auth_error() {
-        let err = anyhow::anyhow!("Error: 401 Unauthorized");
-        assert!(!FailoverProvider::is_retryable(&err));
+    fn test_cooldown_prefers_retry_after_and_backs_off() {
+        assert_eq!(
+            cooldown_for(1, Some(Duration::from_secs(12))),
+            Duration::from_secs(12)
+        );
+        assert_eq!(
+            cooldown_for(3, Some(Duration::from_millis(200))),
+            Duration::from_secs(1)
+        );
+        assert_eq!(cooldown_for(1, None), Duration::from_secs(30));
+        assert_eq!(cooldown_for(2, None), Duration::from_secs(60));
+        assert_eq!(cooldown_for(3, None), Duration::from_secs(120));
+        assert_eq!(
+            cooldown_for(20, None),
+            Duration::from_secs(MAX_COOLDOWN_SECS)
+        );
+        assert_eq!(
+            cooldown_for(1, Some(Duration::from_secs(86_400))),
+            Duration::from_secs(MAX_COOLDOWN_SECS)
+        );
+    }
+
+    #[test]
+    fn test_circuit_opens_half_opens_and_closes() {
+        let registry = ProviderHealthRegistry::in_memory();
+        let now = 1_000;
+
+        assert!(registry.try_acquire("openai", now));
+        let cooldown = registry.record_failure("openai", &rate_limited(None), now);
+        assert_eq!(cooldown, Duration::from_secs(30));
+
+        // Open: skipped until the cooldown expires
+        assert!(!registry.try_acquire("openai", now + 10));
+        let health = &registry.snapshot_at(now + 10)[0];
+        assert_eq!(health.state, CircuitState::Open);
+        assert_eq!(health.retry_in_secs, Some(20));
+
+        // Half-open: exactly one probe is let through
+        assert!(registry.try_acquire("openai", now + 30));
+        assert!(!registry.try_acquire("openai", now + 31));
+
+        // Probe fails: circuit reopens with a doubled cooldown
+        let cooldown = registry.record_failure("openai", &rate_limited(None), now + 32);
+        assert_eq!(cooldown, Duration::from_secs(60));
+        assert!(!registry.try_acquire("openai", now + 60));
+
+        // Probe succeeds: circuit closes and the backoff resets
+        assert!(registry.try_acquire("openai", now + 92));
+        registry.record_response("openai", now + 93, true);
+        assert!(registry.try_acquire("openai", now + 94));
+        let health = &registry.snapshot_at(now + 94)[0];
+        assert_eq!(health.state, CircuitState::Closed);
+        assert_eq!(health.consecutive_failures, 0);
+        assert_eq!(health.last_success_at, Some(now + 93));
+    }
+
+    #[test]
+    fn test_lost_probe_is_retried() {
+        let registry = ProviderHealthRegistry::in_memory();
+        registry.record_failure("xai", &rate_limited(Some(Duration::from_secs(5))), 0);
+
+        assert!(registry.try_acquire("xai", 10));
+        assert!(!registry.try_acquire("xai", 11));
+        assert!(registry.try_acquire("xai", 10 + PROBE_TIMEOUT_SECS));
+    }
+
+    #[test]
+    fn test_health_persists_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("provider_health.json");
+
+        let registry = ProviderHealthRegistry::load(&path);
+        let now = now_secs();
+        registry.record_failure(
+            "anthropic",
+            &rate_limited(Some(Duration::from_secs(600))),
+            now,
+        );
+        drop(registry);
+
+        let reloaded = ProviderHealthRegistry::load(&path);
+        assert!(!reloaded.try_acquire("anthropic", now + 1));
+        let health = reloaded.get("anthropic");
+        assert_eq!(health.state, CircuitState::Open);
+        assert!(
+            health
+                .last_error
+                .as_deref()
+                .unwrap()
+                .contains("rate limited")
+        );
+    }
+
+    /// A `FlakyProvider` named `name` serving `model` in a failover chain
+    fn chain_entry(
+        model: &str,
+        name: &'static str,
+        failures: usize,
+    ) -> (String, Box<dyn LLMProvider>) {
+        (
+            model.to_string(),
+            Box::new(FlakyProvider {
+                name,
+                failures,
+                calls: AtomicUsize::new(0),
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_failover_skips_open_circuit() {
+        let registry = Arc::new(ProviderHealthRegistry::in_memory());
+        let provider = FailoverProvider::with_registry(
+            vec![
+                chain_entry("primary", "primary", 1),
+                chain_entry("fallback", "fallback", 0),
+            ],
+            Arc::clone(&registry),
+        );
+
+        for _ in 0..2 {
+            let response = provider.chat(&[], None).await.unwrap();
+            match response.content {
+                LLMResponseContent::Text(text) => assert_eq!(text, "fallback"),
+                _ => panic!("expected text"),
+            }
+        }
+
+        let health = provider.health();
+        assert_eq!(health[0].provider, "primary");
+        assert_eq!(health[0].state, CircuitState::Open);
+        assert_eq!(health[0].consecutive_failures, 1);
+        assert!(health[0].retry_in_secs.unwrap() > 40);
+        assert_eq!(health[1].state, CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_models_of_one_provider_have_separate_circuits() {
+        let registry = Arc::new(ProviderHealthRegistry::in_memory());
+        let provider = FailoverProvider::with_registry(
+            vec![
+                chain_entry("openai/gpt-4o", "openai", 1),
+                chain_entry("openai/gpt-4o-mini", "openai", 0),
+            ],
+            Arc::clone(&registry),
+        );
+
+        // The primary trips its circuit; the fallback of the same provider
+        // still answers, now and while the primary cools down
+        for _ in 0..2 {
+            provider.chat(&[], None).await.unwrap();
+        }
 
-        let err = anyhow::anyhow!("Error: 400 Bad Request");
-        assert!(!FailoverProvider::is_retryable(&err));
+        let health = provider.health();
+        assert_eq!(health[0].provider, "openai/gpt-4o");
+        assert_eq!(health[0].state, CircuitState::Open);
+        assert_eq!(health[1].provider, "openai/gpt-4o-mini");
+        assert_eq!(health[1].state, CircuitState::Closed);
+        assert!(health[1].last_success_at.is_some());
     }
 }
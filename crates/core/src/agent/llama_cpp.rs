@@ -0,0 +1,674 @@
+//! In-process chat inference for local GGUF models via llama.cpp
+//!
+//! `llamacpp/<model>` loads a GGUF file (from `providers.llamacpp.models_dir`
+//! unless the ID is a path) and runs it without a daemon. Prompts are built
+//! with the model's own chat template. Tools are offered in the system prompt
+//! and the reply is constrained by a GBNF grammar to either plain text or a
+//! single `{"name": ..., "arguments": {...}}` call.
+//!
+//! The provider itself requires the `llamacpp` feature (C++ toolchain); prompt
+//! layout, grammar and tool-call parsing are plain Rust.
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use super::providers::{Message, Role, ToolCall, ToolSchema};
+
+/// Render messages as (role, content) turns for a chat template.
+///
+/// Templates only know system/user/assistant, and many require the roles to
+/// alternate, so tool calls become JSON in the assistant turn, tool results
+/// become user turns, and adjacent turns with the same role are merged.
+pub fn chat_turns(messages: &[Message], tools: Option<&[ToolSchema]>) -> Vec<(String, String)> {
+    let mut turns: Vec<(String, String)> = Vec::new();
+    let mut push = |role: &str, content: String| {
+        if content.is_empty() {
+            return;
+        }
+        match turns.last_mut() {
+            Some((last_role, last)) if last_role == role => {
+                last.push_str("\n\n");
+                last.push_str(&content);
+            }
+            _ => turns.push((role.to_string(), content)),
+        }
+    };
+
+    let tool_prompt = tools.filter(|t| !t.is_empty()).map(tool_instructions);
+    let has_system = messages.iter().any(|m| m.role == Role::System);
+    if !has_system && let Some(ref prompt) = tool_prompt {
+        push("system", prompt.clone());
+    }
+
+    for (i, m) in messages.iter().enumerate() {
+        match m.role {
+            Role::System => {
+                let mut content = m.content.clone();
+                // Tool instructions go after the first system message
+                if let Some(ref prompt) = tool_prompt
+                    && messages.iter().position(|m| m.role == Role::System) == Some(i)
+                {
+                    content.push_str("\n\n");
+                    content.push_str(prompt);
+                }
+                push("system", content);
+            }
+            Role::User => push("user", m.content.clone()),
+            Role::Assistant => {
+                let mut content = m.content.clone();
+                for call in m.tool_calls.iter().flatten() {
+                    if !content.is_empty() {
+                        content.push('\n');
+                    }
+                    content.push_str(&tool_call_json(call));
+                }
+                push("assistant", content);
+            }
+            Role::Tool => {
+                let name = m
+                    .tool_call_id
+                    .as_deref()
+                    .and_then(|id| tool_name_for(&messages[..i], id))
+                    .unwrap_or("tool");
+                push("user", format!("Result of {}:\n{}", name, m.content));
+            }
+        }
+    }
+
+    turns
+}
+
+/// System prompt section describing the available tools
+fn tool_instructions(tools: &[ToolSchema]) -> String {
+    let mut prompt = String::from(
+        "## Tools\n\n\
+         To call a tool, reply with only a JSON object of the form \
+         {\"name\": \"<tool>\", \"arguments\": {...}} and nothing else. \
+         The result will be sent back to you. Otherwise, answer normally.\n",
+    );
+    for tool in tools {
+        prompt.push_str(&format!(
+            "\n- {}: {}\n  parameters: {}",
+            tool.name, tool.description, tool.parameters
+        ));
+    }
+    prompt
+}
+
+fn tool_call_json(call: &ToolCall) -> String {
+    let arguments: Value =
+        serde_json::from_str(&call.arguments).unwrap_or_else(|_| Value::Object(Default::default()));
+    serde_json::json!({ "name": call.name, "arguments": arguments }).to_string()
+}
+
+/// Name of the tool an earlier assistant turn called with `id`
+fn tool_name_for<'a>(history: &'a [Message], id: &str) -> Option<&'a str> {
+    history
+        .iter()
+        .rev()
+        .flat_map(|m| m.tool_calls.iter().flatten())
+        .find(|c| c.id == id)
+        .map(|c| c.name.as_str())
+}
+
+/// GBNF grammar for a reply that is either free text or one tool call.
+///
+/// Text may not start with `{`, so a reply that opens a JSON object must be a
+/// complete call to one of `tools`.
+pub fn tool_call_grammar(tools: &[ToolSchema]) -> String {
+    let names = tools
+        .iter()
+        .map(|t| format!("\"\\\"{}\\\"\"", gbnf_escape(&t.name)))
+        .collect::<Vec<_>>()
+        .join(" | ");
+
+    format!(
+        r#"root ::= call | answer
+answer ::= [^{{\x00] [^\x00]*
+call ::= "{{" ws "\"name\"" ws ":" ws name ws "," ws "\"arguments\"" ws ":" ws object ws "}}"
+name ::= {names}
+value ::= object | array | string | number | "true" | "false" | "null"
+object ::= "{{" ws ( string ws ":" ws value ( ws "," ws string ws ":" ws value )* )? ws "}}"
+array ::= "[" ws ( value ( ws "," ws value )* )? ws "]"
+string ::= "\"" ( [^"\\\x7F\x00-\x1F] | "\\" ( ["\\/bfnrt] | "u" [0-9a-fA-F] [0-9a-fA-F] [0-9a-fA-F] [0-9a-fA-F] ) )* "\""
+number ::= "-"? ( "0" | [1-9] [0-9]* ) ( "." [0-9]+ )? ( [eE] [-+]? [0-9]+ )?
+ws ::= [ \t\n]*
+"#
+    )
+}
+
+/// Escape a literal for use inside a GBNF string (which is itself quoted JSON)
+fn gbnf_escape(s: &str) -> String {
+    s.replace('\\', "\\\\\\\\").replace('"', "\\\\\\\"")
+}
+
+/// Parse a reply produced under `tool_call_grammar` into a tool call.
+/// Returns `None` for plain-text answers and unknown tools.
+pub fn parse_tool_call(text: &str, tools: &[ToolSchema]) -> Option<ToolCall> {
+    let value: Value = serde_json::from_str(text.trim()).ok()?;
+    let name = value.get("name")?.as_str()?;
+    if !tools.iter().any(|t| t.name == name) {
+        return None;
+    }
+    let arguments = match value.get("arguments") {
+        Some(args @ Value::Object(_)) => args.to_string(),
+        _ => "{}".to_string(),
+    };
+    Some(ToolCall {
+        id: format!("call_{}", uuid::Uuid::new_v4().simple()),
+        name: name.to_string(),
+        arguments,
+        signature: None,
+    })
+}
+
+/// Locate the GGUF file for a `llamacpp/<model>` ID: a path (absolute or
+/// existing relative to the working directory), otherwise a file in
+/// `models_dir`. `.gguf` is appended when missing.
+pub fn model_path(model_id: &str, models_dir: &str) -> PathBuf {
+    let mut id = shellexpand::tilde(model_id).into_owned();
+    // Not `Path::extension`: model names are full of dots ("qwen2.5-7b")
+    if !id.to_lowercase().ends_with(".gguf") {
+        id.push_str(".gguf");
+    }
+    let path = PathBuf::from(id);
+    if path.is_absolute() || path.exists() {
+        return path;
+    }
+    Path::new(&shellexpand::tilde(models_dir).into_owned()).join(path)
+}
+
+// llama.cpp backend
+
+/// llama.cpp may only be initialized once per process; chat models and GGUF
+/// embeddings share this backend.
+#[cfg(any(feature = "gguf", feature = "llamacpp"))]
+pub(crate) fn shared_backend()
+-> anyhow::Result<std::sync::Arc<llama_cpp_2::llama_backend::LlamaBackend>> {
+    use llama_cpp_2::llama_backend::LlamaBackend;
+    use std::sync::{Arc, Mutex, OnceLock};
+
+    static BACKEND: OnceLock<Mutex<Option<Arc<LlamaBackend>>>> = OnceLock::new();
+    let mut slot = BACKEND
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(backend) = slot.as_ref() {
+        return Ok(Arc::clone(backend));
+    }
+    let mut backend = LlamaBackend::init()?;
+    backend.void_logs();
+    let backend = Arc::new(backend);
+    *slot = Some(Arc::clone(&backend));
+    Ok(backend)
+}
+
+#[cfg(feature = "llamacpp")]
+pub use provider::LlamaCppChatProvider;
+
+#[cfg(feature = "llamacpp")]
+mod provider {
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use llama_cpp_2::context::params::LlamaContextParams;
+    use llama_cpp_2::llama_backend::LlamaBackend;
+    use llama_cpp_2::llama_batch::LlamaBatch;
+    use llama_cpp_2::model::params::LlamaModelParams;
+    use llama_cpp_2::model::{AddBos, LlamaChatMessage, LlamaChatTemplate, LlamaModel, Special};
+    use llama_cpp_2::sampling::LlamaSampler;
+    use std::collections::HashMap;
+    use std::num::NonZeroU32;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+    use tokio::sync::mpsc;
+    use tracing::debug;
+
+    use super::{chat_turns, parse_tool_call, shared_backend, tool_call_grammar};
+    use crate::agent::providers::{
+        LLMProvider, LLMResponse, LLMResponseContent, Message, Role, StreamChunk, StreamResult,
+        ToolSchema, Usage,
+    };
+    use crate::config::LlamaCppConfig;
+
+    /// Tokens decoded per batch while ingesting the prompt
+    const PROMPT_BATCH: usize = 512;
+
+    type SharedModel = Arc<StdMutex<LlamaModel>>;
+
+    /// Loaded models by path, so new sessions don't reload multi-GB files
+    fn load_model(backend: &LlamaBackend, path: &Path, gpu_layers: u32) -> Result<SharedModel> {
+        static MODELS: OnceLock<StdMutex<HashMap<PathBuf, SharedModel>>> = OnceLock::new();
+        let mut models = MODELS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(model) = models.get(path) {
+            return Ok(Arc::clone(model));
+        }
+
+        if !path.exists() {
+            anyhow::bail!(
+                "GGUF model file not found: '{}'.\n\
+                 Download a chat model (e.g. from https://huggingface.co/models?library=gguf) \
+                 into providers.llamacpp.models_dir or pass a path: llamacpp/<file.gguf>",
+                path.display()
+            );
+        }
+        debug!("Loading GGUF chat model: {}", path.display());
+        let params = LlamaModelParams::default().with_n_gpu_layers(gpu_layers);
+        let model = Arc::new(StdMutex::new(LlamaModel::load_from_file(
+            backend, path, &params,
+        )?));
+        models.insert(path.to_path_buf(), Arc::clone(&model));
+        Ok(model)
+    }
+
+    /// Sampling settings for one generation
+    #[derive(Clone)]
+    struct GenerateParams {
+        context_size: u32,
+        threads: Option<i32>,
+        temperature: f32,
+        max_tokens: usize,
+    }
+
+    /// Output of one generation
+    struct Generation {
+        text: String,
+        prompt_tokens: u64,
+        output_tokens: u64,
+    }
+
+    /// Chat provider running a GGUF model in-process
+    pub struct LlamaCppChatProvider {
+        model: SharedModel,
+        backend: Arc<LlamaBackend>,
+        model_name: String,
+        params: GenerateParams,
+    }
+
+    impl LlamaCppChatProvider {
+        pub fn new(path: &Path, config: &LlamaCppConfig, max_tokens: usize) -> Result<Self> {
+            let backend = shared_backend()?;
+            let model = load_model(&backend, path, config.gpu_layers)?;
+            let model_name = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("gguf")
+                .to_string();
+
+            Ok(Self {
+                model,
+                backend,
+                model_name,
+                params: GenerateParams {
+                    context_size: config.context_size,
+                    threads: config.threads,
+                    temperature: config.temperature,
+                    max_tokens,
+                },
+            })
+        }
+
+        /// Run a generation on a blocking thread, sending text pieces to `pieces`
+        async fn generate(
+            &self,
+            messages: &[Message],
+            tools: Option<&[ToolSchema]>,
+            pieces: Option<mpsc::UnboundedSender<String>>,
+        ) -> Result<Generation> {
+            let turns = chat_turns(messages, tools);
+            let grammar = tools.filter(|t| !t.is_empty()).map(tool_call_grammar);
+            let model = Arc::clone(&self.model);
+            let backend = Arc::clone(&self.backend);
+            let params = self.params.clone();
+
+            tokio::task::spawn_blocking(move || {
+                let model = model
+                    .lock()
+                    .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+                generate_blocking(&model, &backend, &params, turns, grammar, pieces)
+            })
+            .await?
+        }
+
+        fn response(generation: Generation, tools: Option<&[ToolSchema]>) -> LLMResponse {
+            let usage = Usage {
+                input_tokens: generation.prompt_tokens,
+                output_tokens: generation.output_tokens,
+                ..Default::default()
+            };
+            match tools.and_then(|t| parse_tool_call(&generation.text, t)) {
+                Some(call) => LLMResponse::tool_calls_with_usage(vec![call], usage),
+                None => LLMResponse::text_with_usage(generation.text.trim().to_string(), usage),
+            }
+        }
+    }
+
+    fn generate_blocking(
+        model: &LlamaModel,
+        backend: &LlamaBackend,
+        params: &GenerateParams,
+        turns: Vec<(String, String)>,
+        grammar: Option<String>,
+        pieces: Option<mpsc::UnboundedSender<String>>,
+    ) -> Result<Generation> {
+        // Models without an embedded template get ChatML, the most common format
+        let template = match model.chat_template(None) {
+            Ok(template) => template,
+            Err(_) => LlamaChatTemplate::new("chatml")?,
+        };
+        let chat = turns
+            .into_iter()
+            .map(|(role, content)| LlamaChatMessage::new(role, content))
+            .collect::<Result<Vec<_>, _>>()?;
+        let prompt = model.apply_chat_template(&template, &chat, true)?;
+
+        // Most templates already start with the BOS token
+        let bos = model.token_to_str(model.token_bos(), Special::Tokenize)?;
+        let add_bos = if !bos.is_empty() && prompt.starts_with(&bos) {
+            AddBos::Never
+        } else {
+            AddBos::Always
+        };
+        let tokens = model.str_to_token(&prompt, add_bos)?;
+        let n_ctx = params.context_size as usize;
+        if tokens.len() >= n_ctx {
+            anyhow::bail!(
+                "Prompt is {} tokens, but the llama.cpp context is {} (providers.llamacpp.context_size)",
+                tokens.len(),
+                n_ctx
+            );
+        }
+
+        let mut ctx_params = LlamaContextParams::default()
+            .with_n_ctx(NonZeroU32::new(params.context_size))
+            .with_n_batch(PROMPT_BATCH as u32);
+        if let Some(threads) = params.threads {
+            ctx_params = ctx_params
+                .with_n_threads(threads)
+                .with_n_threads_batch(threads);
+        }
+        let mut ctx = model.new_context(backend, ctx_params)?;
+
+        // Ingest the prompt; only the last token needs logits
+        let mut batch = LlamaBatch::new(PROMPT_BATCH, 1);
+        for (chunk_index, chunk) in tokens.chunks(PROMPT_BATCH).enumerate() {
+            batch.clear();
+            for (i, token) in chunk.iter().enumerate() {
+                let pos = chunk_index * PROMPT_BATCH + i;
+                batch.add(*token, pos as i32, &[0], pos == tokens.len() - 1)?;
+            }
+            ctx.decode(&mut batch)?;
+        }
+
+        let mut samplers = Vec::new();
+        if let Some(ref grammar) = grammar {
+            samplers.push(LlamaSampler::grammar(model, grammar, "root")?);
+        }
+        if params.temperature > 0.0 {
+            samplers.push(LlamaSampler::temp(params.temperature));
+            samplers.push(LlamaSampler::dist(rand::random()));
+        } else {
+            samplers.push(LlamaSampler::greedy());
+        }
+        let mut sampler = LlamaSampler::chain_simple(samplers);
+
+        let limit = n_ctx.min(tokens.len() + params.max_tokens);
+        let mut n_cur = tokens.len();
+        let mut text = String::new();
+        // Tokens can split multi-byte characters; hold bytes until they are valid UTF-8
+        let mut pending: Vec<u8> = Vec::new();
+
+        while n_cur < limit {
+            let token = sampler.sample(&ctx, batch.n_tokens() - 1);
+            if model.is_eog_token(token) {
+                break;
+            }
+
+            pending.extend(model.token_to_bytes(token, Special::Plaintext)?);
+            let valid = match std::str::from_utf8(&pending) {
+                Ok(s) => s.len(),
+                Err(e) => e.valid_up_to(),
+            };
+            if valid > 0 {
+                let piece = String::from_utf8_lossy(&pending[..valid]).into_owned();
+                pending.drain(..valid);
+                text.push_str(&piece);
+                if let Some(ref tx) = pieces
+                    && tx.send(piece).is_err()
+                {
+                    // Receiver dropped: the stream was cancelled
+                    break;
+                }
+            }
+
+            batch.clear();
+            batch.add(token, n_cur as i32, &[0], true)?;
+            ctx.decode(&mut batch)?;
+            n_cur += 1;
+        }
+
+        Ok(Generation {
+            text,
+            prompt_tokens: tokens.len() as u64,
+            output_tokens: (n_cur - tokens.len()) as u64,
+        })
+    }
+
+    fn text_chunk(delta: String) -> StreamChunk {
+        StreamChunk {
+            delta,
+            done: false,
+            tool_calls: None,
+            usage: None,
+            thinking: None,
+            thinking_blocks: None,
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for LlamaCppChatProvider {
+        fn name(&self) -> String {
+            format!("llamacpp/{}", self.model_name)
+        }
+
+        async fn chat(
+            &self,
+            messages: &[Message],
+            tools: Option<&[ToolSchema]>,
+        ) -> Result<LLMResponse> {
+            let generation = self.generate(messages, tools, None).await?;
+            Ok(Self::response(generation, tools))
+        }
+
+        async fn summarize(&self, text: &str) -> Result<String> {
+            let messages = vec![Message {
+                role: Role::User,
+                content: format!(
+                    "Summarize the following conversation concisely, preserving key information and context:\n\n{}",
+                    text
+                ),
+                tool_calls: None,
+                tool_call_id: None,
+                images: Vec::new(),
+                thinking: Vec::new(),
+            }];
+
+            let response = self.chat(&messages, None).await?;
+            match response.content {
+                LLMResponseContent::Text(summary) => Ok(summary),
+                LLMResponseContent::ToolCalls(_) => {
+                    anyhow::bail!("Unexpected tool calls in summarization")
+                }
+            }
+        }
+
+        /// Streams text as it is generated. With tools, a reply that opens a
+        /// JSON object is held back and returned as a tool call when done.
+        async fn chat_stream(
+            &self,
+            messages: &[Message],
+            tools: Option<&[ToolSchema]>,
+        ) -> Result<StreamResult> {
+            let (tx, mut rx) = mpsc::unbounded_channel();
+            let tools_owned: Option<Vec<ToolSchema>> = tools.map(|t| t.to_vec());
+            let with_tools = tools.is_some_and(|t| !t.is_empty());
+
+            let this = Self {
+                model: Arc::clone(&self.model),
+                backend: Arc::clone(&self.backend),
+                model_name: self.model_name.clone(),
+                params: self.params.clone(),
+            };
+            let messages = messages.to_vec();
+            let generation = tokio::spawn(async move {
+                let result = this
+                    .generate(&messages, tools_owned.as_deref(), Some(tx))
+                    .await;
+                result.map(|g| (g, tools_owned))
+            });
+
+            let stream = async_stream::stream! {
+                // Until the first visible character we can't tell text from a tool call
+                let mut held = String::new();
+                let mut streaming = !with_tools;
+                while let Some(piece) = rx.recv().await {
+                    if streaming {
+                        yield Ok(text_chunk(piece));
+                        continue;
+                    }
+                    held.push_str(&piece);
+                    match held.trim_start().chars().next() {
+                        Some('{') | None => {}
+                        Some(_) => {
+                            streaming = true;
+                            yield Ok(text_chunk(held.trim_start().to_string()));
+                            held.clear();
+                        }
+                    }
+                }
+
+                match generation.await {
+                    Ok(Ok((generation, tools))) => {
+                        let response = Self::response(generation, tools.as_deref());
+                        let mut done = StreamChunk::from_response(response);
+                        if streaming {
+                            // Text was already streamed
+                            done.delta.clear();
+                        }
+                        yield Ok(done);
+                    }
+                    Ok(Err(e)) => yield Err(e),
+                    Err(e) => yield Err(e.into()),
+                }
+            };
+
+            Ok(Box::pin(stream))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(role: Role, content: &str) -> Message {
+        Message {
+            role,
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: Vec::new(),
+        }
+    }
+
+    fn tools() -> Vec<ToolSchema> {
+        vec![ToolSchema {
+            name: "memory_search".to_string(),
+            description: "Search memory".to_string(),
+            parameters: json!({ "type": "object", "properties": { "query": { "type": "string" } } }),
+        }]
+    }
+
+    #[test]
+    fn test_chat_turns_render_tool_exchange() {
+        let mut assistant = message(Role::Assistant, "");
+        assistant.tool_calls = Some(vec![ToolCall {
+            id: "call_1".to_string(),
+            name: "memory_search".to_string(),
+            arguments: "{\"query\":\"rust\"}".to_string(),
+            signature: None,
+        }]);
+        let mut result = message(Role::Tool, "Rust notes");
+        result.tool_call_id = Some("call_1".to_string());
+        let messages = vec![
+            message(Role::System, "Be brief."),
+            message(Role::User, "What do I know about rust?"),
+            assistant,
+            result,
+            message(Role::User, "Summarize it"),
+        ];
+
+        let turns = chat_turns(&messages, Some(&tools()));
+        let roles: Vec<&str> = turns.iter().map(|(r, _)| r.as_str()).collect();
+        assert_eq!(roles, vec!["system", "user", "assistant", "user"]);
+        assert!(turns[0].1.starts_with("Be brief.\n\n## Tools"));
+        assert!(turns[0].1.contains("- memory_search: Search memory"));
+        assert_eq!(
+            turns[2].1,
+            "{\"arguments\":{\"query\":\"rust\"},\"name\":\"memory_search\"}"
+        );
+        assert_eq!(
+            turns[3].1,
+            "Result of memory_search:\nRust notes\n\nSummarize it"
+        );
+    }
+
+    #[test]
+    fn test_chat_turns_without_system_adds_tool_prompt() {
+        let turns = chat_turns(&[message(Role::User, "Hi")], Some(&tools()));
+        assert_eq!(turns[0].0, "system");
+        assert_eq!(turns[1], ("user".to_string(), "Hi".to_string()));
+
+        let turns = chat_turns(&[message(Role::User, "Hi")], None);
+        assert_eq!(turns.len(), 1);
+    }
+
+    #[test]
+    fn test_tool_call_grammar_lists_tool_names() {
+        let grammar = tool_call_grammar(&tools());
+        assert!(grammar.starts_with("root ::= call | answer\n"));
+        assert!(grammar.contains("name ::= \"\\\"memory_search\\\"\"\n"));
+        assert!(grammar.contains("answer ::= [^{\\x00] [^\\x00]*\n"));
+    }
+
+    #[test]
+    fn test_parse_tool_call() {
+        let call = parse_tool_call(
+            " {\"name\": \"memory_search\", \"arguments\": {\"query\": \"rust\"}}\n",
+            &tools(),
+        )
+        .unwrap();
+        assert_eq!(call.name, "memory_search");
+        assert_eq!(call.arguments, "{\"query\":\"rust\"}");
+        assert!(call.id.starts_with("call_"));
+
+        assert!(parse_tool_call("The answer is 42.", &tools()).is_none());
+        assert!(parse_tool_call("{\"name\": \"bash\", \"arguments\": {}}", &tools()).is_none());
+    }
+
+    #[test]
+    fn test_model_path() {
+        assert_eq!(
+            model_path("qwen2.5-7b-instruct-q4_k_m", "/models"),
+            PathBuf::from("/models/qwen2.5-7b-instruct-q4_k_m.gguf")
+        );
+        assert_eq!(
+            model_path("/opt/llm/phi-4.Q4_K_M.gguf", "/models"),
+            PathBuf::from("/opt/llm/phi-4.Q4_K_M.gguf")
+        );
+    }
+}
@@ -1,20 +1,43 @@
+pub mod approval;
+pub mod bedrock;
+pub mod cancel;
 pub mod failover;
 pub mod hardcoded_filters;
+pub mod llama_cpp;
+pub mod model_catalog;
 pub mod path_utils;
+pub mod provider_error;
 pub mod providers;
+pub mod replay;
 pub mod sanitize;
 pub mod session;
 pub mod session_pruning;
 pub mod session_store;
 pub mod skills;
+pub mod structured;
 pub mod system_prompt;
+pub mod tokenizer;
 pub mod tool_filters;
 pub mod tools;
+pub mod usage_ledger;
 
+#[cfg(test)]
+#[path = "./test/mock_http.rs"]
+pub(crate) mod mock_http;
+
+pub use approval::{
+    ApprovalBroker, ApprovalDecision, ApprovalEvent, ApprovalHandler, ApprovalPolicy,
+    ApprovalRequest, ToolApprovals,
+};
+pub use bedrock::{AwsCredentials, BedrockProvider};
+pub use cancel::{Cancelled, TurnCanceller};
+pub use model_catalog::{ModelCatalog, ModelPricing, ModelSpec, ResolvedModel};
+pub use provider_error::ProviderError;
 pub use providers::{
     ImageAttachment, LLMProvider, LLMResponse, LLMResponseContent, Message, Role, StreamChunk,
-    StreamEvent, StreamResult, ToolCall, ToolSchema, Usage,
+    StreamEvent, StreamResult, ThinkingBlock, ToolCall, ToolSchema, Usage,
 };
+pub use replay::{RecordingProvider, ReplayProvider};
 pub use sanitize::{
     EXTERNAL_CONTENT_END, EXTERNAL_CONTENT_START, MEMORY_CONTENT_END, MEMORY_CONTENT_START,
     MemorySource, SanitizeResult, TOOL_OUTPUT_END, TOOL_OUTPUT_START, detect_suspicious_patterns,
@@ -22,28 +45,35 @@ pub use sanitize::{
     wrap_tool_output,
 };
 pub use session::{
-    DEFAULT_AGENT_ID, Session, SessionInfo, SessionMessage, SessionSearchResult, SessionStatus,
-    get_last_session_id, get_last_session_id_for_agent, get_sessions_dir_for_agent, get_state_dir,
-    list_sessions, list_sessions_for_agent, search_sessions, search_sessions_for_agent,
+    BranchInfo, Compaction, DEFAULT_AGENT_ID, PinnedMessage, Session, SessionInfo, SessionMessage,
+    SessionSearchResult, SessionStatus, get_last_session_id, get_last_session_id_for_agent,
+    get_sessions_dir_for_agent, get_state_dir, list_sessions, list_sessions_for_agent,
+    search_sessions, search_sessions_for_agent,
 };
 pub use session_pruning::{PruneResult, preview_prune, prune_all_agents, prune_sessions};
 pub use session_store::{SessionEntry, SessionStore};
 pub use skills::{Skill, SkillInvocation, get_skills_summary, load_skills, parse_skill_command};
+pub use structured::{ResponseSchema, StructuredResponse};
 pub use system_prompt::{
     HEARTBEAT_OK_TOKEN, SILENT_REPLY_TOKEN, build_heartbeat_prompt, filter_silent_reply,
     is_heartbeat_ok, is_silent_reply,
 };
+pub use tokenizer::{TokenCounter, TokenizerInfo, TokenizerRegistry};
 pub use tools::{
     Tool, ToolResult, create_spawn_agent_tool, create_spawn_agent_tool_at_depth,
     extract_tool_detail,
 };
+pub use usage_ledger::{
+    BudgetExceeded, BudgetPeriod, UsageEntry, UsageGroup, UsageLedger, UsageSummary, summarize,
+};
 
 use anyhow::Result;
+use futures::FutureExt;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::{debug, info};
 
-use crate::config::{Config, SearchProviderType};
+use crate::config::{Config, ReasoningEffort, SearchProviderType};
 use crate::memory::{MemoryChunk, MemoryManager};
 
 /// Soft threshold buffer before compaction (tokens)
@@ -97,6 +127,27 @@ pub struct Agent {
     verified_security_policy: Option<String>,
     /// Loop detection for repeated tool calls
     loop_detector: LoopDetector,
+    /// Token counting for the current model, shared with the session
+    token_counter: TokenCounter,
+    /// Capabilities of the current model (from the model catalog)
+    model_spec: ModelSpec,
+    /// Whether `config.context_window` follows the model (no explicit override)
+    auto_context_window: bool,
+    /// Spend ledger (None if the state directory could not be resolved)
+    usage_ledger: Option<UsageLedger>,
+    /// Agent id and channel that ledger entries are attributed to
+    usage_agent_id: String,
+    usage_channel: String,
+    /// Model in use before a budget downgrade, restored once spend is back under the limit
+    budget_downgraded_from: Option<String>,
+    /// Reasoning effort chosen for this session (None follows the config)
+    reasoning: Option<ReasoningEffort>,
+    /// Approval rules and the decisions remembered for this session
+    approvals: ToolApprovals,
+    /// Asks the user about calls that need approval (None denies them)
+    approval_handler: Option<Arc<dyn ApprovalHandler>>,
+    /// Lets other tasks stop the turn in progress
+    canceller: TurnCanceller,
 }
 
 /// Detects when the agent is stuck in a tool-call loop
@@ -152,20 +203,27 @@ impl LoopDetector {
 
 impl Agent {
     pub async fn new(
-        config: AgentConfig,
+        mut config: AgentConfig,
         app_config: &Config,
         memory: Arc<MemoryManager>,
     ) -> Result<Self> {
         let primary_provider = providers::create_provider(&config.model, app_config)?;
+        let model_spec = ModelCatalog::from_config(app_config)
+            .resolve(&config.model)
+            .spec;
+        let auto_context_window = config.context_window == 0;
+        if auto_context_window {
+            config.context_window = model_spec.context_window;
+        }
 
         // Wrap with FailoverProvider if fallback_models configured
         let provider: Box<dyn LLMProvider> = if app_config.agent.fallback_models.is_empty() {
             primary_provider
         } else {
-            let mut providers_vec = vec![primary_provider];
+            let mut providers_vec = vec![(config.model.clone(), primary_provider)];
             for model in &app_config.agent.fallback_models {
                 match providers::create_provider(model, app_config) {
-                    Ok(p) => providers_vec.push(p),
+                    Ok(p) => providers_vec.push((model.clone(), p)),
                     Err(e) => {
                         tracing::warn!(
                             "Failed to create fallback provider for model '{}': {}",
@@ -184,9 +242,14 @@ impl Agent {
                 Box::new(failover::FailoverProvider::new(providers_vec))
             } else {
                 // Only primary available, no wrapping needed
-                providers_vec.remove(0)
+                providers_vec.remove(0).1
             }
         };
+        let token_counter = TokenizerRegistry::from_config(app_config).counter_for(&config.model);
+        let provider: Box<dyn LLMProvider> = Box::new(tokenizer::UsageReconcilingProvider::new(
+            provider,
+            token_counter.clone(),
+        ));
 
         // Memory is already wrapped in Arc, create safe tools sharing it
         let mut tools = tools::create_safe_tools(app_config, Some(Arc::clone(&memory)))?;
@@ -303,11 +366,13 @@ impl Agent {
             }
         };
 
+        let approvals = ToolApprovals::from_config(&app_config.tools)?;
+
         Ok(Self {
             config,
             app_config: app_config.clone(),
             provider,
-            session: Session::new(),
+            session: Session::with_token_counter(token_counter.clone()),
             memory,
             tools,
             cumulative_usage: Usage::default(),
@@ -316,19 +381,38 @@ impl Agent {
             search_cost_usd: 0.0,
             verified_security_policy,
             loop_detector: LoopDetector::new(app_config.agent.max_tool_repeats),
+            token_counter,
+            model_spec,
+            auto_context_window,
+            usage_ledger: UsageLedger::open_default().ok(),
+            usage_agent_id: DEFAULT_AGENT_ID.to_string(),
+            usage_channel: "unknown".to_string(),
+            budget_downgraded_from: None,
+            reasoning: None,
+            approvals,
+            approval_handler: None,
+            canceller: TurnCanceller::default(),
         })
     }
 
     /// Create an agent with custom pre-built tools (e.g., for Gen mode).
     pub fn new_with_tools(
         app_config: Config,
-        _agent_id: &str,
+        agent_id: &str,
         memory: Arc<MemoryManager>,
         tools: Vec<Box<dyn Tool>>,
     ) -> Result<Self> {
+        let model_spec = ModelCatalog::from_config(&app_config)
+            .resolve(&app_config.agent.default_model)
+            .spec;
+        let auto_context_window = app_config.agent.context_window == 0;
         let agent_config = AgentConfig {
             model: app_config.agent.default_model.clone(),
-            context_window: app_config.agent.context_window,
+            context_window: if auto_context_window {
+                model_spec.context_window
+            } else {
+                app_config.agent.context_window
+            },
             reserve_tokens: app_config.agent.reserve_tokens,
         };
         let primary_provider = providers::create_provider(&agent_config.model, &app_config)?;
@@ -337,10 +421,10 @@ impl Agent {
         let provider: Box<dyn LLMProvider> = if app_config.agent.fallback_models.is_empty() {
             primary_provider
         } else {
-            let mut providers_vec = vec![primary_provider];
+            let mut providers_vec = vec![(agent_config.model.clone(), primary_provider)];
             for model in &app_config.agent.fallback_models {
                 match providers::create_provider(model, &app_config) {
-                    Ok(p) => providers_vec.push(p),
+                    Ok(p) => providers_vec.push((model.clone(), p)),
                     Err(e) => {
                         tracing::warn!(
                             "Failed to create fallback provider for model '{}': {}",
@@ -358,9 +442,15 @@ impl Agent {
                 );
                 Box::new(failover::FailoverProvider::new(providers_vec))
             } else {
-                providers_vec.remove(0)
+                providers_vec.remove(0).1
             }
         };
+        let token_counter =
+            TokenizerRegistry::from_config(&app_config).counter_for(&agent_config.model);
+        let provider: Box<dyn LLMProvider> = Box::new(tokenizer::UsageReconcilingProvider::new(
+            provider,
+            token_counter.clone(),
+        ));
 
         // Load security policy
         let workspace = app_config.workspace_path();
@@ -375,12 +465,13 @@ impl Agent {
         };
 
         let max_tool_repeats = app_config.agent.max_tool_repeats;
+        let approvals = ToolApprovals::from_config(&app_config.tools)?;
 
         Ok(Self {
             config: agent_config,
             app_config,
             provider,
-            session: Session::new(),
+            session: Session::with_token_counter(token_counter.clone()),
             memory,
             tools,
             cumulative_usage: Usage::default(),
@@ -389,6 +480,17 @@ impl Agent {
             search_cost_usd: 0.0,
             verified_security_policy,
             loop_detector: LoopDetector::new(max_tool_repeats),
+            token_counter,
+            model_spec,
+            auto_context_window,
+            usage_ledger: UsageLedger::open_default().ok(),
+            usage_agent_id: agent_id.to_string(),
+            usage_channel: "gen".to_string(),
+            budget_downgraded_from: None,
+            reasoning: None,
+            approvals,
+            approval_handler: None,
+            canceller: TurnCanceller::default(),
         })
     }
 
@@ -401,13 +503,38 @@ impl Agent {
         &self.config.model
     }
 
-    /// Check if a tool requires user approval before execution
+    /// Check if calls to a tool can require user approval before execution
     pub fn requires_approval(&self, tool_name: &str) -> bool {
-        self.app_config
-            .tools
-            .require_approval
-            .iter()
-            .any(|t| t == tool_name)
+        self.approvals.applies_to(tool_name)
+    }
+
+    /// Check whether this particular call must be approved, applying the
+    /// [tools.approval] rules and "always allow" decisions from this session
+    pub fn needs_approval(&self, call: &ToolCall) -> bool {
+        self.approvals.needs_approval(call)
+    }
+
+    /// Remember a decision made outside the agent's own approval flow
+    pub fn record_approval(&mut self, call: &ToolCall, decision: ApprovalDecision) {
+        self.approvals.record(call, decision);
+    }
+
+    /// Install the handler asked about tool calls that need approval.
+    /// Without one, such calls are denied.
+    pub fn set_approval_handler(&mut self, handler: Arc<dyn ApprovalHandler>) {
+        self.approval_handler = Some(handler);
+    }
+
+    /// Handle for cancelling this agent's turns from another task. Take it
+    /// before starting a turn; the turn itself holds the agent.
+    pub fn canceller(&self) -> TurnCanceller {
+        self.canceller.clone()
+    }
+
+    /// Let `canceller` stop this agent's turns, e.g. one handed out before
+    /// the agent was created
+    pub fn set_canceller(&mut self, canceller: TurnCanceller) {
+        self.canceller = canceller;
     }
 
     /// Get the list of tools that require approval
@@ -417,13 +544,46 @@ impl Agent {
 
     /// Switch to a different model
     pub fn set_model(&mut self, model: &str) -> Result<()> {
-        let provider = providers::create_provider(model, &self.app_config)?;
+        let provider =
+            providers::create_provider_with_reasoning(model, &self.app_config, self.reasoning)?;
+        let token_counter = TokenizerRegistry::from_config(&self.app_config).counter_for(model);
         self.config.model = model.to_string();
-        self.provider = provider;
+        self.provider = Box::new(tokenizer::UsageReconcilingProvider::new(
+            provider,
+            token_counter.clone(),
+        ));
+        self.session.set_token_counter(token_counter.clone());
+        self.token_counter = token_counter;
+        self.model_spec = ModelCatalog::from_config(&self.app_config)
+            .resolve(model)
+            .spec;
+        if self.auto_context_window {
+            self.config.context_window = self.model_spec.context_window;
+        }
         info!("Switched to model: {}", model);
         Ok(())
     }
 
+    /// Reasoning effort requests will use with the current model
+    pub fn reasoning(&self) -> ReasoningEffort {
+        self.model_spec
+            .reasoning_effort(self.reasoning, self.app_config.agent.reasoning)
+    }
+
+    /// Override the reasoning effort for this session (None restores the
+    /// configured effort). Fails if the current model can't reason.
+    pub fn set_reasoning(&mut self, effort: Option<ReasoningEffort>) -> Result<()> {
+        if effort.is_some_and(|e| e.is_enabled()) && !self.model_spec.supports_reasoning {
+            anyhow::bail!(
+                "Model {} does not support reasoning effort (set supports_reasoning in [models] if it does)",
+                self.config.model
+            );
+        }
+        self.reasoning = effort;
+        let model = self.config.model.clone();
+        self.set_model(&model)
+    }
+
     pub fn memory_chunk_count(&self) -> usize {
         self.memory.chunk_count().unwrap_or(0)
     }
@@ -447,6 +607,16 @@ impl Agent {
         self.config.reserve_tokens
     }
 
+    /// Capabilities and limits of the current model
+    pub fn model_spec(&self) -> &ModelSpec {
+        &self.model_spec
+    }
+
+    /// Tokenizer and calibration used for context accounting
+    pub fn tokenizer_info(&self) -> TokenizerInfo {
+        self.token_counter.info()
+    }
+
     /// Get current context usage info
     pub fn context_usage(&self) -> (usize, usize, usize) {
         let used = self.session.token_count();
@@ -482,12 +652,80 @@ impl Agent {
         &self.cumulative_usage
     }
 
-    /// Add usage from an API response to cumulative totals
-    fn add_usage(&mut self, usage: Option<Usage>) {
+    /// Attribute ledger entries to an agent and channel ("cli", "telegram", "cron", ...)
+    pub fn set_usage_scope(&mut self, agent_id: &str, channel: &str) {
+        self.usage_agent_id = agent_id.to_string();
+        self.usage_channel = channel.to_string();
+    }
+
+    /// Add usage from an API response to cumulative totals and the usage ledger.
+    ///
+    /// Called internally for non-streaming calls; streaming callers pass the
+    /// usage from the final `StreamChunk`.
+    pub fn add_usage(&mut self, usage: Option<Usage>) {
         if let Some(u) = usage {
             self.cumulative_usage.input_tokens += u.input_tokens;
             self.cumulative_usage.output_tokens += u.output_tokens;
+            self.cumulative_usage.cache_read_tokens += u.cache_read_tokens;
+            self.cumulative_usage.cache_write_tokens += u.cache_write_tokens;
+            self.record_usage(UsageEntry::for_model(
+                &self.usage_agent_id,
+                &self.usage_channel,
+                &self.model_spec,
+                &u,
+            ));
+        }
+    }
+
+    fn record_usage(&self, entry: UsageEntry) {
+        if let Some(ledger) = &self.usage_ledger
+            && let Err(e) = ledger.record(&entry)
+        {
+            tracing::warn!("Failed to record usage: {}", e);
+        }
+    }
+
+    /// Enforce `[budget]` limits before starting a turn.
+    ///
+    /// With `action = "block"` this fails the turn; with `"downgrade"` it switches
+    /// to `downgrade_model` and switches back once spend is under the limit again.
+    fn enforce_budget(&mut self) -> Result<()> {
+        let budget = &self.app_config.budget;
+        let Some(ledger) = &self.usage_ledger else {
+            return Ok(());
+        };
+        if !budget.is_enabled() {
+            return Ok(());
+        }
+
+        let exceeded = match ledger.check_budget(budget, &self.usage_agent_id, chrono::Local::now())
+        {
+            Ok(exceeded) => exceeded,
+            Err(e) => {
+                tracing::warn!("Failed to read usage ledger: {}", e);
+                return Ok(());
+            }
+        };
+
+        let Some(exceeded) = exceeded else {
+            if let Some(original) = self.budget_downgraded_from.take() {
+                info!("Back under budget; restoring model {}", original);
+                self.set_model(&original)?;
+            }
+            return Ok(());
+        };
+
+        let downgrade = match (budget.action, &budget.downgrade_model) {
+            (crate::config::BudgetAction::Downgrade, Some(model)) => model.clone(),
+            _ => anyhow::bail!("{}. Raise the limit in [budget] to continue.", exceeded),
+        };
+        if self.config.model != downgrade {
+            tracing::warn!("{}; switching to {}", exceeded, downgrade);
+            let original = self.config.model.clone();
+            self.set_model(&downgrade)?;
+            self.budget_downgraded_from.get_or_insert(original);
         }
+        Ok(())
     }
 
     fn use_native_web_search(&self) -> bool {
@@ -501,6 +739,11 @@ impl Agent {
     }
 
     fn include_tool_for_provider(&self, tool_name: &str) -> bool {
+        // Models without function calling get no tools at all
+        if !self.model_spec.supports_tools {
+            return false;
+        }
+
         // Elide the web search tool if the provider support native search
         if tool_name == "web_search" {
             return !self.use_native_web_search();
@@ -553,7 +796,29 @@ impl Agent {
         if cached {
             self.search_cached_hits += 1;
         } else {
-            self.search_cost_usd += self.configured_search_cost_usd();
+            let cost = self.configured_search_cost_usd();
+            self.search_cost_usd += cost;
+            if cost > 0.0 {
+                let provider = self
+                    .app_config
+                    .tools
+                    .web_search
+                    .as_ref()
+                    .map(|ws| format!("{:?}", ws.provider).to_lowercase())
+                    .unwrap_or_default();
+                self.record_usage(UsageEntry {
+                    timestamp: chrono::Utc::now(),
+                    agent_id: self.usage_agent_id.clone(),
+                    channel: self.usage_channel.clone(),
+                    provider,
+                    model: "web_search".to_string(),
+                    input_tokens: 0,
+                    output_tokens: 0,
+                    cache_read_tokens: 0,
+                    cache_write_tokens: 0,
+                    cost_usd: cost,
+                });
+            }
         }
     }
 
@@ -601,6 +866,7 @@ impl Agent {
                     tool_calls: None,
                     tool_call_id: None,
                     images: Vec::new(),
+                    thinking: Vec::new(),
                 });
             }
         }
@@ -609,10 +875,11 @@ impl Agent {
     }
 
     pub async fn new_session(&mut self) -> Result<()> {
-        self.session = Session::new();
+        self.session = Session::with_token_counter(self.token_counter.clone());
         self.search_queries = 0;
         self.search_cached_hits = 0;
         self.search_cost_usd = 0.0;
+        self.approvals.reset_session();
 
         // Reset provider session state (e.g., clear Claude CLI session ID)
         self.provider.reset_session();
@@ -644,8 +911,10 @@ impl Agent {
             system_prompt
         } else {
             format!(
-                "{}\n\n---\n\n# Workspace Context\n\n{}",
-                system_prompt, memory_context
+                "{}{}{}",
+                system_prompt,
+                system_prompt::WORKSPACE_CONTEXT_SEPARATOR,
+                memory_context
             )
         };
 
@@ -657,10 +926,25 @@ impl Agent {
 
     pub async fn resume_session(&mut self, session_id: &str) -> Result<()> {
         self.session = Session::load(session_id)?;
+        self.session.set_token_counter(self.token_counter.clone());
+        self.approvals.reset_session();
         info!("Resumed session: {}", session_id);
         Ok(())
     }
 
+    /// Drop image attachments the current model cannot accept
+    fn supported_images(&self, images: Vec<ImageAttachment>) -> Vec<ImageAttachment> {
+        if images.is_empty() || self.model_spec.supports_images {
+            return images;
+        }
+        tracing::warn!(
+            "Model {} does not accept images; dropping {} attachment(s)",
+            self.config.model,
+            images.len()
+        );
+        Vec::new()
+    }
+
     pub async fn chat(&mut self, message: &str) -> Result<String> {
         self.chat_with_images(message, Vec::new()).await
     }
@@ -670,6 +954,9 @@ impl Agent {
         message: &str,
         images: Vec<ImageAttachment>,
     ) -> Result<String> {
+        self.enforce_budget()?;
+        self.canceller.begin_turn();
+
         // Reset loop detector for new turn
         self.loop_detector.reset();
 
@@ -679,9 +966,15 @@ impl Agent {
             content: message.to_string(),
             tool_calls: None,
             tool_call_id: None,
-            images,
+            images: self.supported_images(images),
+            thinking: Vec::new(),
         });
 
+        self.respond().await
+    }
+
+    /// Answer the user message at the end of the active branch
+    async fn respond(&mut self) -> Result<String> {
         // Check if we should run pre-compaction memory flush (soft threshold)
         if self.should_memory_flush() {
             info!("Running pre-compaction memory flush (soft threshold)");
@@ -701,8 +994,7 @@ impl Agent {
 
         // Invoke LLM
         let response = self
-            .provider
-            .chat(&messages, Some(tool_schemas.as_slice()))
+            .provider_chat(&messages, Some(tool_schemas.as_slice()))
             .await?;
 
         // Handle token update if refreshed during chat
@@ -722,11 +1014,129 @@ impl Agent {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         });
 
         Ok(final_response)
     }
 
+    /// Replace the last reply with a fresh one. The previous reply stays in
+    /// the session tree as another branch.
+    pub async fn regenerate(&mut self) -> Result<String> {
+        let Some(user_id) = self.session.last_user_message_id().map(str::to_string) else {
+            anyhow::bail!("No user message to regenerate a reply for");
+        };
+
+        self.enforce_budget()?;
+        self.canceller.begin_turn();
+        self.loop_detector.reset();
+        self.fork_at(&user_id)?;
+        self.respond().await
+    }
+
+    /// Replace an earlier user message and answer it, forking a new branch
+    /// from just before that message.
+    pub async fn edit_message(&mut self, message_id: &str, content: &str) -> Result<String> {
+        let original = self.session.active_message(message_id)?;
+        if original.message.role != Role::User {
+            anyhow::bail!("Only user messages can be edited");
+        }
+        let id = original.id.clone();
+        let images = original.message.images.clone();
+
+        self.session.fork_before(&id)?;
+        // CLI-backed providers keep their own linear history
+        self.provider.reset_session();
+        self.chat_with_images(content, images).await
+    }
+
+    /// Cut the active branch after `message_id`; the next message starts a
+    /// new branch from there.
+    pub fn fork_at(&mut self, message_id: &str) -> Result<()> {
+        self.session.fork_at(message_id)?;
+        self.provider.reset_session();
+        Ok(())
+    }
+
+    /// Switch to the branch through `message_id` (a full ID or unique prefix)
+    pub fn switch_branch(&mut self, message_id: &str) -> Result<()> {
+        self.session.switch_branch(message_id)?;
+        self.provider.reset_session();
+        Ok(())
+    }
+
+    /// Branches of the current session tree, oldest first
+    pub fn branches(&self) -> Vec<BranchInfo> {
+        self.session.branches()
+    }
+
+    /// ID of the latest user message on the active branch
+    pub fn last_user_message_id(&self) -> Option<&str> {
+        self.session.last_user_message_id()
+    }
+
+    /// Pin `note` for the rest of the session, or the latest user or
+    /// assistant message when no note is given
+    pub fn pin(&mut self, note: Option<&str>) -> Result<PinnedMessage> {
+        if let Some(note) = note {
+            return Ok(self.session.pin_note(note).clone());
+        }
+
+        let Some(id) = self
+            .session
+            .raw_messages()
+            .iter()
+            .rev()
+            .find(|sm| {
+                matches!(sm.message.role, Role::User | Role::Assistant)
+                    && !sm.message.content.is_empty()
+            })
+            .map(|sm| sm.id.clone())
+        else {
+            anyhow::bail!("No message to pin");
+        };
+        Ok(self.session.pin_message(&id)?.clone())
+    }
+
+    pub fn unpin(&mut self, id: &str) -> Result<()> {
+        self.session.unpin(id)
+    }
+
+    pub fn pinned_messages(&self) -> &[PinnedMessage] {
+        self.session.pinned()
+    }
+
+    /// Instructions that apply to this session only
+    pub fn session_instructions(&self) -> Option<&str> {
+        self.session.instructions()
+    }
+
+    pub fn set_session_instructions(&mut self, instructions: Option<String>) {
+        self.session.set_instructions(instructions);
+    }
+
+    /// Stateless chat whose reply must match `schema` (OpenAI API `response_format`).
+    ///
+    /// Like `chat_with_messages` this does not modify the session; no tools are
+    /// offered and no system prompt is added.
+    pub async fn chat_structured(
+        &mut self,
+        messages: &[Message],
+        schema: &ResponseSchema,
+    ) -> Result<StructuredResponse> {
+        self.enforce_budget()?;
+        self.canceller.begin_turn();
+
+        let response = self
+            .canceller
+            .run(self.provider.chat_structured(messages, schema))
+            .await?;
+        let _ = self.handle_token_update();
+        self.add_usage(response.usage.clone());
+
+        Ok(response)
+    }
+
     /// Stateless chat with provided messages (for OpenAI API compatibility)
     ///
     /// This method takes a list of messages directly and does NOT modify the session.
@@ -737,6 +1147,9 @@ impl Agent {
         messages: &[Message],
         tools: Option<&[ToolSchema]>,
     ) -> Result<LLMResponse> {
+        self.enforce_budget()?;
+        self.canceller.begin_turn();
+
         // Reset loop detector for this call
         self.loop_detector.reset();
 
@@ -764,6 +1177,7 @@ impl Agent {
                 tool_calls: None,
                 tool_call_id: None,
                 images: Vec::new(),
+                thinking: Vec::new(),
             });
         }
 
@@ -778,8 +1192,7 @@ impl Agent {
 
         // Invoke LLM
         let response = self
-            .provider
-            .chat(&api_messages, Some(tool_schemas.as_slice()))
+            .provider_chat(&api_messages, Some(tool_schemas.as_slice()))
             .await?;
 
         // Handle token update if refreshed during chat
@@ -813,16 +1226,11 @@ impl Agent {
                     tool_calls: Some(calls.clone()),
                     tool_call_id: None,
                     images: Vec::new(),
+                    thinking: response.thinking,
                 });
 
-                // Execute each tool call and add results
+                // Check for stuck loop before executing the round
                 for call in &calls {
-                    debug!(
-                        "Executing tool: {} with args: {}",
-                        call.name, call.arguments
-                    );
-
-                    // Check for stuck loop
                     self.loop_detector.record(&call.name, &call.arguments);
                     if self.loop_detector.is_stuck() {
                         let tool_name = self.loop_detector.last_tool_name().unwrap_or("unknown");
@@ -837,8 +1245,13 @@ impl Agent {
                             tool_name
                         )));
                     }
+                }
 
-                    let result = self.execute_tool(call).await;
+                // Execute the tool calls and add results in call order
+                let results = self
+                    .execute_tools(&calls, &mut |_, _| {}, &mut |_, _| {})
+                    .await;
+                for (call, result) in calls.iter().zip(results) {
                     let output = match result {
                         Ok((content, _warnings)) => content,
                         Err(e) => format!("Error: {}", e),
@@ -850,13 +1263,13 @@ impl Agent {
                         tool_calls: None,
                         tool_call_id: Some(call.id.clone()),
                         images: Vec::new(),
+                        thinking: Vec::new(),
                     });
                 }
 
                 // Continue conversation with tool results
                 let next_response = self
-                    .provider
-                    .chat(&updated_messages, Some(tool_schemas))
+                    .provider_chat(&updated_messages, Some(tool_schemas))
                     .await?;
 
                 // Recursively handle (in case of more tool calls)
@@ -877,16 +1290,8 @@ impl Agent {
         match response.content {
             LLMResponseContent::Text(text) => Ok(text),
             LLMResponseContent::ToolCalls(calls) => {
-                // Execute tool calls
-                let mut results = Vec::new();
-
+                // Check for stuck loop before executing
                 for call in &calls {
-                    debug!(
-                        "Executing tool: {} with args: {}",
-                        call.name, call.arguments
-                    );
-
-                    // Check for stuck loop before executing
                     self.loop_detector.record(&call.name, &call.arguments);
                     if self.loop_detector.is_stuck() {
                         let tool_name = self.loop_detector.last_tool_name().unwrap_or("unknown");
@@ -908,17 +1313,22 @@ impl Agent {
                         self.loop_detector.reset();
                         return Ok(stuck_msg);
                     }
+                }
 
-                    let result = self.execute_tool(call).await;
-                    let output = match result {
-                        Ok((content, _warnings)) => content,
-                        Err(e) => format!("Error: {}", e),
-                    };
-                    results.push(ToolResult {
+                // Execute tool calls
+                let results: Vec<ToolResult> = self
+                    .execute_tools(&calls, &mut |_, _| {}, &mut |_, _| {})
+                    .await
+                    .into_iter()
+                    .zip(&calls)
+                    .map(|(result, call)| ToolResult {
                         call_id: call.id.clone(),
-                        output,
-                    });
-                }
+                        output: match result {
+                            Ok((content, _warnings)) => content,
+                            Err(e) => format!("Error: {}", e),
+                        },
+                    })
+                    .collect();
 
                 // Add tool call message
                 self.session.add_message(Message {
@@ -927,6 +1337,7 @@ impl Agent {
                     tool_calls: Some(calls),
                     tool_call_id: None,
                     images: Vec::new(),
+                    thinking: response.thinking,
                 });
 
                 // Add tool results
@@ -937,6 +1348,7 @@ impl Agent {
                         tool_calls: None,
                         tool_call_id: Some(result.call_id.clone()),
                         images: Vec::new(),
+                        thinking: Vec::new(),
                     });
                 }
 
@@ -944,8 +1356,7 @@ impl Agent {
                 let messages = self.messages_for_api_call();
                 let tool_schemas = self.tool_schemas_for_provider();
                 let next_response = self
-                    .provider
-                    .chat(&messages, Some(tool_schemas.as_slice()))
+                    .provider_chat(&messages, Some(tool_schemas.as_slice()))
                     .await?;
 
                 // Recursively handle (in case of more tool calls)
@@ -975,29 +1386,28 @@ impl Agent {
                         tool_calls: Some(calls.clone()),
                         tool_call_id: None,
                         images: Vec::new(),
+                        thinking: response.thinking,
                     });
                     if let Err(e) = self.session.save_for_agent(agent_id) {
                         debug!("Incremental session save failed: {}", e);
                     }
 
-                    // Execute each tool call, saving session after each so that partial it's visible
-                    // during a long run, even partial progress if interrupted
-                    for call in &calls {
-                        debug!(
-                            "Executing tool: {} with args: {}",
-                            call.name, call.arguments
-                        );
-
-                        let result = self.execute_tool(call).await;
+                    // Execute the tool calls, saving session after each result so that
+                    // partial progress is visible during a long run, even if interrupted
+                    let results = self
+                        .execute_tools(&calls, &mut |_, _| {}, &mut |_, _| {})
+                        .await;
+                    for (call, result) in calls.iter().zip(results) {
                         self.session.add_message(Message {
                             role: Role::Tool,
                             content: match result {
-                                Ok((content, _warnings)) => content.clone(),
+                                Ok((content, _warnings)) => content,
                                 Err(e) => format!("Error: {}", e),
                             },
                             tool_calls: None,
                             tool_call_id: Some(call.id.clone()),
                             images: Vec::new(),
+                            thinking: Vec::new(),
                         });
                         if let Err(e) = self.session.save_for_agent(agent_id) {
                             debug!("Incremental session save failed: {}", e);
@@ -1008,8 +1418,7 @@ impl Agent {
                     let messages = self.messages_for_api_call();
                     let tool_schemas = self.tool_schemas_for_provider();
                     response = self
-                        .provider
-                        .chat(&messages, Some(tool_schemas.as_slice()))
+                        .provider_chat(&messages, Some(tool_schemas.as_slice()))
                         .await?;
                 }
             }
@@ -1019,6 +1428,9 @@ impl Agent {
     /// Like `chat`, but saves the session log to `agent_id`'s sessions directory after each
     /// tool call round. Used by the heartbeat runner so in-progress sessions are visible.
     pub async fn chat_saving_session(&mut self, message: &str, agent_id: &str) -> Result<String> {
+        self.enforce_budget()?;
+        self.canceller.begin_turn();
+
         // Add user message and start out saved session file
         self.session.add_message(Message {
             role: Role::User,
@@ -1026,6 +1438,7 @@ impl Agent {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         });
         if let Err(e) = self.session.save_for_agent(agent_id) {
             debug!("Incremental session save failed: {}", e);
@@ -1058,8 +1471,7 @@ impl Agent {
 
         // Invoke LLM
         let response = self
-            .provider
-            .chat(&messages, Some(tool_schemas.as_slice()))
+            .provider_chat(&messages, Some(tool_schemas.as_slice()))
             .await?;
 
         // Handle tool calls, saving session after each round
@@ -1078,6 +1490,7 @@ impl Agent {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         });
         if let Err(e) = self.session.save_for_agent(agent_id) {
             debug!("Incremental session save failed: {}", e);
@@ -1105,33 +1518,19 @@ impl Agent {
             LLMResponseContent::Text(text) => Ok(text),
             LLMResponseContent::ToolCalls(calls) => {
                 // Execute tool calls
-                let mut results = Vec::new();
-
-                for call in &calls {
-                    // Notify caller that tool is starting
-                    on_tool_start(&call.name, &call.arguments);
-
-                    debug!(
-                        "Executing tool: {} with args: {}",
-                        call.name, call.arguments
-                    );
-
-                    let result = self.execute_tool(call).await;
-                    let output = match result {
-                        Ok((content, _warnings)) => {
-                            on_tool_end(&call.name, Ok(()));
-                            content
-                        }
-                        Err(e) => {
-                            on_tool_end(&call.name, Err(&e.to_string()));
-                            format!("Error: {}", e)
-                        }
-                    };
-                    results.push(ToolResult {
+                let results: Vec<ToolResult> = self
+                    .execute_tools(&calls, on_tool_start, on_tool_end)
+                    .await
+                    .into_iter()
+                    .zip(&calls)
+                    .map(|(result, call)| ToolResult {
                         call_id: call.id.clone(),
-                        output,
-                    });
-                }
+                        output: match result {
+                            Ok((content, _warnings)) => content,
+                            Err(e) => format!("Error: {}", e),
+                        },
+                    })
+                    .collect();
 
                 // Add tool call message
                 self.session.add_message(Message {
@@ -1140,6 +1539,7 @@ impl Agent {
                     tool_calls: Some(calls),
                     tool_call_id: None,
                     images: Vec::new(),
+                    thinking: response.thinking,
                 });
 
                 // Add tool results
@@ -1150,6 +1550,7 @@ impl Agent {
                         tool_calls: None,
                         tool_call_id: Some(result.call_id.clone()),
                         images: Vec::new(),
+                        thinking: Vec::new(),
                     });
                 }
 
@@ -1157,8 +1558,7 @@ impl Agent {
                 let messages = self.messages_for_api_call();
                 let tool_schemas = self.tool_schemas_for_provider();
                 let next_response = self
-                    .provider
-                    .chat(&messages, Some(tool_schemas.as_slice()))
+                    .provider_chat(&messages, Some(tool_schemas.as_slice()))
                     .await?;
 
                 // Handle token update
@@ -1175,16 +1575,117 @@ impl Agent {
         }
     }
 
-    async fn execute_tool(&mut self, call: &ToolCall) -> Result<(String, Vec<String>)> {
-        let raw_output = {
-            let tool = self
-                .tools
-                .iter()
-                .find(|tool| tool.name() == call.name)
-                .ok_or_else(|| anyhow::anyhow!("Unknown tool: {}", call.name))?;
-            tool.execute(&call.arguments).await?
+    /// Execute one assistant turn's tool calls. Calls that need approval are
+    /// decided first; denied calls fail without running. Consecutive
+    /// parallel-safe calls run concurrently (up to `tools.max_parallel`); other
+    /// tools run alone, in order. Results are returned in call order.
+    ///
+    /// Cancelling the turn drops the running batch (killing bash children)
+    /// and fails it and every later call with [`Cancelled`].
+    async fn execute_tools<F1, F2>(
+        &mut self,
+        calls: &[ToolCall],
+        on_tool_start: &mut F1,
+        on_tool_end: &mut F2,
+    ) -> Vec<Result<(String, Vec<String>)>>
+    where
+        F1: FnMut(&str, &str) + Send,
+        F2: FnMut(&str, Result<(), &str>) + Send,
+    {
+        let mut approved = Vec::with_capacity(calls.len());
+        let mut denials = Vec::with_capacity(calls.len());
+        for call in calls {
+            let verdict = self.check_approval(call).await;
+            if verdict.is_ok() {
+                approved.push(call.clone());
+            }
+            denials.push(verdict.err());
+        }
+
+        let limit = self.app_config.tools.max_parallel;
+        let mut results = Vec::with_capacity(approved.len());
+
+        for batch in tools::parallel_batches(&self.tools, &approved) {
+            let batch = &approved[batch];
+            if self.canceller.token().is_cancelled() {
+                results.extend(batch.iter().map(|_| Err(Cancelled.into())));
+                continue;
+            }
+            for call in batch {
+                // Notify caller that tool is starting
+                on_tool_start(&call.name, &call.arguments);
+
+                debug!(
+                    "Executing tool: {} with args: {}",
+                    call.name, call.arguments
+                );
+            }
+
+            let outputs = self
+                .canceller
+                .run(tools::run_tool_batch(&self.tools, batch, limit).map(Ok))
+                .await
+                .unwrap_or_else(|_| batch.iter().map(|_| Err(Cancelled.into())).collect());
+            for (call, output) in batch.iter().zip(outputs) {
+                let result = output.map(|raw_output| self.process_tool_output(call, raw_output));
+                match &result {
+                    Ok(_) => on_tool_end(&call.name, Ok(())),
+                    Err(e) => on_tool_end(&call.name, Err(&e.to_string())),
+                }
+                results.push(result);
+            }
+        }
+
+        let mut results = results.into_iter();
+        denials
+            .into_iter()
+            .map(|denial| match denial {
+                Some(err) => Err(err),
+                None => results.next().expect("one result per approved call"),
+            })
+            .collect()
+    }
+
+    /// Ask the approval handler about a call if it needs approval
+    async fn check_approval(&mut self, call: &ToolCall) -> Result<()> {
+        if self.canceller.token().is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        if !self.approvals.needs_approval(call) {
+            return Ok(());
+        }
+        let Some(handler) = self.approval_handler.clone() else {
+            anyhow::bail!(
+                "Tool '{}' requires approval, but nobody is available to approve it here",
+                call.name
+            );
         };
 
+        let decision = self.canceller.run(handler.decide(call).map(Ok)).await?;
+        self.approvals.record(call, decision);
+        match decision {
+            ApprovalDecision::Approve | ApprovalDecision::AlwaysAllow => Ok(()),
+            ApprovalDecision::Deny => anyhow::bail!("The user denied running {}", call.name),
+        }
+    }
+
+    /// Ask the provider for the next reply, giving up if the turn is cancelled
+    async fn provider_chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        self.canceller
+            .run(self.provider.chat(messages, tools))
+            .await
+    }
+
+    /// Track usage and apply sanitization to a tool's raw output
+    fn process_tool_output(
+        &mut self,
+        call: &ToolCall,
+        raw_output: String,
+    ) -> (String, Vec<String>) {
         if call.name == "web_search" {
             self.track_web_search_usage(&raw_output);
         }
@@ -1208,10 +1709,10 @@ impl Agent {
                 );
             }
 
-            return Ok((result.content, result.warnings));
+            return (result.content, result.warnings);
         }
 
-        Ok((raw_output, Vec::new()))
+        (raw_output, Vec::new())
     }
 
     async fn build_memory_context(&self) -> Result<String> {
@@ -1365,13 +1866,18 @@ impl Agent {
 
     fn should_compact(&self) -> bool {
         self.session.token_count()
-            > (self.config.context_window - self.config.reserve_tokens - SECURITY_BLOCK_RESERVE)
+            > self
+                .config
+                .context_window
+                .saturating_sub(self.config.reserve_tokens + SECURITY_BLOCK_RESERVE)
     }
 
     /// Check if we should run pre-compaction memory flush (soft threshold)
     fn should_memory_flush(&self) -> bool {
-        let hard_limit =
-            self.config.context_window - self.config.reserve_tokens - SECURITY_BLOCK_RESERVE;
+        let hard_limit = self
+            .config
+            .context_window
+            .saturating_sub(self.config.reserve_tokens + SECURITY_BLOCK_RESERVE);
         let soft_limit = hard_limit.saturating_sub(MEMORY_FLUSH_SOFT_THRESHOLD);
 
         self.session.token_count() > soft_limit && self.session.should_memory_flush()
@@ -1417,6 +1923,7 @@ impl Agent {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         });
 
         // Get tool schemas so agent can write files
@@ -1438,6 +1945,7 @@ impl Agent {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         });
 
         if !is_silent_reply(&final_response) {
@@ -1534,10 +2042,11 @@ impl Agent {
     }
 
     pub fn clear_session(&mut self) {
-        self.session = Session::new();
+        self.session = Session::with_token_counter(self.token_counter.clone());
         self.search_queries = 0;
         self.search_cached_hits = 0;
         self.search_cost_usd = 0.0;
+        self.approvals.reset_session();
         self.provider.reset_session();
     }
 
@@ -1586,13 +2095,17 @@ impl Agent {
         message: &str,
         images: Vec<ImageAttachment>,
     ) -> Result<StreamResult> {
+        self.enforce_budget()?;
+        self.canceller.begin_turn();
+
         // Add user message with images
         self.session.add_message(Message {
             role: Role::User,
             content: message.to_string(),
             tool_calls: None,
             tool_call_id: None,
-            images,
+            images: self.supported_images(images),
+            thinking: Vec::new(),
         });
 
         // Check if we should run pre-compaction memory flush (soft threshold)
@@ -1612,10 +2125,12 @@ impl Agent {
         // Get tool schemas so the model knows the correct tool call format
         let tool_schemas = self.tool_schemas_for_provider();
 
-        // Get stream from provider with tools
-        self.provider
-            .chat_stream(&messages, Some(&tool_schemas))
-            .await
+        // Get stream from provider with tools; it ends early if the turn is cancelled
+        let stream = self
+            .canceller
+            .run(self.provider.chat_stream(&messages, Some(&tool_schemas)))
+            .await?;
+        Ok(self.canceller.stream(stream))
     }
 
     /// Complete a streaming chat by adding the assistant response to the session
@@ -1626,16 +2141,19 @@ impl Agent {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         });
     }
 
-    /// Execute tool calls that were accumulated during streaming
+    /// Execute tool calls that were accumulated during streaming, storing the
+    /// stream's thinking blocks with the tool call message.
     /// Returns (final_response, Vec<(tool_name, warnings)>)
     /// The on_tool_start callback is called before each tool executes (including recursive calls)
     /// The on_tool_end callback is called after each tool executes
     pub async fn execute_streaming_tool_calls<F1, F2>(
         &mut self,
         text_response: &str,
+        thinking: Vec<ThinkingBlock>,
         tool_calls: Vec<ToolCall>,
         mut on_tool_start: F1,
         mut on_tool_end: F2,
@@ -1651,30 +2169,19 @@ impl Agent {
             tool_calls: Some(tool_calls.clone()),
             tool_call_id: None,
             images: Vec::new(),
+            thinking,
         });
 
         // Execute each tool and collect results
         let mut results = Vec::new();
         let mut all_warnings: Vec<(String, Vec<String>)> = Vec::new();
-        for call in &tool_calls {
-            // Notify caller that tool is starting
-            on_tool_start(&call.name, &call.arguments);
-
-            debug!(
-                "Executing tool: {} with args: {}",
-                call.name, call.arguments
-            );
-
-            let result = self.execute_tool(call).await;
+        let outputs = self
+            .execute_tools(&tool_calls, &mut on_tool_start, &mut on_tool_end)
+            .await;
+        for (call, result) in tool_calls.iter().zip(outputs) {
             let (output, warnings) = match result {
-                Ok((content, warnings)) => {
-                    on_tool_end(&call.name, Ok(()));
-                    (content, warnings)
-                }
-                Err(e) => {
-                    on_tool_end(&call.name, Err(&e.to_string()));
-                    (format!("Error: {}", e), Vec::new())
-                }
+                Ok((content, warnings)) => (content, warnings),
+                Err(e) => (format!("Error: {}", e), Vec::new()),
             };
             if !warnings.is_empty() {
                 all_warnings.push((call.name.clone(), warnings));
@@ -1693,6 +2200,7 @@ impl Agent {
                 tool_calls: None,
                 tool_call_id: Some(result.call_id.clone()),
                 images: Vec::new(),
+                thinking: Vec::new(),
             });
         }
 
@@ -1700,8 +2208,7 @@ impl Agent {
         let messages = self.messages_for_api_call();
         let tool_schemas = self.tool_schemas_for_provider();
         let response = self
-            .provider
-            .chat(&messages, Some(tool_schemas.as_slice()))
+            .provider_chat(&messages, Some(tool_schemas.as_slice()))
             .await?;
 
         // Handle token update
@@ -1719,6 +2226,7 @@ impl Agent {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         });
 
         Ok((final_response, all_warnings))
@@ -1747,6 +2255,7 @@ impl Agent {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         });
     }
 
@@ -1758,6 +2267,7 @@ impl Agent {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         });
     }
 
@@ -1769,13 +2279,17 @@ impl Agent {
         message: &str,
         images: Vec<ImageAttachment>,
     ) -> Result<impl futures::Stream<Item = Result<StreamEvent>> + '_> {
+        self.enforce_budget()?;
+        self.canceller.begin_turn();
+
         // Add user message
         self.session.add_message(Message {
             role: Role::User,
             content: message.to_string(),
             tool_calls: None,
             tool_call_id: None,
-            images,
+            images: self.supported_images(images),
+            thinking: Vec::new(),
         });
 
         // Check if we should run pre-compaction memory flush (soft threshold)
@@ -1813,12 +2327,15 @@ impl Agent {
                 // Try streaming first (without tools since most providers don't support tool streaming)
                 // Then check for tool calls in the response
                 let response = self
-                    .provider
-                    .chat(&messages, Some(tool_schemas.as_slice()))
+                    .provider_chat(&messages, Some(tool_schemas.as_slice()))
                     .await;
 
                 match response {
                     Ok(resp) => {
+                        if let Some(thinking) = resp.thinking_text() {
+                            yield Ok(StreamEvent::Thinking(thinking));
+                        }
+
                         // Track usage
                         self.add_usage(resp.usage);
 
@@ -1839,6 +2356,7 @@ impl Agent {
                                     tool_calls: None,
                                     tool_call_id: None,
                                     images: Vec::new(),
+                                    thinking: resp.thinking,
                                 });
                                 break;
                             }
@@ -1850,9 +2368,23 @@ impl Agent {
                                 id: call.id.clone(),
                                 arguments: call.arguments.clone(),
                             });
+                        }
 
-                            // Execute tool
-                            let result = self.execute_tool(call).await;
+                        // Add tool call message to session
+                        self.session.add_message(Message {
+                            role: Role::Assistant,
+                            content: String::new(),
+                            tool_calls: Some(calls.clone()),
+                            tool_call_id: None,
+                            images: Vec::new(),
+                            thinking: resp.thinking,
+                        });
+
+                        // Execute tools
+                        let results = self
+                            .execute_tools(&calls, &mut |_, _| {}, &mut |_, _| {})
+                            .await;
+                        for (call, result) in calls.iter().zip(results) {
                             let (output, warnings) = match result {
                                 Ok((content, warnings)) => (content, warnings),
                                 Err(e) => (format!("Error: {}", e), Vec::new()),
@@ -1872,18 +2404,10 @@ impl Agent {
                                 tool_calls: None,
                                 tool_call_id: Some(call.id.clone()),
                                 images: Vec::new(),
+                                thinking: Vec::new(),
                             });
                         }
 
-                        // Add tool call message to session
-                        self.session.add_message(Message {
-                            role: Role::Assistant,
-                            content: String::new(),
-                            tool_calls: Some(calls),
-                            tool_call_id: None,
-                            images: Vec::new(),
-                        });
-
                         // Continue loop to get next response
                             }
                         }
@@ -1931,6 +2455,8 @@ impl Agent {
 #[derive(Clone)]
 pub struct AgentHandle {
     inner: Arc<tokio::sync::Mutex<Agent>>,
+    /// Kept outside the mutex, which a running turn holds
+    canceller: TurnCanceller,
 }
 
 // Compile-time assertion: AgentHandle must be Send + Sync.
@@ -1943,10 +2469,16 @@ impl AgentHandle {
     /// Create a new handle wrapping an existing Agent.
     pub fn new(agent: Agent) -> Self {
         Self {
+            canceller: agent.canceller(),
             inner: Arc::new(tokio::sync::Mutex::new(agent)),
         }
     }
 
+    /// Cancel the turn in progress, if any. Does not wait for the agent lock.
+    pub fn cancel(&self) {
+        self.canceller.cancel();
+    }
+
     /// Send a chat message and return the full response text.
     pub async fn chat(&self, message: &str) -> Result<String> {
         let mut agent = self.inner.lock().await;
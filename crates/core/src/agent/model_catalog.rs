@@ -0,0 +1,576 @@
+//! Model capability catalog
+//!
+//! Records what each model can do — context window, output limit, tool calling,
+//! image input, reasoning — and what it costs. Built-in entries cover the models LocalGPT
+//! ships aliases for; `[models."provider/model"]` tables in config override a
+//! built-in entry field by field or add new models (e.g. local Ollama tags).
+//!
+//! The catalog also owns model name resolution: short aliases (`opus`, `gpt`)
+//! and provider-specific API ids (`claude-opus-4-5` → `claude-opus-4-5-20251101`).
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::providers::Usage;
+use crate::config::{Config, ModelOverride, ReasoningEffort};
+
+/// Context window assumed for models the catalog does not know
+const DEFAULT_CONTEXT_WINDOW: usize = 128_000;
+
+/// Ollama serves models with a small `num_ctx` unless configured otherwise
+const DEFAULT_OLLAMA_CONTEXT_WINDOW: usize = 32_768;
+
+/// Default `providers.llamacpp.context_size`
+const DEFAULT_LLAMACPP_CONTEXT_WINDOW: usize = 8_192;
+
+/// Output limit assumed for models the catalog does not know
+const DEFAULT_MAX_OUTPUT_TOKENS: usize = 8_192;
+
+/// Price per million tokens, in USD
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write: Option<f64>,
+}
+
+impl ModelPricing {
+    /// Cost of one request in USD. Cache reads and writes fall back to the
+    /// input price when the model has no cache pricing.
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        let cache_read = self.cache_read.unwrap_or(self.input);
+        let cache_write = self.cache_write.unwrap_or(self.input);
+        (usage.input_tokens as f64 * self.input
+            + usage.output_tokens as f64 * self.output
+            + usage.cache_read_tokens as f64 * cache_read
+            + usage.cache_write_tokens as f64 * cache_write)
+            / 1_000_000.0
+    }
+}
+
+/// Capabilities and limits of one model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelSpec {
+    /// Provider prefix (e.g. "anthropic")
+    pub provider: String,
+    /// Canonical model id (e.g. "claude-opus-4-6")
+    pub id: String,
+    /// Id sent to the provider API when it differs from `id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_id: Option<String>,
+    /// Alternative model ids accepted for this provider (e.g. "opus-4.6")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    pub context_window: usize,
+    pub max_output_tokens: usize,
+    pub supports_tools: bool,
+    pub supports_images: bool,
+    /// Accepts a thinking budget / reasoning effort
+    #[serde(default)]
+    pub supports_reasoning: bool,
+    /// Reasoning effort configured for this model (overrides `agent.reasoning`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<ReasoningEffort>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<ModelPricing>,
+    /// False when the spec is a provider default for a model not in the catalog
+    #[serde(default)]
+    pub known: bool,
+}
+
+impl ModelSpec {
+    /// "provider/id"
+    pub fn full_id(&self) -> String {
+        format!("{}/{}", self.provider, self.id)
+    }
+
+    /// Reasoning effort to request: an explicit per-session choice, then the
+    /// model's configured effort, then the agent default. Off for models that
+    /// don't support reasoning.
+    pub fn reasoning_effort(
+        &self,
+        requested: Option<ReasoningEffort>,
+        default: ReasoningEffort,
+    ) -> ReasoningEffort {
+        if !self.supports_reasoning {
+            return ReasoningEffort::Off;
+        }
+        requested.or(self.reasoning).unwrap_or(default)
+    }
+
+    fn matches(&self, model_id: &str) -> bool {
+        self.id == model_id
+            || self.api_id.as_deref() == Some(model_id)
+            || self.aliases.iter().any(|a| a == model_id)
+    }
+
+    fn apply(&mut self, o: &ModelOverride) {
+        if let Some(v) = o.context_window {
+            self.context_window = v;
+        }
+        if let Some(v) = o.max_output_tokens {
+            self.max_output_tokens = v;
+        }
+        if let Some(v) = o.supports_tools {
+            self.supports_tools = v;
+        }
+        if let Some(v) = o.supports_images {
+            self.supports_images = v;
+        }
+        if let Some(v) = o.supports_reasoning {
+            self.supports_reasoning = v;
+        }
+        if o.reasoning.is_some() {
+            self.reasoning = o.reasoning;
+        }
+        if let Some(v) = &o.api_id {
+            self.api_id = Some(v.clone());
+        }
+        for alias in &o.aliases {
+            if !self.aliases.contains(alias) {
+                self.aliases.push(alias.clone());
+            }
+        }
+        if o.input_price.is_some() || o.output_price.is_some() {
+            let mut pricing = self.pricing.unwrap_or_default();
+            if let Some(v) = o.input_price {
+                pricing.input = v;
+            }
+            if let Some(v) = o.output_price {
+                pricing.output = v;
+            }
+            self.pricing = Some(pricing);
+        }
+        if let Some(pricing) = self.pricing.as_mut() {
+            if o.cache_read_price.is_some() {
+                pricing.cache_read = o.cache_read_price;
+            }
+            if o.cache_write_price.is_some() {
+                pricing.cache_write = o.cache_write_price;
+            }
+        }
+        self.known = true;
+    }
+}
+
+/// A model string resolved against the catalog
+#[derive(Debug, Clone)]
+pub struct ResolvedModel {
+    /// Provider prefix, if one was given or could be inferred
+    pub provider: Option<String>,
+    /// Model id to send to the provider
+    pub model_id: String,
+    pub spec: ModelSpec,
+}
+
+struct BuiltinModel {
+    provider: &'static str,
+    id: &'static str,
+    api_id: Option<&'static str>,
+    aliases: &'static [&'static str],
+    context_window: usize,
+    max_output_tokens: usize,
+    tools: bool,
+    images: bool,
+    reasoning: bool,
+    /// (input, output, cache read, cache write) per million tokens
+    pricing: Option<(f64, f64, Option<f64>, Option<f64>)>,
+}
+
+/// Short aliases usable without a provider prefix (OpenClaw-compatible)
+const BUILTIN_ALIASES: &[(&str, &str)] = &[
+    ("opus", "anthropic/claude-opus-4-6"),
+    ("sonnet", "anthropic/claude-sonnet-4-6"),
+    ("gpt", "openai/gpt-4o"),
+    ("gpt-mini", "openai/gpt-4o-mini"),
+    ("glm", "glm/glm-4.7"),
+    ("grok", "xai/grok-3-mini"),
+    ("codex", "codex-cli/o4-mini"),
+];
+
+#[rustfmt::skip]
+const BUILTIN_MODELS: &[BuiltinModel] = &[
+    // Anthropic — https://docs.anthropic.com/en/docs/about-claude/models
+    BuiltinModel { provider: "anthropic", id: "claude-opus-4-6", api_id: None, aliases: &["opus", "opus-4.6"], context_window: 200_000, max_output_tokens: 128_000, tools: true, images: true, reasoning: true, pricing: Some((5.0, 25.0, Some(0.5), Some(6.25))) },
+    BuiltinModel { provider: "anthropic", id: "claude-sonnet-4-6", api_id: None, aliases: &["sonnet", "sonnet-4.6"], context_window: 200_000, max_output_tokens: 64_000, tools: true, images: true, reasoning: true, pricing: Some((3.0, 15.0, Some(0.3), Some(3.75))) },
+    BuiltinModel { provider: "anthropic", id: "claude-opus-4-5", api_id: Some("claude-opus-4-5-20251101"), aliases: &["opus-4.5"], context_window: 200_000, max_output_tokens: 64_000, tools: true, images: true, reasoning: true, pricing: Some((5.0, 25.0, Some(0.5), Some(6.25))) },
+    BuiltinModel { provider: "anthropic", id: "claude-sonnet-4-5", api_id: Some("claude-sonnet-4-5-20250929"), aliases: &["sonnet-4.5"], context_window: 200_000, max_output_tokens: 64_000, tools: true, images: true, reasoning: true, pricing: Some((3.0, 15.0, Some(0.3), Some(3.75))) },
+    BuiltinModel { provider: "anthropic", id: "claude-haiku-4-5", api_id: Some("claude-haiku-4-5-20251001"), aliases: &["haiku", "haiku-4.5"], context_window: 200_000, max_output_tokens: 64_000, tools: true, images: true, reasoning: true, pricing: Some((1.0, 5.0, Some(0.1), Some(1.25))) },
+    // OpenAI — https://platform.openai.com/docs/models
+    BuiltinModel { provider: "openai", id: "gpt-4o", api_id: None, aliases: &[], context_window: 128_000, max_output_tokens: 16_384, tools: true, images: true, reasoning: false, pricing: Some((2.5, 10.0, Some(1.25), None)) },
+    BuiltinModel { provider: "openai", id: "gpt-4o-mini", api_id: None, aliases: &[], context_window: 128_000, max_output_tokens: 16_384, tools: true, images: true, reasoning: false, pricing: Some((0.15, 0.6, Some(0.075), None)) },
+    BuiltinModel { provider: "openai", id: "gpt-4.1", api_id: None, aliases: &[], context_window: 1_047_576, max_output_tokens: 32_768, tools: true, images: true, reasoning: false, pricing: Some((2.0, 8.0, Some(0.5), None)) },
+    BuiltinModel { provider: "openai", id: "gpt-4.1-mini", api_id: None, aliases: &[], context_window: 1_047_576, max_output_tokens: 32_768, tools: true, images: true, reasoning: false, pricing: Some((0.4, 1.6, Some(0.1), None)) },
+    BuiltinModel { provider: "openai", id: "gpt-5", api_id: None, aliases: &[], context_window: 400_000, max_output_tokens: 128_000, tools: true, images: true, reasoning: true, pricing: Some((1.25, 10.0, Some(0.125), None)) },
+    BuiltinModel { provider: "openai", id: "o4-mini", api_id: None, aliases: &[], context_window: 200_000, max_output_tokens: 100_000, tools: true, images: true, reasoning: true, pricing: Some((1.1, 4.4, Some(0.275), None)) },
+    // xAI — https://docs.x.ai/docs/models
+    BuiltinModel { provider: "xai", id: "grok-3-mini", api_id: None, aliases: &[], context_window: 131_072, max_output_tokens: 16_384, tools: true, images: false, reasoning: true, pricing: Some((0.3, 0.5, Some(0.075), None)) },
+    BuiltinModel { provider: "xai", id: "grok-3", api_id: None, aliases: &[], context_window: 131_072, max_output_tokens: 16_384, tools: true, images: false, reasoning: false, pricing: Some((3.0, 15.0, Some(0.75), None)) },
+    BuiltinModel { provider: "xai", id: "grok-4", api_id: None, aliases: &[], context_window: 256_000, max_output_tokens: 32_768, tools: true, images: true, reasoning: false, pricing: Some((3.0, 15.0, Some(0.75), None)) },
+    // Zhipu GLM
+    BuiltinModel { provider: "glm", id: "glm-4.7", api_id: None, aliases: &[], context_window: 200_000, max_output_tokens: 128_000, tools: true, images: false, reasoning: false, pricing: Some((0.6, 2.2, Some(0.11), None)) },
+    // Google Gemini — https://ai.google.dev/gemini-api/docs/models
+    BuiltinModel { provider: "gemini", id: "gemini-2.5-pro", api_id: None, aliases: &[], context_window: 1_048_576, max_output_tokens: 65_536, tools: true, images: true, reasoning: true, pricing: Some((1.25, 10.0, Some(0.31), None)) },
+    BuiltinModel { provider: "gemini", id: "gemini-2.5-flash", api_id: None, aliases: &[], context_window: 1_048_576, max_output_tokens: 65_536, tools: true, images: true, reasoning: true, pricing: Some((0.3, 2.5, Some(0.075), None)) },
+];
+
+impl From<&BuiltinModel> for ModelSpec {
+    fn from(m: &BuiltinModel) -> Self {
+        Self {
+            provider: m.provider.to_string(),
+            id: m.id.to_string(),
+            api_id: m.api_id.map(str::to_string),
+            aliases: m.aliases.iter().map(|a| a.to_string()).collect(),
+            context_window: m.context_window,
+            max_output_tokens: m.max_output_tokens,
+            supports_tools: m.tools,
+            supports_images: m.images,
+            supports_reasoning: m.reasoning,
+            reasoning: None,
+            pricing: m
+                .pricing
+                .map(|(input, output, cache_read, cache_write)| ModelPricing {
+                    input,
+                    output,
+                    cache_read,
+                    cache_write,
+                }),
+            known: true,
+        }
+    }
+}
+
+/// Built-in model specs merged with `[models]` config overrides
+#[derive(Debug, Clone)]
+pub struct ModelCatalog {
+    models: Vec<ModelSpec>,
+    aliases: HashMap<String, String>,
+}
+
+impl Default for ModelCatalog {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl ModelCatalog {
+    /// Catalog with only the compiled-in models
+    pub fn builtin() -> Self {
+        Self {
+            models: BUILTIN_MODELS.iter().map(ModelSpec::from).collect(),
+            aliases: BUILTIN_ALIASES
+                .iter()
+                .map(|(a, t)| (a.to_string(), t.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Built-in catalog with `[models."provider/model"]` overrides applied
+    pub fn from_config(config: &Config) -> Self {
+        let mut catalog = Self::builtin();
+        for (key, o) in &config.models {
+            let key = key.to_lowercase();
+            let Some((provider, model_id)) = key.split_once('/') else {
+                tracing::warn!("Ignoring [models.\"{}\"]: expected provider/model", key);
+                continue;
+            };
+
+            match catalog
+                .models
+                .iter_mut()
+                .find(|m| m.provider == provider && m.matches(model_id))
+            {
+                Some(spec) => spec.apply(o),
+                None => {
+                    let mut spec = Self::provider_default(provider, model_id);
+                    spec.apply(o);
+                    catalog.models.push(spec);
+                }
+            }
+
+            for alias in &o.aliases {
+                // Aliases without a provider prefix are global shortcuts too
+                catalog.aliases.insert(alias.to_lowercase(), key.clone());
+            }
+        }
+        catalog
+    }
+
+    /// All catalogued models
+    pub fn models(&self) -> &[ModelSpec] {
+        &self.models
+    }
+
+    /// Look up a model by "provider/model" or alias without inferring a provider
+    pub fn get(&self, model: &str) -> Option<&ModelSpec> {
+        let model = self.expand_alias(model);
+        let (provider, model_id) = model.split_once('/')?;
+        self.find(provider, model_id)
+    }
+
+    /// Resolve a user-supplied model string.
+    ///
+    /// Applies global aliases, splits the provider prefix (inferring it from
+    /// well-known id prefixes when absent), maps provider aliases to the API id,
+    /// and returns the model's spec. Models served through another route (e.g.
+    /// `claude-cli/opus`) borrow the capabilities of the underlying model but keep
+    /// the id the user gave, since that route has its own naming.
+    pub fn resolve(&self, model: &str) -> ResolvedModel {
+        let expanded = self.expand_alias(model);
+
+        let (provider, model_id) = match expanded.split_once('/') {
+            Some((p, m)) => (Some(p.to_lowercase()), m.to_string()),
+            None => (
+                infer_provider(&expanded).map(str::to_string),
+                expanded.clone(),
+            ),
+        };
+        let lookup_id = model_id.to_lowercase();
+
+        if let Some(provider) = provider.as_deref()
+            && let Some(spec) = self.find(provider, &lookup_id)
+        {
+            return ResolvedModel {
+                provider: Some(provider.to_string()),
+                model_id: spec.api_id.clone().unwrap_or_else(|| spec.id.clone()),
+                spec: spec.clone(),
+            };
+        }
+
+        // Same model reached through a different route (CLI wrappers, Copilot, ...)
+        let borrowed = self
+            .models
+            .iter()
+            .find(|m| m.matches(&lookup_id))
+            .cloned()
+            .map(|mut spec| {
+                spec.provider = provider.clone().unwrap_or_default();
+                spec
+            });
+
+        let spec = borrowed.unwrap_or_else(|| {
+            Self::provider_default(provider.as_deref().unwrap_or_default(), &lookup_id)
+        });
+
+        ResolvedModel {
+            provider,
+            model_id,
+            spec,
+        }
+    }
+
+    /// Effective context window: an explicit override (> 0) wins over the catalog
+    pub fn context_window(&self, model: &str, configured: usize) -> usize {
+        if configured > 0 {
+            configured
+        } else {
+            self.resolve(model).spec.context_window
+        }
+    }
+
+    fn expand_alias(&self, model: &str) -> String {
+        let trimmed = model.trim();
+        self.aliases
+            .get(&trimmed.to_lowercase())
+            .cloned()
+            .unwrap_or_else(|| trimmed.to_string())
+    }
+
+    fn find(&self, provider: &str, model_id: &str) -> Option<&ModelSpec> {
+        let model_id = model_id.to_lowercase();
+        self.models
+            .iter()
+            .find(|m| m.provider == provider && m.matches(&model_id))
+    }
+
+    /// Conservative spec for a model the catalog does not know
+    fn provider_default(provider: &str, model_id: &str) -> ModelSpec {
+        let context_window = match provider {
+            "ollama" => DEFAULT_OLLAMA_CONTEXT_WINDOW,
+            "llamacpp" => DEFAULT_LLAMACPP_CONTEXT_WINDOW,
+            _ => DEFAULT_CONTEXT_WINDOW,
+        };
+        ModelSpec {
+            provider: provider.to_string(),
+            id: model_id.to_string(),
+            api_id: None,
+            aliases: Vec::new(),
+            context_window,
+            max_output_tokens: DEFAULT_MAX_OUTPUT_TOKENS,
+            supports_tools: true,
+            // In-process llama.cpp models are text-only
+            supports_images: provider != "llamacpp",
+            supports_reasoning: false,
+            reasoning: None,
+            pricing: None,
+            known: false,
+        }
+    }
+}
+
+/// Provider for a bare model id, from well-known prefixes
+fn infer_provider(model: &str) -> Option<&'static str> {
+    let model = model.to_lowercase();
+    if model.starts_with("gpt-") || model.starts_with("o1") {
+        Some("openai")
+    } else if model.starts_with("claude-") {
+        Some("anthropic")
+    } else if model.starts_with("glm-") {
+        Some("glm")
+    } else if model.starts_with("grok-") {
+        Some("xai")
+    } else if model.starts_with("gemini-") {
+        Some("gemini")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_aliases() {
+        let catalog = ModelCatalog::builtin();
+        let resolved = catalog.resolve("opus");
+        assert_eq!(resolved.provider.as_deref(), Some("anthropic"));
+        assert_eq!(resolved.model_id, "claude-opus-4-6");
+        assert_eq!(resolved.spec.context_window, 200_000);
+
+        assert_eq!(catalog.resolve("gpt").model_id, "gpt-4o");
+        assert_eq!(catalog.resolve("gpt-mini").model_id, "gpt-4o-mini");
+        assert_eq!(catalog.resolve("grok").model_id, "grok-3-mini");
+        let codex = catalog.resolve("codex");
+        assert_eq!(codex.provider.as_deref(), Some("codex-cli"));
+        assert_eq!(codex.model_id, "o4-mini");
+    }
+
+    #[test]
+    fn test_provider_aliases_map_to_api_ids() {
+        let catalog = ModelCatalog::builtin();
+        assert_eq!(
+            catalog.resolve("anthropic/claude-opus-4-5").model_id,
+            "claude-opus-4-5-20251101"
+        );
+        assert_eq!(
+            catalog.resolve("anthropic/haiku").model_id,
+            "claude-haiku-4-5-20251001"
+        );
+        assert_eq!(
+            catalog.resolve("anthropic/sonnet").model_id,
+            "claude-sonnet-4-6"
+        );
+        // Bare ids infer the provider
+        let resolved = catalog.resolve("claude-sonnet-4-5");
+        assert_eq!(resolved.provider.as_deref(), Some("anthropic"));
+        assert_eq!(resolved.model_id, "claude-sonnet-4-5-20250929");
+    }
+
+    #[test]
+    fn test_other_routes_borrow_capabilities_but_keep_id() {
+        let catalog = ModelCatalog::builtin();
+        let resolved = catalog.resolve("claude-cli/opus");
+        assert_eq!(resolved.provider.as_deref(), Some("claude-cli"));
+        assert_eq!(resolved.model_id, "opus");
+        assert!(resolved.spec.known);
+        assert_eq!(resolved.spec.context_window, 200_000);
+        assert_eq!(resolved.spec.provider, "claude-cli");
+    }
+
+    #[test]
+    fn test_unknown_models_get_provider_defaults() {
+        let catalog = ModelCatalog::builtin();
+        let resolved = catalog.resolve("ollama/llama3");
+        assert_eq!(resolved.model_id, "llama3");
+        assert!(!resolved.spec.known);
+        assert_eq!(resolved.spec.context_window, DEFAULT_OLLAMA_CONTEXT_WINDOW);
+
+        let resolved = catalog.resolve("llamacpp/Qwen2.5-7B-Instruct-Q4_K_M");
+        assert_eq!(resolved.model_id, "Qwen2.5-7B-Instruct-Q4_K_M");
+        assert_eq!(
+            resolved.spec.context_window,
+            DEFAULT_LLAMACPP_CONTEXT_WINDOW
+        );
+        assert!(!resolved.spec.supports_images);
+
+        let resolved = catalog.resolve("custom-model");
+        assert_eq!(resolved.provider, None);
+        assert_eq!(resolved.model_id, "custom-model");
+        assert_eq!(resolved.spec.context_window, DEFAULT_CONTEXT_WINDOW);
+    }
+
+    #[test]
+    fn test_config_overrides_and_additions() {
+        let mut config = Config::default();
+        config.models.insert(
+            "anthropic/claude-sonnet-4-6".to_string(),
+            ModelOverride {
+                context_window: Some(1_000_000),
+                ..Default::default()
+            },
+        );
+        config.models.insert(
+            "ollama/qwen3:8b".to_string(),
+            ModelOverride {
+                context_window: Some(40_960),
+                supports_images: Some(false),
+                aliases: vec!["qwen".to_string()],
+                ..Default::default()
+            },
+        );
+
+        let catalog = ModelCatalog::from_config(&config);
+        let sonnet = catalog.resolve("sonnet").spec;
+        assert_eq!(sonnet.context_window, 1_000_000);
+        assert_eq!(sonnet.max_output_tokens, 64_000);
+
+        let qwen = catalog.resolve("qwen");
+        assert_eq!(qwen.provider.as_deref(), Some("ollama"));
+        assert_eq!(qwen.model_id, "qwen3:8b");
+        assert!(qwen.spec.known);
+        assert!(!qwen.spec.supports_images);
+        assert_eq!(qwen.spec.context_window, 40_960);
+        assert!(catalog.get("ollama/qwen3:8b").is_some());
+    }
+
+    #[test]
+    fn test_reasoning_effort_resolution() {
+        let mut config = Config::default();
+        config.models.insert(
+            "anthropic/claude-opus-4-6".to_string(),
+            ModelOverride {
+                reasoning: Some(ReasoningEffort::High),
+                ..Default::default()
+            },
+        );
+        let catalog = ModelCatalog::from_config(&config);
+
+        let opus = catalog.resolve("opus").spec;
+        assert_eq!(
+            opus.reasoning_effort(None, ReasoningEffort::Low),
+            ReasoningEffort::High
+        );
+        assert_eq!(
+            opus.reasoning_effort(Some(ReasoningEffort::Off), ReasoningEffort::Low),
+            ReasoningEffort::Off
+        );
+
+        let sonnet = catalog.resolve("sonnet").spec;
+        assert_eq!(
+            sonnet.reasoning_effort(None, ReasoningEffort::Medium),
+            ReasoningEffort::Medium
+        );
+
+        // Models without reasoning support never get an effort
+        let gpt = catalog.resolve("gpt").spec;
+        assert_eq!(
+            gpt.reasoning_effort(Some(ReasoningEffort::High), ReasoningEffort::High),
+            ReasoningEffort::Off
+        );
+    }
+}
@@ -0,0 +1,546 @@
+//! Typed errors returned by LLM providers
+//!
+//! Providers classify HTTP failures into a `ProviderError` so callers (failover,
+//! cooldowns, user-facing messages) can act on the actual cause instead of
+//! matching on error strings.
+
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+
+/// Error returned by an `LLMProvider` call.
+///
+/// Providers return this wrapped in `anyhow::Error`; use [`ProviderError::from_anyhow`]
+/// to recover it.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ProviderError {
+    /// Provider rejected the request due to rate limits or quota (HTTP 429)
+    #[error("{provider} rate limited{}: {message}", format_retry_after(.retry_after))]
+    RateLimited {
+        provider: String,
+        retry_after: Option<Duration>,
+        message: String,
+    },
+
+    /// Credentials missing, invalid, or lacking permission (HTTP 401/403)
+    #[error("{provider} authentication failed: {message}")]
+    Auth { provider: String, message: String },
+
+    /// Request was malformed or refused (HTTP 4xx other than the above)
+    #[error("{provider} rejected the request: {message}")]
+    BadRequest { provider: String, message: String },
+
+    /// Prompt exceeded the model's context window
+    #[error("{provider} context window exceeded: {message}")]
+    ContextOverflow { provider: String, message: String },
+
+    /// Provider-side failure or overload (HTTP 5xx, 529)
+    #[error("{provider} server error ({status}): {message}")]
+    Server {
+        provider: String,
+        status: u16,
+        message: String,
+    },
+
+    /// Network failure (connect, timeout, reset, interrupted stream) or a crashed CLI subprocess
+    #[error("{provider} request failed: {message}")]
+    Transport { provider: String, message: String },
+}
+
+fn format_retry_after(retry_after: &Option<Duration>) -> String {
+    match retry_after {
+        Some(d) => format!(" (retry after {}s)", d.as_secs().max(1)),
+        None => String::new(),
+    }
+}
+
+/// Substrings providers use in error bodies when the prompt is too long
+const CONTEXT_OVERFLOW_MARKERS: &[&str] = &[
+    "context_length_exceeded",
+    "context length",
+    "context window",
+    "prompt is too long",
+    "maximum context",
+    "too many tokens",
+    "input is too long",
+    "exceeds the maximum number of tokens",
+];
+
+impl ProviderError {
+    /// Whether the failure is transient and another provider (or a later retry) may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ProviderError::RateLimited { .. }
+                | ProviderError::Server { .. }
+                | ProviderError::Transport { .. }
+        )
+    }
+
+    /// Server-requested delay before retrying, if any
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ProviderError::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Name of the provider that produced the error
+    pub fn provider(&self) -> &str {
+        match self {
+            ProviderError::RateLimited { provider, .. }
+            | ProviderError::Auth { provider, .. }
+            | ProviderError::BadRequest { provider, .. }
+            | ProviderError::ContextOverflow { provider, .. }
+            | ProviderError::Server { provider, .. }
+            | ProviderError::Transport { provider, .. } => provider,
+        }
+    }
+
+    /// Find a `ProviderError` in an `anyhow::Error` chain.
+    ///
+    /// Bare `reqwest::Error`s propagated with `?` are not found here; use
+    /// [`ProviderError::classify`] to also cover those.
+    pub fn from_anyhow(err: &anyhow::Error) -> Option<&ProviderError> {
+        err.chain().find_map(|e| e.downcast_ref::<ProviderError>())
+    }
+
+    /// Classify an arbitrary provider failure.
+    ///
+    /// Returns the embedded `ProviderError` if present, a `Transport` error for
+    /// network-level `reqwest` failures, or `None` if the cause is unknown.
+    pub fn classify(err: &anyhow::Error) -> Option<ProviderError> {
+        if let Some(provider_err) = Self::from_anyhow(err) {
+            return Some(provider_err.clone());
+        }
+        err.chain()
+            .find_map(|e| e.downcast_ref::<reqwest::Error>())
+            .filter(|e| e.is_timeout() || e.is_connect() || e.is_request() || e.is_body())
+            .map(|e| Self::transport("unknown", e))
+    }
+
+    /// Build a `Transport` error from a failed `reqwest` call
+    pub fn transport(provider: &str, err: &reqwest::Error) -> Self {
+        let message = if err.is_timeout() {
+            format!("timed out: {}", err)
+        } else if err.is_connect() {
+            format!("connection failed: {}", err)
+        } else {
+            err.to_string()
+        };
+        ProviderError::Transport {
+            provider: provider.to_string(),
+            message,
+        }
+    }
+
+    /// Classify a non-success HTTP response from its status, headers and body
+    pub fn from_status(
+        provider: &str,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &str,
+    ) -> Self {
+        let provider = provider.to_string();
+        let message = extract_error_message(body);
+
+        match status.as_u16() {
+            429 => ProviderError::RateLimited {
+                provider,
+                retry_after: parse_retry_after(headers),
+                message,
+            },
+            401 | 403 => ProviderError::Auth { provider, message },
+            400 | 413 | 422 if is_context_overflow(body) => {
+                ProviderError::ContextOverflow { provider, message }
+            }
+            408 => ProviderError::Transport { provider, message },
+            code if code >= 500 => ProviderError::Server {
+                provider,
+                status: code,
+                message,
+            },
+            _ => ProviderError::BadRequest { provider, message },
+        }
+    }
+
+    /// Classify a failed OAuth token refresh or exchange.
+    ///
+    /// Any client error means the stored credentials are no longer usable, so it is
+    /// reported as `Auth` rather than `BadRequest`.
+    pub fn from_token_refresh(
+        provider: &str,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &str,
+    ) -> Self {
+        match Self::from_status(provider, status, headers, body) {
+            ProviderError::BadRequest { provider, message }
+            | ProviderError::ContextOverflow { provider, message } => ProviderError::Auth {
+                provider,
+                message: format!("token refresh failed: {}", message),
+            },
+            ProviderError::Auth { provider, message } => ProviderError::Auth {
+                provider,
+                message: format!("token refresh failed: {}", message),
+            },
+            other => other,
+        }
+    }
+
+    /// Classify an `error` object embedded in an otherwise successful (200) response body
+    pub fn from_error_body(provider: &str, error: &Value) -> Self {
+        let provider = provider.to_string();
+        // OpenAI sends both `type` and `code`, Anthropic `type`, Google `status`
+        let kind = ["type", "code", "status"]
+            .iter()
+            .filter_map(|key| error[*key].as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        let message = error["message"]
+            .as_str()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| error.to_string());
+        let code = error["code"].as_u64().unwrap_or(0) as u16;
+
+        if kind.contains("rate_limit") || kind.contains("resource_exhausted") || code == 429 {
+            ProviderError::RateLimited {
+                provider,
+                retry_after: None,
+                message,
+            }
+        } else if kind.contains("auth")
+            || kind.contains("api_key")
+            || kind.contains("permission_denied")
+            || kind.contains("unauthenticated")
+            || code == 401
+            || code == 403
+        {
+            ProviderError::Auth { provider, message }
+        } else if is_context_overflow(&kind) || is_context_overflow(&message) {
+            ProviderError::ContextOverflow { provider, message }
+        } else if kind.contains("overloaded")
+            || kind.contains("server")
+            || kind.split_whitespace().any(|k| k == "api_error")
+            || kind.contains("internal")
+            || kind.contains("unavailable")
+            || code >= 500
+        {
+            ProviderError::Server {
+                provider,
+                status: if code >= 500 { code } else { 500 },
+                message,
+            }
+        } else {
+            ProviderError::BadRequest { provider, message }
+        }
+    }
+
+    /// Classify the stderr of a CLI-backed provider that exited unsuccessfully
+    pub fn from_cli_failure(provider: &str, stderr: &str) -> Self {
+        let provider = provider.to_string();
+        let message = stderr.trim().to_string();
+        let lower = message.to_lowercase();
+
+        if lower.contains("rate limit") || lower.contains("usage limit") || lower.contains("quota")
+        {
+            ProviderError::RateLimited {
+                provider,
+                retry_after: None,
+                message,
+            }
+        } else if lower.contains("not logged in")
+            || lower.contains("unauthorized")
+            || lower.contains("authentication")
+            || lower.contains("invalid api key")
+        {
+            ProviderError::Auth { provider, message }
+        } else if is_context_overflow(&lower) {
+            ProviderError::ContextOverflow { provider, message }
+        } else {
+            ProviderError::Transport { provider, message }
+        }
+    }
+
+    /// Return the response if its status is a success, otherwise read the body and classify it
+    pub async fn check_response(
+        provider: &str,
+        response: reqwest::Response,
+    ) -> std::result::Result<reqwest::Response, ProviderError> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let headers = response.headers().clone();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| Self::transport(provider, &e))?;
+        Err(Self::from_status(provider, status, &headers, &body))
+    }
+}
+
+/// Work out how long to wait before retrying from the rate-limit headers of a response.
+///
+/// Checks, in order: `retry-after-ms`, the standard `Retry-After` (seconds or HTTP date),
+/// then the provider-specific reset headers (`x-ratelimit-reset-*` durations from OpenAI
+/// and xAI, `anthropic-ratelimit-*-reset` timestamps), taking the longest reset.
+pub(crate) fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+    };
+
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.parse::<f64>().ok())
+        && ms.is_finite()
+        && ms >= 0.0
+    {
+        return Some(Duration::from_secs_f64(ms / 1000.0));
+    }
+
+    if let Some(value) = header("retry-after") {
+        if let Ok(secs) = value.parse::<f64>()
+            && secs.is_finite()
+            && secs >= 0.0
+        {
+            return Some(Duration::from_secs_f64(secs));
+        }
+        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(value) {
+            return Some(duration_until(date.with_timezone(&chrono::Utc)));
+        }
+    }
+
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let name = name.as_str();
+            let value = value.to_str().ok()?.trim();
+            if name.starts_with("x-ratelimit-reset") {
+                parse_reset_duration(value)
+            } else if name.starts_with("anthropic-ratelimit-") && name.ends_with("-reset") {
+                chrono::DateTime::parse_from_rfc3339(value)
+                    .ok()
+                    .map(|date| duration_until(date.with_timezone(&chrono::Utc)))
+            } else {
+                None
+            }
+        })
+        .max()
+}
+
+fn duration_until(when: chrono::DateTime<chrono::Utc>) -> Duration {
+    (when - chrono::Utc::now()).to_std().unwrap_or_default()
+}
+
+/// Parse reset durations such as `"20ms"`, `"1.5s"`, `"6m0s"` or `"1h2m3s"`
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    if let Ok(secs) = value.parse::<f64>() {
+        return (secs.is_finite() && secs >= 0.0).then(|| Duration::from_secs_f64(secs));
+    }
+
+    let mut total = 0.0;
+    let mut number = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let amount: f64 = number.parse().ok()?;
+        number.clear();
+        total += match c {
+            'h' => amount * 3600.0,
+            'm' if chars.peek() == Some(&'s') => {
+                chars.next();
+                amount / 1000.0
+            }
+            'm' => amount * 60.0,
+            's' => amount,
+            _ => return None,
+        };
+    }
+
+    number.is_empty().then(|| Duration::from_secs_f64(total))
+}
+
+fn is_context_overflow(text: &str) -> bool {
+    let lower = text.to_lowercase();
+    CONTEXT_OVERFLOW_MARKERS.iter().any(|m| lower.contains(m))
+}
+
+/// Pull a human-readable message out of a JSON error body, falling back to the raw body
+fn extract_error_message(body: &str) -> String {
+    if let Ok(json) = serde_json::from_str::<Value>(body) {
+        let error = json.get("error").unwrap_or(&json);
+        if let Some(message) = error["message"].as_str().or_else(|| error.as_str()) {
+            return message.to_string();
+        }
+    }
+    let trimmed = body.trim();
+    if trimmed.is_empty() {
+        "(empty response body)".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn headers_with_retry_after(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(reqwest::header::RETRY_AFTER, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_from_status_rate_limited_with_retry_after() {
+        let err = ProviderError::from_status(
+            "openai",
+            StatusCode::TOO_MANY_REQUESTS,
+            &headers_with_retry_after("12"),
+            r#"{"error":{"message":"Rate limit reached","type":"requests"}}"#,
+        );
+        assert!(matches!(err, ProviderError::RateLimited { .. }));
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(12)));
+        assert!(err.is_retryable());
+        assert_eq!(
+            err.to_string(),
+            "openai rate limited (retry after 12s): Rate limit reached"
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after_variants() {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after-ms", "1500".parse().unwrap());
+        headers.insert(reqwest::header::RETRY_AFTER, "30".parse().unwrap());
+        assert_eq!(
+            parse_retry_after(&headers),
+            Some(Duration::from_millis(1500))
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-reset-requests", "1s".parse().unwrap());
+        headers.insert("x-ratelimit-reset-tokens", "6m0s".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(360)));
+
+        let mut headers = HeaderMap::new();
+        let reset = (chrono::Utc::now() + chrono::Duration::seconds(90)).to_rfc3339();
+        headers.insert("anthropic-ratelimit-tokens-reset", reset.parse().unwrap());
+        let wait = parse_retry_after(&headers).expect("reset timestamp");
+        assert!(wait > Duration::from_secs(80) && wait <= Duration::from_secs(90));
+
+        assert_eq!(parse_retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_parse_reset_duration() {
+        assert_eq!(
+            parse_reset_duration("20ms"),
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(
+            parse_reset_duration("1h2m3s"),
+            Some(Duration::from_secs(3723))
+        );
+        assert_eq!(
+            parse_reset_duration("2.5s"),
+            Some(Duration::from_millis(2500))
+        );
+        assert_eq!(parse_reset_duration("soon"), None);
+    }
+
+    #[test]
+    fn test_from_status_classification() {
+        let headers = HeaderMap::new();
+        let auth = ProviderError::from_status("a", StatusCode::UNAUTHORIZED, &headers, "nope");
+        assert!(matches!(auth, ProviderError::Auth { .. }));
+        assert!(!auth.is_retryable());
+
+        let server = ProviderError::from_status("a", StatusCode::BAD_GATEWAY, &headers, "");
+        assert!(matches!(server, ProviderError::Server { status: 502, .. }));
+        assert!(server.is_retryable());
+
+        let overloaded =
+            ProviderError::from_status("a", StatusCode::from_u16(529).unwrap(), &headers, "");
+        assert!(overloaded.is_retryable());
+
+        let bad = ProviderError::from_status("a", StatusCode::BAD_REQUEST, &headers, "bad tool");
+        assert!(matches!(bad, ProviderError::BadRequest { .. }));
+        assert!(!bad.is_retryable());
+    }
+
+    #[test]
+    fn test_from_status_context_overflow() {
+        let body = r#"{"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long: 210000 tokens > 200000 maximum"}}"#;
+        let err = ProviderError::from_status(
+            "anthropic",
+            StatusCode::BAD_REQUEST,
+            &HeaderMap::new(),
+            body,
+        );
+        assert!(matches!(err, ProviderError::ContextOverflow { .. }));
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_from_error_body() {
+        let err = ProviderError::from_error_body(
+            "openai",
+            &json!({"message": "You exceeded your quota", "type": "rate_limit_exceeded"}),
+        );
+        assert!(matches!(err, ProviderError::RateLimited { .. }));
+
+        let err = ProviderError::from_error_body(
+            "openai",
+            &json!({"message": "Incorrect API key", "code": "invalid_api_key"}),
+        );
+        assert!(matches!(err, ProviderError::Auth { .. }));
+
+        let err = ProviderError::from_error_body(
+            "openai",
+            &json!({"message": "max 128000 tokens", "code": "context_length_exceeded"}),
+        );
+        assert!(matches!(err, ProviderError::ContextOverflow { .. }));
+
+        let err = ProviderError::from_error_body(
+            "anthropic",
+            &json!({"type": "overloaded_error", "message": "Overloaded"}),
+        );
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn test_message_with_status_digits_is_not_retryable() {
+        let err = ProviderError::from_error_body(
+            "openai",
+            &json!({"message": "max_tokens must be at most 500", "type": "invalid_request_error"}),
+        );
+        assert!(matches!(err, ProviderError::BadRequest { .. }));
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_from_anyhow_roundtrip() {
+        let err: anyhow::Error = ProviderError::Auth {
+            provider: "xai".to_string(),
+            message: "bad key".to_string(),
+        }
+        .into();
+        let err = err.context("chat failed");
+        let found = ProviderError::from_anyhow(&err).expect("provider error in chain");
+        assert_eq!(found.provider(), "xai");
+        assert!(ProviderError::classify(&err).is_some());
+
+        let plain = anyhow::anyhow!("Error: 503 Service Unavailable");
+        assert!(ProviderError::classify(&plain).is_none());
+    }
+}
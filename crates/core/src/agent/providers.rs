@@ -14,7 +14,14 @@ use std::sync::Mutex as StdMutex;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tracing::{debug, info};
 
-use crate::config::Config;
+use super::bedrock::{AwsCredentials, BedrockProvider, resolve_region};
+use super::llama_cpp;
+use super::model_catalog::ModelCatalog;
+use super::provider_error::ProviderError;
+use super::replay::{self, RecordingProvider, ReplayProvider};
+use super::structured::{self, ResponseSchema, StructuredResponse};
+use super::system_prompt::split_cacheable_prefix;
+use crate::config::{Config, PromptCacheTtl, ReasoningEffort};
 use crate::paths::DEFAULT_CONFIG_DIR_STR;
 
 /// Image attachment for multimodal messages
@@ -37,6 +44,39 @@ pub struct Message {
     /// Optional image attachments (for multimodal messages)
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub images: Vec<ImageAttachment>,
+    /// Reasoning the model produced before this assistant message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thinking: Vec<ThinkingBlock>,
+}
+
+/// A block of model reasoning ("extended thinking").
+///
+/// Anthropic signs thinking blocks and requires them to be sent back unchanged
+/// on the assistant turn that made tool calls, so the signature (or the
+/// encrypted payload of a redacted block) is kept alongside the text.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThinkingBlock {
+    /// Reasoning text (empty for redacted blocks)
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Encrypted payload of a redacted block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redacted: Option<String>,
+}
+
+impl ThinkingBlock {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Whether the provider can verify this block when it is sent back
+    pub fn is_signed(&self) -> bool {
+        self.signature.is_some() || self.redacted.is_some()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -53,6 +93,10 @@ pub struct ToolCall {
     pub id: String,
     pub name: String,
     pub arguments: String,
+    /// Opaque signature the provider attached to the call (Gemini thought
+    /// signatures), sent back unchanged with the call on later turns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,19 +109,75 @@ pub struct ToolSchema {
 /// Token usage statistics from API response
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Usage {
+    /// Input tokens billed at the full rate (excludes cache reads and writes)
     pub input_tokens: u64,
     pub output_tokens: u64,
+    /// Input tokens served from the provider's prompt cache
+    #[serde(default)]
+    pub cache_read_tokens: u64,
+    /// Input tokens written to the provider's prompt cache
+    #[serde(default)]
+    pub cache_write_tokens: u64,
 }
 
 impl Usage {
     pub fn total(&self) -> u64 {
-        self.input_tokens + self.output_tokens
+        self.prompt_tokens() + self.output_tokens
+    }
+
+    /// Size of the prompt, whether or not it was served from the cache
+    pub fn prompt_tokens(&self) -> u64 {
+        self.input_tokens + self.cache_read_tokens + self.cache_write_tokens
+    }
+
+    /// Parse an OpenAI Chat Completions `usage` object. `prompt_tokens` there
+    /// includes cached tokens, which are split out into `cache_read_tokens`.
+    fn from_openai(u: &Value) -> Self {
+        let prompt = u["prompt_tokens"].as_u64().unwrap_or(0);
+        let cached = u["prompt_tokens_details"]["cached_tokens"]
+            .as_u64()
+            .unwrap_or(0)
+            .min(prompt);
+        Self {
+            input_tokens: prompt - cached,
+            output_tokens: u["completion_tokens"].as_u64().unwrap_or(0),
+            cache_read_tokens: cached,
+            cache_write_tokens: 0,
+        }
+    }
+
+    /// Parse a Gemini `usageMetadata` object. Thought tokens are billed as output.
+    fn from_gemini(u: &Value) -> Self {
+        let prompt = u["promptTokenCount"].as_u64().unwrap_or(0);
+        let cached = u["cachedContentTokenCount"]
+            .as_u64()
+            .unwrap_or(0)
+            .min(prompt);
+        Self {
+            input_tokens: prompt - cached,
+            output_tokens: u["candidatesTokenCount"].as_u64().unwrap_or(0)
+                + u["thoughtsTokenCount"].as_u64().unwrap_or(0),
+            cache_read_tokens: cached,
+            cache_write_tokens: 0,
+        }
+    }
+
+    /// Parse an Anthropic Messages `usage` object
+    fn from_anthropic(u: &Value) -> Self {
+        Self {
+            input_tokens: u["input_tokens"].as_u64().unwrap_or(0),
+            output_tokens: u["output_tokens"].as_u64().unwrap_or(0),
+            cache_read_tokens: u["cache_read_input_tokens"].as_u64().unwrap_or(0),
+            cache_write_tokens: u["cache_creation_input_tokens"].as_u64().unwrap_or(0),
+        }
     }
 }
 
 pub struct LLMResponse {
     pub content: LLMResponseContent,
     pub usage: Option<Usage>,
+    /// Reasoning produced before the answer or tool calls
+    pub thinking: Vec<ThinkingBlock>,
 }
 
 pub enum LLMResponseContent {
@@ -90,6 +190,7 @@ impl LLMResponse {
         Self {
             content: LLMResponseContent::Text(content),
             usage: None,
+            thinking: Vec::new(),
         }
     }
 
@@ -97,6 +198,7 @@ impl LLMResponse {
         Self {
             content: LLMResponseContent::Text(content),
             usage: Some(usage),
+            thinking: Vec::new(),
         }
     }
 
@@ -104,6 +206,7 @@ impl LLMResponse {
         Self {
             content: LLMResponseContent::ToolCalls(calls),
             usage: None,
+            thinking: Vec::new(),
         }
     }
 
@@ -111,8 +214,26 @@ impl LLMResponse {
         Self {
             content: LLMResponseContent::ToolCalls(calls),
             usage: Some(usage),
+            thinking: Vec::new(),
         }
     }
+
+    pub fn with_thinking(mut self, thinking: Vec<ThinkingBlock>) -> Self {
+        self.thinking = thinking;
+        self
+    }
+
+    /// Readable reasoning text, if the model returned any
+    pub fn thinking_text(&self) -> Option<String> {
+        let text = self
+            .thinking
+            .iter()
+            .map(|t| t.text.as_str())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        (!text.is_empty()).then_some(text)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -121,6 +242,32 @@ pub struct StreamChunk {
     pub done: bool,
     /// Tool calls accumulated during streaming (only set when done=true)
     pub tool_calls: Option<Vec<ToolCall>>,
+    /// Token usage for the whole request (only set when done=true, if reported)
+    pub usage: Option<Usage>,
+    /// Reasoning text delta, shown separately from the answer
+    pub thinking: Option<String>,
+    /// Complete reasoning blocks for the response (only set when done=true),
+    /// to be stored with the assistant message
+    pub thinking_blocks: Option<Vec<ThinkingBlock>>,
+}
+
+impl StreamChunk {
+    /// A single final chunk carrying a complete (non-streamed) response
+    pub fn from_response(resp: LLMResponse) -> Self {
+        let thinking = resp.thinking_text();
+        let (delta, tool_calls) = match resp.content {
+            LLMResponseContent::Text(text) => (text, None),
+            LLMResponseContent::ToolCalls(calls) => (String::new(), Some(calls)),
+        };
+        Self {
+            delta,
+            done: true,
+            tool_calls,
+            usage: resp.usage,
+            thinking,
+            thinking_blocks: (!resp.thinking.is_empty()).then_some(resp.thinking),
+        }
+    }
 }
 
 /// Events emitted during streaming with tools
@@ -128,6 +275,8 @@ pub struct StreamChunk {
 pub enum StreamEvent {
     /// Text content chunk
     Content(String),
+    /// Reasoning the model produced before answering
+    Thinking(String),
     /// Tool call started
     ToolCallStart {
         name: String,
@@ -181,6 +330,17 @@ pub trait LLMProvider: Send + Sync {
 
     async fn summarize(&self, text: &str) -> Result<String>;
 
+    /// Chat with the reply constrained to a JSON schema. Providers with a
+    /// native JSON-schema mode override this; the default describes the schema
+    /// in the system prompt, then validates the reply and retries.
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        schema: &ResponseSchema,
+    ) -> Result<StructuredResponse> {
+        structured::chat_with_validation(self, messages, schema).await
+    }
+
     /// Whether this provider supports native, server-side web search.
     fn supports_native_search(&self) -> bool {
         false
@@ -204,115 +364,83 @@ pub trait LLMProvider: Send + Sync {
     ) -> Result<StreamResult> {
         // Default implementation: single chunk with full response
         let resp = self.chat(messages, tools).await?;
-        match resp.content {
-            LLMResponseContent::Text(text) => Ok(Box::pin(futures::stream::once(async move {
-                Ok(StreamChunk {
-                    delta: text,
-                    done: true,
-                    tool_calls: None,
-                })
-            }))),
-            LLMResponseContent::ToolCalls(calls) => {
-                Ok(Box::pin(futures::stream::once(async move {
-                    Ok(StreamChunk {
-                        delta: String::new(),
-                        done: true,
-                        tool_calls: Some(calls),
-                    })
-                })))
-            }
-        }
+        Ok(Box::pin(futures::stream::once(async move {
+            Ok(StreamChunk::from_response(resp))
+        })))
     }
 }
 
-/// Resolve model alias to provider/model format (OpenClaw-compatible)
-fn resolve_model_alias(model: &str) -> String {
-    // OpenClaw-compatible aliases
-    match model.to_lowercase().as_str() {
-        // Short aliases → latest 4.6 models
-        "opus" => "anthropic/claude-opus-4-6".to_string(),
-        "sonnet" => "anthropic/claude-sonnet-4-6".to_string(),
-        "gpt" => "openai/gpt-4o".to_string(),
-        "gpt-mini" => "openai/gpt-4o-mini".to_string(),
-        "glm" => "glm/glm-4.7".to_string(),
-        "grok" => "xai/grok-3-mini".to_string(),
-        "codex" => "codex-cli/o4-mini".to_string(),
-        _ => model.to_string(),
-    }
+pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvider>> {
+    create_provider_with_reasoning(model, config, None)
 }
 
-/// Map OpenClaw model ID to actual API model ID
-/// See: https://docs.anthropic.com/en/docs/about-claude/models
-fn normalize_model_id(provider: &str, model_id: &str) -> String {
-    match provider {
-        "anthropic" => {
-            match model_id.to_lowercase().as_str() {
-                // Claude 4.6 models (latest)
-                "claude-opus-4-6" | "opus" | "opus-4.6" => "claude-opus-4-6".to_string(),
-                "claude-sonnet-4-6" | "sonnet" | "sonnet-4.6" => "claude-sonnet-4-6".to_string(),
-                // Claude 4.5 models (still supported)
-                "claude-opus-4-5" | "opus-4.5" => "claude-opus-4-5-20251101".to_string(),
-                "claude-sonnet-4-5" | "sonnet-4.5" => "claude-sonnet-4-5-20250929".to_string(),
-                "claude-haiku-4-5" | "haiku" | "haiku-4.5" => {
-                    "claude-haiku-4-5-20251001".to_string()
-                }
-                // Default to Opus 4.6 (latest)
-                _ => "claude-opus-4-6".to_string(),
-            }
-        }
-        _ => model_id.to_string(),
+/// Like `create_provider`, with a per-session reasoning effort that takes
+/// precedence over the model's and `agent.reasoning`
+pub fn create_provider_with_reasoning(
+    model: &str,
+    config: &Config,
+    reasoning: Option<ReasoningEffort>,
+) -> Result<Box<dyn LLMProvider>> {
+    let provider = build_provider(model, config, reasoning)?;
+
+    // Record exchanges to a fixture (never when replaying one)
+    match &config.agent.record_fixture {
+        Some(fixture) if provider.name() != "replay" => Ok(Box::new(RecordingProvider::new(
+            provider,
+            replay::fixture_path(fixture),
+        )?)),
+        _ => Ok(provider),
     }
 }
 
-pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvider>> {
+fn build_provider(
+    model: &str,
+    config: &Config,
+    reasoning: Option<ReasoningEffort>,
+) -> Result<Box<dyn LLMProvider>> {
     #[cfg(feature = "claude-cli")]
     let workspace = config.workspace_path();
 
-    // Resolve aliases first (e.g., "opus" → "anthropic/claude-opus-4-5")
-    let model = resolve_model_alias(model);
-
-    // Parse provider/model format (OpenClaw-compatible)
-    let (provider, model_id) = if let Some(pos) = model.find('/') {
-        let (p, m) = model.split_at(pos);
-        (p.to_lowercase(), m[1..].to_string()) // Skip the '/'
-    } else if model.starts_with("gpt-") || model.starts_with("o1") {
-        ("openai".to_string(), model.clone())
-    } else if model.starts_with("claude-") {
-        ("anthropic".to_string(), model.clone())
-    } else if model.starts_with("glm-") {
-        ("glm".to_string(), model.clone())
-    } else if model.starts_with("grok-") {
-        ("xai".to_string(), model.clone())
-    } else if model.starts_with("gemini-") {
-        ("gemini".to_string(), model.clone())
-    } else {
-        // Default to anthropic for unknown models, or ollama if configured
+    // Resolve aliases and API ids through the model catalog
+    // (e.g., "opus" → anthropic/claude-opus-4-6)
+    let resolved = ModelCatalog::from_config(config).resolve(model);
+    let model_id = resolved.model_id;
+    let max_tokens = config.agent.max_tokens.min(resolved.spec.max_output_tokens);
+    let reasoning = resolved
+        .spec
+        .reasoning_effort(reasoning, config.agent.reasoning);
+
+    // Unprefixed models the catalog can't place go to a configured local/default provider
+    let provider = resolved.provider.unwrap_or_else(|| {
         if config.providers.ollama.is_some() {
-            ("ollama".to_string(), model.clone())
+            "ollama".to_string()
         } else if config.providers.anthropic.is_some() {
-            ("anthropic".to_string(), model.clone())
+            "anthropic".to_string()
         } else {
-            ("unknown".to_string(), model.clone())
+            "unknown".to_string()
         }
-    };
+    });
 
     match provider.as_str() {
         "anthropic" => {
             // Prefer OAuth config if available
             if let Some(oauth_config) = &config.providers.anthropic_oauth {
-                let full_model = normalize_model_id("anthropic", &model_id);
-                Ok(Box::new(AnthropicOAuthProvider::new(
-                    OAuthConfig {
-                        access_token: oauth_config.access_token.clone(),
-                        refresh_token: oauth_config.refresh_token.clone(),
-                        client_id: oauth_config.client_id.clone(),
-                        client_secret: oauth_config.client_secret.clone(),
-                        expires_at: oauth_config.expires_at,
-                        base_url: oauth_config.base_url.clone(),
-                    },
-                    &full_model,
-                    config.agent.max_tokens,
-                )?))
+                Ok(Box::new(
+                    AnthropicOAuthProvider::new(
+                        OAuthConfig {
+                            access_token: oauth_config.access_token.clone(),
+                            refresh_token: oauth_config.refresh_token.clone(),
+                            client_id: oauth_config.client_id.clone(),
+                            client_secret: oauth_config.client_secret.clone(),
+                            expires_at: oauth_config.expires_at,
+                            base_url: oauth_config.base_url.clone(),
+                        },
+                        &model_id,
+                        max_tokens,
+                    )?
+                    .with_prompt_cache(config.agent.prompt_cache)
+                    .with_reasoning(reasoning),
+                ))
             } else {
                 let anthropic_config = config.providers.anthropic.as_ref().ok_or_else(|| {
                     anyhow::anyhow!(
@@ -327,30 +455,36 @@ pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvid
                     )
                 })?;
 
-                let full_model = normalize_model_id("anthropic", &model_id);
-                Ok(Box::new(AnthropicProvider::new(
-                    &anthropic_config.api_key,
-                    &anthropic_config.base_url,
-                    &full_model,
-                    config.agent.max_tokens,
-                )?))
+                Ok(Box::new(
+                    AnthropicProvider::new(
+                        &anthropic_config.api_key,
+                        &anthropic_config.base_url,
+                        &model_id,
+                        max_tokens,
+                    )?
+                    .with_prompt_cache(config.agent.prompt_cache)
+                    .with_reasoning(reasoning),
+                ))
             }
         }
 
         "openai" => {
             // Prefer OAuth config if available
             if let Some(oauth_config) = &config.providers.openai_oauth {
-                Ok(Box::new(OpenAIOAuthProvider::new(
-                    OAuthConfig {
-                        access_token: oauth_config.access_token.clone(),
-                        refresh_token: oauth_config.refresh_token.clone(),
-                        client_id: oauth_config.client_id.clone(),
-                        client_secret: oauth_config.client_secret.clone(),
-                        expires_at: oauth_config.expires_at,
-                        base_url: oauth_config.base_url.clone(),
-                    },
-                    &model_id,
-                )?))
+                Ok(Box::new(
+                    OpenAIOAuthProvider::new(
+                        OAuthConfig {
+                            access_token: oauth_config.access_token.clone(),
+                            refresh_token: oauth_config.refresh_token.clone(),
+                            client_id: oauth_config.client_id.clone(),
+                            client_secret: oauth_config.client_secret.clone(),
+                            expires_at: oauth_config.expires_at,
+                            base_url: oauth_config.base_url.clone(),
+                        },
+                        &model_id,
+                    )?
+                    .with_reasoning(reasoning),
+                ))
             } else {
                 let openai_config = config.providers.openai.as_ref().ok_or_else(|| {
                     anyhow::anyhow!(
@@ -365,11 +499,14 @@ pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvid
                     )
                 })?;
 
-                Ok(Box::new(OpenAIProvider::new(
-                    &openai_config.api_key,
-                    &openai_config.base_url,
-                    &model_id,
-                )?))
+                Ok(Box::new(
+                    OpenAIProvider::new(
+                        &openai_config.api_key,
+                        &openai_config.base_url,
+                        &model_id,
+                    )?
+                    .with_reasoning(reasoning),
+                ))
             }
         }
 
@@ -384,11 +521,53 @@ pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvid
                 )
             })?;
 
-            Ok(Box::new(XaiProvider::new(
-                &xai_config.api_key,
-                &xai_config.base_url,
-                &model_id,
-            )?))
+            Ok(Box::new(
+                XaiProvider::new(&xai_config.api_key, &xai_config.base_url, &model_id)?
+                    .with_reasoning(reasoning),
+            ))
+        }
+
+        "azure" => {
+            let azure_config = config.providers.azure_openai.as_ref().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Azure OpenAI provider not configured.\n\
+                    Add to {}/config.toml:\n\n\
+                    [providers.azure_openai]\n\
+                    endpoint = \"https://my-resource.openai.azure.com\"\n\
+                    api_key = \"${{AZURE_OPENAI_API_KEY}}\"",
+                    DEFAULT_CONFIG_DIR_STR
+                )
+            })?;
+            let auth = match (&azure_config.api_key, &azure_config.ad_token) {
+                (Some(api_key), _) => AzureAuth::ApiKey(api_key.clone()),
+                (None, Some(token)) => AzureAuth::BearerToken(token.clone()),
+                (None, None) => anyhow::bail!(
+                    "Azure OpenAI needs api_key or ad_token in [providers.azure_openai]"
+                ),
+            };
+
+            Ok(Box::new(
+                AzureOpenAIProvider::new(
+                    &azure_config.endpoint,
+                    azure_config.deployment_for(&model_id),
+                    &azure_config.api_version,
+                    auth,
+                )?
+                .with_reasoning(reasoning),
+            ))
+        }
+
+        "bedrock" => {
+            // Credentials and region may come entirely from the AWS environment
+            let bedrock_config = config.providers.bedrock.clone().unwrap_or_default();
+            let credentials = AwsCredentials::resolve(&bedrock_config)?;
+            let region = resolve_region(&bedrock_config)?;
+
+            Ok(Box::new(
+                BedrockProvider::new(credentials, &region, &model_id, max_tokens)?
+                    .with_endpoint(bedrock_config.endpoint.as_deref())
+                    .with_reasoning(reasoning),
+            ))
         }
 
         #[cfg(feature = "claude-cli")]
@@ -450,10 +629,9 @@ pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvid
                 )
             })?;
 
-            Ok(Box::new(OllamaProvider::new(
-                &ollama_config.endpoint,
-                &model_id,
-            )?))
+            Ok(Box::new(
+                OllamaProvider::new(&ollama_config.endpoint, &model_id)?.with_reasoning(reasoning),
+            ))
         }
 
         "glm" => {
@@ -467,38 +645,51 @@ pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvid
                 )
             })?;
 
-            Ok(Box::new(OpenAIProvider::new(
-                &glm_config.api_key,
-                &glm_config.base_url,
-                &model_id,
-            )?))
+            Ok(Box::new(
+                OpenAIProvider::new(&glm_config.api_key, &glm_config.base_url, &model_id)?
+                    .with_reasoning(reasoning),
+            ))
         }
 
         "gemini" => {
-            let gemini_config = config.providers.gemini_oauth.as_ref().ok_or_else(|| {
-                anyhow::anyhow!(
-                    "Gemini OAuth provider not configured.\n\
-                    Add to {}/config.toml:\n\n\
-                    [providers.gemini_oauth]\n\
-                    access_token = \"${{GEMINI_OAUTH_TOKEN}}\"\n\
-                    # Optional: refresh_token for automatic token renewal\n\
-                    # Optional: project_id for enterprise/subscription plans",
-                    DEFAULT_CONFIG_DIR_STR
-                )
-            })?;
+            // Prefer OAuth config if available
+            if let Some(oauth_config) = &config.providers.gemini_oauth {
+                Ok(Box::new(GeminiOAuthProvider::new(
+                    OAuthConfig {
+                        access_token: oauth_config.access_token.clone(),
+                        refresh_token: oauth_config.refresh_token.clone(),
+                        client_id: oauth_config.client_id.clone(),
+                        client_secret: oauth_config.client_secret.clone(),
+                        expires_at: oauth_config.expires_at,
+                        base_url: oauth_config.base_url.clone(),
+                    },
+                    &model_id,
+                    oauth_config.project_id.as_deref(),
+                )?))
+            } else {
+                let gemini_config = config.providers.gemini.as_ref().ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Gemini provider not configured.\n\
+                        Add to {}/config.toml:\n\n\
+                        [providers.gemini]\n\
+                        api_key = \"${{GEMINI_API_KEY}}\"\n\n\
+                        Or use OAuth subscription credentials:\n\n\
+                        [providers.gemini_oauth]\n\
+                        access_token = \"${{GEMINI_OAUTH_TOKEN}}\"",
+                        DEFAULT_CONFIG_DIR_STR
+                    )
+                })?;
 
-            Ok(Box::new(GeminiOAuthProvider::new(
-                OAuthConfig {
-                    access_token: gemini_config.access_token.clone(),
-                    refresh_token: gemini_config.refresh_token.clone(),
-                    client_id: gemini_config.client_id.clone(),
-                    client_secret: gemini_config.client_secret.clone(),
-                    expires_at: gemini_config.expires_at,
-                    base_url: gemini_config.base_url.clone(),
-                },
-                &model_id,
-                gemini_config.project_id.as_deref(),
-            )?))
+                Ok(Box::new(
+                    GeminiProvider::new(
+                        &gemini_config.api_key,
+                        &gemini_config.base_url,
+                        &model_id,
+                    )?
+                    .with_google_search(gemini_config.google_search)
+                    .with_reasoning(reasoning),
+                ))
+            }
         }
 
         "github" => {
@@ -533,14 +724,42 @@ pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvid
                 )
             })?;
 
-            Ok(Box::new(OpenAICompatibleProvider::new(
-                &compat_config.base_url,
-                &compat_config.api_key,
-                &model_id,
-                compat_config.extra_headers.clone(),
-            )?))
+            Ok(Box::new(
+                OpenAICompatibleProvider::new(
+                    &compat_config.base_url,
+                    &compat_config.api_key,
+                    &model_id,
+                    compat_config.extra_headers.clone(),
+                )?
+                .with_reasoning(reasoning),
+            ))
+        }
+
+        "llamacpp" => {
+            let llamacpp_config = config.providers.llamacpp.clone().unwrap_or_default();
+            let path = llama_cpp::model_path(&model_id, &llamacpp_config.models_dir);
+
+            #[cfg(feature = "llamacpp")]
+            {
+                Ok(Box::new(llama_cpp::LlamaCppChatProvider::new(
+                    &path,
+                    &llamacpp_config,
+                    max_tokens,
+                )?))
+            }
+            #[cfg(not(feature = "llamacpp"))]
+            anyhow::bail!(
+                "Model '{}' ({}) needs llama.cpp support.\n\
+                Rebuild with: cargo install localgpt --features llamacpp",
+                model,
+                path.display()
+            )
         }
 
+        "replay" => Ok(Box::new(ReplayProvider::from_file(replay::fixture_path(
+            &model_id,
+        ))?)),
+
         _ => {
             // Fallback: try Claude CLI if configured
             #[cfg(feature = "claude-cli")]
@@ -558,11 +777,15 @@ pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvid
                 - anthropic/claude-opus-4-5, anthropic/claude-sonnet-4-5\n  \
                 - openai/gpt-4o, openai/gpt-4o-mini\n  \
                 - xai/grok-3-mini\n  \
+                - azure/<deployment>, bedrock/anthropic.claude-sonnet-4-5-20250929-v1:0\n  \
+                - gemini/gemini-2.5-pro, gemini/gemini-2.5-flash\n  \
                 - glm/glm-4.7\n  \
                 - claude-cli/opus, claude-cli/sonnet\n  \
                 - gemini-cli/gemini-3.1-pro-preview\n  \
                 - ollama/llama3, ollama/mistral\n  \
-                - openai-compat/<model> (OpenRouter, DeepSeek, Groq, etc.)\n\n\
+                - llamacpp/<file.gguf> (in-process, --features llamacpp)\n  \
+                - openai-compat/<model> (OpenRouter, DeepSeek, Groq, etc.)\n  \
+                - replay/<fixture> (recorded exchanges, see agent.record_fixture)\n\n\
                 Or use aliases: opus, sonnet, haiku, gpt, gpt-mini, grok, glm",
                 provider,
                 model
@@ -571,12 +794,280 @@ pub fn create_provider(model: &str, config: &Config) -> Result<Box<dyn LLMProvid
     }
 }
 
+// OpenAI Chat Completions streaming (shared by all OpenAI-shaped providers)
+
+/// Accumulates incremental `tool_calls` deltas from an OpenAI-format stream.
+///
+/// OpenAI streams each tool call as a sequence of deltas keyed by `index`:
+/// the first delta carries `id` and `function.name`, later ones append
+/// fragments to `function.arguments`.
+#[derive(Debug, Default)]
+struct OpenAIToolCallDeltas {
+    calls: Vec<ToolCall>,
+}
+
+impl OpenAIToolCallDeltas {
+    fn apply(&mut self, deltas: &[Value]) {
+        for (position, delta) in deltas.iter().enumerate() {
+            // Some compatible servers omit `index`; fall back to array position
+            let index = delta["index"]
+                .as_u64()
+                .map(|i| i as usize)
+                .unwrap_or(position);
+            while self.calls.len() <= index {
+                self.calls.push(ToolCall {
+                    id: String::new(),
+                    name: String::new(),
+                    arguments: String::new(),
+                    signature: None,
+                });
+            }
+
+            let call = &mut self.calls[index];
+            if let Some(id) = delta["id"].as_str()
+                && !id.is_empty()
+            {
+                call.id = id.to_string();
+            }
+            if let Some(name) = delta["function"]["name"].as_str() {
+                call.name.push_str(name);
+            }
+            match &delta["function"]["arguments"] {
+                Value::String(fragment) => call.arguments.push_str(fragment),
+                Value::Null => {}
+                // Non-conforming servers may send the arguments as an object
+                other => call.arguments.push_str(&other.to_string()),
+            }
+        }
+    }
+
+    fn finish(self) -> Option<Vec<ToolCall>> {
+        let calls: Vec<ToolCall> = self
+            .calls
+            .into_iter()
+            .filter(|c| !c.name.is_empty())
+            .enumerate()
+            .map(|(i, mut c)| {
+                if c.id.is_empty() {
+                    c.id = format!("call_{}", i);
+                }
+                if c.arguments.trim().is_empty() {
+                    c.arguments = "{}".to_string();
+                }
+                c
+            })
+            .collect();
+
+        if calls.is_empty() { None } else { Some(calls) }
+    }
+}
+
+/// Convert an OpenAI Chat Completions SSE byte stream into `StreamChunk`s.
+///
+/// Text deltas are yielded as they arrive; tool call deltas are assembled and
+/// returned on the final `done` chunk, matching the Anthropic streaming contract.
+fn openai_sse_stream<S, B, E>(byte_stream: S, label: String) -> StreamResult
+where
+    S: Stream<Item = std::result::Result<B, E>> + Send + 'static,
+    B: AsRef<[u8]> + Send,
+    E: std::fmt::Display + Send,
+{
+    let stream = async_stream::stream! {
+        let mut byte_stream = Box::pin(byte_stream);
+        let mut buffer = String::new();
+        let mut tool_calls = OpenAIToolCallDeltas::default();
+        let mut usage: Option<Usage> = None;
+        let mut reasoning = String::new();
+        let mut finished = false;
+
+        'outer: while let Some(chunk) = byte_stream.next().await {
+            let bytes = match chunk {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    yield Err(ProviderError::Transport {
+                        provider: label.clone(),
+                        message: format!("stream interrupted: {}", e),
+                    }
+                    .into());
+                    finished = true;
+                    break;
+                }
+            };
+            buffer.push_str(&String::from_utf8_lossy(bytes.as_ref()));
+
+            // Process complete lines; SSE events are separated by blank lines
+            while let Some(pos) = buffer.find('\n') {
+                let line = buffer[..pos].trim_end_matches('\r').to_string();
+                buffer.drain(..=pos);
+
+                let Some(data) = line.strip_prefix("data:") else {
+                    continue;
+                };
+                let data = data.trim_start();
+
+                if data == "[DONE]" {
+                    yield Ok(StreamChunk {
+                        delta: String::new(),
+                        done: true,
+                        tool_calls: std::mem::take(&mut tool_calls).finish(),
+                        usage: usage.take(),
+                        thinking: None,
+                        thinking_blocks: (!reasoning.is_empty())
+                            .then(|| vec![ThinkingBlock::text(std::mem::take(&mut reasoning))]),
+                    });
+                    finished = true;
+                    break 'outer;
+                }
+
+                let Ok(json) = serde_json::from_str::<Value>(data) else {
+                    debug!("{}: skipping unparseable stream line: {}", label, data);
+                    continue;
+                };
+
+                if let Some(error) = json.get("error") {
+                    yield Err(ProviderError::from_error_body(&label, error).into());
+                    finished = true;
+                    break 'outer;
+                }
+
+                // Sent on the last chunk when `stream_options.include_usage` is set
+                if let Some(u) = json.get("usage").filter(|u| u.is_object()) {
+                    usage = Some(Usage::from_openai(u));
+                }
+
+                let Some(choice) = json["choices"].get(0) else {
+                    // Usage-only or keep-alive chunk
+                    continue;
+                };
+                let delta = &choice["delta"];
+
+                if let Some(text) = openai_reasoning_text(delta) {
+                    reasoning.push_str(text);
+                    yield Ok(StreamChunk {
+                        delta: String::new(),
+                        done: false,
+                        tool_calls: None,
+                        usage: None,
+                        thinking: Some(text.to_string()),
+                        thinking_blocks: None,
+                    });
+                }
+
+                if let Some(text) = delta["content"].as_str()
+                    && !text.is_empty()
+                {
+                    yield Ok(StreamChunk {
+                        delta: text.to_string(),
+                        done: false,
+                        tool_calls: None,
+                        usage: None,
+                        thinking: None,
+                        thinking_blocks: None,
+                    });
+                }
+
+                if let Some(calls) = delta["tool_calls"].as_array() {
+                    tool_calls.apply(calls);
+                }
+            }
+        }
+
+        // Some servers close the connection without sending [DONE]
+        if !finished {
+            yield Ok(StreamChunk {
+                delta: String::new(),
+                done: true,
+                tool_calls: tool_calls.finish(),
+                usage,
+                thinking: None,
+                thinking_blocks: (!reasoning.is_empty()).then(|| vec![ThinkingBlock::text(reasoning)]),
+            });
+        }
+    };
+
+    Box::pin(stream)
+}
+
+/// Send a streaming OpenAI-format request and wrap the response as a `StreamResult`.
+async fn send_openai_stream_request(
+    request: reqwest::RequestBuilder,
+    label: &str,
+) -> Result<StreamResult> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| ProviderError::transport(label, &e))?;
+    let response = ProviderError::check_response(label, response).await?;
+
+    Ok(openai_sse_stream(
+        response.bytes_stream(),
+        label.to_string(),
+    ))
+}
+
+/// Request reasoning on an OpenAI-format body (o-series and gpt-5 models;
+/// OpenRouter maps it onto other providers' thinking settings)
+fn apply_openai_reasoning(body: &mut Value, effort: ReasoningEffort) {
+    if effort.is_enabled() {
+        body["reasoning_effort"] = json!(effort.as_str());
+    }
+}
+
+/// Reasoning text in an OpenAI-format message or delta. OpenAI itself doesn't
+/// return it; DeepSeek, vLLM and llama.cpp use `reasoning_content`, OpenRouter
+/// uses `reasoning`.
+fn openai_reasoning_text(message: &Value) -> Option<&str> {
+    message["reasoning_content"]
+        .as_str()
+        .or_else(|| message["reasoning"].as_str())
+        .filter(|t| !t.is_empty())
+}
+
+/// OpenAI `response_format` requesting a reply that matches `schema`
+fn openai_response_format(schema: &ResponseSchema) -> Value {
+    let mut json_schema = json!({
+        "name": schema.name,
+        "schema": schema.schema,
+        "strict": schema.strict
+    });
+    if let Some(ref description) = schema.description {
+        json_schema["description"] = json!(description);
+    }
+    json!({ "type": "json_schema", "json_schema": json_schema })
+}
+
+/// Parse a Chat Completions response to a `response_format` request
+fn openai_structured_reply(
+    provider: &str,
+    response_body: &Value,
+    schema: &ResponseSchema,
+) -> Result<StructuredResponse> {
+    if let Some(error) = response_body.get("error") {
+        return Err(ProviderError::from_error_body(provider, error).into());
+    }
+
+    let message = &response_body["choices"][0]["message"];
+    if let Some(refusal) = message["refusal"].as_str() {
+        return Err(ProviderError::BadRequest {
+            provider: provider.to_string(),
+            message: format!("model refused: {}", refusal),
+        }
+        .into());
+    }
+
+    let mut structured =
+        schema.parse_native(provider, message["content"].as_str().unwrap_or(""))?;
+    structured.usage = response_body.get("usage").map(Usage::from_openai);
+    Ok(structured)
+}
+
 // OpenAI Provider
 pub struct OpenAIProvider {
     client: Client,
     api_key: String,
     base_url: String,
     model: String,
+    reasoning: ReasoningEffort,
 }
 
 impl OpenAIProvider {
@@ -586,9 +1077,16 @@ impl OpenAIProvider {
             api_key: api_key.to_string(),
             base_url: base_url.to_string(),
             model: model.to_string(),
+            reasoning: ReasoningEffort::default(),
         })
     }
 
+    /// Send `reasoning_effort` with each request
+    pub fn with_reasoning(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning = effort;
+        self
+    }
+
     fn format_tools(&self, tools: &[ToolSchema]) -> Vec<Value> {
         tools
             .iter()
@@ -691,6 +1189,7 @@ impl LLMProvider for OpenAIProvider {
             "model": self.model,
             "messages": self.format_messages(messages)
         });
+        apply_openai_reasoning(&mut body, self.reasoning);
 
         if let Some(tools) = tools
             && !tools.is_empty()
@@ -707,7 +1206,9 @@ impl LLMProvider for OpenAIProvider {
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
-            .await?;
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        let response = ProviderError::check_response(&self.name(), response).await?;
 
         let response_body: Value = response.json().await?;
         debug!(
@@ -717,7 +1218,7 @@ impl LLMProvider for OpenAIProvider {
 
         // Check for errors
         if let Some(error) = response_body.get("error") {
-            anyhow::bail!("OpenAI API error: {}", error);
+            return Err(ProviderError::from_error_body(&self.name(), error).into());
         }
 
         let choice = response_body["choices"]
@@ -725,12 +1226,13 @@ impl LLMProvider for OpenAIProvider {
             .ok_or_else(|| anyhow::anyhow!("No choices in response"))?;
 
         let message = &choice["message"];
+        let thinking: Vec<ThinkingBlock> = openai_reasoning_text(message)
+            .map(ThinkingBlock::text)
+            .into_iter()
+            .collect();
 
         // Parse usage
-        let usage = response_body.get("usage").map(|u| Usage {
-            input_tokens: u["prompt_tokens"].as_u64().unwrap_or(0),
-            output_tokens: u["completion_tokens"].as_u64().unwrap_or(0),
-        });
+        let usage = response_body.get("usage").map(Usage::from_openai);
 
         // Check for tool calls
         if let Some(tool_calls) = message.get("tool_calls")
@@ -745,6 +1247,7 @@ impl LLMProvider for OpenAIProvider {
                         .as_str()
                         .unwrap_or("{}")
                         .to_string(),
+                    signature: None,
                 })
                 .collect();
 
@@ -752,6 +1255,7 @@ impl LLMProvider for OpenAIProvider {
                 return Ok(LLMResponse {
                     content: LLMResponseContent::ToolCalls(parsed_calls),
                     usage,
+                    thinking,
                 });
             }
         }
@@ -761,9 +1265,42 @@ impl LLMProvider for OpenAIProvider {
         Ok(LLMResponse {
             content: LLMResponseContent::Text(content),
             usage,
+            thinking,
         })
     }
 
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        schema: &ResponseSchema,
+    ) -> Result<StructuredResponse> {
+        let mut body = json!({
+            "model": self.model,
+            "messages": self.format_messages(messages),
+            "response_format": openai_response_format(schema)
+        });
+        apply_openai_reasoning(&mut body, self.reasoning);
+
+        debug!(
+            "OpenAI structured request: {}",
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let response = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        let response = ProviderError::check_response(&self.name(), response).await?;
+
+        let response_body: Value = response.json().await?;
+        openai_structured_reply(&self.name(), &response_body, schema)
+    }
+
     async fn summarize(&self, text: &str) -> Result<String> {
         let messages = vec![Message {
             role: Role::User,
@@ -774,6 +1311,7 @@ impl LLMProvider for OpenAIProvider {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         }];
 
         match self.chat(&messages, None).await?.content {
@@ -781,15 +1319,50 @@ impl LLMProvider for OpenAIProvider {
             _ => anyhow::bail!("Unexpected response type"),
         }
     }
-}
 
-// OpenAI-Compatible Provider (OpenRouter, DeepSeek, Groq, vLLM, LiteLLM, Together AI, etc.)
-pub struct OpenAICompatibleProvider {
-    client: Client,
-    base_url: String,
-    api_key: String,
-    model: String,
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        let mut body = json!({
+            "model": self.model,
+            "messages": self.format_messages(messages),
+            "stream": true,
+            "stream_options": { "include_usage": true }
+        });
+        apply_openai_reasoning(&mut body, self.reasoning);
+
+        if let Some(tools) = tools
+            && !tools.is_empty()
+        {
+            body["tools"] = json!(self.format_tools(tools));
+        }
+
+        debug!(
+            "OpenAI streaming request: {}",
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let request = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&body);
+
+        send_openai_stream_request(request, &self.name()).await
+    }
+}
+
+// OpenAI-Compatible Provider (OpenRouter, DeepSeek, Groq, vLLM, LiteLLM, Together AI, etc.)
+pub struct OpenAICompatibleProvider {
+    client: Client,
+    base_url: String,
+    api_key: String,
+    model: String,
     extra_headers: std::collections::HashMap<String, String>,
+    reasoning: ReasoningEffort,
 }
 
 impl OpenAICompatibleProvider {
@@ -805,9 +1378,16 @@ impl OpenAICompatibleProvider {
             api_key: api_key.to_string(),
             model: model.to_string(),
             extra_headers,
+            reasoning: ReasoningEffort::default(),
         })
     }
 
+    /// Send `reasoning_effort` with each request
+    pub fn with_reasoning(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning = effort;
+        self
+    }
+
     fn format_tools(&self, tools: &[ToolSchema]) -> Vec<Value> {
         tools
             .iter()
@@ -910,6 +1490,7 @@ impl LLMProvider for OpenAICompatibleProvider {
             "model": self.model,
             "messages": self.format_messages(messages)
         });
+        apply_openai_reasoning(&mut body, self.reasoning);
 
         if let Some(tools) = tools
             && !tools.is_empty()
@@ -934,7 +1515,12 @@ impl LLMProvider for OpenAICompatibleProvider {
             request = request.header(key, value);
         }
 
-        let response = request.json(&body).send().await?;
+        let response = request
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        let response = ProviderError::check_response(&self.name(), response).await?;
 
         let response_body: Value = response.json().await?;
         debug!(
@@ -944,11 +1530,7 @@ impl LLMProvider for OpenAICompatibleProvider {
 
         // Check for errors
         if let Some(error) = response_body.get("error") {
-            anyhow::bail!(
-                "OpenAI-Compatible API error from {}: {}",
-                self.base_url,
-                error
-            );
+            return Err(ProviderError::from_error_body(&self.name(), error).into());
         }
 
         let choice = response_body["choices"]
@@ -956,12 +1538,13 @@ impl LLMProvider for OpenAICompatibleProvider {
             .ok_or_else(|| anyhow::anyhow!("No choices in response from {}", self.base_url))?;
 
         let message = &choice["message"];
+        let thinking: Vec<ThinkingBlock> = openai_reasoning_text(message)
+            .map(ThinkingBlock::text)
+            .into_iter()
+            .collect();
 
         // Parse usage
-        let usage = response_body.get("usage").map(|u| Usage {
-            input_tokens: u["prompt_tokens"].as_u64().unwrap_or(0),
-            output_tokens: u["completion_tokens"].as_u64().unwrap_or(0),
-        });
+        let usage = response_body.get("usage").map(Usage::from_openai);
 
         // Check for tool calls
         if let Some(tool_calls) = message.get("tool_calls")
@@ -976,6 +1559,7 @@ impl LLMProvider for OpenAICompatibleProvider {
                         .as_str()
                         .unwrap_or("{}")
                         .to_string(),
+                    signature: None,
                 })
                 .collect();
 
@@ -983,6 +1567,7 @@ impl LLMProvider for OpenAICompatibleProvider {
                 return Ok(LLMResponse {
                     content: LLMResponseContent::ToolCalls(parsed_calls),
                     usage,
+                    thinking,
                 });
             }
         }
@@ -992,9 +1577,51 @@ impl LLMProvider for OpenAICompatibleProvider {
         Ok(LLMResponse {
             content: LLMResponseContent::Text(content),
             usage,
+            thinking,
         })
     }
 
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        schema: &ResponseSchema,
+    ) -> Result<StructuredResponse> {
+        let mut body = json!({
+            "model": self.model,
+            "messages": self.format_messages(messages),
+            "response_format": openai_response_format(schema)
+        });
+        apply_openai_reasoning(&mut body, self.reasoning);
+
+        let mut request = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json");
+        for (key, value) in &self.extra_headers {
+            request = request.header(key, value);
+        }
+
+        let response = request
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+
+        // Not every compatible server implements json_schema response formats
+        if response.status() == reqwest::StatusCode::BAD_REQUEST {
+            debug!(
+                "{} rejected response_format, validating replies instead",
+                self.base_url
+            );
+            return structured::chat_with_validation(self, messages, schema).await;
+        }
+        let response = ProviderError::check_response(&self.name(), response).await?;
+
+        let response_body: Value = response.json().await?;
+        openai_structured_reply(&self.name(), &response_body, schema)
+    }
+
     async fn summarize(&self, text: &str) -> Result<String> {
         let messages = vec![Message {
             role: Role::User,
@@ -1005,6 +1632,7 @@ impl LLMProvider for OpenAICompatibleProvider {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         }];
 
         match self.chat(&messages, None).await?.content {
@@ -1012,100 +1640,485 @@ impl LLMProvider for OpenAICompatibleProvider {
             _ => anyhow::bail!("Unexpected response type"),
         }
     }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        let mut body = json!({
+            "model": self.model,
+            "messages": self.format_messages(messages),
+            "stream": true
+        });
+        apply_openai_reasoning(&mut body, self.reasoning);
+
+        if let Some(tools) = tools
+            && !tools.is_empty()
+        {
+            body["tools"] = json!(self.format_tools(tools));
+        }
+
+        debug!(
+            "OpenAI-Compatible streaming request to {}: {}",
+            self.base_url,
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let mut request = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json");
+
+        for (key, value) in &self.extra_headers {
+            request = request.header(key, value);
+        }
+
+        send_openai_stream_request(request.json(&body), &self.name()).await
+    }
 }
 
-// xAI Provider (Responses API + native web_search passthrough)
-pub struct XaiProvider {
+// Azure OpenAI Provider (deployment URLs, `api-version` and `api-key`/Entra ID auth)
+pub struct AzureOpenAIProvider {
     client: Client,
-    api_key: String,
-    base_url: String,
-    model: String,
+    endpoint: String,
+    deployment: String,
+    api_version: String,
+    auth: AzureAuth,
+    reasoning: ReasoningEffort,
 }
 
-impl XaiProvider {
-    pub fn new(api_key: &str, base_url: &str, model: &str) -> Result<Self> {
+/// How requests to an Azure OpenAI resource authenticate
+#[derive(Debug, Clone)]
+pub enum AzureAuth {
+    /// Resource key, sent in the `api-key` header
+    ApiKey(String),
+    /// Microsoft Entra ID access token, sent as a bearer token
+    BearerToken(String),
+}
+
+impl AzureOpenAIProvider {
+    pub fn new(
+        endpoint: &str,
+        deployment: &str,
+        api_version: &str,
+        auth: AzureAuth,
+    ) -> Result<Self> {
         Ok(Self {
             client: Client::new(),
-            api_key: api_key.to_string(),
-            base_url: base_url.to_string(),
-            model: model.to_string(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            deployment: deployment.to_string(),
+            api_version: api_version.to_string(),
+            auth,
+            reasoning: ReasoningEffort::default(),
         })
     }
 
+    /// Send `reasoning_effort` with each request (o-series deployments)
+    pub fn with_reasoning(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning = effort;
+        self
+    }
+
+    fn url(&self) -> String {
+        format!(
+            "{}/openai/deployments/{}/chat/completions?api-version={}",
+            self.endpoint, self.deployment, self.api_version
+        )
+    }
+
+    fn request(&self, body: &Value) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .post(self.url())
+            .header("Content-Type", "application/json");
+        let request = match &self.auth {
+            AzureAuth::ApiKey(key) => request.header("api-key", key),
+            AzureAuth::BearerToken(token) => {
+                request.header("Authorization", format!("Bearer {}", token))
+            }
+        };
+        request.json(body)
+    }
+
     fn format_tools(&self, tools: &[ToolSchema]) -> Vec<Value> {
         tools
             .iter()
             .map(|t| {
                 json!({
                     "type": "function",
-                    "name": t.name,
-                    "description": t.description,
-                    "parameters": t.parameters
+                    "function": {
+                        "name": t.name,
+                        "description": t.description,
+                        "parameters": t.parameters
+                    }
                 })
             })
             .collect()
     }
 
-    fn format_text_message(role: &str, content: &str) -> Value {
-        json!({
-            "role": role,
-            "content": content
-        })
-    }
+    fn format_messages(&self, messages: &[Message]) -> Vec<Value> {
+        messages
+            .iter()
+            .map(|m| {
+                let role = match m.role {
+                    Role::System => "system",
+                    Role::User => "user",
+                    Role::Assistant => "assistant",
+                    Role::Tool => "tool",
+                };
 
-    fn format_message_with_images(role: &str, content: &str, images: &[ImageAttachment]) -> Value {
-        let mut parts: Vec<Value> = Vec::new();
+                let content: Value = if m.role == Role::User && !m.images.is_empty() {
+                    let mut content_parts: Vec<Value> = m
+                        .images
+                        .iter()
+                        .map(|img| {
+                            json!({
+                                "type": "image_url",
+                                "image_url": {
+                                    "url": format!("data:{};base64,{}", img.media_type, img.data)
+                                }
+                            })
+                        })
+                        .collect();
+                    if !m.content.is_empty() {
+                        content_parts.push(json!({
+                            "type": "text",
+                            "text": m.content
+                        }));
+                    }
+                    json!(content_parts)
+                } else {
+                    json!(m.content)
+                };
 
-        if !content.is_empty() {
-            parts.push(json!({
-                "type": "input_text",
-                "text": content
-            }));
-        }
+                let mut msg = json!({
+                    "role": role,
+                    "content": content
+                });
 
-        for image in images {
-            parts.push(json!({
-                "type": "input_image",
-                "image_url": format!("data:{};base64,{}", image.media_type, image.data)
-            }));
-        }
+                if let Some(ref tool_calls) = m.tool_calls {
+                    msg["tool_calls"] = json!(
+                        tool_calls
+                            .iter()
+                            .map(|tc| {
+                                json!({
+                                    "id": tc.id,
+                                    "type": "function",
+                                    "function": {
+                                        "name": tc.name,
+                                        "arguments": tc.arguments
+                                    }
+                                })
+                            })
+                            .collect::<Vec<_>>()
+                    );
+                }
 
-        json!({
-            "role": role,
-            "content": parts
-        })
+                if let Some(ref tool_call_id) = m.tool_call_id {
+                    msg["tool_call_id"] = json!(tool_call_id);
+                }
+
+                msg
+            })
+            .collect()
     }
+}
 
-    fn format_input(&self, messages: &[Message]) -> Vec<Value> {
-        let mut formatted = Vec::new();
+#[async_trait]
+impl LLMProvider for AzureOpenAIProvider {
+    fn name(&self) -> String {
+        "azure".to_string()
+    }
 
-        for message in messages {
-            match message.role {
-                Role::System | Role::User | Role::Assistant => {
-                    let role = match message.role {
-                        Role::System => "system",
-                        Role::User => "user",
-                        Role::Assistant => "assistant",
-                        Role::Tool => unreachable!(),
-                    };
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        let mut body = json!({
+            "messages": self.format_messages(messages)
+        });
+        apply_openai_reasoning(&mut body, self.reasoning);
 
-                    if let Some(tool_calls) = message.tool_calls.as_ref()
-                        && !tool_calls.is_empty()
-                    {
-                        if !message.content.is_empty() {
-                            formatted.push(Self::format_text_message(role, &message.content));
-                        }
+        if let Some(tools) = tools
+            && !tools.is_empty()
+        {
+            body["tools"] = json!(self.format_tools(tools));
+        }
 
-                        for tool_call in tool_calls {
-                            formatted.push(json!({
-                                "type": "function_call",
-                                "call_id": tool_call.id,
-                                "name": tool_call.name,
-                                "arguments": tool_call.arguments
-                            }));
-                        }
-                    } else if message.images.is_empty() {
-                        formatted.push(Self::format_text_message(role, &message.content));
+        debug!(
+            "Azure OpenAI request to {}: {}",
+            self.deployment,
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let response = self
+            .request(&body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        let response = ProviderError::check_response(&self.name(), response).await?;
+
+        let response_body: Value = response.json().await?;
+        debug!(
+            "Azure OpenAI response: {}",
+            serde_json::to_string_pretty(&response_body)?
+        );
+
+        if let Some(error) = response_body.get("error") {
+            return Err(ProviderError::from_error_body(&self.name(), error).into());
+        }
+
+        let choice = response_body["choices"]
+            .get(0)
+            .ok_or_else(|| anyhow::anyhow!("No choices in response from {}", self.deployment))?;
+
+        // Content filtered responses have no message content
+        if choice["finish_reason"] == "content_filter" {
+            return Err(ProviderError::BadRequest {
+                provider: self.name(),
+                message: "response blocked by the Azure content filter".to_string(),
+            }
+            .into());
+        }
+
+        let message = &choice["message"];
+        let thinking: Vec<ThinkingBlock> = openai_reasoning_text(message)
+            .map(ThinkingBlock::text)
+            .into_iter()
+            .collect();
+        let usage = response_body.get("usage").map(Usage::from_openai);
+
+        if let Some(calls) = message["tool_calls"].as_array() {
+            let parsed_calls: Vec<ToolCall> = calls
+                .iter()
+                .map(|tc| ToolCall {
+                    id: tc["id"].as_str().unwrap_or("").to_string(),
+                    name: tc["function"]["name"].as_str().unwrap_or("").to_string(),
+                    arguments: tc["function"]["arguments"]
+                        .as_str()
+                        .unwrap_or("{}")
+                        .to_string(),
+                    signature: None,
+                })
+                .collect();
+
+            if !parsed_calls.is_empty() {
+                return Ok(LLMResponse {
+                    content: LLMResponseContent::ToolCalls(parsed_calls),
+                    usage,
+                    thinking,
+                });
+            }
+        }
+
+        let content = message["content"].as_str().unwrap_or("").to_string();
+
+        Ok(LLMResponse {
+            content: LLMResponseContent::Text(content),
+            usage,
+            thinking,
+        })
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        schema: &ResponseSchema,
+    ) -> Result<StructuredResponse> {
+        let mut body = json!({
+            "messages": self.format_messages(messages),
+            "response_format": openai_response_format(schema)
+        });
+        apply_openai_reasoning(&mut body, self.reasoning);
+
+        let response = self
+            .request(&body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        let response = ProviderError::check_response(&self.name(), response).await?;
+
+        let response_body: Value = response.json().await?;
+        openai_structured_reply(&self.name(), &response_body, schema)
+    }
+
+    async fn summarize(&self, text: &str) -> Result<String> {
+        let messages = vec![Message {
+            role: Role::User,
+            content: format!(
+                "Summarize the following conversation concisely, preserving key information and context:\n\n{}",
+                text
+            ),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: Vec::new(),
+        }];
+
+        match self.chat(&messages, None).await?.content {
+            LLMResponseContent::Text(summary) => Ok(summary),
+            _ => anyhow::bail!("Unexpected response type"),
+        }
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        let mut body = json!({
+            "messages": self.format_messages(messages),
+            "stream": true,
+            "stream_options": { "include_usage": true }
+        });
+        apply_openai_reasoning(&mut body, self.reasoning);
+
+        if let Some(tools) = tools
+            && !tools.is_empty()
+        {
+            body["tools"] = json!(self.format_tools(tools));
+        }
+
+        debug!(
+            "Azure OpenAI streaming request to {}: {}",
+            self.deployment,
+            serde_json::to_string_pretty(&body)?
+        );
+
+        send_openai_stream_request(self.request(&body), &self.name()).await
+    }
+}
+
+// xAI Provider (Responses API + native web_search passthrough)
+pub struct XaiProvider {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    model: String,
+    reasoning: ReasoningEffort,
+}
+
+impl XaiProvider {
+    pub fn new(api_key: &str, base_url: &str, model: &str) -> Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            api_key: api_key.to_string(),
+            base_url: base_url.to_string(),
+            model: model.to_string(),
+            reasoning: ReasoningEffort::default(),
+        })
+    }
+
+    /// Send a reasoning effort with each request
+    pub fn with_reasoning(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning = effort;
+        self
+    }
+
+    /// Set `reasoning.effort`; grok-3-mini accepts only "low" and "high"
+    fn apply_reasoning(&self, body: &mut Value) {
+        let effort = match self.reasoning {
+            ReasoningEffort::Off => return,
+            ReasoningEffort::Low => "low",
+            ReasoningEffort::Medium | ReasoningEffort::High => "high",
+        };
+        body["reasoning"] = json!({ "effort": effort });
+    }
+
+    /// Reasoning summaries from Responses API `reasoning` output items
+    fn parse_reasoning(output: &[Value]) -> Vec<ThinkingBlock> {
+        output
+            .iter()
+            .filter(|item| item["type"] == "reasoning")
+            .filter_map(|item| {
+                let text = item["summary"]
+                    .as_array()?
+                    .iter()
+                    .filter_map(|part| part["text"].as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                (!text.is_empty()).then(|| ThinkingBlock::text(text))
+            })
+            .collect()
+    }
+
+    fn format_tools(&self, tools: &[ToolSchema]) -> Vec<Value> {
+        tools
+            .iter()
+            .map(|t| {
+                json!({
+                    "type": "function",
+                    "name": t.name,
+                    "description": t.description,
+                    "parameters": t.parameters
+                })
+            })
+            .collect()
+    }
+
+    fn format_text_message(role: &str, content: &str) -> Value {
+        json!({
+            "role": role,
+            "content": content
+        })
+    }
+
+    fn format_message_with_images(role: &str, content: &str, images: &[ImageAttachment]) -> Value {
+        let mut parts: Vec<Value> = Vec::new();
+
+        if !content.is_empty() {
+            parts.push(json!({
+                "type": "input_text",
+                "text": content
+            }));
+        }
+
+        for image in images {
+            parts.push(json!({
+                "type": "input_image",
+                "image_url": format!("data:{};base64,{}", image.media_type, image.data)
+            }));
+        }
+
+        json!({
+            "role": role,
+            "content": parts
+        })
+    }
+
+    fn format_input(&self, messages: &[Message]) -> Vec<Value> {
+        let mut formatted = Vec::new();
+
+        for message in messages {
+            match message.role {
+                Role::System | Role::User | Role::Assistant => {
+                    let role = match message.role {
+                        Role::System => "system",
+                        Role::User => "user",
+                        Role::Assistant => "assistant",
+                        Role::Tool => unreachable!(),
+                    };
+
+                    if let Some(tool_calls) = message.tool_calls.as_ref()
+                        && !tool_calls.is_empty()
+                    {
+                        if !message.content.is_empty() {
+                            formatted.push(Self::format_text_message(role, &message.content));
+                        }
+
+                        for tool_call in tool_calls {
+                            formatted.push(json!({
+                                "type": "function_call",
+                                "call_id": tool_call.id,
+                                "name": tool_call.name,
+                                "arguments": tool_call.arguments
+                            }));
+                        }
+                    } else if message.images.is_empty() {
+                        formatted.push(Self::format_text_message(role, &message.content));
                     } else {
                         formatted.push(Self::format_message_with_images(
                             role,
@@ -1148,6 +2161,7 @@ impl XaiProvider {
                         .to_string(),
                     name: item["name"].as_str().unwrap_or("").to_string(),
                     arguments,
+                    signature: None,
                 }
             })
             .filter(|call| !call.id.is_empty() && !call.name.is_empty())
@@ -1186,6 +2200,155 @@ impl XaiProvider {
 
         text
     }
+
+    /// Parse a Responses API `usage` object; `input_tokens` includes cached tokens
+    fn parse_usage(u: &Value) -> Usage {
+        let input = u["input_tokens"].as_u64().unwrap_or(0);
+        let cached = u["input_tokens_details"]["cached_tokens"]
+            .as_u64()
+            .unwrap_or(0)
+            .min(input);
+        Usage {
+            input_tokens: input - cached,
+            output_tokens: u["output_tokens"].as_u64().unwrap_or(0),
+            cache_read_tokens: cached,
+            cache_write_tokens: 0,
+        }
+    }
+
+    /// Convert a Responses API SSE byte stream into `StreamChunk`s.
+    ///
+    /// Text arrives as `response.output_text.delta` events; function calls are
+    /// taken from `response.output_item.done` once their arguments are complete.
+    fn sse_stream<S, B, E>(byte_stream: S) -> StreamResult
+    where
+        S: Stream<Item = std::result::Result<B, E>> + Send + 'static,
+        B: AsRef<[u8]> + Send,
+        E: std::fmt::Display + Send,
+    {
+        let stream = async_stream::stream! {
+            let mut byte_stream = Box::pin(byte_stream);
+            let mut buffer = String::new();
+            let mut tool_calls: Vec<ToolCall> = Vec::new();
+            let mut usage: Option<Usage> = None;
+            let mut reasoning = String::new();
+            let mut finished = false;
+
+            'outer: while let Some(chunk) = byte_stream.next().await {
+                let bytes = match chunk {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        yield Err(ProviderError::Transport {
+                            provider: "xai".to_string(),
+                            message: format!("stream interrupted: {}", e),
+                        }
+                        .into());
+                        finished = true;
+                        break;
+                    }
+                };
+                buffer.push_str(&String::from_utf8_lossy(bytes.as_ref()));
+
+                while let Some(pos) = buffer.find('\n') {
+                    let line = buffer[..pos].trim_end_matches('\r').to_string();
+                    buffer.drain(..=pos);
+
+                    // The event type is repeated in the JSON payload, so `event:` lines can be ignored
+                    let Some(data) = line.strip_prefix("data:") else {
+                        continue;
+                    };
+                    let data = data.trim_start();
+                    if data == "[DONE]" {
+                        break 'outer;
+                    }
+
+                    let Ok(event) = serde_json::from_str::<Value>(data) else {
+                        debug!("xAI: skipping unparseable stream line: {}", data);
+                        continue;
+                    };
+
+                    match event["type"].as_str().unwrap_or("") {
+                        "response.output_text.delta" => {
+                            if let Some(text) = event["delta"].as_str()
+                                && !text.is_empty()
+                            {
+                                yield Ok(StreamChunk {
+                                    delta: text.to_string(),
+                                    done: false,
+                                    tool_calls: None,
+                                    usage: None,
+                                    thinking: None,
+                                    thinking_blocks: None,
+                                });
+                            }
+                        }
+                        "response.reasoning_summary_text.delta" => {
+                            if let Some(text) = event["delta"].as_str()
+                                && !text.is_empty()
+                            {
+                                reasoning.push_str(text);
+                                yield Ok(StreamChunk {
+                                    delta: String::new(),
+                                    done: false,
+                                    tool_calls: None,
+                                    usage: None,
+                                    thinking: Some(text.to_string()),
+                                    thinking_blocks: None,
+                                });
+                            }
+                        }
+                        "response.output_item.done" => {
+                            tool_calls.extend(Self::parse_tool_calls(std::slice::from_ref(
+                                &event["item"],
+                            )));
+                        }
+                        "response.completed" => {
+                            // Fall back to the final output if no item events were seen
+                            if tool_calls.is_empty()
+                                && let Some(output) = event["response"]["output"].as_array()
+                            {
+                                tool_calls = Self::parse_tool_calls(output);
+                            }
+                            let u = &event["response"]["usage"];
+                            if u.is_object() {
+                                usage = Some(Self::parse_usage(u));
+                            }
+                            break 'outer;
+                        }
+                        "response.failed" | "error" => {
+                            let error = if event["response"]["error"].is_null() {
+                                &event
+                            } else {
+                                &event["response"]["error"]
+                            };
+                            yield Err(ProviderError::from_error_body("xai", error).into());
+                            finished = true;
+                            break 'outer;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            if !finished {
+                yield Ok(StreamChunk {
+                    delta: String::new(),
+                    done: true,
+                    tool_calls: if tool_calls.is_empty() {
+                        None
+                    } else {
+                        Some(tool_calls)
+                    },
+                    usage,
+                    thinking: None,
+                    thinking_blocks: (!reasoning.is_empty())
+                        .then(|| vec![ThinkingBlock::text(reasoning)]),
+                });
+            }
+        };
+
+        Box::pin(stream)
+    }
 }
 
 #[async_trait]
@@ -1213,6 +2376,7 @@ impl LLMProvider for XaiProvider {
             "model": self.model,
             "input": self.format_input(messages)
         });
+        self.apply_reasoning(&mut body);
 
         let mut all_tools = Vec::new();
         if let Some(tool_schemas) = tools {
@@ -1237,7 +2401,9 @@ impl LLMProvider for XaiProvider {
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
-            .await?;
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        let response = ProviderError::check_response(&self.name(), response).await?;
 
         let response_body: Value = response.json().await?;
         debug!(
@@ -1247,23 +2413,22 @@ impl LLMProvider for XaiProvider {
 
         // Check for errors
         if let Some(error) = response_body.get("error") {
-            anyhow::bail!("xAI API error: {}", error);
+            return Err(ProviderError::from_error_body(&self.name(), error).into());
         }
 
         let output = response_body["output"]
             .as_array()
             .cloned()
             .unwrap_or_default();
-        let usage = response_body.get("usage").map(|u| Usage {
-            input_tokens: u["input_tokens"].as_u64().unwrap_or(0),
-            output_tokens: u["output_tokens"].as_u64().unwrap_or(0),
-        });
+        let usage = response_body.get("usage").map(XaiProvider::parse_usage);
+        let thinking = Self::parse_reasoning(&output);
 
         let parsed_calls = Self::parse_tool_calls(&output);
         if !parsed_calls.is_empty() {
             return Ok(LLMResponse {
                 content: LLMResponseContent::ToolCalls(parsed_calls),
                 usage,
+                thinking,
             });
         }
 
@@ -1272,6 +2437,7 @@ impl LLMProvider for XaiProvider {
         Ok(LLMResponse {
             content: LLMResponseContent::Text(content),
             usage,
+            thinking,
         })
     }
 
@@ -1285,6 +2451,7 @@ impl LLMProvider for XaiProvider {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         }];
 
         match self.chat(&messages, None).await?.content {
@@ -1292,6 +2459,51 @@ impl LLMProvider for XaiProvider {
             _ => anyhow::bail!("Unexpected response type"),
         }
     }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        let mut body = json!({
+            "model": self.model,
+            "input": self.format_input(messages),
+            "stream": true
+        });
+        self.apply_reasoning(&mut body);
+
+        let mut all_tools = Vec::new();
+        if let Some(tool_schemas) = tools {
+            let client_has_web_search = tool_schemas.iter().any(|t| t.name == "web_search");
+            if !client_has_web_search {
+                all_tools.extend(self.native_tool_definitions());
+            }
+            if !tool_schemas.is_empty() {
+                all_tools.extend(self.format_tools(tool_schemas));
+            }
+        }
+        if !all_tools.is_empty() {
+            body["tools"] = json!(all_tools);
+        }
+
+        debug!(
+            "xAI streaming request: {}",
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let response = self
+            .client
+            .post(format!("{}/responses", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        let response = ProviderError::check_response(&self.name(), response).await?;
+
+        Ok(Self::sse_stream(response.bytes_stream()))
+    }
 }
 
 // Anthropic Provider
@@ -1301,6 +2513,120 @@ pub struct AnthropicProvider {
     base_url: String,
     model: String,
     max_tokens: usize,
+    prompt_cache: PromptCacheTtl,
+    reasoning: ReasoningEffort,
+}
+
+/// `cache_control` marker for a prompt cache breakpoint (None when caching is off)
+fn anthropic_cache_control(ttl: PromptCacheTtl) -> Option<Value> {
+    match ttl {
+        PromptCacheTtl::Off => None,
+        PromptCacheTtl::FiveMinutes => Some(json!({ "type": "ephemeral" })),
+        PromptCacheTtl::OneHour => Some(json!({ "type": "ephemeral", "ttl": "1h" })),
+    }
+}
+
+/// Set the `system` field of an Anthropic Messages request and place prompt
+/// cache breakpoints.
+///
+/// Breakpoints go after the cross-session system prefix (so every new session
+/// reuses it), at the end of the system prompt, and on the second-to-last
+/// message. The last message carries the per-turn security block and changes
+/// every turn; the one before it is where the next turn's prefix will match.
+fn apply_anthropic_prompt_cache(body: &mut Value, system: Option<String>, ttl: PromptCacheTtl) {
+    let Some(cache_control) = anthropic_cache_control(ttl) else {
+        if let Some(system) = system {
+            body["system"] = json!(system);
+        }
+        return;
+    };
+
+    if let Some(system) = system {
+        let parts = match split_cacheable_prefix(&system) {
+            Some((prefix, rest)) => vec![prefix, rest],
+            None => vec![system.as_str()],
+        };
+        let blocks: Vec<Value> = parts
+            .into_iter()
+            .map(|text| json!({ "type": "text", "text": text, "cache_control": cache_control }))
+            .collect();
+        body["system"] = json!(blocks);
+    }
+
+    let Some(messages) = body["messages"].as_array_mut() else {
+        return;
+    };
+    if messages.len() < 2 {
+        return;
+    }
+    let index = messages.len() - 2;
+    let content = &mut messages[index]["content"];
+    if let Some(text) = content.as_str() {
+        // Empty text blocks can't carry a breakpoint
+        if text.is_empty() {
+            return;
+        }
+        *content = json!([{ "type": "text", "text": text }]);
+    }
+    if let Some(last) = content.as_array_mut().and_then(|blocks| blocks.last_mut()) {
+        last["cache_control"] = cache_control;
+    }
+}
+
+/// Smallest `budget_tokens` Anthropic accepts
+const ANTHROPIC_MIN_THINKING_BUDGET: usize = 1_024;
+
+/// Enable extended thinking on an Anthropic Messages request. The budget counts
+/// toward `max_tokens`, so it is capped at half of it to leave room for the answer.
+pub(super) fn apply_anthropic_thinking(
+    body: &mut Value,
+    effort: ReasoningEffort,
+    max_tokens: usize,
+) {
+    let Some(budget) = effort.thinking_budget() else {
+        return;
+    };
+    let budget = budget.min(max_tokens / 2);
+    if budget < ANTHROPIC_MIN_THINKING_BUDGET {
+        return;
+    }
+    body["thinking"] = json!({ "type": "enabled", "budget_tokens": budget });
+}
+
+/// Thinking blocks to send back ahead of an assistant turn's tool calls.
+/// Only signed blocks verify; reasoning from other providers is dropped.
+fn anthropic_thinking_blocks(thinking: &[ThinkingBlock]) -> Vec<Value> {
+    thinking
+        .iter()
+        .filter_map(|t| match (&t.redacted, &t.signature) {
+            (Some(data), _) => Some(json!({ "type": "redacted_thinking", "data": data })),
+            (None, Some(signature)) => Some(json!({
+                "type": "thinking",
+                "thinking": t.text,
+                "signature": signature
+            })),
+            (None, None) => None,
+        })
+        .collect()
+}
+
+/// Thinking and redacted thinking blocks from an Anthropic `content` array
+fn parse_anthropic_thinking(content: &[Value]) -> Vec<ThinkingBlock> {
+    content
+        .iter()
+        .filter_map(|c| match c["type"].as_str()? {
+            "thinking" => Some(ThinkingBlock {
+                text: c["thinking"].as_str().unwrap_or("").to_string(),
+                signature: c["signature"].as_str().map(str::to_string),
+                redacted: None,
+            }),
+            "redacted_thinking" => Some(ThinkingBlock {
+                redacted: c["data"].as_str().map(str::to_string),
+                ..Default::default()
+            }),
+            _ => None,
+        })
+        .collect()
 }
 
 impl AnthropicProvider {
@@ -1311,9 +2637,23 @@ impl AnthropicProvider {
             base_url: base_url.to_string(),
             model: model.to_string(),
             max_tokens,
+            prompt_cache: PromptCacheTtl::default(),
+            reasoning: ReasoningEffort::default(),
         })
     }
 
+    /// Set the prompt cache lifetime ("off" disables cache breakpoints)
+    pub fn with_prompt_cache(mut self, ttl: PromptCacheTtl) -> Self {
+        self.prompt_cache = ttl;
+        self
+    }
+
+    /// Enable extended thinking with a budget derived from `effort`
+    pub fn with_reasoning(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning = effort;
+        self
+    }
+
     fn format_tools(&self, tools: &[ToolSchema]) -> Vec<Value> {
         tools
             .iter()
@@ -1383,9 +2723,12 @@ impl AnthropicProvider {
                                 "input": serde_json::from_str::<Value>(&tc.arguments).unwrap_or(json!({}))
                             })
                         }).collect();
+                        // Thinking blocks must precede the tool calls they led to
+                        let mut blocks = anthropic_thinking_blocks(&m.thinking);
+                        blocks.extend(tool_use);
                         formatted.push(json!({
                             "role": "assistant",
-                            "content": tool_use
+                            "content": blocks
                         }));
                     } else {
                         formatted.push(json!({
@@ -1443,9 +2786,8 @@ impl LLMProvider for AnthropicProvider {
             "messages": formatted_messages
         });
 
-        if let Some(system) = system_prompt {
-            body["system"] = json!(system);
-        }
+        apply_anthropic_prompt_cache(&mut body, system_prompt, self.prompt_cache);
+        apply_anthropic_thinking(&mut body, self.reasoning, self.max_tokens);
 
         let mut all_tools = Vec::new();
         if let Some(tool_schemas) = tools {
@@ -1474,7 +2816,9 @@ impl LLMProvider for AnthropicProvider {
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
-            .await?;
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        let response = ProviderError::check_response(&self.name(), response).await?;
 
         let response_body: Value = response.json().await?;
         debug!(
@@ -1484,18 +2828,16 @@ impl LLMProvider for AnthropicProvider {
 
         // Check for errors
         if let Some(error) = response_body.get("error") {
-            anyhow::bail!("Anthropic API error: {}", error);
+            return Err(ProviderError::from_error_body(&self.name(), error).into());
         }
 
         let content = response_body["content"]
             .as_array()
             .ok_or_else(|| anyhow::anyhow!("No content in response"))?;
 
-        // Parse usage (Anthropic uses input_tokens/output_tokens directly)
-        let usage = response_body.get("usage").map(|u| Usage {
-            input_tokens: u["input_tokens"].as_u64().unwrap_or(0),
-            output_tokens: u["output_tokens"].as_u64().unwrap_or(0),
-        });
+        // Parse usage (input_tokens excludes cache reads and writes)
+        let usage = response_body.get("usage").map(Usage::from_anthropic);
+        let thinking = parse_anthropic_thinking(content);
 
         // Check for tool use
         let tool_calls: Vec<ToolCall> = content
@@ -1505,6 +2847,7 @@ impl LLMProvider for AnthropicProvider {
                 id: c["id"].as_str().unwrap_or("").to_string(),
                 name: c["name"].as_str().unwrap_or("").to_string(),
                 arguments: serde_json::to_string(&c["input"]).unwrap_or("{}".to_string()),
+                signature: None,
             })
             .collect();
 
@@ -1512,6 +2855,7 @@ impl LLMProvider for AnthropicProvider {
             return Ok(LLMResponse {
                 content: LLMResponseContent::ToolCalls(tool_calls),
                 usage,
+                thinking,
             });
         }
 
@@ -1526,9 +2870,71 @@ impl LLMProvider for AnthropicProvider {
         Ok(LLMResponse {
             content: LLMResponseContent::Text(text),
             usage,
+            thinking,
         })
     }
 
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        schema: &ResponseSchema,
+    ) -> Result<StructuredResponse> {
+        // Tool input schemas must describe an object
+        if schema.schema["type"] != "object" {
+            return structured::chat_with_validation(self, messages, schema).await;
+        }
+
+        // The schema becomes a tool the model is forced to call. Forced tool
+        // use can't be combined with extended thinking, so no thinking here.
+        let (system_prompt, formatted_messages) = self.format_messages(messages);
+        let mut body = json!({
+            "model": self.model,
+            "max_tokens": self.max_tokens,
+            "messages": formatted_messages,
+            "tools": [{
+                "name": schema.name,
+                "description": schema
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| "Return the response as structured data".to_string()),
+                "input_schema": schema.schema
+            }],
+            "tool_choice": { "type": "tool", "name": schema.name }
+        });
+        apply_anthropic_prompt_cache(&mut body, system_prompt, self.prompt_cache);
+
+        debug!(
+            "Anthropic structured request: {}",
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let response = self
+            .client
+            .post(format!("{}/v1/messages", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        let response = ProviderError::check_response(&self.name(), response).await?;
+
+        let response_body: Value = response.json().await?;
+        if let Some(error) = response_body.get("error") {
+            return Err(ProviderError::from_error_body(&self.name(), error).into());
+        }
+
+        let input = response_body["content"]
+            .as_array()
+            .and_then(|content| content.iter().find(|c| c["type"] == "tool_use"))
+            .map(|c| c["input"].to_string())
+            .unwrap_or_default();
+        let mut structured = schema.parse_native(&self.name(), &input)?;
+        structured.usage = response_body.get("usage").map(Usage::from_anthropic);
+        Ok(structured)
+    }
+
     async fn summarize(&self, text: &str) -> Result<String> {
         let messages = vec![Message {
             role: Role::User,
@@ -1539,6 +2945,7 @@ impl LLMProvider for AnthropicProvider {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         }];
 
         match self.chat(&messages, None).await?.content {
@@ -1561,9 +2968,8 @@ impl LLMProvider for AnthropicProvider {
             "stream": true
         });
 
-        if let Some(system) = system_prompt {
-            body["system"] = json!(system);
-        }
+        apply_anthropic_prompt_cache(&mut body, system_prompt, self.prompt_cache);
+        apply_anthropic_thinking(&mut body, self.reasoning, self.max_tokens);
 
         let mut all_tools = Vec::new();
         if let Some(tool_schemas) = tools {
@@ -1592,13 +2998,11 @@ impl LLMProvider for AnthropicProvider {
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
-            .await?;
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
 
         // Check for error status
-        if !response.status().is_success() {
-            let error_body = response.text().await?;
-            anyhow::bail!("Anthropic API error: {}", error_body);
-        }
+        let response = ProviderError::check_response(&self.name(), response).await?;
 
         // Anthropic streams Server-Sent Events (SSE)
         // We need to track tool_use blocks and accumulate their JSON input
@@ -1611,6 +3015,11 @@ impl LLMProvider for AnthropicProvider {
             let mut current_tool_id: Option<String> = None;
             let mut current_tool_name: Option<String> = None;
             let mut current_tool_input: String = String::new();
+            let mut usage = Usage::default();
+
+            // Thinking blocks, kept whole (with signatures) for the session
+            let mut thinking_blocks: Vec<ThinkingBlock> = Vec::new();
+            let mut current_thinking: Option<ThinkingBlock> = None;
 
             while let Some(chunk) = byte_stream.next().await {
                 match chunk {
@@ -1636,6 +3045,9 @@ impl LLMProvider for AnthropicProvider {
                                             delta: String::new(),
                                             done: true,
                                             tool_calls,
+                                            usage: Some(usage.clone()),
+                                            thinking: None,
+                                            thinking_blocks: (!thinking_blocks.is_empty()).then(|| thinking_blocks.clone()),
                                         });
                                         continue;
                                     }
@@ -1652,31 +3064,67 @@ impl LLMProvider for AnthropicProvider {
                                                         delta: delta.to_string(),
                                                         done: false,
                                                         tool_calls: None,
+                                                        usage: None,
+                                                        thinking: None,
+                                                        thinking_blocks: None,
                                                     });
                                                 } else if let Some(input_delta) = json["delta"]["partial_json"].as_str() {
                                                     // Accumulate tool input JSON
                                                     current_tool_input.push_str(input_delta);
-                                                }
+                                                } else if let Some(thinking) = json["delta"]["thinking"].as_str() {
+                                                    if let Some(block) = current_thinking.as_mut() {
+                                                        block.text.push_str(thinking);
+                                                    }
+                                                    yield Ok(StreamChunk {
+                                                        delta: String::new(),
+                                                        done: false,
+                                                        tool_calls: None,
+                                                        usage: None,
+                                                        thinking: Some(thinking.to_string()),
+                                                        thinking_blocks: None,
+                                                    });
+                                                } else if let Some(signature) = json["delta"]["signature"].as_str()
+                                                    && let Some(block) = current_thinking.as_mut() {
+                                                        block.signature = Some(signature.to_string());
+                                                    }
                                             }
 
                                             // Tool use block started
                                             "content_block_start" => {
-                                                if let Some(content_block) = json.get("content_block")
-                                                    && content_block["type"] == "tool_use" {
-                                                        current_tool_id = content_block["id"].as_str().map(|s| s.to_string());
-                                                        current_tool_name = content_block["name"].as_str().map(|s| s.to_string());
-                                                        current_tool_input.clear();
+                                                if let Some(content_block) = json.get("content_block") {
+                                                    match content_block["type"].as_str() {
+                                                        Some("tool_use") => {
+                                                            current_tool_id = content_block["id"].as_str().map(|s| s.to_string());
+                                                            current_tool_name = content_block["name"].as_str().map(|s| s.to_string());
+                                                            current_tool_input.clear();
+                                                        }
+                                                        Some("thinking") => {
+                                                            current_thinking = Some(ThinkingBlock::default());
+                                                        }
+                                                        Some("redacted_thinking") => {
+                                                            thinking_blocks.push(ThinkingBlock {
+                                                                redacted: content_block["data"].as_str().map(|s| s.to_string()),
+                                                                ..Default::default()
+                                                            });
+                                                        }
+                                                        _ => {}
                                                     }
+                                                }
                                             }
 
                                             // Content block finished
                                             "content_block_stop" => {
+                                                if let Some(block) = current_thinking.take() {
+                                                    thinking_blocks.push(block);
+                                                }
+
                                                 // If we were accumulating a tool call, finalize it
                                                 if let (Some(id), Some(name)) = (current_tool_id.take(), current_tool_name.take()) {
                                                     pending_tool_calls.push(ToolCall {
                                                         id,
                                                         name,
                                                         arguments: std::mem::take(&mut current_tool_input),
+                                                        signature: None,
                                                     });
                                                 }
                                             }
@@ -1692,15 +3140,25 @@ impl LLMProvider for AnthropicProvider {
                                                     delta: String::new(),
                                                     done: true,
                                                     tool_calls,
+                                                    usage: Some(usage.clone()),
+                                                    thinking: None,
+                                                    thinking_blocks: (!thinking_blocks.is_empty()).then(|| thinking_blocks.clone()),
                                                 });
                                             }
 
+                                            // Input tokens arrive up front, output tokens at the end
+                                            "message_start" => {
+                                                usage = Usage::from_anthropic(&json["message"]["usage"]);
+                                            }
+                                            "message_delta" => {
+                                                if let Some(output) = json["usage"]["output_tokens"].as_u64() {
+                                                    usage.output_tokens = output;
+                                                }
+                                            }
+
                                             // Error
                                             "error" => {
-                                                let error_msg = json["error"]["message"]
-                                                    .as_str()
-                                                    .unwrap_or("Unknown error");
-                                                yield Err(anyhow::anyhow!("Anthropic error: {}", error_msg));
+                                                yield Err(ProviderError::from_error_body("anthropic", &json["error"]).into());
                                             }
 
                                             _ => {} // Ignore other events
@@ -1711,7 +3169,11 @@ impl LLMProvider for AnthropicProvider {
                         }
                     }
                     Err(e) => {
-                        yield Err(anyhow::anyhow!("Stream error: {}", e));
+                        yield Err(ProviderError::Transport {
+                            provider: "anthropic".to_string(),
+                            message: format!("stream interrupted: {}", e),
+                        }
+                        .into());
                         break;
                     }
                 }
@@ -1727,6 +3189,7 @@ pub struct OllamaProvider {
     client: Client,
     endpoint: String,
     model: String,
+    reasoning: ReasoningEffort,
 }
 
 impl OllamaProvider {
@@ -1735,8 +3198,25 @@ impl OllamaProvider {
             client: Client::new(),
             endpoint: endpoint.to_string(),
             model: model.to_string(),
+            reasoning: ReasoningEffort::default(),
         })
     }
+
+    /// Ask thinking models (qwen3, deepseek-r1, ...) to reason before answering
+    pub fn with_reasoning(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning = effort;
+        self
+    }
+
+    /// `message.thinking` from an Ollama chat response
+    fn parse_thinking(response_body: &Value) -> Vec<ThinkingBlock> {
+        response_body["message"]["thinking"]
+            .as_str()
+            .filter(|t| !t.is_empty())
+            .map(ThinkingBlock::text)
+            .into_iter()
+            .collect()
+    }
 }
 
 #[async_trait]
@@ -1787,6 +3267,9 @@ impl LLMProvider for OllamaProvider {
             "messages": formatted_messages,
             "stream": false
         });
+        if self.reasoning.is_enabled() {
+            body["think"] = json!(true);
+        }
 
         // Send tool schemas if provided
         if let Some(tool_schemas) = tools
@@ -1816,7 +3299,8 @@ impl LLMProvider for OllamaProvider {
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
-            .await?;
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
 
         // If Ollama returns 400 (model doesn't support tools), retry without tools
         if response.status() == reqwest::StatusCode::BAD_REQUEST && body.get("tools").is_some() {
@@ -1829,7 +3313,10 @@ impl LLMProvider for OllamaProvider {
                 .header("Content-Type", "application/json")
                 .json(&body_no_tools)
                 .send()
-                .await?;
+                .await
+                .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+            let retry_response =
+                ProviderError::check_response(&self.name(), retry_response).await?;
             let response_body: Value = retry_response.json().await?;
             let content = response_body["message"]["content"]
                 .as_str()
@@ -1839,6 +3326,7 @@ impl LLMProvider for OllamaProvider {
                 Some(Usage {
                     input_tokens: response_body["prompt_eval_count"].as_u64().unwrap_or(0),
                     output_tokens: response_body["eval_count"].as_u64().unwrap_or(0),
+                    ..Default::default()
                 })
             } else {
                 None
@@ -1846,9 +3334,11 @@ impl LLMProvider for OllamaProvider {
             return Ok(LLMResponse {
                 content: LLMResponseContent::Text(content),
                 usage,
+                thinking: Self::parse_thinking(&response_body),
             });
         }
 
+        let response = ProviderError::check_response(&self.name(), response).await?;
         let response_body: Value = response.json().await?;
         debug!(
             "Ollama response: {}",
@@ -1860,10 +3350,12 @@ impl LLMProvider for OllamaProvider {
             Some(Usage {
                 input_tokens: response_body["prompt_eval_count"].as_u64().unwrap_or(0),
                 output_tokens: response_body["eval_count"].as_u64().unwrap_or(0),
+                ..Default::default()
             })
         } else {
             None
         };
+        let thinking = Self::parse_thinking(&response_body);
 
         // Check for tool calls in response
         if let Some(tool_calls) = response_body["message"]["tool_calls"].as_array()
@@ -1883,6 +3375,7 @@ impl LLMProvider for OllamaProvider {
                         id: format!("call_{}", i),
                         name,
                         arguments,
+                        signature: None,
                     })
                 })
                 .collect();
@@ -1891,6 +3384,7 @@ impl LLMProvider for OllamaProvider {
                 return Ok(LLMResponse {
                     content: LLMResponseContent::ToolCalls(calls),
                     usage,
+                    thinking,
                 });
             }
         }
@@ -1903,9 +3397,64 @@ impl LLMProvider for OllamaProvider {
         Ok(LLMResponse {
             content: LLMResponseContent::Text(content),
             usage,
+            thinking,
         })
     }
 
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        schema: &ResponseSchema,
+    ) -> Result<StructuredResponse> {
+        let formatted_messages: Vec<Value> = messages
+            .iter()
+            .map(|m| {
+                json!({
+                    "role": match m.role {
+                        Role::System => "system",
+                        Role::User => "user",
+                        Role::Assistant => "assistant",
+                        Role::Tool => "tool",
+                    },
+                    "content": m.content
+                })
+            })
+            .collect();
+
+        // Ollama constrains generation to a JSON schema passed as `format`
+        let body = json!({
+            "model": self.model,
+            "messages": formatted_messages,
+            "format": schema.schema,
+            "stream": false
+        });
+
+        debug!(
+            "Ollama structured request: {}",
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.endpoint))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        let response = ProviderError::check_response(&self.name(), response).await?;
+
+        let response_body: Value = response.json().await?;
+        let content = response_body["message"]["content"].as_str().unwrap_or("");
+        let mut structured = schema.parse_native(&self.name(), content)?;
+        structured.usage = Some(Usage {
+            input_tokens: response_body["prompt_eval_count"].as_u64().unwrap_or(0),
+            output_tokens: response_body["eval_count"].as_u64().unwrap_or(0),
+            ..Default::default()
+        });
+        Ok(structured)
+    }
+
     async fn summarize(&self, text: &str) -> Result<String> {
         let messages = vec![Message {
             role: Role::User,
@@ -1916,6 +3465,7 @@ impl LLMProvider for OllamaProvider {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         }];
 
         match self.chat(&messages, None).await?.content {
@@ -1932,24 +3482,9 @@ impl LLMProvider for OllamaProvider {
         // For tool-enabled requests, use non-streaming to properly handle tool calls
         if tools.is_some() && tools.map(|t| !t.is_empty()).unwrap_or(false) {
             let resp = self.chat(messages, tools).await?;
-            return match resp.content {
-                LLMResponseContent::Text(text) => Ok(Box::pin(futures::stream::once(async move {
-                    Ok(StreamChunk {
-                        delta: text,
-                        done: true,
-                        tool_calls: None,
-                    })
-                }))),
-                LLMResponseContent::ToolCalls(calls) => {
-                    Ok(Box::pin(futures::stream::once(async move {
-                        Ok(StreamChunk {
-                            delta: String::new(),
-                            done: true,
-                            tool_calls: Some(calls),
-                        })
-                    })))
-                }
-            };
+            return Ok(Box::pin(futures::stream::once(async move {
+                Ok(StreamChunk::from_response(resp))
+            })));
         }
 
         let formatted_messages: Vec<Value> = messages
@@ -1967,11 +3502,14 @@ impl LLMProvider for OllamaProvider {
             })
             .collect();
 
-        let body = json!({
+        let mut body = json!({
             "model": self.model,
             "messages": formatted_messages,
             "stream": true
         });
+        if self.reasoning.is_enabled() {
+            body["think"] = json!(true);
+        }
 
         debug!(
             "Ollama streaming request: {}",
@@ -1984,12 +3522,15 @@ impl LLMProvider for OllamaProvider {
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
-            .await?;
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        let response = ProviderError::check_response(&self.name(), response).await?;
 
         // Ollama streams newline-delimited JSON
         let stream = async_stream::stream! {
             let mut byte_stream = response.bytes_stream();
             let mut buffer = String::new();
+            let mut thinking = String::new();
 
             while let Some(chunk) = byte_stream.next().await {
                 match chunk {
@@ -2010,18 +3551,42 @@ impl LLMProvider for OllamaProvider {
                                     .as_str()
                                     .unwrap_or("")
                                     .to_string();
+                                let thinking_delta = json["message"]["thinking"]
+                                    .as_str()
+                                    .filter(|t| !t.is_empty())
+                                    .map(|t| t.to_string());
+                                if let Some(delta) = &thinking_delta {
+                                    thinking.push_str(delta);
+                                }
                                 let done = json["done"].as_bool().unwrap_or(false);
+                                let usage = if done && json.get("prompt_eval_count").is_some() {
+                                    Some(Usage {
+                                        input_tokens: json["prompt_eval_count"].as_u64().unwrap_or(0),
+                                        output_tokens: json["eval_count"].as_u64().unwrap_or(0),
+                                        ..Default::default()
+                                    })
+                                } else {
+                                    None
+                                };
 
                                 yield Ok(StreamChunk {
                                     delta: content,
                                     done,
                                     tool_calls: None,
+                                    usage,
+                                    thinking: thinking_delta,
+                                    thinking_blocks: (done && !thinking.is_empty())
+                                        .then(|| vec![ThinkingBlock::text(thinking.clone())]),
                                 });
                             }
                         }
                     }
                     Err(e) => {
-                        yield Err(anyhow::anyhow!("Stream error: {}", e));
+                        yield Err(ProviderError::Transport {
+                            provider: "ollama".to_string(),
+                            message: format!("stream interrupted: {}", e),
+                        }
+                        .into());
                         break;
                     }
                 }
@@ -2125,7 +3690,7 @@ impl ClaudeCliProvider {
                 }
             } else {
                 // Some other error - propagate it
-                anyhow::bail!("Claude CLI failed: {}", stderr);
+                return Err(ProviderError::from_cli_failure("claude-cli", &stderr).into());
             }
         }
 
@@ -2152,7 +3717,7 @@ impl ClaudeCliProvider {
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("Claude CLI failed: {}", stderr);
+            return Err(ProviderError::from_cli_failure("claude-cli", &stderr).into());
         }
 
         Ok((output, true))
@@ -2405,6 +3970,7 @@ impl LLMProvider for ClaudeCliProvider {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         }];
 
         match self.chat(&messages, None).await?.content {
@@ -2502,6 +4068,9 @@ impl LLMProvider for ClaudeCliProvider {
                                         delta: format!("[Model: {} | Tools: {}]\n", model, tools_count),
                                         done: false,
                                         tool_calls: None,
+                                        usage: None,
+                                        thinking: None,
+                                        thinking_blocks: None,
                                     });
                                 }
                         }
@@ -2560,6 +4129,9 @@ impl LLMProvider for ClaudeCliProvider {
                                                 delta: tool_msg,
                                                 done: false,
                                                 tool_calls: None,
+                                                usage: None,
+                                                thinking: None,
+                                                thinking_blocks: None,
                                             });
                                         }
                                     }
@@ -2574,6 +4146,9 @@ impl LLMProvider for ClaudeCliProvider {
                                     delta,
                                     done: false,
                                     tool_calls: None,
+                                    usage: None,
+                                    thinking: None,
+                                    thinking_blocks: None,
                                 });
                             }
                         }
@@ -2596,6 +4171,9 @@ impl LLMProvider for ClaudeCliProvider {
                                             delta: format!(" [{}]\n", status),
                                             done: false,
                                             tool_calls: None,
+                                            usage: None,
+                                            thinking: None,
+                                            thinking_blocks: None,
                                         });
                                     }
                                 }
@@ -2636,6 +4214,9 @@ impl LLMProvider for ClaudeCliProvider {
                                             delta,
                                             done: false,
                                             tool_calls: None,
+                                            usage: None,
+                                            thinking: None,
+                                            thinking_blocks: None,
                                         });
                                     }
                                 }
@@ -2646,6 +4227,9 @@ impl LLMProvider for ClaudeCliProvider {
                                 delta: String::new(),
                                 done: true,
                                 tool_calls: None,
+                                usage: None,
+                                thinking: None,
+                                thinking_blocks: None,
                             });
                         }
 
@@ -2675,7 +4259,7 @@ impl LLMProvider for ClaudeCliProvider {
                         use tokio::io::AsyncReadExt;
                         let _ = stderr.read_to_string(&mut error_buf).await;
                         if !error_buf.is_empty() {
-                            yield Err(anyhow::anyhow!("Claude CLI failed: {}", error_buf));
+                            yield Err(ProviderError::from_cli_failure("claude-cli", &error_buf).into());
                         }
                     }
                 }
@@ -2788,7 +4372,7 @@ impl GeminiCliProvider {
                 }
             } else {
                 // Some other error - propagate it
-                anyhow::bail!("Gemini CLI failed: {}", stderr);
+                return Err(ProviderError::from_cli_failure("gemini-cli", &stderr).into());
             }
         }
 
@@ -2815,7 +4399,7 @@ impl GeminiCliProvider {
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("Gemini CLI failed: {}", stderr);
+            return Err(ProviderError::from_cli_failure("gemini-cli", &stderr).into());
         }
 
         Ok((output, true))
@@ -2957,6 +4541,7 @@ impl LLMProvider for GeminiCliProvider {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         }];
         match self.chat(&messages, None).await?.content {
             LLMResponseContent::Text(summary) => Ok(summary),
@@ -2982,6 +4567,9 @@ impl LLMProvider for GeminiCliProvider {
                 delta: text,
                 done: true,
                 tool_calls: None,
+                usage: None,
+                thinking: None,
+                thinking_blocks: None,
             });
         };
 
@@ -3092,7 +4680,7 @@ impl CodexCliProvider {
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("Codex CLI failed: {}", stderr);
+            return Err(ProviderError::from_cli_failure("codex-cli", &stderr).into());
         }
 
         Ok((output, existing_session.is_none()))
@@ -3174,6 +4762,7 @@ impl LLMProvider for CodexCliProvider {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         }];
         match self.chat(&messages, None).await?.content {
             LLMResponseContent::Text(summary) => Ok(summary),
@@ -3198,6 +4787,9 @@ impl LLMProvider for CodexCliProvider {
                 delta: text,
                 done: true,
                 tool_calls: None,
+                usage: None,
+                thinking: None,
+                thinking_blocks: None,
             });
         };
 
@@ -3218,10 +4810,63 @@ mod tests {
         let usage = Usage {
             input_tokens: 100,
             output_tokens: 50,
+            ..Default::default()
         };
         assert_eq!(usage.total(), 150);
     }
 
+    #[test]
+    fn test_usage_cache_fields() {
+        let openai = Usage::from_openai(&json!({
+            "prompt_tokens": 1200,
+            "completion_tokens": 40,
+            "prompt_tokens_details": { "cached_tokens": 1024 }
+        }));
+        assert_eq!(openai.input_tokens, 176);
+        assert_eq!(openai.cache_read_tokens, 1024);
+        assert_eq!(openai.prompt_tokens(), 1200);
+
+        let anthropic = Usage::from_anthropic(&json!({
+            "input_tokens": 20,
+            "output_tokens": 40,
+            "cache_read_input_tokens": 3000,
+            "cache_creation_input_tokens": 500
+        }));
+        assert_eq!(anthropic.prompt_tokens(), 3520);
+        assert_eq!(anthropic.total(), 3560);
+    }
+
+    #[test]
+    fn test_anthropic_prompt_cache_breakpoints() {
+        let system = "You are a personal assistant.\n\n## Current Time\nSession started: now";
+        let mut body = json!({
+            "messages": [
+                { "role": "user", "content": "hi" },
+                { "role": "assistant", "content": "hello" },
+                { "role": "user", "content": "next" }
+            ]
+        });
+        apply_anthropic_prompt_cache(
+            &mut body,
+            Some(system.to_string()),
+            PromptCacheTtl::FiveMinutes,
+        );
+
+        let blocks = body["system"].as_array().unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0]["text"], "You are a personal assistant.");
+        assert_eq!(blocks[0]["cache_control"]["type"], "ephemeral");
+        assert_eq!(
+            body["messages"][1]["content"][0]["cache_control"]["type"],
+            "ephemeral"
+        );
+        assert_eq!(body["messages"][2]["content"], "next");
+
+        let mut body = json!({ "messages": [] });
+        apply_anthropic_prompt_cache(&mut body, Some(system.to_string()), PromptCacheTtl::Off);
+        assert_eq!(body["system"], system);
+    }
+
     #[test]
     fn test_usage_default() {
         let usage = Usage::default();
@@ -3241,6 +4886,7 @@ mod tests {
         let usage = Usage {
             input_tokens: 10,
             output_tokens: 5,
+            ..Default::default()
         };
         let resp = LLMResponse::text_with_usage("hello".to_string(), usage);
         assert!(matches!(resp.content, LLMResponseContent::Text(_)));
@@ -3252,25 +4898,13 @@ mod tests {
             id: "1".to_string(),
             name: "test".to_string(),
             arguments: "{}".to_string(),
+            signature: None,
         }];
         let resp = LLMResponse::tool_calls(calls);
         assert!(matches!(resp.content, LLMResponseContent::ToolCalls(_)));
         assert!(resp.usage.is_none());
     }
 
-    #[test]
-    fn test_resolve_model_alias() {
-        assert_eq!(resolve_model_alias("opus"), "anthropic/claude-opus-4-6");
-        assert_eq!(resolve_model_alias("sonnet"), "anthropic/claude-sonnet-4-6");
-        assert_eq!(resolve_model_alias("gpt"), "openai/gpt-4o");
-        assert_eq!(resolve_model_alias("gpt-mini"), "openai/gpt-4o-mini");
-        assert_eq!(resolve_model_alias("grok"), "xai/grok-3-mini");
-        assert_eq!(
-            resolve_model_alias("custom-model"),
-            "custom-model".to_string()
-        );
-    }
-
     #[test]
     fn test_xai_native_search_definition() {
         let provider = XaiProvider::new("test-key", "https://api.x.ai/v1", "grok-3-mini")
@@ -3327,9 +4961,11 @@ mod tests {
                     id: "call_1".to_string(),
                     name: "memory_search".to_string(),
                     arguments: "{\"query\":\"rust\"}".to_string(),
+                    signature: None,
                 }]),
                 tool_call_id: None,
                 images: Vec::new(),
+                thinking: Vec::new(),
             },
             Message {
                 role: Role::Tool,
@@ -3337,6 +4973,7 @@ mod tests {
                 tool_calls: None,
                 tool_call_id: Some("call_1".to_string()),
                 images: Vec::new(),
+                thinking: Vec::new(),
             },
         ];
 
@@ -3347,46 +4984,362 @@ mod tests {
         assert_eq!(formatted[1]["call_id"], "call_1");
         assert_eq!(formatted[1]["output"], "result");
     }
-}
 
-// Anthropic OAuth Provider (for Claude Pro/Max subscription plans)
-pub struct AnthropicOAuthProvider {
-    client: Client,
-    access_token: std::sync::Arc<std::sync::RwLock<String>>,
-    refresh_token: Option<String>,
-    client_id: Option<String>,
-    client_secret: Option<String>,
-    expires_at: std::sync::Arc<std::sync::RwLock<Option<u64>>>,
-    base_url: String,
-    model: String,
-    max_tokens: usize,
-}
+    async fn collect_chunks(stream: StreamResult) -> Vec<StreamChunk> {
+        stream
+            .map(|chunk| chunk.expect("chunk should parse"))
+            .collect()
+            .await
+    }
 
-impl AnthropicOAuthProvider {
-    pub fn new(config: OAuthConfig, model: &str, max_tokens: usize) -> Result<Self> {
-        Ok(Self {
-            client: Client::new(),
-            access_token: std::sync::Arc::new(std::sync::RwLock::new(config.access_token)),
-            refresh_token: config.refresh_token,
-            client_id: config.client_id,
-            client_secret: config.client_secret,
-            expires_at: std::sync::Arc::new(std::sync::RwLock::new(config.expires_at)),
-            base_url: config.base_url,
-            model: model.to_string(),
-            max_tokens,
-        })
+    fn sse_bytes(parts: &[&str]) -> impl Stream<Item = std::result::Result<Vec<u8>, String>> {
+        futures::stream::iter(
+            parts
+                .iter()
+                .map(|p| Ok(p.as_bytes().to_vec()))
+                .collect::<Vec<_>>(),
+        )
     }
 
-    async fn refresh_access_token(&self) -> Result<()> {
-        let refresh_token = self
-            .refresh_token
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No refresh token available"))?;
-        let client_id = self
-            .client_id
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No client ID available"))?;
-        let client_secret = self
+    #[tokio::test]
+    async fn test_openai_sse_stream_text_deltas() {
+        let stream = openai_sse_stream(
+            sse_bytes(&[
+                "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\",\"content\":\"\"}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\ndata: {\"choices\":[{\"del",
+                "ta\":{\"content\":\"lo\"}}]}\r\n\r\n",
+                "data: [DONE]\n\n",
+            ]),
+            "OpenAI".to_string(),
+        );
+
+        let chunks = collect_chunks(stream).await;
+        let text: String = chunks.iter().map(|c| c.delta.as_str()).collect();
+        assert_eq!(text, "Hello");
+        assert!(chunks.last().unwrap().done);
+        assert!(chunks.last().unwrap().tool_calls.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_openai_sse_stream_assembles_tool_calls() {
+        let stream = openai_sse_stream(
+            sse_bytes(&[
+                "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_a\",\"type\":\"function\",\"function\":{\"name\":\"read_file\",\"arguments\":\"\"}}]}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{\\\"path\\\":\"}}]}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":1,\"id\":\"call_b\",\"function\":{\"name\":\"bash\",\"arguments\":\"{}\"}}]}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"\\\"a.txt\\\"}\"}}]}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"tool_calls\"}]}\n\n",
+                "data: [DONE]\n\n",
+            ]),
+            "OpenAI".to_string(),
+        );
+
+        let chunks = collect_chunks(stream).await;
+        assert_eq!(chunks.len(), 1);
+        let calls = chunks[0].tool_calls.as_ref().expect("tool calls");
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_a");
+        assert_eq!(calls[0].name, "read_file");
+        assert_eq!(calls[0].arguments, "{\"path\":\"a.txt\"}");
+        assert_eq!(calls[1].id, "call_b");
+        assert_eq!(calls[1].name, "bash");
+        assert_eq!(calls[1].arguments, "{}");
+    }
+
+    #[tokio::test]
+    async fn test_openai_sse_stream_without_done_marker() {
+        let stream = openai_sse_stream(
+            sse_bytes(&[
+                "data:{\"choices\":[{\"delta\":{\"tool_calls\":[{\"function\":{\"name\":\"bash\"}}]}}]}\n\n",
+            ]),
+            "GLM".to_string(),
+        );
+
+        let chunks = collect_chunks(stream).await;
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].done);
+        let calls = chunks[0].tool_calls.as_ref().expect("tool calls");
+        assert_eq!(calls[0].id, "call_0");
+        assert_eq!(calls[0].arguments, "{}");
+    }
+
+    #[tokio::test]
+    async fn test_openai_sse_stream_surfaces_error_event() {
+        let mut stream = openai_sse_stream(
+            sse_bytes(&["data: {\"error\":{\"message\":\"overloaded\"}}\n\n"]),
+            "OpenAI".to_string(),
+        );
+
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("overloaded"));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_xai_sse_stream_text_and_function_call() {
+        let stream = XaiProvider::sse_stream(sse_bytes(&[
+            "event: response.output_text.delta\ndata: {\"type\":\"response.output_text.delta\",\"delta\":\"Checking\"}\n\n",
+            "event: response.function_call_arguments.delta\ndata: {\"type\":\"response.function_call_arguments.delta\",\"delta\":\"{\\\"q\"}\n\n",
+            "event: response.output_item.done\ndata: {\"type\":\"response.output_item.done\",\"item\":{\"type\":\"function_call\",\"call_id\":\"call_1\",\"name\":\"memory_search\",\"arguments\":\"{\\\"query\\\":\\\"rust\\\"}\"}}\n\n",
+            "event: response.completed\ndata: {\"type\":\"response.completed\",\"response\":{\"output\":[]}}\n\n",
+        ]));
+
+        let chunks = collect_chunks(stream).await;
+        assert_eq!(chunks[0].delta, "Checking");
+        let last = chunks.last().unwrap();
+        assert!(last.done);
+        let calls = last.tool_calls.as_ref().expect("tool calls");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].name, "memory_search");
+        assert_eq!(calls[0].arguments, "{\"query\":\"rust\"}");
+    }
+
+    #[test]
+    fn test_gemini_format_contents_pairs_function_responses() {
+        let message = |role, content: &str| Message {
+            role,
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: Vec::new(),
+        };
+        let call = |id: &str, name: &str| ToolCall {
+            id: id.to_string(),
+            name: name.to_string(),
+            arguments: "{}".to_string(),
+            signature: None,
+        };
+
+        let mut assistant = message(Role::Assistant, "");
+        let mut first = call("call_a", "read_file");
+        first.signature = Some("sig".to_string());
+        assistant.tool_calls = Some(vec![first, call("call_b", "bash")]);
+        let mut result_a = message(Role::Tool, "contents");
+        result_a.tool_call_id = Some("call_a".to_string());
+        let mut result_b = message(Role::Tool, "ok");
+        result_b.tool_call_id = Some("call_b".to_string());
+
+        let (system, contents) = GeminiProvider::format_contents(&[
+            message(Role::System, "Be brief."),
+            message(Role::User, "Look around"),
+            assistant,
+            result_a,
+            result_b,
+        ]);
+
+        assert_eq!(system.unwrap()["parts"][0]["text"], "Be brief.");
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[1]["role"], "model");
+        assert_eq!(contents[1]["parts"][0]["thoughtSignature"], "sig");
+        let responses = contents[2]["parts"].as_array().unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["functionResponse"]["name"], "read_file");
+        assert_eq!(responses[1]["functionResponse"]["name"], "bash");
+        assert_eq!(responses[1]["functionResponse"]["response"]["result"], "ok");
+    }
+
+    #[test]
+    fn test_gemini_google_search_tool() {
+        let provider = GeminiProvider::new("key", "https://example.com", "gemini-2.5-flash")
+            .expect("provider should construct");
+        assert!(!provider.supports_native_search());
+        let schema = ToolSchema {
+            name: "bash".to_string(),
+            description: "Run a command".to_string(),
+            parameters: json!({"type": "object"}),
+        };
+
+        // Grounding only goes out on turns without function tools
+        let body = provider.build_body(&[], Some(std::slice::from_ref(&schema)));
+        let tools = body["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0]["functionDeclarations"][0]["name"], "bash");
+        let body = provider.build_body(&[], Some(&[]));
+        assert!(body["tools"][0]["googleSearch"].is_object());
+        let body = provider.build_body(&[], None);
+        assert!(body["tools"][0]["googleSearch"].is_object());
+
+        let provider = provider.with_google_search(false);
+        assert!(provider.build_body(&[], Some(&[])).get("tools").is_none());
+        assert!(provider.build_body(&[], None).get("tools").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_gemini_sse_stream_thoughts_text_and_sources() {
+        let stream = GeminiProvider::sse_stream(sse_bytes(&[
+            "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"Searching\",\"thought\":true}]}}]}\r\n\r\n",
+            "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"Rust 1.0 \"}]}}]}\r\n\r\ndata: {\"candidates\":[{\"content\":",
+            "{\"role\":\"model\",\"parts\":[{\"text\":\"shipped in 2015.\"}]},\"groundingMetadata\":{\"groundingChunks\":[{\"web\":{\"uri\":\"https://example.com/rust\",\"title\":\"Rust\"}}]}}],",
+            "\"usageMetadata\":{\"promptTokenCount\":10,\"candidatesTokenCount\":6,\"thoughtsTokenCount\":4}}\r\n\r\n",
+        ]));
+
+        let chunks = collect_chunks(stream).await;
+        assert_eq!(chunks[0].thinking.as_deref(), Some("Searching"));
+        let text: String = chunks.iter().map(|c| c.delta.as_str()).collect();
+        assert_eq!(
+            text,
+            "Rust 1.0 shipped in 2015.\n\nSources:\n- [Rust](https://example.com/rust)"
+        );
+        let last = chunks.last().unwrap();
+        assert!(last.done);
+        assert!(last.tool_calls.is_none());
+        assert_eq!(last.usage.as_ref().unwrap().output_tokens, 10);
+        assert_eq!(last.thinking_blocks.as_ref().unwrap()[0].text, "Searching");
+    }
+
+    #[tokio::test]
+    async fn test_gemini_sse_stream_function_call() {
+        let stream = GeminiProvider::sse_stream(sse_bytes(&[
+            "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"functionCall\":{\"name\":\"memory_search\",\"args\":{\"query\":\"rust\"}},\"thoughtSignature\":\"c2ln\"}]},\"finishReason\":\"STOP\"}]}\r\n\r\n",
+        ]));
+
+        let chunks = collect_chunks(stream).await;
+        assert_eq!(chunks.len(), 1);
+        let calls = chunks[0].tool_calls.as_ref().expect("tool calls");
+        assert_eq!(calls[0].name, "memory_search");
+        assert_eq!(calls[0].arguments, "{\"query\":\"rust\"}");
+        assert_eq!(calls[0].signature.as_deref(), Some("c2ln"));
+        assert!(calls[0].id.starts_with("call_"));
+    }
+
+    fn user_message(content: &str) -> Message {
+        Message {
+            role: Role::User,
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_azure_chat_against_mock_server() {
+        let response = json!({
+            "choices": [{
+                "message": { "role": "assistant", "content": "Hello from Azure" },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 9, "completion_tokens": 4 }
+        });
+        let (base_url, request) = crate::agent::mock_http::serve_once(
+            200,
+            "application/json",
+            response.to_string().into_bytes(),
+        )
+        .await;
+
+        let provider = AzureOpenAIProvider::new(
+            &base_url,
+            "prod-gpt4o",
+            "2024-10-21",
+            AzureAuth::ApiKey("azure-key".to_string()),
+        )
+        .unwrap();
+        let resp = provider.chat(&[user_message("Hi")], None).await.unwrap();
+
+        assert!(matches!(resp.content, LLMResponseContent::Text(ref t) if t == "Hello from Azure"));
+        assert_eq!(resp.usage.unwrap().input_tokens, 9);
+        let request = request.await.unwrap();
+        assert_eq!(
+            request.path,
+            "/openai/deployments/prod-gpt4o/chat/completions?api-version=2024-10-21"
+        );
+        assert_eq!(request.header("api-key"), Some("azure-key"));
+        assert!(request.header("authorization").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_azure_content_filter_is_bad_request() {
+        let response = json!({
+            "choices": [{
+                "message": { "role": "assistant", "content": null },
+                "finish_reason": "content_filter"
+            }]
+        });
+        let (base_url, request) = crate::agent::mock_http::serve_once(
+            200,
+            "application/json",
+            response.to_string().into_bytes(),
+        )
+        .await;
+
+        let provider = AzureOpenAIProvider::new(
+            &base_url,
+            "prod-gpt4o",
+            "2024-10-21",
+            AzureAuth::BearerToken("entra-token".to_string()),
+        )
+        .unwrap();
+        let Err(err) = provider.chat(&[user_message("Hi")], None).await else {
+            panic!("expected content filter error");
+        };
+
+        assert!(matches!(
+            ProviderError::from_anyhow(&err),
+            Some(ProviderError::BadRequest { .. })
+        ));
+        let request = request.await.unwrap();
+        assert_eq!(request.header("authorization"), Some("Bearer entra-token"));
+    }
+}
+
+// Anthropic OAuth Provider (for Claude Pro/Max subscription plans)
+pub struct AnthropicOAuthProvider {
+    client: Client,
+    access_token: std::sync::Arc<std::sync::RwLock<String>>,
+    refresh_token: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    expires_at: std::sync::Arc<std::sync::RwLock<Option<u64>>>,
+    base_url: String,
+    model: String,
+    max_tokens: usize,
+    prompt_cache: PromptCacheTtl,
+    reasoning: ReasoningEffort,
+}
+
+impl AnthropicOAuthProvider {
+    pub fn new(config: OAuthConfig, model: &str, max_tokens: usize) -> Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            access_token: std::sync::Arc::new(std::sync::RwLock::new(config.access_token)),
+            refresh_token: config.refresh_token,
+            client_id: config.client_id,
+            client_secret: config.client_secret,
+            expires_at: std::sync::Arc::new(std::sync::RwLock::new(config.expires_at)),
+            base_url: config.base_url,
+            model: model.to_string(),
+            max_tokens,
+            prompt_cache: PromptCacheTtl::default(),
+            reasoning: ReasoningEffort::default(),
+        })
+    }
+
+    /// Set the prompt cache lifetime ("off" disables cache breakpoints)
+    pub fn with_prompt_cache(mut self, ttl: PromptCacheTtl) -> Self {
+        self.prompt_cache = ttl;
+        self
+    }
+
+    /// Enable extended thinking with a budget derived from `effort`
+    pub fn with_reasoning(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning = effort;
+        self
+    }
+
+    async fn refresh_access_token(&self) -> Result<()> {
+        let refresh_token = self
+            .refresh_token
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No refresh token available"))?;
+        let client_id = self
+            .client_id
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No client ID available"))?;
+        let client_secret = self
             .client_secret
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("No client secret available"))?;
@@ -3405,11 +5358,20 @@ impl AnthropicOAuthProvider {
             .post(format!("{}/oauth/token", self.base_url))
             .form(&params)
             .send()
-            .await?;
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
 
         if !response.status().is_success() {
+            let status = response.status();
+            let headers = response.headers().clone();
             let error_text = response.text().await?;
-            anyhow::bail!("Failed to refresh Anthropic access token: {}", error_text);
+            return Err(ProviderError::from_token_refresh(
+                &self.name(),
+                status,
+                &headers,
+                &error_text,
+            )
+            .into());
         }
 
         let json: Value = response.json().await?;
@@ -3528,9 +5490,12 @@ impl AnthropicOAuthProvider {
                                 "input": serde_json::from_str::<Value>(&tc.arguments).unwrap_or(json!({}))
                             })
                         }).collect();
+                        // Thinking blocks must precede the tool calls they led to
+                        let mut blocks = anthropic_thinking_blocks(&m.thinking);
+                        blocks.extend(tool_use);
                         formatted.push(json!({
                             "role": "assistant",
-                            "content": tool_use
+                            "content": blocks
                         }));
                     } else {
                         formatted.push(json!({
@@ -3552,38 +5517,532 @@ impl AnthropicOAuthProvider {
                     }
                 }
             }
-        }
+        }
+
+        (system_prompt, formatted)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for AnthropicOAuthProvider {
+    fn name(&self) -> String {
+        "anthropic-oauth".to_string()
+    }
+
+    fn token_update(&self) -> Option<OAuthTokenUpdate> {
+        let access_token = self.access_token.read().ok()?.clone();
+        let expires_at = *self.expires_at.read().ok()?;
+        Some(OAuthTokenUpdate {
+            provider: "anthropic".to_string(),
+            access_token,
+            refresh_token: self.refresh_token.clone(),
+            expires_at,
+        })
+    }
+
+    fn supports_native_search(&self) -> bool {
+        true
+    }
+
+    fn native_tool_definitions(&self) -> Vec<Value> {
+        vec![json!({
+            "type": "web_search_20250305",
+            "name": "web_search"
+        })]
+    }
+
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        self.ensure_valid_token().await?;
+
+        let (system_prompt, formatted_messages) = self.format_messages(messages);
+
+        let mut body = json!({
+            "model": self.model,
+            "max_tokens": self.max_tokens,
+            "messages": formatted_messages
+        });
+
+        apply_anthropic_prompt_cache(&mut body, system_prompt, self.prompt_cache);
+        apply_anthropic_thinking(&mut body, self.reasoning, self.max_tokens);
+
+        let mut all_tools = Vec::new();
+        if let Some(tool_schemas) = tools {
+            let client_has_web_search = tool_schemas.iter().any(|t| t.name == "web_search");
+            if !client_has_web_search {
+                all_tools.extend(self.native_tool_definitions());
+            }
+            if !tool_schemas.is_empty() {
+                all_tools.extend(self.format_tools(tool_schemas));
+            }
+        }
+        if !all_tools.is_empty() {
+            body["tools"] = json!(all_tools);
+        }
+
+        debug!(
+            "Anthropic OAuth request: {}",
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let current_access_token = self
+            .access_token
+            .read()
+            .map_err(|_| anyhow::anyhow!("Failed to acquire read lock on access_token"))?
+            .clone();
+        let response = self
+            .client
+            .post(format!("{}/v1/messages", self.base_url))
+            .header("Authorization", format!("Bearer {}", current_access_token))
+            .header("anthropic-version", "2023-06-01")
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+
+        let status = response.status();
+        let response =
+            if status == reqwest::StatusCode::UNAUTHORIZED && self.refresh_token.is_some() {
+                debug!("Anthropic OAuth returned 401 Unauthorized, attempting to refresh token...");
+                self.refresh_access_token().await?;
+                let new_access_token = self
+                    .access_token
+                    .read()
+                    .map_err(|_| anyhow::anyhow!("Failed to acquire read lock on access_token"))?
+                    .clone();
+
+                self.client
+                    .post(format!("{}/v1/messages", self.base_url))
+                    .header("Authorization", format!("Bearer {}", new_access_token))
+                    .header("anthropic-version", "2023-06-01")
+                    .header("Content-Type", "application/json")
+                    .json(&body)
+                    .send()
+                    .await
+                    .map_err(|e| ProviderError::transport(&self.name(), &e))?
+            } else {
+                response
+            };
+        let response = ProviderError::check_response(&self.name(), response).await?;
+
+        let response_body: Value = response.json().await?;
+        debug!(
+            "Anthropic OAuth response: {}",
+            serde_json::to_string_pretty(&response_body)?
+        );
+
+        if let Some(error) = response_body.get("error") {
+            return Err(ProviderError::from_error_body(&self.name(), error).into());
+        }
+
+        let content = response_body["content"]
+            .as_array()
+            .ok_or_else(|| anyhow::anyhow!("No content in response"))?;
+
+        let usage = response_body.get("usage").map(Usage::from_anthropic);
+        let thinking = parse_anthropic_thinking(content);
+
+        let tool_calls: Vec<ToolCall> = content
+            .iter()
+            .filter(|c| c["type"] == "tool_use")
+            .map(|c| ToolCall {
+                id: c["id"].as_str().unwrap_or("").to_string(),
+                name: c["name"].as_str().unwrap_or("").to_string(),
+                arguments: serde_json::to_string(&c["input"]).unwrap_or("{}".to_string()),
+                signature: None,
+            })
+            .collect();
+
+        if !tool_calls.is_empty() {
+            return Ok(LLMResponse {
+                content: LLMResponseContent::ToolCalls(tool_calls),
+                usage,
+                thinking,
+            });
+        }
+
+        let text = content
+            .iter()
+            .filter(|c| c["type"] == "text")
+            .map(|c| c["text"].as_str().unwrap_or(""))
+            .collect::<Vec<_>>()
+            .join("");
+
+        Ok(LLMResponse {
+            content: LLMResponseContent::Text(text),
+            usage,
+            thinking,
+        })
+    }
+
+    async fn summarize(&self, text: &str) -> Result<String> {
+        let messages = vec![Message {
+            role: Role::User,
+            content: format!(
+                "Summarize the following conversation concisely, preserving key information and context:\n\n{}",
+                text
+            ),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: Vec::new(),
+        }];
+
+        match self.chat(&messages, None).await?.content {
+            LLMResponseContent::Text(summary) => Ok(summary),
+            _ => anyhow::bail!("Unexpected response type"),
+        }
+    }
+}
+
+// Gemini Provider (Generative Language API with an API key + native Google Search grounding)
+pub struct GeminiProvider {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    model: String,
+    google_search: bool,
+    reasoning: ReasoningEffort,
+}
+
+impl GeminiProvider {
+    pub fn new(api_key: &str, base_url: &str, model: &str) -> Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            api_key: api_key.to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            google_search: true,
+            reasoning: ReasoningEffort::default(),
+        })
+    }
+
+    /// Enable or disable Google Search grounding
+    pub fn with_google_search(mut self, enabled: bool) -> Self {
+        self.google_search = enabled;
+        self
+    }
+
+    /// Send a thinking budget with each request
+    pub fn with_reasoning(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning = effort;
+        self
+    }
+
+    fn url(&self, method: &str) -> String {
+        format!("{}/v1beta/models/{}:{}", self.base_url, self.model, method)
+    }
+
+    /// Split messages into a `systemInstruction` and Gemini `contents`.
+    ///
+    /// Tool results only carry the call id, so the function name is looked up
+    /// from the model turn that made the call. Consecutive results are merged
+    /// into a single turn, answering every call of the preceding model turn.
+    fn format_contents(messages: &[Message]) -> (Option<Value>, Vec<Value>) {
+        let mut system = Vec::new();
+        let mut contents: Vec<Value> = Vec::new();
+        let mut call_names: std::collections::HashMap<&str, &str> =
+            std::collections::HashMap::new();
+
+        for m in messages {
+            match m.role {
+                Role::System => system.push(m.content.as_str()),
+                Role::User => {
+                    let mut parts = Vec::new();
+                    if !m.content.is_empty() {
+                        parts.push(json!({"text": m.content}));
+                    }
+                    for img in &m.images {
+                        parts.push(json!({
+                            "inlineData": {
+                                "mimeType": img.media_type,
+                                "data": img.data
+                            }
+                        }));
+                    }
+                    if !parts.is_empty() {
+                        contents.push(json!({"role": "user", "parts": parts}));
+                    }
+                }
+                Role::Assistant => {
+                    let mut parts = Vec::new();
+                    if !m.content.is_empty() {
+                        parts.push(json!({"text": m.content}));
+                    }
+                    for tc in m.tool_calls.iter().flatten() {
+                        call_names.insert(&tc.id, &tc.name);
+                        let mut part = json!({
+                            "functionCall": {
+                                "name": tc.name,
+                                "args": serde_json::from_str::<Value>(&tc.arguments)
+                                    .unwrap_or(json!({}))
+                            }
+                        });
+                        if let Some(ref signature) = tc.signature {
+                            part["thoughtSignature"] = json!(signature);
+                        }
+                        parts.push(part);
+                    }
+                    if !parts.is_empty() {
+                        contents.push(json!({"role": "model", "parts": parts}));
+                    }
+                }
+                Role::Tool => {
+                    let id = m.tool_call_id.as_deref().unwrap_or_default();
+                    let part = json!({
+                        "functionResponse": {
+                            "name": call_names.get(id).copied().unwrap_or(id),
+                            "response": {"result": m.content}
+                        }
+                    });
+                    match contents.last_mut() {
+                        Some(last) if last["parts"][0].get("functionResponse").is_some() => {
+                            if let Some(parts) = last["parts"].as_array_mut() {
+                                parts.push(part);
+                            }
+                        }
+                        _ => contents.push(json!({"role": "user", "parts": [part]})),
+                    }
+                }
+            }
+        }
+
+        let system =
+            (!system.is_empty()).then(|| json!({"parts": [{"text": system.join("\n\n")}]}));
+        (system, contents)
+    }
+
+    fn format_tools(tools: &[ToolSchema]) -> Value {
+        let declarations: Vec<Value> = tools
+            .iter()
+            .map(|t| {
+                json!({
+                    "name": t.name,
+                    "description": t.description,
+                    "parametersJsonSchema": t.parameters
+                })
+            })
+            .collect();
+        json!({ "functionDeclarations": declarations })
+    }
+
+    fn build_body(&self, messages: &[Message], tools: Option<&[ToolSchema]>) -> Value {
+        let (system, contents) = Self::format_contents(messages);
+        let mut body = json!({ "contents": contents });
+        if let Some(system) = system {
+            body["systemInstruction"] = system;
+        }
+
+        // generateContent rejects Search grounding combined with function
+        // calling, so grounding is only requested on turns without tools
+        let all_tools = match tools {
+            Some(tool_schemas) if !tool_schemas.is_empty() => {
+                vec![Self::format_tools(tool_schemas)]
+            }
+            _ => self.native_tool_definitions(),
+        };
+        if !all_tools.is_empty() {
+            body["tools"] = json!(all_tools);
+        }
+
+        if let Some(budget) = self.reasoning.thinking_budget() {
+            body["generationConfig"] = json!({
+                "thinkingConfig": {
+                    "thinkingBudget": budget,
+                    "includeThoughts": true
+                }
+            });
+        }
+
+        body
+    }
+
+    /// Answer text, thought summaries and function calls from a candidate's `parts`
+    fn parse_parts(parts: &[Value]) -> (String, String, Vec<ToolCall>) {
+        let mut text = String::new();
+        let mut thoughts = String::new();
+        let mut tool_calls = Vec::new();
+
+        for part in parts {
+            if let Some(fc) = part.get("functionCall") {
+                tool_calls.push(ToolCall {
+                    id: fc["id"]
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple())),
+                    name: fc["name"].as_str().unwrap_or("").to_string(),
+                    arguments: serde_json::to_string(&fc["args"]).unwrap_or("{}".to_string()),
+                    signature: part["thoughtSignature"].as_str().map(str::to_string),
+                });
+            } else if let Some(t) = part["text"].as_str() {
+                if part["thought"].as_bool().unwrap_or(false) {
+                    thoughts.push_str(t);
+                } else {
+                    text.push_str(t);
+                }
+            }
+        }
+
+        (text, thoughts, tool_calls)
+    }
+
+    /// Markdown list of the web pages a grounded answer was based on
+    fn grounding_sources(candidate: &Value) -> Option<String> {
+        let chunks = candidate["groundingMetadata"]["groundingChunks"].as_array()?;
+        let sources: Vec<String> = chunks
+            .iter()
+            .filter_map(|c| {
+                let uri = c["web"]["uri"].as_str()?;
+                let title = c["web"]["title"].as_str().unwrap_or(uri);
+                Some(format!("- [{}]({})", title, uri))
+            })
+            .collect();
+        (!sources.is_empty()).then(|| format!("\n\nSources:\n{}", sources.join("\n")))
+    }
+
+    /// Reject responses where the prompt itself was blocked
+    fn check_prompt_feedback(&self, response_body: &Value) -> Result<()> {
+        if let Some(reason) = response_body["promptFeedback"]["blockReason"].as_str() {
+            return Err(ProviderError::BadRequest {
+                provider: self.name(),
+                message: format!("prompt blocked: {}", reason),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Convert a `streamGenerateContent?alt=sse` byte stream into `StreamChunk`s.
+    ///
+    /// Every event is a complete `GenerateContentResponse` holding the next
+    /// slice of the candidate; function calls always arrive whole.
+    fn sse_stream<S, B, E>(byte_stream: S) -> StreamResult
+    where
+        S: Stream<Item = std::result::Result<B, E>> + Send + 'static,
+        B: AsRef<[u8]> + Send,
+        E: std::fmt::Display + Send,
+    {
+        let stream = async_stream::stream! {
+            let mut byte_stream = Box::pin(byte_stream);
+            let mut buffer = String::new();
+            let mut tool_calls: Vec<ToolCall> = Vec::new();
+            let mut usage: Option<Usage> = None;
+            let mut reasoning = String::new();
+            let mut sources: Option<String> = None;
+
+            while let Some(chunk) = byte_stream.next().await {
+                let bytes = match chunk {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        yield Err(ProviderError::Transport {
+                            provider: "gemini".to_string(),
+                            message: format!("stream interrupted: {}", e),
+                        }
+                        .into());
+                        return;
+                    }
+                };
+                buffer.push_str(&String::from_utf8_lossy(bytes.as_ref()));
+
+                while let Some(pos) = buffer.find('\n') {
+                    let line = buffer[..pos].trim_end_matches('\r').to_string();
+                    buffer.drain(..=pos);
+
+                    let Some(data) = line.strip_prefix("data:") else {
+                        continue;
+                    };
+                    let Ok(event) = serde_json::from_str::<Value>(data.trim_start()) else {
+                        debug!("Gemini: skipping unparseable stream line: {}", data);
+                        continue;
+                    };
+
+                    if let Some(error) = event.get("error") {
+                        yield Err(ProviderError::from_error_body("gemini", error).into());
+                        return;
+                    }
+                    if event["usageMetadata"].is_object() {
+                        usage = Some(Usage::from_gemini(&event["usageMetadata"]));
+                    }
+
+                    let candidate = &event["candidates"][0];
+                    if let Some(s) = Self::grounding_sources(candidate) {
+                        sources = Some(s);
+                    }
+                    let Some(parts) = candidate["content"]["parts"].as_array() else {
+                        continue;
+                    };
+                    let (text, thoughts, calls) = Self::parse_parts(parts);
+                    tool_calls.extend(calls);
+
+                    if !thoughts.is_empty() {
+                        reasoning.push_str(&thoughts);
+                        yield Ok(StreamChunk {
+                            delta: String::new(),
+                            done: false,
+                            tool_calls: None,
+                            usage: None,
+                            thinking: Some(thoughts),
+                            thinking_blocks: None,
+                        });
+                    }
+                    if !text.is_empty() {
+                        yield Ok(StreamChunk {
+                            delta: text,
+                            done: false,
+                            tool_calls: None,
+                            usage: None,
+                            thinking: None,
+                            thinking_blocks: None,
+                        });
+                    }
+                }
+            }
 
-        (system_prompt, formatted)
+            yield Ok(StreamChunk {
+                delta: if tool_calls.is_empty() {
+                    sources.unwrap_or_default()
+                } else {
+                    String::new()
+                },
+                done: true,
+                tool_calls: if tool_calls.is_empty() {
+                    None
+                } else {
+                    Some(tool_calls)
+                },
+                usage,
+                thinking: None,
+                thinking_blocks: (!reasoning.is_empty())
+                    .then(|| vec![ThinkingBlock::text(reasoning)]),
+            });
+        };
+
+        Box::pin(stream)
     }
 }
 
 #[async_trait]
-impl LLMProvider for AnthropicOAuthProvider {
+impl LLMProvider for GeminiProvider {
     fn name(&self) -> String {
-        "anthropic-oauth".to_string()
-    }
-
-    fn token_update(&self) -> Option<OAuthTokenUpdate> {
-        let access_token = self.access_token.read().ok()?.clone();
-        let expires_at = *self.expires_at.read().ok()?;
-        Some(OAuthTokenUpdate {
-            provider: "anthropic".to_string(),
-            access_token,
-            refresh_token: self.refresh_token.clone(),
-            expires_at,
-        })
+        "gemini".to_string()
     }
 
+    // Grounding cannot replace the web_search tool: it is dropped whenever
+    // function tools are sent (see build_body)
     fn supports_native_search(&self) -> bool {
-        true
+        false
     }
 
     fn native_tool_definitions(&self) -> Vec<Value> {
-        vec![json!({
-            "type": "web_search_20250305",
-            "name": "web_search"
-        })]
+        if self.google_search {
+            vec![json!({ "googleSearch": {} })]
+        } else {
+            Vec::new()
+        }
     }
 
     async fn chat(
@@ -3591,126 +6050,112 @@ impl LLMProvider for AnthropicOAuthProvider {
         messages: &[Message],
         tools: Option<&[ToolSchema]>,
     ) -> Result<LLMResponse> {
-        self.ensure_valid_token().await?;
-
-        let (system_prompt, formatted_messages) = self.format_messages(messages);
-
-        let mut body = json!({
-            "model": self.model,
-            "max_tokens": self.max_tokens,
-            "messages": formatted_messages
-        });
-
-        if let Some(system) = system_prompt {
-            body["system"] = json!(system);
-        }
-
-        let mut all_tools = Vec::new();
-        if let Some(tool_schemas) = tools {
-            let client_has_web_search = tool_schemas.iter().any(|t| t.name == "web_search");
-            if !client_has_web_search {
-                all_tools.extend(self.native_tool_definitions());
-            }
-            if !tool_schemas.is_empty() {
-                all_tools.extend(self.format_tools(tool_schemas));
-            }
-        }
-        if !all_tools.is_empty() {
-            body["tools"] = json!(all_tools);
-        }
+        let body = self.build_body(messages, tools);
 
-        debug!(
-            "Anthropic OAuth request: {}",
-            serde_json::to_string_pretty(&body)?
-        );
+        debug!("Gemini request: {}", serde_json::to_string_pretty(&body)?);
 
-        let current_access_token = self
-            .access_token
-            .read()
-            .map_err(|_| anyhow::anyhow!("Failed to acquire read lock on access_token"))?
-            .clone();
         let response = self
             .client
-            .post(format!("{}/v1/messages", self.base_url))
-            .header("Authorization", format!("Bearer {}", current_access_token))
-            .header("anthropic-version", "2023-06-01")
+            .post(self.url("generateContent"))
+            .header("x-goog-api-key", &self.api_key)
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
-            .await?;
-
-        let status = response.status();
-        let response =
-            if status == reqwest::StatusCode::UNAUTHORIZED && self.refresh_token.is_some() {
-                debug!("Anthropic OAuth returned 401 Unauthorized, attempting to refresh token...");
-                self.refresh_access_token().await?;
-                let new_access_token = self
-                    .access_token
-                    .read()
-                    .map_err(|_| anyhow::anyhow!("Failed to acquire read lock on access_token"))?
-                    .clone();
-
-                self.client
-                    .post(format!("{}/v1/messages", self.base_url))
-                    .header("Authorization", format!("Bearer {}", new_access_token))
-                    .header("anthropic-version", "2023-06-01")
-                    .header("Content-Type", "application/json")
-                    .json(&body)
-                    .send()
-                    .await?
-            } else {
-                response
-            };
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        let response = ProviderError::check_response(&self.name(), response).await?;
 
         let response_body: Value = response.json().await?;
         debug!(
-            "Anthropic OAuth response: {}",
+            "Gemini response: {}",
             serde_json::to_string_pretty(&response_body)?
         );
 
         if let Some(error) = response_body.get("error") {
-            anyhow::bail!("Anthropic OAuth API error: {}", error);
+            return Err(ProviderError::from_error_body(&self.name(), error).into());
         }
+        self.check_prompt_feedback(&response_body)?;
 
-        let content = response_body["content"]
+        let candidate = &response_body["candidates"][0];
+        let parts = candidate["content"]["parts"]
             .as_array()
-            .ok_or_else(|| anyhow::anyhow!("No content in response"))?;
-
-        let usage = response_body.get("usage").map(|u| Usage {
-            input_tokens: u["input_tokens"].as_u64().unwrap_or(0),
-            output_tokens: u["output_tokens"].as_u64().unwrap_or(0),
-        });
-
-        let tool_calls: Vec<ToolCall> = content
-            .iter()
-            .filter(|c| c["type"] == "tool_use")
-            .map(|c| ToolCall {
-                id: c["id"].as_str().unwrap_or("").to_string(),
-                name: c["name"].as_str().unwrap_or("").to_string(),
-                arguments: serde_json::to_string(&c["input"]).unwrap_or("{}".to_string()),
-            })
-            .collect();
+            .cloned()
+            .unwrap_or_default();
+        let usage = response_body.get("usageMetadata").map(Usage::from_gemini);
+        let (mut text, thoughts, tool_calls) = Self::parse_parts(&parts);
+        let thinking = if thoughts.is_empty() {
+            Vec::new()
+        } else {
+            vec![ThinkingBlock::text(thoughts)]
+        };
 
         if !tool_calls.is_empty() {
             return Ok(LLMResponse {
                 content: LLMResponseContent::ToolCalls(tool_calls),
                 usage,
+                thinking,
             });
         }
 
-        let text = content
-            .iter()
-            .filter(|c| c["type"] == "text")
-            .map(|c| c["text"].as_str().unwrap_or(""))
-            .collect::<Vec<_>>()
-            .join("");
+        if let Some(sources) = Self::grounding_sources(candidate) {
+            text.push_str(&sources);
+        }
 
         Ok(LLMResponse {
             content: LLMResponseContent::Text(text),
             usage,
+            thinking,
         })
     }
 
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        schema: &ResponseSchema,
+    ) -> Result<StructuredResponse> {
+        // No tools: Search grounding can't be combined with a response schema
+        let mut body = self.build_body(messages, None);
+        if let Some(body) = body.as_object_mut() {
+            body.remove("tools");
+        }
+        if !body["generationConfig"].is_object() {
+            body["generationConfig"] = json!({});
+        }
+        body["generationConfig"]["responseMimeType"] = json!("application/json");
+        body["generationConfig"]["responseJsonSchema"] = schema.schema.clone();
+
+        debug!(
+            "Gemini structured request: {}",
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let response = self
+            .client
+            .post(self.url("generateContent"))
+            .header("x-goog-api-key", &self.api_key)
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        let response = ProviderError::check_response(&self.name(), response).await?;
+
+        let response_body: Value = response.json().await?;
+        if let Some(error) = response_body.get("error") {
+            return Err(ProviderError::from_error_body(&self.name(), error).into());
+        }
+        self.check_prompt_feedback(&response_body)?;
+
+        let parts = response_body["candidates"][0]["content"]["parts"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let (text, _thoughts, _tool_calls) = Self::parse_parts(&parts);
+        let mut structured = schema.parse_native(&self.name(), &text)?;
+        structured.usage = response_body.get("usageMetadata").map(Usage::from_gemini);
+        Ok(structured)
+    }
+
     async fn summarize(&self, text: &str) -> Result<String> {
         let messages = vec![Message {
             role: Role::User,
@@ -3721,6 +6166,7 @@ impl LLMProvider for AnthropicOAuthProvider {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         }];
 
         match self.chat(&messages, None).await?.content {
@@ -3728,6 +6174,32 @@ impl LLMProvider for AnthropicOAuthProvider {
             _ => anyhow::bail!("Unexpected response type"),
         }
     }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        let body = self.build_body(messages, tools);
+
+        debug!(
+            "Gemini streaming request: {}",
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let response = self
+            .client
+            .post(format!("{}?alt=sse", self.url("streamGenerateContent")))
+            .header("x-goog-api-key", &self.api_key)
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+        let response = ProviderError::check_response(&self.name(), response).await?;
+
+        Ok(Self::sse_stream(response.bytes_stream()))
+    }
 }
 
 // Gemini OAuth Provider (for Google AI subscription plans)
@@ -3791,11 +6263,20 @@ impl GeminiOAuthProvider {
             .post("https://oauth2.googleapis.com/token")
             .form(&params)
             .send()
-            .await?;
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
 
         if !response.status().is_success() {
+            let status = response.status();
+            let headers = response.headers().clone();
             let error_text = response.text().await?;
-            anyhow::bail!("Failed to refresh Gemini access token: {}", error_text);
+            return Err(ProviderError::from_token_refresh(
+                &self.name(),
+                status,
+                &headers,
+                &error_text,
+            )
+            .into());
         }
 
         let json: Value = response.json().await?;
@@ -4017,7 +6498,8 @@ impl LLMProvider for GeminiOAuthProvider {
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
-            .await?;
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
 
         let mut status = response.status();
 
@@ -4039,7 +6521,8 @@ impl LLMProvider for GeminiOAuthProvider {
                     .header("Content-Type", "application/json")
                     .json(&body)
                     .send()
-                    .await?;
+                    .await
+                    .map_err(|e| ProviderError::transport(&self.name(), &e))?;
 
                 status = retry_response.status();
                 retry_response
@@ -4047,9 +6530,16 @@ impl LLMProvider for GeminiOAuthProvider {
                 response
             };
 
+        let headers = response.headers().clone();
         let response_text = response.text().await?;
         debug!("Gemini OAuth response ({}): {}", status, response_text);
 
+        if !status.is_success() {
+            return Err(
+                ProviderError::from_status(&self.name(), status, &headers, &response_text).into(),
+            );
+        }
+
         let response_body: Value = match serde_json::from_str(&response_text) {
             Ok(val) => val,
             Err(e) => {
@@ -4063,7 +6553,7 @@ impl LLMProvider for GeminiOAuthProvider {
         };
 
         if let Some(error) = response_body.get("error") {
-            anyhow::bail!("Gemini OAuth API error: {}", error);
+            return Err(ProviderError::from_error_body(&self.name(), error).into());
         }
 
         let candidates = response_body["candidates"]
@@ -4087,6 +6577,7 @@ impl LLMProvider for GeminiOAuthProvider {
                 id: format!("call_{}", i),
                 name: fc["name"].as_str().unwrap_or("").to_string(),
                 arguments: serde_json::to_string(&fc["args"]).unwrap_or("{}".to_string()),
+                signature: None,
             })
             .collect();
 
@@ -4094,6 +6585,7 @@ impl LLMProvider for GeminiOAuthProvider {
             return Ok(LLMResponse {
                 content: LLMResponseContent::ToolCalls(tool_calls),
                 usage: None,
+                thinking: Vec::new(),
             });
         }
 
@@ -4108,6 +6600,7 @@ impl LLMProvider for GeminiOAuthProvider {
         Ok(LLMResponse {
             content: LLMResponseContent::Text(text),
             usage: None,
+            thinking: Vec::new(),
         })
     }
 
@@ -4121,6 +6614,7 @@ impl LLMProvider for GeminiOAuthProvider {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         }];
 
         match self.chat(&messages, None).await?.content {
@@ -4140,6 +6634,7 @@ pub struct OpenAIOAuthProvider {
     expires_at: std::sync::Arc<std::sync::RwLock<Option<u64>>>,
     base_url: String,
     model: String,
+    reasoning: ReasoningEffort,
 }
 
 impl OpenAIOAuthProvider {
@@ -4153,9 +6648,16 @@ impl OpenAIOAuthProvider {
             expires_at: std::sync::Arc::new(std::sync::RwLock::new(config.expires_at)),
             base_url: config.base_url,
             model: model.to_string(),
+            reasoning: ReasoningEffort::default(),
         })
     }
 
+    /// Send `reasoning_effort` with each request
+    pub fn with_reasoning(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning = effort;
+        self
+    }
+
     async fn refresh_access_token(&self) -> Result<()> {
         let refresh_token = self
             .refresh_token
@@ -4184,11 +6686,20 @@ impl OpenAIOAuthProvider {
             .post(format!("{}/oauth/token", self.base_url))
             .form(&params)
             .send()
-            .await?;
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
 
         if !response.status().is_success() {
+            let status = response.status();
+            let headers = response.headers().clone();
             let error_text = response.text().await?;
-            anyhow::bail!("Failed to refresh OpenAI access token: {}", error_text);
+            return Err(ProviderError::from_token_refresh(
+                &self.name(),
+                status,
+                &headers,
+                &error_text,
+            )
+            .into());
         }
 
         let json: Value = response.json().await?;
@@ -4360,6 +6871,7 @@ impl LLMProvider for OpenAIOAuthProvider {
             "model": self.model,
             "messages": self.format_messages(messages)
         });
+        apply_openai_reasoning(&mut body, self.reasoning);
 
         if let Some(tools) = tools
             && !tools.is_empty()
@@ -4384,7 +6896,8 @@ impl LLMProvider for OpenAIOAuthProvider {
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
-            .await?;
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
 
         let status = response.status();
         let response =
@@ -4403,10 +6916,12 @@ impl LLMProvider for OpenAIOAuthProvider {
                     .header("Content-Type", "application/json")
                     .json(&body)
                     .send()
-                    .await?
+                    .await
+                    .map_err(|e| ProviderError::transport(&self.name(), &e))?
             } else {
                 response
             };
+        let response = ProviderError::check_response(&self.name(), response).await?;
 
         let response_body: Value = response.json().await?;
         debug!(
@@ -4416,7 +6931,7 @@ impl LLMProvider for OpenAIOAuthProvider {
 
         // Check for errors
         if let Some(error) = response_body.get("error") {
-            anyhow::bail!("OpenAI OAuth API error: {}", error);
+            return Err(ProviderError::from_error_body(&self.name(), error).into());
         }
 
         let choice = response_body["choices"]
@@ -4424,12 +6939,13 @@ impl LLMProvider for OpenAIOAuthProvider {
             .ok_or_else(|| anyhow::anyhow!("No choices in response"))?;
 
         let message = &choice["message"];
+        let thinking: Vec<ThinkingBlock> = openai_reasoning_text(message)
+            .map(ThinkingBlock::text)
+            .into_iter()
+            .collect();
 
         // Parse usage
-        let usage = response_body.get("usage").map(|u| Usage {
-            input_tokens: u["prompt_tokens"].as_u64().unwrap_or(0),
-            output_tokens: u["completion_tokens"].as_u64().unwrap_or(0),
-        });
+        let usage = response_body.get("usage").map(Usage::from_openai);
 
         // Check for tool calls
         if let Some(tool_calls) = message.get("tool_calls")
@@ -4444,6 +6960,7 @@ impl LLMProvider for OpenAIOAuthProvider {
                         .as_str()
                         .unwrap_or("{}")
                         .to_string(),
+                    signature: None,
                 })
                 .collect();
 
@@ -4451,6 +6968,7 @@ impl LLMProvider for OpenAIOAuthProvider {
                 return Ok(LLMResponse {
                     content: LLMResponseContent::ToolCalls(parsed_calls),
                     usage,
+                    thinking,
                 });
             }
         }
@@ -4460,6 +6978,7 @@ impl LLMProvider for OpenAIOAuthProvider {
         Ok(LLMResponse {
             content: LLMResponseContent::Text(content),
             usage,
+            thinking,
         })
     }
 
@@ -4473,6 +6992,7 @@ impl LLMProvider for OpenAIOAuthProvider {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         }];
 
         match self.chat(&messages, None).await?.content {
@@ -4480,6 +7000,69 @@ impl LLMProvider for OpenAIOAuthProvider {
             _ => anyhow::bail!("Unexpected response type"),
         }
     }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        self.ensure_valid_token().await?;
+
+        let mut body = json!({
+            "model": self.model,
+            "messages": self.format_messages(messages),
+            "stream": true
+        });
+        apply_openai_reasoning(&mut body, self.reasoning);
+
+        if let Some(tools) = tools
+            && !tools.is_empty()
+        {
+            body["tools"] = json!(self.format_tools(tools));
+        }
+
+        debug!(
+            "OpenAI OAuth streaming request: {}",
+            serde_json::to_string_pretty(&body)?
+        );
+
+        let current_access_token = self
+            .access_token
+            .read()
+            .map_err(|_| anyhow::anyhow!("Failed to acquire read lock on access_token"))?
+            .clone();
+        let response = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .header("Authorization", format!("Bearer {}", current_access_token))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
+
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED && self.refresh_token.is_some() {
+            debug!("OpenAI OAuth returned 401 Unauthorized, attempting to refresh token...");
+            self.refresh_access_token().await?;
+            let new_access_token = self
+                .access_token
+                .read()
+                .map_err(|_| anyhow::anyhow!("Failed to acquire read lock on access_token"))?
+                .clone();
+
+            let request = self
+                .client
+                .post(format!("{}/chat/completions", self.base_url))
+                .header("Authorization", format!("Bearer {}", new_access_token))
+                .header("Content-Type", "application/json")
+                .json(&body);
+            return send_openai_stream_request(request, &self.name()).await;
+        }
+
+        let response = ProviderError::check_response(&self.name(), response).await?;
+
+        Ok(openai_sse_stream(response.bytes_stream(), self.name()))
+    }
 }
 
 // GitHub Copilot Provider
@@ -4548,11 +7131,20 @@ impl GitHubCopilotProvider {
             .header("Accept", "application/json")
             .form(&params)
             .send()
-            .await?;
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
 
         if !response.status().is_success() {
+            let status = response.status();
+            let headers = response.headers().clone();
             let error_text = response.text().await?;
-            anyhow::bail!("Failed to refresh GitHub access token: {}", error_text);
+            return Err(ProviderError::from_token_refresh(
+                &self.name(),
+                status,
+                &headers,
+                &error_text,
+            )
+            .into());
         }
 
         let json: Value = response.json().await?;
@@ -4626,11 +7218,20 @@ impl GitHubCopilotProvider {
             .header("User-Agent", "LocalGPT/1.0")
             .header("Accept", "application/json")
             .send()
-            .await?;
+            .await
+            .map_err(|e| ProviderError::transport(&self.name(), &e))?;
 
         if !response.status().is_success() {
+            let status = response.status();
+            let headers = response.headers().clone();
             let error_text = response.text().await?;
-            anyhow::bail!("GitHub Copilot token exchange failed: {}", error_text);
+            return Err(ProviderError::from_token_refresh(
+                &self.name(),
+                status,
+                &headers,
+                &error_text,
+            )
+            .into());
         }
 
         let json: Value = response.json().await?;
@@ -4777,4 +7378,31 @@ impl LLMProvider for GitHubCopilotProvider {
 
         openai_provider.summarize(text).await
     }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        let token = self.ensure_valid_token().await?;
+        let base_url = self
+            .base_url
+            .read()
+            .map_err(|_| anyhow::anyhow!("Lock error"))?
+            .clone();
+
+        let openai_provider = OpenAIOAuthProvider::new(
+            OAuthConfig {
+                access_token: token,
+                refresh_token: None,
+                client_id: None,
+                client_secret: None,
+                expires_at: None,
+                base_url,
+            },
+            &self.model,
+        )?;
+
+        openai_provider.chat_stream(messages, tools).await
+    }
 }
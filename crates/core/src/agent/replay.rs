@@ -0,0 +1,758 @@
+//! Record-and-replay providers for deterministic offline runs
+//!
+//! `RecordingProvider` wraps a real provider and appends every exchange to a
+//! JSONL fixture file (`agent.record_fixture` or `--record <file>`).
+//! `ReplayProvider` serves a fixture back in order; select it with the model
+//! ID `replay/<fixture>`, where `<fixture>` is a path (`.jsonl` is appended
+//! when it has no extension).
+//!
+//! Only successful exchanges are recorded. Replay does not require prompts to
+//! match the recording exactly (they embed dates and workspace state); a
+//! differing last message is logged, a different kind of call is an error.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+
+use super::providers::{
+    LLMProvider, LLMResponse, LLMResponseContent, Message, OAuthTokenUpdate, Role, StreamChunk,
+    StreamResult, ThinkingBlock, ToolCall, ToolSchema, Usage,
+};
+use super::structured::{ResponseSchema, StructuredResponse};
+
+/// Which `LLMProvider` method produced an exchange
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExchangeKind {
+    Chat,
+    ChatStream,
+    ChatStructured,
+    Summarize,
+}
+
+impl ExchangeKind {
+    /// `chat` and `chat_stream` recordings can stand in for each other
+    fn serves(self, requested: ExchangeKind) -> bool {
+        use ExchangeKind::*;
+        self == requested || matches!((self, requested), (Chat, ChatStream) | (ChatStream, Chat))
+    }
+}
+
+/// One request/response pair in a fixture file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub kind: ExchangeKind,
+    /// Model the exchange was recorded against (informational)
+    #[serde(default)]
+    pub provider: String,
+    pub messages: Vec<Message>,
+    /// Names of the tools offered with the request
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+    pub response: RecordedResponse,
+}
+
+/// A provider response as stored in a fixture
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    /// Answer text (for `chat_structured`, the JSON value)
+    #[serde(default)]
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thinking: Vec<ThinkingBlock>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+impl RecordedResponse {
+    fn from_response(response: &LLMResponse) -> Self {
+        let (text, tool_calls) = match &response.content {
+            LLMResponseContent::Text(text) => (text.clone(), Vec::new()),
+            LLMResponseContent::ToolCalls(calls) => (String::new(), calls.clone()),
+        };
+        Self {
+            text,
+            tool_calls,
+            thinking: response.thinking.clone(),
+            usage: response.usage.clone(),
+        }
+    }
+
+    fn into_response(self) -> LLMResponse {
+        let content = if self.tool_calls.is_empty() {
+            LLMResponseContent::Text(self.text)
+        } else {
+            LLMResponseContent::ToolCalls(self.tool_calls)
+        };
+        LLMResponse {
+            content,
+            usage: self.usage,
+            thinking: self.thinking,
+        }
+    }
+}
+
+/// Resolve the `<fixture>` part of a `replay/<fixture>` model ID
+pub fn fixture_path(fixture: &str) -> PathBuf {
+    let path = PathBuf::from(shellexpand::tilde(fixture).into_owned());
+    if path.extension().is_some() {
+        path
+    } else {
+        path.with_extension("jsonl")
+    }
+}
+
+/// Read every exchange in a fixture file
+pub fn load_fixture(path: &Path) -> Result<Vec<Exchange>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read replay fixture {}", path.display()))?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Invalid exchange at {}:{}", path.display(), i + 1))
+        })
+        .collect()
+}
+
+fn tool_names(tools: Option<&[ToolSchema]>) -> Vec<String> {
+    tools
+        .unwrap_or_default()
+        .iter()
+        .map(|t| t.name.clone())
+        .collect()
+}
+
+// Recording Provider
+
+/// Wraps a provider and appends each successful exchange to a fixture file
+pub struct RecordingProvider {
+    inner: Box<dyn LLMProvider>,
+    path: PathBuf,
+    /// Serializes appends from concurrent calls
+    write_lock: std::sync::Arc<Mutex<()>>,
+}
+
+impl RecordingProvider {
+    pub fn new(inner: Box<dyn LLMProvider>, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(Self {
+            inner,
+            path,
+            write_lock: Default::default(),
+        })
+    }
+
+    fn exchange(
+        &self,
+        kind: ExchangeKind,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+        response: RecordedResponse,
+    ) -> Exchange {
+        Exchange {
+            kind,
+            provider: self.inner.name(),
+            messages: messages.to_vec(),
+            tools: tool_names(tools),
+            response,
+        }
+    }
+
+    fn record(&self, exchange: &Exchange) {
+        if let Err(e) = append_exchange(&self.path, &self.write_lock, exchange) {
+            warn!(
+                "Failed to record exchange to {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+fn append_exchange(path: &Path, lock: &Mutex<()>, exchange: &Exchange) -> Result<()> {
+    let line = serde_json::to_string(exchange)?;
+    let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+#[async_trait]
+impl LLMProvider for RecordingProvider {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn token_update(&self) -> Option<OAuthTokenUpdate> {
+        self.inner.token_update()
+    }
+
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        let response = self.inner.chat(messages, tools).await?;
+        self.record(&self.exchange(
+            ExchangeKind::Chat,
+            messages,
+            tools,
+            RecordedResponse::from_response(&response),
+        ));
+        Ok(response)
+    }
+
+    async fn summarize(&self, text: &str) -> Result<String> {
+        let summary = self.inner.summarize(text).await?;
+        let request = Message {
+            role: Role::User,
+            content: text.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: Vec::new(),
+        };
+        self.record(&self.exchange(
+            ExchangeKind::Summarize,
+            &[request],
+            None,
+            RecordedResponse {
+                text: summary.clone(),
+                ..Default::default()
+            },
+        ));
+        Ok(summary)
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        schema: &ResponseSchema,
+    ) -> Result<StructuredResponse> {
+        let response = self.inner.chat_structured(messages, schema).await?;
+        self.record(&self.exchange(
+            ExchangeKind::ChatStructured,
+            messages,
+            None,
+            RecordedResponse {
+                text: response.value.to_string(),
+                usage: response.usage.clone(),
+                ..Default::default()
+            },
+        ));
+        Ok(response)
+    }
+
+    fn supports_native_search(&self) -> bool {
+        self.inner.supports_native_search()
+    }
+
+    fn native_tool_definitions(&self) -> Vec<Value> {
+        self.inner.native_tool_definitions()
+    }
+
+    fn reset_session(&self) {
+        self.inner.reset_session()
+    }
+
+    /// Chunks pass through unchanged; the assembled response is recorded
+    /// once the stream completes
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        let mut inner = self.inner.chat_stream(messages, tools).await?;
+        let mut exchange = self.exchange(
+            ExchangeKind::ChatStream,
+            messages,
+            tools,
+            Default::default(),
+        );
+        let path = self.path.clone();
+        let lock = self.write_lock.clone();
+
+        let stream = async_stream::stream! {
+            let mut thinking_text = String::new();
+            while let Some(chunk) = inner.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        yield Err(e);
+                        return;
+                    }
+                };
+
+                let response = &mut exchange.response;
+                response.text.push_str(&chunk.delta);
+                if let Some(ref thinking) = chunk.thinking {
+                    thinking_text.push_str(thinking);
+                }
+                if let Some(ref calls) = chunk.tool_calls {
+                    response.tool_calls.extend(calls.iter().cloned());
+                }
+                if chunk.usage.is_some() {
+                    response.usage = chunk.usage.clone();
+                }
+                if let Some(ref blocks) = chunk.thinking_blocks {
+                    response.thinking = blocks.clone();
+                }
+                yield Ok(chunk);
+            }
+
+            if exchange.response.thinking.is_empty() && !thinking_text.is_empty() {
+                exchange.response.thinking = vec![ThinkingBlock::text(thinking_text)];
+            }
+            if let Err(e) = append_exchange(&path, &lock, &exchange) {
+                warn!("Failed to record exchange to {}: {}", path.display(), e);
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+// Replay Provider
+
+/// Serves the exchanges of a fixture file in order
+pub struct ReplayProvider {
+    path: PathBuf,
+    exchanges: Vec<Exchange>,
+    next: Mutex<usize>,
+}
+
+impl ReplayProvider {
+    pub fn from_file(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let exchanges = load_fixture(&path)?;
+        Ok(Self::new(path, exchanges))
+    }
+
+    pub fn new(path: impl Into<PathBuf>, exchanges: Vec<Exchange>) -> Self {
+        Self {
+            path: path.into(),
+            exchanges,
+            next: Mutex::new(0),
+        }
+    }
+
+    /// Exchanges served so far
+    pub fn position(&self) -> usize {
+        *self.next.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Take the next recorded response for a `kind` request
+    fn next_response(&self, kind: ExchangeKind, messages: &[Message]) -> Result<RecordedResponse> {
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        let index = *next;
+        let exchange = self.exchanges.get(index).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Replay fixture {} exhausted after {} exchanges",
+                self.path.display(),
+                self.exchanges.len()
+            )
+        })?;
+        if !exchange.kind.serves(kind) {
+            anyhow::bail!(
+                "Replay fixture {} exchange {} was recorded from {:?}, but {:?} was called",
+                self.path.display(),
+                index + 1,
+                exchange.kind,
+                kind
+            );
+        }
+
+        let recorded = exchange.messages.last().map(|m| m.content.as_str());
+        let requested = messages.last().map(|m| m.content.as_str());
+        if recorded != requested {
+            warn!(
+                "Replay fixture {} exchange {}: last message differs from the recording",
+                self.path.display(),
+                index + 1
+            );
+        }
+
+        *next += 1;
+        Ok(exchange.response.clone())
+    }
+}
+
+#[async_trait]
+impl LLMProvider for ReplayProvider {
+    fn name(&self) -> String {
+        "replay".to_string()
+    }
+
+    async fn chat(
+        &self,
+        messages: &[Message],
+        _tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        Ok(self
+            .next_response(ExchangeKind::Chat, messages)?
+            .into_response())
+    }
+
+    async fn summarize(&self, text: &str) -> Result<String> {
+        let request = Message {
+            role: Role::User,
+            content: text.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: Vec::new(),
+        };
+        Ok(self
+            .next_response(ExchangeKind::Summarize, &[request])?
+            .text)
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        _schema: &ResponseSchema,
+    ) -> Result<StructuredResponse> {
+        let response = self.next_response(ExchangeKind::ChatStructured, messages)?;
+        Ok(StructuredResponse {
+            value: serde_json::from_str(&response.text)?,
+            usage: response.usage,
+        })
+    }
+
+    /// Replays the recorded text as one delta followed by the `done` chunk
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        _tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        let response = self.next_response(ExchangeKind::ChatStream, messages)?;
+        let mut chunks = Vec::new();
+        if let Some(thinking) = LLMResponse::text(String::new())
+            .with_thinking(response.thinking.clone())
+            .thinking_text()
+        {
+            chunks.push(Ok(StreamChunk {
+                delta: String::new(),
+                done: false,
+                tool_calls: None,
+                usage: None,
+                thinking: Some(thinking),
+                thinking_blocks: None,
+            }));
+        }
+        if !response.text.is_empty() {
+            chunks.push(Ok(StreamChunk {
+                delta: response.text,
+                done: false,
+                tool_calls: None,
+                usage: None,
+                thinking: None,
+                thinking_blocks: None,
+            }));
+        }
+        chunks.push(Ok(StreamChunk {
+            delta: String::new(),
+            done: true,
+            tool_calls: (!response.tool_calls.is_empty()).then_some(response.tool_calls),
+            usage: response.usage,
+            thinking: None,
+            thinking_blocks: (!response.thinking.is_empty()).then_some(response.thinking),
+        }));
+
+        Ok(Box::pin(futures::stream::iter(chunks)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::sync::Arc;
+
+    /// Provider that answers with a tool call, then text
+    struct ToolThenTextProvider {
+        calls: Mutex<usize>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for ToolThenTextProvider {
+        fn name(&self) -> String {
+            "scripted".to_string()
+        }
+
+        async fn chat(
+            &self,
+            _messages: &[Message],
+            _tools: Option<&[ToolSchema]>,
+        ) -> Result<LLMResponse> {
+            let mut calls = self.calls.lock().unwrap();
+            *calls += 1;
+            if *calls == 1 {
+                Ok(LLMResponse::tool_calls(vec![ToolCall {
+                    id: "call_1".to_string(),
+                    name: "memory_search".to_string(),
+                    arguments: "{\"query\":\"rust\"}".to_string(),
+                    signature: None,
+                }]))
+            } else {
+                Ok(LLMResponse::text_with_usage(
+                    "Found it.".to_string(),
+                    Usage {
+                        input_tokens: 12,
+                        output_tokens: 3,
+                        ..Default::default()
+                    },
+                )
+                .with_thinking(vec![ThinkingBlock::text("Look it up")]))
+            }
+        }
+
+        async fn summarize(&self, text: &str) -> Result<String> {
+            Ok(format!("summary of {} chars", text.len()))
+        }
+    }
+
+    fn user(content: &str) -> Message {
+        Message {
+            role: Role::User,
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: Vec::new(),
+        }
+    }
+
+    fn tools() -> Vec<ToolSchema> {
+        vec![ToolSchema {
+            name: "memory_search".to_string(),
+            description: "Search memory".to_string(),
+            parameters: serde_json::json!({ "type": "object" }),
+        }]
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let recorder = RecordingProvider::new(
+            Box::new(ToolThenTextProvider {
+                calls: Mutex::new(0),
+            }),
+            &path,
+        )
+        .unwrap();
+
+        let tools = tools();
+        recorder
+            .chat(&[user("Find rust")], Some(&tools))
+            .await
+            .unwrap();
+        let stream = recorder
+            .chat_stream(&[user("Find rust")], Some(&tools))
+            .await
+            .unwrap();
+        let _: Vec<_> = stream.collect().await;
+        recorder.summarize("a long conversation").await.unwrap();
+
+        let exchanges = load_fixture(&path).unwrap();
+        assert_eq!(exchanges.len(), 3);
+        assert_eq!(exchanges[0].kind, ExchangeKind::Chat);
+        assert_eq!(exchanges[0].tools, vec!["memory_search"]);
+        assert_eq!(exchanges[1].kind, ExchangeKind::ChatStream);
+        assert_eq!(exchanges[1].response.text, "Found it.");
+
+        let replay = ReplayProvider::from_file(&path).unwrap();
+        let first = replay.chat(&[user("Find rust")], None).await.unwrap();
+        let LLMResponseContent::ToolCalls(calls) = first.content else {
+            panic!("expected tool calls");
+        };
+        assert_eq!(calls[0].arguments, "{\"query\":\"rust\"}");
+
+        // A streamed recording can be served to a non-streaming call
+        let second = replay.chat(&[user("Find rust")], None).await.unwrap();
+        assert!(matches!(second.content, LLMResponseContent::Text(ref t) if t == "Found it."));
+        assert_eq!(second.usage.as_ref().unwrap().input_tokens, 12);
+        assert_eq!(second.thinking_text().as_deref(), Some("Look it up"));
+
+        assert_eq!(
+            replay.summarize("a long conversation").await.unwrap(),
+            "summary of 19 chars"
+        );
+        let Err(err) = replay.chat(&[user("again")], None).await else {
+            panic!("expected exhausted fixture");
+        };
+        assert!(err.to_string().contains("exhausted after 3 exchanges"));
+    }
+
+    #[tokio::test]
+    async fn test_replay_stream_and_kind_mismatch() {
+        let exchanges = vec![
+            Exchange {
+                kind: ExchangeKind::Chat,
+                provider: "anthropic".to_string(),
+                messages: vec![user("Hi")],
+                tools: Vec::new(),
+                response: RecordedResponse {
+                    text: "Hello!".to_string(),
+                    ..Default::default()
+                },
+            },
+            Exchange {
+                kind: ExchangeKind::Summarize,
+                provider: "anthropic".to_string(),
+                messages: vec![user("transcript")],
+                tools: Vec::new(),
+                response: RecordedResponse {
+                    text: "Short.".to_string(),
+                    ..Default::default()
+                },
+            },
+        ];
+        let replay = ReplayProvider::new("inline", exchanges);
+
+        let chunks: Vec<StreamChunk> = replay
+            .chat_stream(&[user("Hi")], None)
+            .await
+            .unwrap()
+            .map(|c| c.unwrap())
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].delta, "Hello!");
+        assert!(chunks[1].done);
+
+        let Err(err) = replay.chat(&[user("Hi")], None).await else {
+            panic!("expected kind mismatch");
+        };
+        assert!(err.to_string().contains("recorded from Summarize"));
+        assert_eq!(replay.position(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_model_id_selects_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bug-123.jsonl");
+        let exchange = Exchange {
+            kind: ExchangeKind::Chat,
+            provider: "openai".to_string(),
+            messages: vec![user("Hi")],
+            tools: Vec::new(),
+            response: RecordedResponse {
+                text: "Replayed".to_string(),
+                ..Default::default()
+            },
+        };
+        std::fs::write(
+            &path,
+            format!("{}\n", serde_json::to_string(&exchange).unwrap()),
+        )
+        .unwrap();
+
+        let model = format!("replay/{}", dir.path().join("bug-123").display());
+        let provider =
+            crate::agent::providers::create_provider(&model, &Config::default()).unwrap();
+        let response = provider.chat(&[user("Hi")], None).await.unwrap();
+        assert!(matches!(response.content, LLMResponseContent::Text(ref t) if t == "Replayed"));
+    }
+
+    #[tokio::test]
+    async fn test_agent_turn_replays_tool_call_and_follow_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let fixture = dir.path().join("turn.jsonl");
+        let exchanges = [
+            Exchange {
+                kind: ExchangeKind::Chat,
+                provider: "anthropic".to_string(),
+                messages: vec![user("Find rust")],
+                tools: vec!["memory_search".to_string()],
+                response: RecordedResponse {
+                    tool_calls: vec![ToolCall {
+                        id: "call_1".to_string(),
+                        name: "memory_search".to_string(),
+                        arguments: "{\"query\":\"rust\"}".to_string(),
+                        signature: None,
+                    }],
+                    ..Default::default()
+                },
+            },
+            Exchange {
+                kind: ExchangeKind::Chat,
+                provider: "anthropic".to_string(),
+                messages: vec![user("Find rust")],
+                tools: vec!["memory_search".to_string()],
+                response: RecordedResponse {
+                    text: "Found it.".to_string(),
+                    ..Default::default()
+                },
+            },
+        ];
+        let lines: String = exchanges
+            .iter()
+            .map(|e| format!("{}\n", serde_json::to_string(e).unwrap()))
+            .collect();
+        std::fs::write(&fixture, lines).unwrap();
+
+        let mut config = Config {
+            paths: crate::paths::Paths::from_root(dir.path().join("home")),
+            ..Default::default()
+        };
+        config.agent.default_model = format!("replay/{}", fixture.display());
+        config.memory.embedding_provider = "none".to_string();
+        config.security.disable_policy = true;
+        let memory = Arc::new(
+            crate::memory::MemoryManager::new_with_full_config(
+                &config.memory,
+                Some(&config),
+                "main",
+            )
+            .unwrap(),
+        );
+        let tools =
+            crate::agent::tools::create_safe_tools(&config, Some(Arc::clone(&memory))).unwrap();
+        let mut agent = crate::agent::Agent::new_with_tools(config, "main", memory, tools).unwrap();
+        agent.usage_ledger = None;
+
+        let answer = agent.chat("Find rust").await.unwrap();
+        assert_eq!(answer, "Found it.");
+
+        // The tool call and its result are in the session ahead of the answer
+        let messages: Vec<&Message> = agent
+            .raw_session_messages()
+            .iter()
+            .map(|m| &m.message)
+            .collect();
+        let roles: Vec<Role> = messages.iter().map(|m| m.role).collect();
+        assert_eq!(
+            roles,
+            vec![Role::User, Role::Assistant, Role::Tool, Role::Assistant]
+        );
+        let result = messages.iter().find(|m| m.role == Role::Tool).unwrap();
+        assert_eq!(result.tool_call_id.as_deref(), Some("call_1"));
+
+        // Both recorded exchanges were consumed by the turn
+        let Err(err) = agent.chat("again").await else {
+            panic!("expected exhausted fixture");
+        };
+        assert!(format!("{:#}", err).contains("exhausted after 2 exchanges"));
+    }
+}
@@ -2,21 +2,34 @@
 //!
 //! JSONL format matches Pi's SessionManager for OpenClaw compatibility:
 //! - Header: {type: "session", version, id, timestamp, cwd}
-//! - Messages: {type: "message", message: {role, content, ...}}
+//! - Messages: {type: "message", id, parentId, message: {role, content, ...}}
+//! - Compactions: {type: "compaction", id, parentId, summary, firstKeptEntryId, tokensBefore}
+//!
+//! Entries form a tree through `parentId`: editing or regenerating a turn forks
+//! a new branch, and the header's `leafId` records which branch is active.
+//! Pinned messages and per-session instructions also live in the header.
 
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader, Write};
 use std::path::PathBuf;
 use uuid::Uuid;
 
-use super::providers::{LLMProvider, Message, Role, ToolCall, Usage};
+use super::providers::{LLMProvider, Message, Role, ThinkingBlock, ToolCall, Usage};
+use super::tokenizer::TokenCounter;
 
 /// Current session format version (matches Pi)
-pub const CURRENT_SESSION_VERSION: u32 = 1;
+pub const CURRENT_SESSION_VERSION: u32 = 2;
+
+/// Messages compaction always leaves verbatim at the end of the active branch
+const KEEP_RECENT_MESSAGES: usize = 4;
+
+/// Summary segments kept before the oldest two are folded into one
+const MAX_SUMMARY_SEGMENTS: usize = 4;
 
 /// Session state (internal representation)
 #[derive(Debug, Clone)]
@@ -24,9 +37,18 @@ pub struct Session {
     id: String,
     created_at: DateTime<Utc>,
     cwd: String,
+    /// Active branch, root first
     messages: Vec<SessionMessage>,
+    /// Entries not on the active branch, kept so branches can be switched back
+    branches: Vec<SessionMessage>,
     system_context: Option<String>,
+    /// User-provided instructions for this session only
+    instructions: Option<String>,
+    /// Messages and notes sent with every turn, whatever was compacted
+    pinned: Vec<PinnedMessage>,
+    /// Raw tokenizer count; calibrated on read via `token_counter`
     token_count: usize,
+    token_counter: TokenCounter,
     compaction_count: u32,
     memory_flush_compaction_count: u32,
 }
@@ -34,6 +56,13 @@ pub struct Session {
 /// Message with metadata for persistence
 #[derive(Debug, Clone)]
 pub struct SessionMessage {
+    /// Short entry ID, unique within the session
+    pub id: String,
+    /// Entry this message follows; `None` for a root
+    pub parent_id: Option<String>,
+    /// Set when this message is a compaction summary; earlier history on the
+    /// branch stays in the tree but is no longer sent to the model
+    pub compaction: Option<Compaction>,
     pub message: Message,
     pub provider: Option<String>,
     pub model: Option<String>,
@@ -43,6 +72,32 @@ pub struct SessionMessage {
     pub timestamp: u64,
 }
 
+/// Message or note pinned for the whole session
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinnedMessage {
+    /// Entry ID of the pinned message, or a fresh ID for a note
+    pub id: String,
+    pub content: String,
+}
+
+/// Rolling summary recorded by a compaction
+#[derive(Debug, Clone)]
+pub struct Compaction {
+    /// Summaries of successive compacted segments, oldest first
+    pub segments: Vec<String>,
+    pub first_kept_id: String,
+    pub tokens_before: usize,
+}
+
+impl Compaction {
+    fn summary_text(&self) -> String {
+        format!(
+            "Previous conversation summary:\n\n{}",
+            self.segments.join("\n\n")
+        )
+    }
+}
+
 /// Per-message usage tracking (Pi-compatible)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -72,8 +127,8 @@ impl From<&Usage> for MessageUsage {
         Self {
             input: usage.input_tokens,
             output: usage.output_tokens,
-            cache_read: None,
-            cache_write: None,
+            cache_read: (usage.cache_read_tokens > 0).then_some(usage.cache_read_tokens),
+            cache_write: (usage.cache_write_tokens > 0).then_some(usage.cache_write_tokens),
             total_tokens: usage.total(),
             cost: None, // Cost calculation not implemented
         }
@@ -83,6 +138,9 @@ impl From<&Usage> for MessageUsage {
 impl SessionMessage {
     pub fn new(message: Message) -> Self {
         Self {
+            id: new_entry_id(),
+            parent_id: None,
+            compaction: None,
             message,
             provider: None,
             model: None,
@@ -101,6 +159,9 @@ impl SessionMessage {
         stop_reason: Option<&str>,
     ) -> Self {
         Self {
+            id: new_entry_id(),
+            parent_id: None,
+            compaction: None,
             message,
             provider: provider.map(|s| s.to_string()),
             model: model.map(|s| s.to_string()),
@@ -112,6 +173,21 @@ impl SessionMessage {
     }
 }
 
+/// One branch of a session tree, identified by its last message
+#[derive(Debug, Clone)]
+pub struct BranchInfo {
+    pub leaf_id: String,
+    pub message_count: usize,
+    /// Last user message on the branch
+    pub preview: String,
+    pub timestamp: u64,
+    pub active: bool,
+}
+
+fn new_entry_id() -> String {
+    Uuid::new_v4().simple().to_string()[..8].to_string()
+}
+
 #[derive(Debug, Clone)]
 pub struct SessionStatus {
     pub id: String,
@@ -140,19 +216,37 @@ impl Session {
             created_at: Utc::now(),
             cwd,
             messages: Vec::new(),
+            branches: Vec::new(),
             system_context: None,
+            instructions: None,
+            pinned: Vec::new(),
             token_count: 0,
+            token_counter: TokenCounter::default(),
             compaction_count: 0,
             memory_flush_compaction_count: 0,
         }
     }
 
+    /// New session counted with a model-specific tokenizer
+    pub fn with_token_counter(counter: TokenCounter) -> Self {
+        let mut session = Self::new();
+        session.token_counter = counter;
+        session
+    }
+
     pub fn id(&self) -> &str {
         &self.id
     }
 
+    /// Estimated context size in tokens (calibrated against provider usage)
     pub fn token_count(&self) -> usize {
-        self.token_count
+        self.token_counter.calibrate(self.token_count)
+    }
+
+    /// Count tokens with the given model's tokenizer from now on
+    pub fn set_token_counter(&mut self, counter: TokenCounter) {
+        self.token_counter = counter;
+        self.recalculate_tokens();
     }
 
     pub fn compaction_count(&self) -> u32 {
@@ -174,9 +268,7 @@ impl Session {
 
     /// Add a message without metadata
     pub fn add_message(&mut self, message: Message) {
-        let tokens = estimate_tokens(&message.content);
-        self.token_count += tokens;
-        self.messages.push(SessionMessage::new(message));
+        self.push(SessionMessage::new(message));
     }
 
     /// Add a message with provider/model metadata
@@ -188,9 +280,7 @@ impl Session {
         usage: Option<&Usage>,
         stop_reason: Option<&str>,
     ) {
-        let tokens = estimate_tokens(&message.content);
-        self.token_count += tokens;
-        self.messages.push(SessionMessage::with_metadata(
+        self.push(SessionMessage::with_metadata(
             message,
             provider,
             model,
@@ -199,16 +289,108 @@ impl Session {
         ));
     }
 
+    /// Append to the active branch, chaining onto its current leaf
+    fn push(&mut self, mut sm: SessionMessage) {
+        sm.parent_id = self.messages.last().map(|last| last.id.clone());
+        self.token_count += self.token_counter.count_message(&sm.message);
+        self.messages.push(sm);
+    }
+
+    /// Session instructions, if any were set
+    pub fn instructions(&self) -> Option<&str> {
+        self.instructions.as_deref()
+    }
+
+    pub fn set_instructions(&mut self, instructions: Option<String>) {
+        self.instructions = instructions.filter(|text| !text.trim().is_empty());
+        self.recalculate_tokens();
+    }
+
+    pub fn pinned(&self) -> &[PinnedMessage] {
+        &self.pinned
+    }
+
+    /// Pin a message from the active branch for the rest of the session
+    pub fn pin_message(&mut self, message_id: &str) -> Result<&PinnedMessage> {
+        let pos = self.active_position(message_id)?;
+        let sm = &self.messages[pos];
+        if sm.message.content.is_empty() || sm.message.role == Role::Tool {
+            anyhow::bail!("Only user and assistant text can be pinned");
+        }
+        if !self.pinned.iter().any(|p| p.id == sm.id) {
+            self.pinned.push(PinnedMessage {
+                id: sm.id.clone(),
+                content: sm.message.content.clone(),
+            });
+        }
+        self.recalculate_tokens();
+        Ok(self
+            .pinned
+            .iter()
+            .find(|p| p.id == self.messages[pos].id)
+            .unwrap())
+    }
+
+    /// Pin a free-form note for the rest of the session
+    pub fn pin_note(&mut self, text: &str) -> &PinnedMessage {
+        self.pinned.push(PinnedMessage {
+            id: new_entry_id(),
+            content: text.to_string(),
+        });
+        self.recalculate_tokens();
+        self.pinned.last().unwrap()
+    }
+
+    pub fn unpin(&mut self, id: &str) -> Result<()> {
+        let before = self.pinned.len();
+        self.pinned.retain(|p| p.id != id);
+        if self.pinned.len() == before {
+            anyhow::bail!("Nothing pinned with ID {}", id);
+        }
+        self.recalculate_tokens();
+        Ok(())
+    }
+
+    fn is_pinned(&self, message_id: &str) -> bool {
+        self.pinned.iter().any(|p| p.id == message_id)
+    }
+
+    /// System prompt sent to the model: the system context followed by the
+    /// session instructions and any pins not already in the active branch
+    fn system_prompt(&self) -> Option<String> {
+        let mut sections: Vec<String> = self.system_context.iter().cloned().collect();
+
+        if let Some(ref instructions) = self.instructions {
+            sections.push(format!("## Session Instructions\n\n{}", instructions));
+        }
+
+        let pins: Vec<String> = self
+            .pinned
+            .iter()
+            .filter(|p| !self.messages.iter().any(|sm| sm.id == p.id))
+            .map(|p| format!("- {}", p.content))
+            .collect();
+        if !pins.is_empty() {
+            sections.push(format!(
+                "## Pinned\n\nThe user pinned these for the whole conversation:\n\n{}",
+                pins.join("\n")
+            ));
+        }
+
+        (!sections.is_empty()).then(|| sections.join("\n\n"))
+    }
+
     pub fn messages_for_llm(&self) -> Vec<Message> {
         let mut messages = Vec::new();
 
-        if let Some(ref context) = self.system_context {
+        if let Some(system_prompt) = self.system_prompt() {
             messages.push(Message {
                 role: Role::System,
-                content: context.clone(),
+                content: system_prompt,
                 tool_calls: None,
                 tool_call_id: None,
                 images: Vec::new(),
+                thinking: Vec::new(),
             });
         }
 
@@ -233,13 +415,28 @@ impl Session {
             .collect()
     }
 
+    /// Summarize older history on the active branch.
+    ///
+    /// Only messages since the previous compaction are summarized; their
+    /// summary is appended to the previous one as a new segment. The cut is
+    /// made at a turn boundary so no tool result is separated from its call,
+    /// and pinned messages are left out of the summary, since
+    /// `messages_for_llm` sends them once they leave the branch. Summarized
+    /// messages stay in the session tree, so the full history is still saved.
     pub async fn compact(&mut self, provider: &dyn LLMProvider) -> Result<()> {
-        if self.messages.len() < 4 {
+        let previous = self.messages.first().and_then(|sm| sm.compaction.clone());
+        let start = usize::from(previous.is_some());
+        let Some(cut) = self.compaction_cut(start) else {
             return Ok(());
-        }
+        };
 
-        let keep_count = 4;
-        let to_summarize = &self.messages[..self.messages.len() - keep_count];
+        let to_summarize: Vec<&SessionMessage> = self.messages[start..cut]
+            .iter()
+            .filter(|sm| !self.is_pinned(&sm.id))
+            .collect();
+        if to_summarize.is_empty() {
+            return Ok(());
+        }
 
         let text: String = to_summarize
             .iter()
@@ -247,34 +444,220 @@ impl Session {
             .collect::<Vec<_>>()
             .join("\n\n");
 
-        let summary = provider.summarize(&text).await?;
+        let mut segments = previous.map(|c| c.segments).unwrap_or_default();
+        segments.push(provider.summarize(&text).await?);
+        if segments.len() > MAX_SUMMARY_SEGMENTS {
+            let merged = provider.summarize(&segments[..2].join("\n\n")).await?;
+            segments.splice(..2, [merged]);
+        }
 
-        let mut new_messages = vec![SessionMessage::new(Message {
+        let compaction = Compaction {
+            segments,
+            first_kept_id: self.messages[cut].id.clone(),
+            tokens_before: self.token_count(),
+        };
+        let mut summary = SessionMessage::new(Message {
             role: Role::System,
-            content: format!("Previous conversation summary:\n\n{}", summary),
+            content: compaction.summary_text(),
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
-        })];
+            thinking: Vec::new(),
+        });
+        summary.parent_id = Some(self.messages[cut - 1].id.clone());
+        summary.compaction = Some(compaction);
 
-        new_messages.extend(self.messages[self.messages.len() - keep_count..].to_vec());
+        let summarized: Vec<SessionMessage> = self.messages.drain(..cut).collect();
+        self.branches.extend(summarized);
+        self.messages[0].parent_id = Some(summary.id.clone());
+        self.messages.insert(0, summary);
 
-        self.messages = new_messages;
         self.compaction_count += 1;
         self.recalculate_tokens();
 
         Ok(())
     }
 
+    /// Where to split the active branch for compaction: the start of the
+    /// latest turn that leaves `KEEP_RECENT_MESSAGES` after it, or failing
+    /// that (one long tool loop) the latest message that is not a tool result.
+    fn compaction_cut(&self, start: usize) -> Option<usize> {
+        let latest = self.messages.len().checked_sub(KEEP_RECENT_MESSAGES)?;
+        let candidates = (start + 1..=latest).rev();
+        candidates
+            .clone()
+            .find(|&i| self.messages[i].message.role == Role::User)
+            .or_else(|| {
+                candidates
+                    .into_iter()
+                    .find(|&i| self.messages[i].message.role != Role::Tool)
+            })
+    }
+
+    /// ID of the most recent user message on the active branch
+    pub fn last_user_message_id(&self) -> Option<&str> {
+        self.messages
+            .iter()
+            .rev()
+            .find(|sm| sm.message.role == Role::User)
+            .map(|sm| sm.id.as_str())
+    }
+
+    /// Cut the active branch after `message_id`. The later messages stay in the
+    /// tree, and the next message added starts a sibling branch.
+    pub fn fork_at(&mut self, message_id: &str) -> Result<()> {
+        let pos = self.active_position(message_id)?;
+        let tail = self.messages.split_off(pos + 1);
+        self.branches.extend(tail);
+        self.recalculate_tokens();
+        Ok(())
+    }
+
+    /// Cut the active branch just before `message_id`, so a replacement for
+    /// that message becomes its sibling (used for edits).
+    pub fn fork_before(&mut self, message_id: &str) -> Result<()> {
+        let pos = self.active_position(message_id)?;
+        let tail = self.messages.split_off(pos);
+        self.branches.extend(tail);
+        self.recalculate_tokens();
+        Ok(())
+    }
+
+    /// The message with ID `message_id` (a full ID or unique prefix) on the
+    /// active branch
+    pub fn active_message(&self, message_id: &str) -> Result<&SessionMessage> {
+        let pos = self.active_position(message_id)?;
+        Ok(&self.messages[pos])
+    }
+
+    fn active_position(&self, message_id: &str) -> Result<usize> {
+        let id = self.resolve_entry_id(message_id)?;
+        self.messages
+            .iter()
+            .position(|sm| sm.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Message {} is not on the active branch", id))
+    }
+
+    /// Resolve a full entry ID or a unique prefix of one
+    fn resolve_entry_id(&self, message_id: &str) -> Result<String> {
+        let all = || self.messages.iter().chain(&self.branches);
+        if let Some(sm) = all().find(|sm| sm.id == message_id) {
+            return Ok(sm.id.clone());
+        }
+        let matches: Vec<&SessionMessage> = all()
+            .filter(|sm| !message_id.is_empty() && sm.id.starts_with(message_id))
+            .collect();
+        match matches.as_slice() {
+            [sm] => Ok(sm.id.clone()),
+            [] => anyhow::bail!("Message not found: {}", message_id),
+            _ => anyhow::bail!("Message ID prefix is ambiguous: {}", message_id),
+        }
+    }
+
+    /// All branches in the session tree, oldest first. The active branch is
+    /// always included, even when it has been cut short by a fork.
+    pub fn branches(&self) -> Vec<BranchInfo> {
+        let all: Vec<&SessionMessage> = self.messages.iter().chain(&self.branches).collect();
+        let index: HashMap<&str, &SessionMessage> =
+            all.iter().map(|sm| (sm.id.as_str(), *sm)).collect();
+        let parents: HashSet<&str> = all
+            .iter()
+            .filter_map(|sm| sm.parent_id.as_deref())
+            .collect();
+        let active_leaf = self.messages.last().map(|sm| sm.id.as_str());
+
+        let mut branches: Vec<BranchInfo> = all
+            .iter()
+            .filter(|sm| !parents.contains(sm.id.as_str()) || Some(sm.id.as_str()) == active_leaf)
+            .map(|leaf| {
+                let mut message_count = 0;
+                let mut preview = None;
+                let mut cursor = Some(*leaf);
+                while let Some(sm) = cursor {
+                    message_count += 1;
+                    if preview.is_none() && sm.message.role == Role::User {
+                        preview = Some(sm.message.content.as_str());
+                    }
+                    if sm.compaction.is_some() || message_count > all.len() {
+                        break;
+                    }
+                    cursor = sm.parent_id.as_deref().and_then(|p| index.get(p).copied());
+                }
+                BranchInfo {
+                    leaf_id: leaf.id.clone(),
+                    message_count,
+                    preview: preview.unwrap_or_default().to_string(),
+                    timestamp: leaf.timestamp,
+                    active: Some(leaf.id.as_str()) == active_leaf,
+                }
+            })
+            .collect();
+
+        branches.sort_by_key(|b| b.timestamp);
+        branches
+    }
+
+    /// Make the branch through `message_id` active, following its most
+    /// recent continuation down to a leaf.
+    pub fn switch_branch(&mut self, message_id: &str) -> Result<()> {
+        let mut leaf = self.resolve_entry_id(message_id)?;
+
+        let mut pool = std::mem::take(&mut self.messages);
+        pool.append(&mut self.branches);
+
+        while let Some(child) = pool
+            .iter()
+            .filter(|sm| sm.parent_id.as_deref() == Some(leaf.as_str()))
+            .max_by_key(|sm| sm.timestamp)
+        {
+            leaf = child.id.clone();
+        }
+
+        self.set_active_path(pool, &leaf);
+        Ok(())
+    }
+
+    /// Rebuild the active branch by walking parent links up from `leaf_id`,
+    /// stopping at the latest compaction summary; everything else in `pool`
+    /// is kept as other branches or compacted history.
+    fn set_active_path(&mut self, pool: Vec<SessionMessage>, leaf_id: &str) {
+        let index: HashMap<&str, usize> = pool
+            .iter()
+            .enumerate()
+            .map(|(i, sm)| (sm.id.as_str(), i))
+            .collect();
+
+        let mut path = Vec::new();
+        let mut cursor = index.get(leaf_id).copied();
+        while let Some(i) = cursor {
+            if path.contains(&i) {
+                break;
+            }
+            path.push(i);
+            if pool[i].compaction.is_some() {
+                break;
+            }
+            cursor = pool[i]
+                .parent_id
+                .as_deref()
+                .and_then(|p| index.get(p).copied());
+        }
+
+        let mut slots: Vec<Option<SessionMessage>> = pool.into_iter().map(Some).collect();
+        self.messages = path.iter().rev().filter_map(|&i| slots[i].take()).collect();
+        self.branches = slots.into_iter().flatten().collect();
+        self.recalculate_tokens();
+    }
+
     fn recalculate_tokens(&mut self) {
         self.token_count = 0;
 
-        if let Some(ref context) = self.system_context {
-            self.token_count += estimate_tokens(context);
+        if let Some(system_prompt) = self.system_prompt() {
+            self.token_count += self.token_counter.count_text(&system_prompt);
         }
 
         for sm in &self.messages {
-            self.token_count += estimate_tokens(&sm.message.content);
+            self.token_count += self.token_counter.count_message(&sm.message);
         }
     }
 
@@ -316,6 +699,13 @@ impl Session {
             "timestamp": self.created_at.to_rfc3339(),
             "cwd": self.cwd,
             // LocalGPT extensions (ignored by Pi but preserved)
+            "leafId": self.messages.last().map(|sm| sm.id.as_str()),
+            "instructions": self.instructions,
+            "pinned": self
+                .pinned
+                .iter()
+                .map(|p| json!({"id": p.id, "content": p.content}))
+                .collect::<Vec<_>>(),
             "compactionCount": self.compaction_count,
             "memoryFlushCompactionCount": self.memory_flush_compaction_count
         });
@@ -329,12 +719,14 @@ impl Session {
                 tool_calls: None,
                 tool_call_id: None,
                 images: Vec::new(),
+                thinking: Vec::new(),
             }));
             writeln!(file, "{}", serde_json::to_string(&system_msg)?)?;
         }
 
-        // Write messages in Pi format
-        for sm in &self.messages {
+        // Write messages in Pi format; the active branch goes last so readers
+        // that take the final entry as the leaf agree with `leafId`
+        for sm in self.branches.iter().chain(&self.messages) {
             let entry = self.format_message_entry(sm);
             writeln!(file, "{}", serde_json::to_string(&entry)?)?;
         }
@@ -344,6 +736,21 @@ impl Session {
 
     /// Format a message in Pi-compatible format
     fn format_message_entry(&self, sm: &SessionMessage) -> serde_json::Value {
+        if let Some(ref compaction) = sm.compaction {
+            return json!({
+                "type": "compaction",
+                "id": sm.id,
+                "parentId": sm.parent_id,
+                "timestamp": sm.timestamp,
+                "summary": compaction.segments.join("\n\n"),
+                "firstKeptEntryId": compaction.first_kept_id,
+                "tokensBefore": compaction.tokens_before,
+                // LocalGPT extension: segments let the next compaction extend
+                // the summary instead of rewriting it
+                "segments": compaction.segments,
+            });
+        }
+
         let role = match sm.message.role {
             Role::User => "user",
             Role::Assistant => "assistant",
@@ -354,6 +761,22 @@ impl Session {
         // Build content array (Pi format)
         let mut content = Vec::new();
 
+        // Thinking comes first, as the model produced it; signatures are kept so
+        // the blocks can be replayed to Anthropic on a resumed tool-call turn
+        for block in &sm.message.thinking {
+            let mut entry = json!({
+                "type": "thinking",
+                "thinking": block.text
+            });
+            if let Some(ref signature) = block.signature {
+                entry["thinkingSignature"] = json!(signature);
+            }
+            if let Some(ref data) = block.redacted {
+                entry["redactedData"] = json!(data);
+            }
+            content.push(entry);
+        }
+
         // Add text content
         if !sm.message.content.is_empty() {
             content.push(json!({
@@ -383,11 +806,15 @@ impl Session {
             let tc: Vec<serde_json::Value> = tool_calls
                 .iter()
                 .map(|tc| {
-                    json!({
+                    let mut call = json!({
                         "id": tc.id,
                         "name": tc.name,
                         "arguments": tc.arguments
-                    })
+                    });
+                    if let Some(ref signature) = tc.signature {
+                        call["signature"] = json!(signature);
+                    }
+                    call
                 })
                 .collect();
             message["toolCalls"] = json!(tc);
@@ -418,6 +845,8 @@ impl Session {
 
         json!({
             "type": "message",
+            "id": sm.id,
+            "parentId": sm.parent_id,
             "message": message
         })
     }
@@ -443,12 +872,20 @@ impl Session {
             created_at: Utc::now(),
             cwd: ".".to_string(),
             messages: Vec::new(),
+            branches: Vec::new(),
             system_context: None,
+            instructions: None,
+            pinned: Vec::new(),
             token_count: 0,
+            token_counter: TokenCounter::default(),
             compaction_count: 0,
             memory_flush_compaction_count: 0,
         };
 
+        let mut pool: Vec<SessionMessage> = Vec::new();
+        let mut leaf_id: Option<String> = None;
+        let mut pins: Vec<(String, Option<String>)> = Vec::new();
+
         for line in reader.lines() {
             let line = line?;
             if line.trim().is_empty() {
@@ -477,17 +914,45 @@ impl Session {
                     if let Some(count) = entry["memoryFlushCompactionCount"].as_u64() {
                         session.memory_flush_compaction_count = count as u32;
                     }
+                    leaf_id = entry["leafId"].as_str().map(|s| s.to_string());
+                    session.instructions = entry["instructions"].as_str().map(str::to_string);
+                    if let Some(entries) = entry["pinned"].as_array() {
+                        // Older sessions list bare message IDs; their content
+                        // is looked up once the messages are loaded
+                        pins = entries
+                            .iter()
+                            .filter_map(|pin| match pin.as_str() {
+                                Some(id) => Some((id.to_string(), None)),
+                                None => Some((
+                                    pin["id"].as_str()?.to_string(),
+                                    pin["content"].as_str().map(str::to_string),
+                                )),
+                            })
+                            .collect();
+                    }
+                }
+                Some("compaction") => {
+                    if let Some(sm) = Self::parse_compaction_entry(&entry) {
+                        pool.push(sm);
+                    }
                 }
                 // Pi format message
                 Some("message") => {
                     if let Some(msg_obj) = entry.get("message")
-                        && let Some(sm) = Self::parse_pi_message(msg_obj)
+                        && let Some(mut sm) = Self::parse_pi_message(msg_obj)
                     {
                         // System messages become system_context
                         if sm.message.role == Role::System && session.system_context.is_none() {
                             session.system_context = Some(sm.message.content);
                         } else {
-                            session.messages.push(sm);
+                            if let Some(id) = entry["id"].as_str() {
+                                sm.id = id.to_string();
+                                sm.parent_id = entry["parentId"].as_str().map(|s| s.to_string());
+                            } else {
+                                // Version 1 entries have no IDs and form a single chain
+                                sm.parent_id = pool.last().map(|prev| prev.id.clone());
+                            }
+                            pool.push(sm);
                         }
                     }
                 }
@@ -495,10 +960,58 @@ impl Session {
             }
         }
 
-        session.recalculate_tokens();
+        session.pinned = pins
+            .into_iter()
+            .filter_map(|(id, content)| {
+                let content = content.or_else(|| {
+                    pool.iter()
+                        .find(|sm| sm.id == id)
+                        .map(|sm| sm.message.content.clone())
+                })?;
+                Some(PinnedMessage { id, content })
+            })
+            .collect();
+
+        let leaf_id = leaf_id
+            .filter(|id| pool.iter().any(|sm| &sm.id == id))
+            .or_else(|| pool.last().map(|sm| sm.id.clone()))
+            .unwrap_or_default();
+        session.set_active_path(pool, &leaf_id);
         Ok(session)
     }
 
+    /// Parse a compaction entry into the summary message it stands for
+    fn parse_compaction_entry(entry: &serde_json::Value) -> Option<SessionMessage> {
+        let summary = entry["summary"].as_str()?;
+        let segments = entry["segments"]
+            .as_array()
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|s| s.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_else(|| vec![summary.to_string()]);
+        let compaction = Compaction {
+            segments,
+            first_kept_id: entry["firstKeptEntryId"].as_str().unwrap_or("").to_string(),
+            tokens_before: entry["tokensBefore"].as_u64().unwrap_or(0) as usize,
+        };
+
+        let mut sm = SessionMessage::new(Message {
+            role: Role::System,
+            content: compaction.summary_text(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: Vec::new(),
+        });
+        sm.id = entry["id"].as_str()?.to_string();
+        sm.parent_id = entry["parentId"].as_str().map(str::to_string);
+        sm.timestamp = entry["timestamp"].as_u64().unwrap_or(0);
+        sm.compaction = Some(compaction);
+        Some(sm)
+    }
+
     /// Parse Pi format message
     fn parse_pi_message(msg: &serde_json::Value) -> Option<SessionMessage> {
         let role = match msg["role"].as_str()? {
@@ -527,6 +1040,20 @@ impl Session {
             String::new()
         };
 
+        let thinking = msg["content"]
+            .as_array()
+            .map(|arr| {
+                arr.iter()
+                    .filter(|item| item["type"].as_str() == Some("thinking"))
+                    .map(|item| ThinkingBlock {
+                        text: item["thinking"].as_str().unwrap_or("").to_string(),
+                        signature: item["thinkingSignature"].as_str().map(|s| s.to_string()),
+                        redacted: item["redactedData"].as_str().map(|s| s.to_string()),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
         // Parse tool calls
         let tool_calls = msg["toolCalls"].as_array().map(|arr| {
             arr.iter()
@@ -535,6 +1062,7 @@ impl Session {
                         id: tc["id"].as_str()?.to_string(),
                         name: tc["name"].as_str()?.to_string(),
                         arguments: tc["arguments"].as_str().unwrap_or("{}").to_string(),
+                        signature: tc["signature"].as_str().map(str::to_string),
                     })
                 })
                 .collect()
@@ -546,12 +1074,16 @@ impl Session {
         let usage = serde_json::from_value(msg["usage"].clone()).ok();
 
         Some(SessionMessage {
+            id: new_entry_id(),
+            parent_id: None,
+            compaction: None,
             message: Message {
                 role,
                 content,
                 tool_calls,
                 tool_call_id,
                 images: Vec::new(), // TODO: parse images from content array
+                thinking,
             },
             provider: msg["provider"].as_str().map(|s| s.to_string()),
             model: msg["model"].as_str().map(|s| s.to_string()),
@@ -566,7 +1098,7 @@ impl Session {
         SessionStatus {
             id: self.id.clone(),
             message_count: self.messages.len(),
-            token_count: self.token_count,
+            token_count: self.token_count(),
             compaction_count: self.compaction_count,
             api_input_tokens: 0,
             api_output_tokens: 0,
@@ -587,7 +1119,7 @@ impl Session {
         SessionStatus {
             id: self.id.clone(),
             message_count: self.messages.len(),
-            token_count: self.token_count,
+            token_count: self.token_count(),
             compaction_count: self.compaction_count,
             api_input_tokens: input_tokens,
             api_output_tokens: output_tokens,
@@ -629,10 +1161,6 @@ pub fn get_state_dir() -> Result<PathBuf> {
     Ok(paths.state_dir)
 }
 
-fn estimate_tokens(text: &str) -> usize {
-    text.len() / 4
-}
-
 #[derive(Debug, Clone)]
 pub struct SessionInfo {
     pub id: String,
@@ -794,6 +1322,7 @@ fn extract_match_preview(content: &str, query_lower: &str, max_len: usize) -> St
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::providers::{LLMResponse, ToolSchema};
 
     #[test]
     fn test_session_new() {
@@ -808,10 +1337,359 @@ mod tests {
         let usage = Usage {
             input_tokens: 100,
             output_tokens: 50,
+            ..Default::default()
         };
         let msg_usage = MessageUsage::from(&usage);
         assert_eq!(msg_usage.input, 100);
         assert_eq!(msg_usage.output, 50);
         assert_eq!(msg_usage.total_tokens, 150);
     }
+
+    #[test]
+    fn test_thinking_survives_save_and_load() {
+        let session = Session::new();
+        let sm = SessionMessage::new(Message {
+            role: Role::Assistant,
+            content: String::new(),
+            tool_calls: Some(vec![ToolCall {
+                id: "toolu_1".to_string(),
+                name: "bash".to_string(),
+                arguments: "{}".to_string(),
+                signature: Some("call-sig".to_string()),
+            }]),
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: vec![
+                ThinkingBlock {
+                    text: "Check the disk first.".to_string(),
+                    signature: Some("sig".to_string()),
+                    redacted: None,
+                },
+                ThinkingBlock {
+                    redacted: Some("opaque".to_string()),
+                    ..Default::default()
+                },
+            ],
+        });
+
+        let entry = session.format_message_entry(&sm);
+        let parsed = Session::parse_pi_message(&entry["message"]).unwrap();
+        assert_eq!(parsed.message.thinking, sm.message.thinking);
+        assert!(parsed.message.content.is_empty());
+        let calls = parsed.message.tool_calls.unwrap();
+        assert_eq!(calls[0].signature.as_deref(), Some("call-sig"));
+    }
+
+    fn text(role: Role, content: &str) -> Message {
+        Message {
+            role,
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: Vec::new(),
+        }
+    }
+
+    /// Add a message with a strictly increasing timestamp, so branch order
+    /// does not depend on messages landing in different milliseconds
+    fn add(session: &mut Session, role: Role, content: &str) {
+        session.add_message(text(role, content));
+        let seq = (session.messages.len() + session.branches.len()) as u64;
+        session.messages.last_mut().unwrap().timestamp = seq;
+    }
+
+    fn contents(session: &Session) -> Vec<&str> {
+        session
+            .messages()
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect()
+    }
+
+    /// Provider that only summarizes, recording what it was asked to summarize
+    #[derive(Default)]
+    struct RecordingSummarizer {
+        texts: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl LLMProvider for RecordingSummarizer {
+        fn name(&self) -> String {
+            "summarizer".to_string()
+        }
+
+        async fn chat(
+            &self,
+            _messages: &[Message],
+            _tools: Option<&[ToolSchema]>,
+        ) -> Result<LLMResponse> {
+            anyhow::bail!("not used")
+        }
+
+        async fn summarize(&self, text: &str) -> Result<String> {
+            let mut texts = self.texts.lock().unwrap();
+            texts.push(text.to_string());
+            Ok(format!("summary {}", texts.len()))
+        }
+    }
+
+    fn tool_call(session: &mut Session) {
+        session.add_message(Message {
+            tool_calls: Some(vec![ToolCall {
+                id: "call".to_string(),
+                name: "bash".to_string(),
+                arguments: "{}".to_string(),
+                signature: None,
+            }]),
+            ..text(Role::Assistant, "")
+        });
+        session.add_message(Message {
+            tool_call_id: Some("call".to_string()),
+            ..text(Role::Tool, "output")
+        });
+    }
+
+    #[tokio::test]
+    async fn test_compact_cuts_at_turn_boundary() {
+        let provider = RecordingSummarizer::default();
+        let mut session = Session::new();
+        add(&mut session, Role::User, "first question");
+        add(&mut session, Role::Assistant, "first answer");
+        add(&mut session, Role::User, "run it twice");
+        tool_call(&mut session);
+        tool_call(&mut session);
+        add(&mut session, Role::Assistant, "done");
+
+        // The last four messages start with a tool result, so the cut moves
+        // back to the user message that opened the turn
+        session.compact(&provider).await.unwrap();
+
+        let raw = session.raw_messages();
+        assert!(raw[0].compaction.is_some());
+        assert_eq!(
+            raw[0].message.content,
+            "Previous conversation summary:\n\nsummary 1"
+        );
+        assert_eq!(raw[1].message.content, "run it twice");
+        assert_eq!(raw[1].parent_id.as_ref(), Some(&raw[0].id));
+        assert_eq!(raw.len(), 7);
+        assert_eq!(session.compaction_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_compact_is_incremental_and_keeps_pins() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("compact.jsonl");
+        let provider = RecordingSummarizer::default();
+
+        let mut session = Session::new();
+        add(&mut session, Role::User, "my name is Ada");
+        let pin = session.raw_messages()[0].id.clone();
+        session.pin_message(&pin).unwrap();
+        add(&mut session, Role::Assistant, "hi Ada");
+        for i in 0..3 {
+            add(&mut session, Role::User, &format!("question {i}"));
+            add(&mut session, Role::Assistant, &format!("answer {i}"));
+        }
+        session.compact(&provider).await.unwrap();
+        assert_eq!(
+            contents(&session)[1..],
+            ["question 1", "answer 1", "question 2", "answer 2"]
+        );
+        // Once compacted away, the pin is sent with the system prompt instead
+        assert!(
+            session.messages_for_llm()[0]
+                .content
+                .contains("- my name is Ada")
+        );
+
+        for i in 3..5 {
+            add(&mut session, Role::User, &format!("question {i}"));
+            add(&mut session, Role::Assistant, &format!("answer {i}"));
+        }
+        session.compact(&provider).await.unwrap();
+
+        // The second pass only summarizes messages added since the first
+        let texts = provider.texts.lock().unwrap().clone();
+        assert!(!texts[0].contains("my name is Ada"));
+        assert!(texts[1].contains("question 1") && !texts[1].contains("question 0"));
+        let summary = &session.raw_messages()[0];
+        assert_eq!(summary.compaction.as_ref().unwrap().segments.len(), 2);
+        assert!(
+            session.messages_for_llm()[0]
+                .content
+                .contains("- my name is Ada")
+        );
+
+        // Compacted history is still written out and the context reloads intact
+        session.save_to_path(&path).unwrap();
+        let loaded = Session::load_from_path(&path, "compact").unwrap();
+        assert_eq!(contents(&loaded), contents(&session));
+        assert_eq!(loaded.pinned(), session.pinned());
+        assert_eq!(loaded.branches.len(), session.branches.len());
+        assert!(fs::read_to_string(&path).unwrap().contains("question 0"));
+    }
+
+    #[test]
+    fn test_instructions_and_pins_in_system_prompt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pins.jsonl");
+
+        let mut session = Session::new();
+        session.set_system_context("context".to_string());
+        session.set_instructions(Some("Answer in German.".to_string()));
+        let note = session.pin_note("The deadline is Friday.").id.clone();
+        add(&mut session, Role::User, "I use Postgres");
+        let id = session.raw_messages()[0].id.clone();
+        session.pin_message(&id).unwrap();
+
+        let system = session.messages_for_llm()[0].content.clone();
+        assert!(system.starts_with("context\n\n## Session Instructions\n\nAnswer in German."));
+        assert!(system.contains("- The deadline is Friday."));
+        // Pinned messages still on the branch are not repeated
+        assert!(!system.contains("Postgres"));
+
+        session.save_to_path(&path).unwrap();
+        let mut loaded = Session::load_from_path(&path, "pins").unwrap();
+        assert_eq!(loaded.instructions(), Some("Answer in German."));
+        assert_eq!(loaded.pinned(), session.pinned());
+        // The system context entry holds only the context, not the session extras
+        assert_eq!(loaded.system_context.as_deref(), Some("context"));
+
+        loaded.unpin(&note).unwrap();
+        loaded.set_instructions(None);
+        assert_eq!(loaded.messages_for_llm()[0].content, "context");
+        assert!(loaded.unpin(&note).is_err());
+    }
+
+    #[test]
+    fn test_pins_saved_as_bare_ids_still_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("old-pins.jsonl");
+
+        let mut session = Session::new();
+        add(&mut session, Role::User, "I use Postgres");
+        let id = session.raw_messages()[0].id.clone();
+        session.pin_message(&id).unwrap();
+        session.save_to_path(&path).unwrap();
+
+        // Earlier builds saved pins as a list of message IDs
+        let saved = fs::read_to_string(&path).unwrap();
+        let mut lines: Vec<String> = saved.lines().map(str::to_string).collect();
+        let mut header: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(header["type"], "session");
+        header["pinned"] = serde_json::json!([id, "missing-id"]);
+        lines[0] = header.to_string();
+        fs::write(&path, lines.join("\n")).unwrap();
+
+        let loaded = Session::load_from_path(&path, "old-pins").unwrap();
+        assert_eq!(loaded.pinned(), session.pinned());
+        assert_eq!(loaded.pinned()[0].content, "I use Postgres");
+    }
+
+    #[test]
+    fn test_fork_and_switch_branch() {
+        let mut session = Session::new();
+        add(&mut session, Role::User, "hi");
+        add(&mut session, Role::Assistant, "hello");
+        add(&mut session, Role::User, "tell a joke");
+        add(&mut session, Role::Assistant, "joke one");
+
+        let first = session.raw_messages()[0].id.clone();
+        assert_eq!(session.raw_messages()[1].parent_id.as_ref(), Some(&first));
+
+        // Regenerate: cut after the last user message and answer again
+        let user = session.last_user_message_id().unwrap().to_string();
+        session.fork_at(&user).unwrap();
+        assert_eq!(contents(&session), ["hi", "hello", "tell a joke"]);
+        add(&mut session, Role::Assistant, "joke two");
+        assert_eq!(session.branches().len(), 2);
+
+        // Edit: replace the user message with a sibling
+        session.fork_before(&user).unwrap();
+        add(&mut session, Role::User, "tell a story");
+        add(&mut session, Role::Assistant, "once upon a time");
+
+        let branches = session.branches();
+        assert_eq!(branches.len(), 3);
+        assert!(branches.iter().all(|b| b.message_count == 4));
+        assert!(branches[2].active);
+        assert_eq!(branches[2].preview, "tell a story");
+
+        session.switch_branch(&branches[0].leaf_id).unwrap();
+        assert_eq!(
+            contents(&session),
+            ["hi", "hello", "tell a joke", "joke one"]
+        );
+
+        // Switching to an inner message follows its newest continuation
+        session.switch_branch(&user[..6]).unwrap();
+        assert_eq!(
+            contents(&session),
+            ["hi", "hello", "tell a joke", "joke two"]
+        );
+
+        assert!(session.fork_at("missing").is_err());
+    }
+
+    #[test]
+    fn test_active_message_rejects_ambiguous_prefix() {
+        let mut session = Session::new();
+        add(&mut session, Role::User, "first");
+        add(&mut session, Role::Assistant, "reply");
+        add(&mut session, Role::User, "second");
+        session.messages[0].id = "ab12cd".to_string();
+        session.messages[2].id = "ab34ef".to_string();
+
+        let err = session.active_message("ab").unwrap_err();
+        assert!(err.to_string().contains("ambiguous"));
+        assert!(session.active_message("").is_err());
+        assert_eq!(
+            session.active_message("ab3").unwrap().message.content,
+            "second"
+        );
+    }
+
+    #[test]
+    fn test_branches_survive_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tree.jsonl");
+
+        let mut session = Session::new();
+        session.set_system_context("be brief".to_string());
+        add(&mut session, Role::User, "q");
+        add(&mut session, Role::Assistant, "a1");
+        let user = session.last_user_message_id().unwrap().to_string();
+        session.fork_at(&user).unwrap();
+        add(&mut session, Role::Assistant, "a2");
+        session
+            .switch_branch(&session.branches()[0].leaf_id.clone())
+            .unwrap();
+        session.save_to_path(&path).unwrap();
+
+        let loaded = Session::load_from_path(&path, "tree").unwrap();
+        assert_eq!(contents(&loaded), ["q", "a1"]);
+        assert_eq!(loaded.system_context.as_deref(), Some("be brief"));
+        assert_eq!(loaded.branches().len(), 2);
+    }
+
+    #[test]
+    fn test_load_linear_v1_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v1.jsonl");
+        let lines = [
+            json!({"type": "session", "version": 1, "id": "v1"}),
+            json!({"type": "message", "message": {"role": "user", "content": "one"}}),
+            json!({"type": "message", "message": {"role": "assistant", "content": "two"}}),
+        ];
+        let body: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        fs::write(&path, body.join("\n")).unwrap();
+
+        let loaded = Session::load_from_path(&path, "v1").unwrap();
+        assert_eq!(contents(&loaded), ["one", "two"]);
+        let raw = loaded.raw_messages();
+        assert_eq!(raw[1].parent_id.as_ref(), Some(&raw[0].id));
+        assert_eq!(loaded.branches().len(), 1);
+    }
 }
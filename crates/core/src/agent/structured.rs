@@ -0,0 +1,538 @@
+//! Structured output: replies constrained to a JSON schema
+//!
+//! Providers with a native JSON-schema mode (OpenAI `response_format`, Gemini
+//! `responseJsonSchema`, Ollama `format`, Anthropic forced tool use) override
+//! [`LLMProvider::chat_structured`](super::LLMProvider::chat_structured). Everything
+//! else goes through [`chat_with_validation`], which describes the schema in the
+//! system prompt, validates the reply and retries with the validation errors.
+//!
+//! Validation covers the JSON Schema subset models are asked to produce: `type`,
+//! `enum`, `const`, `properties`, `required`, `additionalProperties`, `items`,
+//! `anyOf`/`oneOf`/`allOf`, length and range bounds, and local `$ref`s.
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+use super::provider_error::ProviderError;
+use super::providers::{LLMProvider, LLMResponseContent, Message, Role, Usage};
+
+/// Attempts made by [`chat_with_validation`] before giving up
+const MAX_ATTEMPTS: usize = 3;
+
+/// A JSON schema the reply must conform to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseSchema {
+    /// Schema name (letters, digits, `_` and `-`); some providers require one
+    pub name: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// JSON Schema for the reply
+    pub schema: Value,
+
+    /// Ask providers that support it to enforce the schema exactly
+    /// (OpenAI strict mode requires every property to be listed in `required`)
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl ResponseSchema {
+    pub fn new(name: impl Into<String>, schema: Value) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            schema,
+            strict: false,
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Parse and validate a reply
+    pub fn parse(&self, text: &str) -> Result<Value, Vec<String>> {
+        let value =
+            extract_json(text).ok_or_else(|| vec!["reply is not valid JSON".to_string()])?;
+        validate(&value, &self.schema)?;
+        Ok(value)
+    }
+
+    /// Parse a reply from a native JSON-schema mode, where a mismatch is a provider error
+    pub(crate) fn parse_native(&self, provider: &str, text: &str) -> Result<StructuredResponse> {
+        let value = self
+            .parse(text)
+            .map_err(|errors| ProviderError::BadRequest {
+                provider: provider.to_string(),
+                message: format!(
+                    "reply does not match schema '{}': {}",
+                    self.name,
+                    errors.join("; ")
+                ),
+            })?;
+        Ok(StructuredResponse { value, usage: None })
+    }
+
+    /// Prompt text describing the schema, for providers without a native mode
+    fn instruction(&self) -> String {
+        let schema = serde_json::to_string_pretty(&self.schema).unwrap_or_default();
+        let mut text = String::from(
+            "Reply with a single JSON value that conforms to the JSON Schema below. \
+             Output only the JSON, with no commentary or code fences.\n\n",
+        );
+        if let Some(ref description) = self.description {
+            text.push_str(description);
+            text.push_str("\n\n");
+        }
+        text.push_str(&schema);
+        text
+    }
+}
+
+/// A reply parsed from JSON and validated against its schema
+#[derive(Debug, Clone)]
+pub struct StructuredResponse {
+    pub value: Value,
+    pub usage: Option<Usage>,
+}
+
+impl StructuredResponse {
+    /// Deserialize the value into `T`
+    pub fn into_typed<T: DeserializeOwned>(self) -> Result<T> {
+        Ok(serde_json::from_value(self.value)?)
+    }
+}
+
+/// Structured chat for providers without a native JSON-schema mode.
+///
+/// The schema is appended to the system prompt; replies that fail to parse or
+/// validate are sent back with the errors, up to `MAX_ATTEMPTS` times.
+pub async fn chat_with_validation<P: LLMProvider + ?Sized>(
+    provider: &P,
+    messages: &[Message],
+    schema: &ResponseSchema,
+) -> Result<StructuredResponse> {
+    let mut conversation = messages.to_vec();
+    match conversation.first_mut() {
+        Some(first) if first.role == Role::System => {
+            first.content.push_str("\n\n");
+            first.content.push_str(&schema.instruction());
+        }
+        _ => conversation.insert(0, text_message(Role::System, schema.instruction())),
+    }
+
+    let mut usage: Option<Usage> = None;
+    let mut errors = Vec::new();
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let response = provider.chat(&conversation, None).await?;
+        if let Some(u) = response.usage {
+            let total = usage.get_or_insert_with(Usage::default);
+            total.input_tokens += u.input_tokens;
+            total.output_tokens += u.output_tokens;
+            total.cache_read_tokens += u.cache_read_tokens;
+            total.cache_write_tokens += u.cache_write_tokens;
+        }
+
+        let text = match response.content {
+            LLMResponseContent::Text(text) => text,
+            LLMResponseContent::ToolCalls(_) => String::new(),
+        };
+        match schema.parse(&text) {
+            Ok(value) => return Ok(StructuredResponse { value, usage }),
+            Err(e) => errors = e,
+        }
+
+        debug!(
+            "Structured reply from {} failed validation (attempt {}): {:?}",
+            provider.name(),
+            attempt,
+            errors
+        );
+        conversation.push(text_message(Role::Assistant, text));
+        conversation.push(text_message(
+            Role::User,
+            format!(
+                "That reply does not match the schema:\n- {}\n\nReply again with only the corrected JSON.",
+                errors.join("\n- ")
+            ),
+        ));
+    }
+
+    Err(ProviderError::BadRequest {
+        provider: provider.name(),
+        message: format!(
+            "no reply matching schema '{}' after {} attempts: {}",
+            schema.name,
+            MAX_ATTEMPTS,
+            errors.join("; ")
+        ),
+    }
+    .into())
+}
+
+fn text_message(role: Role, content: String) -> Message {
+    Message {
+        role,
+        content,
+        tool_calls: None,
+        tool_call_id: None,
+        images: Vec::new(),
+        thinking: Vec::new(),
+    }
+}
+
+/// Parse JSON from a model reply, tolerating code fences and surrounding prose
+pub fn extract_json(text: &str) -> Option<Value> {
+    let trimmed = text.trim();
+    if let Ok(value) = serde_json::from_str(trimmed) {
+        return Some(value);
+    }
+
+    let unfenced = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|s| s.trim_end().strip_suffix("```"))
+        .map(str::trim);
+    if let Some(inner) = unfenced
+        && let Ok(value) = serde_json::from_str(inner)
+    {
+        return Some(value);
+    }
+
+    // Outermost object or array embedded in prose
+    let start = trimmed.find(['{', '['])?;
+    let close = if trimmed[start..].starts_with('{') {
+        '}'
+    } else {
+        ']'
+    };
+    let end = trimmed.rfind(close)?;
+    (end > start)
+        .then(|| serde_json::from_str(&trimmed[start..=end]).ok())
+        .flatten()
+}
+
+/// Validate `value` against `schema`, returning every violation found
+pub fn validate(value: &Value, schema: &Value) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    validate_at(value, schema, schema, "$", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn type_matches(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+/// Resolve a local `$ref` (`#/$defs/Name`, `#/definitions/Name`)
+fn resolve_ref<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    root.pointer(reference.strip_prefix('#')?)
+}
+
+fn validate_at(value: &Value, schema: &Value, root: &Value, path: &str, errors: &mut Vec<String>) {
+    let schema = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            errors.push(format!("{}: not allowed", path));
+            return;
+        }
+        Value::Object(_) => schema,
+        _ => return,
+    };
+
+    if let Some(reference) = schema["$ref"].as_str() {
+        match resolve_ref(root, reference) {
+            Some(target) => validate_at(value, target, root, path, errors),
+            None => errors.push(format!("{}: unresolvable $ref {}", path, reference)),
+        }
+        return;
+    }
+
+    let types: Vec<&str> = match &schema["type"] {
+        Value::String(t) => vec![t.as_str()],
+        Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|t| type_matches(value, t)) {
+        errors.push(format!("{}: expected {}", path, types.join(" or ")));
+        return;
+    }
+
+    if let Some(allowed) = schema["enum"].as_array()
+        && !allowed.contains(value)
+    {
+        let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+        errors.push(format!("{}: must be one of {}", path, allowed.join(", ")));
+    }
+    if let Some(expected) = schema.get("const")
+        && value != expected
+    {
+        errors.push(format!("{}: must be {}", path, expected));
+    }
+
+    // oneOf is checked like anyOf; exclusivity rarely matters for model output
+    for key in ["anyOf", "oneOf"] {
+        if let Some(options) = schema[key].as_array()
+            && !options.iter().any(|option| {
+                let mut option_errors = Vec::new();
+                validate_at(value, option, root, path, &mut option_errors);
+                option_errors.is_empty()
+            })
+        {
+            errors.push(format!("{}: does not match any allowed schema", path));
+        }
+    }
+    if let Some(all) = schema["allOf"].as_array() {
+        for sub in all {
+            validate_at(value, sub, root, path, errors);
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            for name in schema["required"].as_array().into_iter().flatten() {
+                if let Some(name) = name.as_str()
+                    && !map.contains_key(name)
+                {
+                    errors.push(format!("{}: missing required property '{}'", path, name));
+                }
+            }
+            let properties = schema["properties"].as_object();
+            for (key, item) in map {
+                let item_path = format!("{}.{}", path, key);
+                match properties.and_then(|p| p.get(key)) {
+                    Some(property) => validate_at(item, property, root, &item_path, errors),
+                    None => match &schema["additionalProperties"] {
+                        Value::Bool(false) => {
+                            errors.push(format!("{}: unexpected property", item_path))
+                        }
+                        extra @ Value::Object(_) => {
+                            validate_at(item, extra, root, &item_path, errors)
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema["minItems"].as_u64()
+                && (items.len() as u64) < min
+            {
+                errors.push(format!("{}: expected at least {} items", path, min));
+            }
+            if let Some(max) = schema["maxItems"].as_u64()
+                && (items.len() as u64) > max
+            {
+                errors.push(format!("{}: expected at most {} items", path, max));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_at(item, item_schema, root, &format!("{}[{}]", path, i), errors);
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema["minLength"].as_u64()
+                && len < min
+            {
+                errors.push(format!("{}: shorter than {} characters", path, min));
+            }
+            if let Some(max) = schema["maxLength"].as_u64()
+                && len > max
+            {
+                errors.push(format!("{}: longer than {} characters", path, max));
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema["minimum"].as_f64()
+                && n < min
+            {
+                errors.push(format!("{}: less than minimum {}", path, min));
+            }
+            if let Some(max) = schema["maximum"].as_f64()
+                && n > max
+            {
+                errors.push(format!("{}: greater than maximum {}", path, max));
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::providers::{LLMResponse, ToolSchema};
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    fn person_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "minLength": 1 },
+                "age": { "type": "integer", "minimum": 0 },
+                "role": { "enum": ["admin", "user"] },
+                "tags": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["name", "age"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn test_validate_accepts_conforming_value() {
+        let value = json!({ "name": "Ada", "age": 36, "role": "admin", "tags": ["math"] });
+        assert!(validate(&value, &person_schema()).is_ok());
+    }
+
+    #[test]
+    fn test_validate_reports_each_violation() {
+        let value = json!({ "name": "", "age": 1.5, "role": "root", "tags": [1], "extra": true });
+        let errors = validate(&value, &person_schema()).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                "$.age: expected integer",
+                "$.extra: unexpected property",
+                "$.name: shorter than 1 characters",
+                "$.role: must be one of \"admin\", \"user\"",
+                "$.tags[0]: expected string",
+            ]
+        );
+
+        let errors = validate(&json!({}), &person_schema()).unwrap_err();
+        assert!(errors.contains(&"$: missing required property 'name'".to_string()));
+    }
+
+    #[test]
+    fn test_validate_resolves_refs_and_any_of() {
+        let schema = json!({
+            "$defs": { "id": { "anyOf": [{ "type": "integer" }, { "type": "string" }] } },
+            "type": "array",
+            "items": { "$ref": "#/$defs/id" }
+        });
+        assert!(validate(&json!([1, "two"]), &schema).is_ok());
+        assert!(validate(&json!([true]), &schema).is_err());
+    }
+
+    #[test]
+    fn test_extract_json_from_fences_and_prose() {
+        assert_eq!(extract_json("{\"a\": 1}"), Some(json!({ "a": 1 })));
+        assert_eq!(
+            extract_json("```json\n{\"a\": 1}\n```"),
+            Some(json!({ "a": 1 }))
+        );
+        assert_eq!(
+            extract_json("Here you go: [1, 2] Hope that helps."),
+            Some(json!([1, 2]))
+        );
+        assert_eq!(extract_json("no json here"), None);
+    }
+
+    /// Provider that replays canned text replies and records the prompts it saw
+    struct ScriptedProvider {
+        replies: Mutex<Vec<&'static str>>,
+        prompts: Mutex<Vec<Vec<Message>>>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for ScriptedProvider {
+        fn name(&self) -> String {
+            "scripted".to_string()
+        }
+
+        async fn chat(
+            &self,
+            messages: &[Message],
+            _tools: Option<&[ToolSchema]>,
+        ) -> Result<LLMResponse> {
+            self.prompts.lock().unwrap().push(messages.to_vec());
+            let reply = self.replies.lock().unwrap().remove(0);
+            Ok(LLMResponse::text_with_usage(
+                reply.to_string(),
+                Usage {
+                    input_tokens: 10,
+                    output_tokens: 5,
+                    ..Default::default()
+                },
+            ))
+        }
+
+        async fn summarize(&self, text: &str) -> Result<String> {
+            Ok(text.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_structured_fallback_retries_with_errors() {
+        let provider = ScriptedProvider {
+            replies: Mutex::new(vec![
+                "Sure! {\"name\": \"Ada\"}",
+                "{\"name\": \"Ada\", \"age\": 36}",
+            ]),
+            prompts: Mutex::new(Vec::new()),
+        };
+        let schema = ResponseSchema::new("person", person_schema());
+        let messages = vec![text_message(
+            Role::User,
+            "Who wrote the first program?".to_string(),
+        )];
+
+        let response = provider.chat_structured(&messages, &schema).await.unwrap();
+
+        assert_eq!(response.value, json!({ "name": "Ada", "age": 36 }));
+        assert_eq!(response.usage.unwrap().input_tokens, 20);
+        let prompts = provider.prompts.lock().unwrap();
+        assert_eq!(prompts.len(), 2);
+        assert_eq!(prompts[0][0].role, Role::System);
+        assert!(
+            prompts[0][0]
+                .content
+                .contains("\"additionalProperties\": false")
+        );
+        let retry = &prompts[1].last().unwrap().content;
+        assert!(retry.contains("missing required property 'age'"));
+    }
+
+    #[tokio::test]
+    async fn test_chat_structured_fallback_gives_up() {
+        let provider = ScriptedProvider {
+            replies: Mutex::new(vec!["nope", "still no", "{}"]),
+            prompts: Mutex::new(Vec::new()),
+        };
+        let schema = ResponseSchema::new("person", person_schema());
+
+        let Err(err) = provider.chat_structured(&[], &schema).await else {
+            panic!("expected validation failure");
+        };
+        assert!(matches!(
+            ProviderError::from_anyhow(&err),
+            Some(ProviderError::BadRequest { .. })
+        ));
+        assert_eq!(provider.prompts.lock().unwrap().len(), MAX_ATTEMPTS);
+    }
+}
@@ -92,18 +92,6 @@ pub fn build_system_prompt(params: SystemPromptParams) -> String {
     );
     lines.push(String::new());
 
-    // Current time section
-    if let Some(ref time) = params.current_time {
-        lines.push("## Current Time".to_string());
-        let tz_info = params
-            .timezone
-            .as_ref()
-            .map(|tz| format!(" ({})", tz))
-            .unwrap_or_default();
-        lines.push(format!("Session started: {}{}", time, tz_info));
-        lines.push(String::new());
-    }
-
     // Memory section
     lines.push("## Memory".to_string());
     lines.push("Memory files in the workspace:".to_string());
@@ -188,9 +176,46 @@ pub fn build_system_prompt(params: SystemPromptParams) -> String {
     runtime_parts.push(format!("arch={}", std::env::consts::ARCH));
     lines.push(runtime_parts.join(" | "));
 
+    // Everything above is identical across sessions and can be prompt-cached,
+    // so per-session content goes last (see `split_cacheable_prefix`)
+    if let Some(ref time) = params.current_time {
+        lines.push(String::new());
+        lines.push(CURRENT_TIME_HEADING.to_string());
+        let tz_info = params
+            .timezone
+            .as_ref()
+            .map(|tz| format!(" ({})", tz))
+            .unwrap_or_default();
+        lines.push(format!("Session started: {}{}", time, tz_info));
+    }
+
     lines.join("\n")
 }
 
+const CURRENT_TIME_HEADING: &str = "## Current Time";
+
+/// Marker the agent puts between the system prompt and workspace memory context
+pub const WORKSPACE_CONTEXT_SEPARATOR: &str = "\n\n---\n\n# Workspace Context\n\n";
+
+/// Split a system message into the part that is stable across sessions
+/// (identity, safety, tools, skills) and the per-session remainder (current
+/// time, workspace memory context).
+///
+/// Providers with explicit prompt caching put a breakpoint after the prefix so
+/// that fresh sessions — every heartbeat tick, every cron run — reuse it.
+/// Returns `None` when there is no per-session part to split off.
+pub fn split_cacheable_prefix(system: &str) -> Option<(&str, &str)> {
+    let time = format!("\n\n{}\n", CURRENT_TIME_HEADING);
+    let boundary = [system.find(&time), system.find(WORKSPACE_CONTEXT_SEPARATOR)]
+        .into_iter()
+        .flatten()
+        .min()?;
+    if boundary == 0 {
+        return None;
+    }
+    Some(system.split_at(boundary))
+}
+
 /// Parameters for building the system prompt
 pub struct SystemPromptParams<'a> {
     pub workspace_dir: String,
@@ -306,6 +331,20 @@ pub fn filter_silent_reply(response: String) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_split_cacheable_prefix() {
+        let system = format!(
+            "You are a personal assistant.\n\n## Current Time\nSession started: now{}memory",
+            WORKSPACE_CONTEXT_SEPARATOR
+        );
+        let (prefix, rest) = split_cacheable_prefix(&system).unwrap();
+        assert_eq!(prefix, "You are a personal assistant.");
+        assert!(rest.starts_with("\n\n## Current Time"));
+        assert!(rest.ends_with("memory"));
+
+        assert!(split_cacheable_prefix("no boundary here").is_none());
+    }
+
     #[test]
     fn test_is_heartbeat_ok() {
         assert!(is_heartbeat_ok("HEARTBEAT_OK"));
@@ -73,6 +73,7 @@ async fn test_default_chat_stream_forwards_tools() {
         tool_calls: None,
         tool_call_id: None,
         images: Vec::new(),
+        thinking: Vec::new(),
     }];
     let tools = vec![ToolSchema {
         name: "bash".to_string(),
@@ -100,6 +101,7 @@ async fn test_default_chat_stream_returns_text_as_stream_chunk() {
         tool_calls: None,
         tool_call_id: None,
         images: Vec::new(),
+        thinking: Vec::new(),
     }];
 
     let mut stream = provider
@@ -121,6 +123,7 @@ async fn test_default_chat_stream_returns_tool_calls_as_stream_chunk() {
         id: "call_1".to_string(),
         name: "bash".to_string(),
         arguments: "{\"command\":\"pwd\"}".to_string(),
+        signature: None,
     }];
     let provider = MockProvider::returning_tool_calls(calls);
     let messages = vec![Message {
@@ -129,6 +132,7 @@ async fn test_default_chat_stream_returns_tool_calls_as_stream_chunk() {
         tool_calls: None,
         tool_call_id: None,
         images: Vec::new(),
+        thinking: Vec::new(),
     }];
 
     let mut stream = provider
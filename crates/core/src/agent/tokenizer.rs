@@ -0,0 +1,757 @@
+//! Per-model token counting
+//!
+//! Session token counts drive compaction and the context usage shown to users,
+//! so they should match what the provider will bill. Each model resolves to a
+//! [`TokenCounter`]: a BPE tokenizer (OpenAI's tiktoken encodings are built in,
+//! other `.tiktoken` rank files can be loaded from disk) plus a calibration
+//! factor that corrects for providers whose tokenizer is not public.
+//!
+//! The calibration starts from a per-family prior and is reconciled against the
+//! prompt size each API response reports (see [`UsageReconcilingProvider`]),
+//! so counts converge on the provider's real numbers over a session.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use tiktoken_rs::CoreBPE;
+use tracing::{debug, warn};
+
+use super::model_catalog::ModelCatalog;
+use super::providers::{
+    LLMProvider, LLMResponse, Message, OAuthTokenUpdate, StreamResult, ToolSchema, Usage,
+};
+use super::structured::{ResponseSchema, StructuredResponse};
+
+/// Tokens added per chat message for role and separators (OpenAI chat format)
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Flat estimate for an attached image (a 512px tile at high detail)
+const IMAGE_TOKENS: usize = 765;
+
+/// Prompts smaller than this are too noisy to calibrate against
+const MIN_RECONCILE_TOKENS: usize = 256;
+
+/// Weight of a new observation in the running calibration factor
+const CALIBRATION_ALPHA: f64 = 0.3;
+
+/// Calibration factors outside this range indicate a mismatch we cannot fix by scaling
+const CALIBRATION_RANGE: (f64, f64) = (0.5, 2.0);
+
+/// Pre-tokenization pattern used by cl100k_base and Llama 3 style rank files
+const CL100K_PATTERN: &str = r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+(?!\S)|\s+";
+
+/// Counts tokens in text
+pub trait Tokenizer: Send + Sync + std::fmt::Debug {
+    /// Encoding name shown in diagnostics (e.g. "o200k_base")
+    fn name(&self) -> &str;
+
+    fn count(&self, text: &str) -> usize;
+}
+
+/// Byte-pair-encoding tokenizer (tiktoken format)
+pub struct BpeTokenizer {
+    name: String,
+    bpe: Arc<CoreBPE>,
+}
+
+impl std::fmt::Debug for BpeTokenizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BpeTokenizer")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl BpeTokenizer {
+    /// Built-in OpenAI encoding by name (`o200k_base` or `cl100k_base`)
+    pub fn builtin(name: &str) -> Option<Self> {
+        static O200K: Lazy<Option<Arc<CoreBPE>>> =
+            Lazy::new(|| tiktoken_rs::o200k_base().ok().map(Arc::new));
+        static CL100K: Lazy<Option<Arc<CoreBPE>>> =
+            Lazy::new(|| tiktoken_rs::cl100k_base().ok().map(Arc::new));
+
+        let bpe = match name {
+            "o200k_base" => O200K.clone()?,
+            "cl100k_base" => CL100K.clone()?,
+            _ => return None,
+        };
+        Some(Self {
+            name: name.to_string(),
+            bpe,
+        })
+    }
+
+    /// Load a `.tiktoken` rank file (one `<base64 token> <rank>` pair per line)
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read tokenizer file {}", path.display()))?;
+
+        let mut encoder = Vec::new();
+        for (line_no, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (token, rank) = line.split_once(' ').with_context(|| {
+                format!(
+                    "{}:{}: expected '<token> <rank>'",
+                    path.display(),
+                    line_no + 1
+                )
+            })?;
+            let token = base64::engine::general_purpose::STANDARD
+                .decode(token)
+                .with_context(|| format!("{}:{}: invalid base64", path.display(), line_no + 1))?;
+            let rank: u32 = rank
+                .trim()
+                .parse()
+                .with_context(|| format!("{}:{}: invalid rank", path.display(), line_no + 1))?;
+            encoder.push((token, rank));
+        }
+        if encoder.is_empty() {
+            anyhow::bail!("Tokenizer file {} is empty", path.display());
+        }
+
+        let bpe = CoreBPE::new(
+            encoder.into_iter().collect(),
+            Default::default(),
+            CL100K_PATTERN,
+        )?;
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "custom".to_string());
+
+        Ok(Self {
+            name,
+            bpe: Arc::new(bpe),
+        })
+    }
+}
+
+impl Tokenizer for BpeTokenizer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count(&self, text: &str) -> usize {
+        self.bpe.encode_ordinary(text).len()
+    }
+}
+
+/// Character-ratio estimate, used only when no BPE encoding can be loaded
+#[derive(Debug)]
+pub struct CharEstimateTokenizer;
+
+impl Tokenizer for CharEstimateTokenizer {
+    fn name(&self) -> &str {
+        "estimate"
+    }
+
+    fn count(&self, text: &str) -> usize {
+        text.len() / 4
+    }
+}
+
+/// Running correction between our count and the provider's reported count
+#[derive(Debug)]
+struct Calibration {
+    state: Mutex<CalibrationState>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CalibrationState {
+    factor: f64,
+    samples: u32,
+}
+
+impl Calibration {
+    fn new(prior: f64) -> Self {
+        Self {
+            state: Mutex::new(CalibrationState {
+                factor: prior,
+                samples: 0,
+            }),
+        }
+    }
+
+    fn get(&self) -> CalibrationState {
+        *self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn observe(&self, ratio: f64) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let ratio = ratio.clamp(CALIBRATION_RANGE.0, CALIBRATION_RANGE.1);
+        state.factor = if state.samples == 0 {
+            ratio
+        } else {
+            state.factor * (1.0 - CALIBRATION_ALPHA) + ratio * CALIBRATION_ALPHA
+        };
+        state.samples = state.samples.saturating_add(1);
+    }
+}
+
+/// Calibrations are shared by every agent using the same model
+static CALIBRATIONS: Lazy<Mutex<HashMap<String, Arc<Calibration>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Tokenizer files are expensive to parse; load each one once per process
+static FILE_TOKENIZERS: Lazy<Mutex<HashMap<PathBuf, Arc<BpeTokenizer>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Diagnostic summary of a [`TokenCounter`]
+#[derive(Debug, Clone)]
+pub struct TokenizerInfo {
+    /// Encoding name (e.g. "o200k_base")
+    pub name: String,
+    /// Multiplier applied to raw counts
+    pub calibration: f64,
+    /// Number of API responses the calibration was reconciled against
+    pub samples: u32,
+}
+
+/// Token counter for one model: tokenizer plus calibration
+#[derive(Clone)]
+pub struct TokenCounter {
+    tokenizer: Arc<dyn Tokenizer>,
+    calibration: Arc<Calibration>,
+    /// Whether API usage reflects our prompt (false for CLI providers that add their own)
+    reconcile: bool,
+}
+
+impl std::fmt::Debug for TokenCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenCounter")
+            .field("tokenizer", &self.tokenizer.name())
+            .field("calibration", &self.calibration.get().factor)
+            .finish()
+    }
+}
+
+impl Default for TokenCounter {
+    /// Uncalibrated character estimate (for sessions not attached to a model)
+    fn default() -> Self {
+        Self {
+            tokenizer: Arc::new(CharEstimateTokenizer),
+            calibration: Arc::new(Calibration::new(1.0)),
+            reconcile: false,
+        }
+    }
+}
+
+impl TokenCounter {
+    /// Raw (uncalibrated) token count of plain text
+    pub fn count_text(&self, text: &str) -> usize {
+        self.tokenizer.count(text)
+    }
+
+    /// Raw token count of a chat message, including tool calls and framing
+    pub fn count_message(&self, message: &Message) -> usize {
+        let mut tokens = MESSAGE_OVERHEAD_TOKENS + self.tokenizer.count(&message.content);
+        if let Some(calls) = &message.tool_calls {
+            for call in calls {
+                tokens += self.tokenizer.count(&call.name) + self.tokenizer.count(&call.arguments);
+            }
+        }
+        tokens + message.images.len() * IMAGE_TOKENS
+    }
+
+    /// Raw token count of a full request: messages plus tool definitions
+    pub fn count_prompt(&self, messages: &[Message], tools: Option<&[ToolSchema]>) -> usize {
+        let message_tokens: usize = messages.iter().map(|m| self.count_message(m)).sum();
+        let tool_tokens: usize = tools
+            .unwrap_or_default()
+            .iter()
+            .map(|t| {
+                self.tokenizer.count(&t.name)
+                    + self.tokenizer.count(&t.description)
+                    + self.tokenizer.count(&t.parameters.to_string())
+            })
+            .sum();
+        message_tokens + tool_tokens
+    }
+
+    /// Apply the calibration factor to a raw count
+    pub fn calibrate(&self, raw: usize) -> usize {
+        (raw as f64 * self.calibration.get().factor).round() as usize
+    }
+
+    /// Reconcile a raw prompt estimate against the usage the provider reported
+    pub fn reconcile(&self, estimated_raw: usize, usage: &Usage) {
+        // Cached tokens are still part of the prompt
+        let reported = usage.prompt_tokens();
+        if !self.reconcile || estimated_raw < MIN_RECONCILE_TOKENS || reported == 0 {
+            return;
+        }
+        let ratio = reported as f64 / estimated_raw as f64;
+        self.calibration.observe(ratio);
+        debug!(
+            "Token count reconciled ({}): estimated {}, reported {}, calibration now {:.3}",
+            self.tokenizer.name(),
+            estimated_raw,
+            reported,
+            self.calibration.get().factor
+        );
+    }
+
+    pub fn info(&self) -> TokenizerInfo {
+        let state = self.calibration.get();
+        TokenizerInfo {
+            name: self.tokenizer.name().to_string(),
+            calibration: state.factor,
+            samples: state.samples,
+        }
+    }
+}
+
+/// How a model family is counted before any reconciliation
+struct FamilyProfile {
+    encoding: &'static str,
+    prior: f64,
+    reconcile: bool,
+}
+
+/// Resolves models to token counters, honoring `[agent.tokenizers]` overrides
+pub struct TokenizerRegistry {
+    /// (model prefix, encoding name or path to a `.tiktoken` file), longest prefix first
+    overrides: Vec<(String, String)>,
+    catalog: ModelCatalog,
+}
+
+impl TokenizerRegistry {
+    pub fn new(overrides: &HashMap<String, String>) -> Self {
+        let mut overrides: Vec<_> = overrides
+            .iter()
+            .map(|(k, v)| (k.to_lowercase(), v.clone()))
+            .collect();
+        overrides.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+        Self {
+            overrides,
+            catalog: ModelCatalog::builtin(),
+        }
+    }
+
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        Self {
+            catalog: ModelCatalog::from_config(config),
+            ..Self::new(&config.agent.tokenizers)
+        }
+    }
+
+    /// Counter for a model (e.g. "openai/gpt-4o", "ollama/llama3", or an alias)
+    pub fn counter_for(&self, model: &str) -> TokenCounter {
+        let resolved = self.catalog.resolve(model);
+        let provider = resolved.provider.unwrap_or_default();
+        let model_id = resolved.model_id.to_lowercase();
+        let model = if provider.is_empty() {
+            model_id.clone()
+        } else {
+            format!("{}/{}", provider, model_id)
+        };
+        let profile = family_profile(&provider, &model_id);
+
+        let tokenizer = self
+            .override_for(&model)
+            .or_else(|| BpeTokenizer::builtin(profile.encoding).map(|t| Arc::new(t) as _))
+            .unwrap_or_else(|| Arc::new(CharEstimateTokenizer) as Arc<dyn Tokenizer>);
+
+        let calibration = {
+            let key = format!("{}@{}", model, tokenizer.name());
+            let mut calibrations = CALIBRATIONS.lock().unwrap_or_else(|e| e.into_inner());
+            Arc::clone(
+                calibrations
+                    .entry(key)
+                    .or_insert_with(|| Arc::new(Calibration::new(profile.prior))),
+            )
+        };
+
+        TokenCounter {
+            tokenizer,
+            calibration,
+            reconcile: profile.reconcile,
+        }
+    }
+
+    fn override_for(&self, model: &str) -> Option<Arc<dyn Tokenizer>> {
+        let (_, spec) = self
+            .overrides
+            .iter()
+            .find(|(prefix, _)| model.starts_with(prefix.as_str()))?;
+
+        if let Some(builtin) = BpeTokenizer::builtin(spec) {
+            return Some(Arc::new(builtin));
+        }
+
+        let path = PathBuf::from(shellexpand::tilde(spec).to_string());
+        let mut cache = FILE_TOKENIZERS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(tokenizer) = cache.get(&path) {
+            return Some(Arc::clone(tokenizer) as _);
+        }
+        match BpeTokenizer::from_file(&path) {
+            Ok(tokenizer) => {
+                let tokenizer = Arc::new(tokenizer);
+                cache.insert(path, Arc::clone(&tokenizer));
+                Some(tokenizer as _)
+            }
+            Err(e) => {
+                warn!("Ignoring tokenizer override for {}: {:#}", model, e);
+                None
+            }
+        }
+    }
+}
+
+/// Encoding and calibration prior for a provider/model pair.
+///
+/// Priors for non-OpenAI families are cl100k_base ratios measured on English prose
+/// and code; reconciliation refines them per model at runtime.
+fn family_profile(provider: &str, model_id: &str) -> FamilyProfile {
+    let openai_encoding = || match tiktoken_rs::tokenizer::get_tokenizer(model_id) {
+        Some(tiktoken_rs::tokenizer::Tokenizer::Cl100kBase) => "cl100k_base",
+        _ => "o200k_base",
+    };
+
+    match provider {
+        "openai" | "github" => FamilyProfile {
+            encoding: openai_encoding(),
+            prior: 1.0,
+            reconcile: true,
+        },
+        "codex-cli" => FamilyProfile {
+            encoding: openai_encoding(),
+            prior: 1.0,
+            reconcile: false,
+        },
+        "anthropic" => FamilyProfile {
+            encoding: "cl100k_base",
+            prior: 1.15,
+            reconcile: true,
+        },
+        "claude-cli" => FamilyProfile {
+            encoding: "cl100k_base",
+            prior: 1.15,
+            reconcile: false,
+        },
+        "gemini-cli" => FamilyProfile {
+            encoding: "cl100k_base",
+            prior: 1.0,
+            reconcile: false,
+        },
+        _ if model_id.contains("gpt-oss") => FamilyProfile {
+            encoding: "o200k_base",
+            prior: 1.0,
+            reconcile: true,
+        },
+        _ => FamilyProfile {
+            encoding: "cl100k_base",
+            prior: small_vocab_prior(model_id),
+            reconcile: true,
+        },
+    }
+}
+
+/// Local models with 32k-entry vocabularies need noticeably more tokens than cl100k
+fn small_vocab_prior(model_id: &str) -> f64 {
+    const SMALL_VOCAB: &[&str] = &[
+        "llama2",
+        "llama-2",
+        "mistral",
+        "mixtral",
+        "phi",
+        "tinyllama",
+    ];
+    if SMALL_VOCAB.iter().any(|m| model_id.contains(m)) {
+        1.2
+    } else {
+        1.0
+    }
+}
+
+/// Provider wrapper that reconciles token counts against reported API usage
+pub struct UsageReconcilingProvider {
+    inner: Box<dyn LLMProvider>,
+    counter: TokenCounter,
+}
+
+impl UsageReconcilingProvider {
+    pub fn new(inner: Box<dyn LLMProvider>, counter: TokenCounter) -> Self {
+        Self { inner, counter }
+    }
+}
+
+#[async_trait]
+impl LLMProvider for UsageReconcilingProvider {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn token_update(&self) -> Option<OAuthTokenUpdate> {
+        self.inner.token_update()
+    }
+
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<LLMResponse> {
+        let response = self.inner.chat(messages, tools).await?;
+        if let Some(usage) = &response.usage {
+            let estimated = self.counter.count_prompt(messages, tools);
+            self.counter.reconcile(estimated, usage);
+        }
+        Ok(response)
+    }
+
+    async fn summarize(&self, text: &str) -> Result<String> {
+        self.inner.summarize(text).await
+    }
+
+    async fn chat_structured(
+        &self,
+        messages: &[Message],
+        schema: &ResponseSchema,
+    ) -> Result<StructuredResponse> {
+        let response = self.inner.chat_structured(messages, schema).await?;
+        if let Some(usage) = &response.usage {
+            // The schema goes out with the request, natively or as an instruction
+            let estimated = self.counter.count_prompt(messages, None)
+                + self.counter.count_text(&schema.schema.to_string());
+            self.counter.reconcile(estimated, usage);
+        }
+        Ok(response)
+    }
+
+    fn supports_native_search(&self) -> bool {
+        self.inner.supports_native_search()
+    }
+
+    fn native_tool_definitions(&self) -> Vec<Value> {
+        self.inner.native_tool_definitions()
+    }
+
+    fn reset_session(&self) {
+        self.inner.reset_session()
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        tools: Option<&[ToolSchema]>,
+    ) -> Result<StreamResult> {
+        let estimated = self.counter.count_prompt(messages, tools);
+        let counter = self.counter.clone();
+        let stream = self.inner.chat_stream(messages, tools).await?;
+
+        // Usage arrives on the final chunk
+        Ok(Box::pin(stream.inspect(move |chunk| {
+            if let Ok(chunk) = chunk
+                && let Some(usage) = &chunk.usage
+            {
+                counter.reconcile(estimated, usage);
+            }
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::providers::{Role, StreamChunk};
+
+    fn user(content: &str) -> Message {
+        Message {
+            role: Role::User,
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            images: Vec::new(),
+            thinking: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_openai_models_use_tiktoken_encodings() {
+        let registry = TokenizerRegistry::new(&HashMap::new());
+        assert_eq!(
+            registry.counter_for("openai/gpt-4o").info().name,
+            "o200k_base"
+        );
+        assert_eq!(
+            registry.counter_for("openai/gpt-4").info().name,
+            "cl100k_base"
+        );
+        assert_eq!(registry.counter_for("gpt").info().name, "o200k_base");
+
+        let counter = registry.counter_for("openai/gpt-4o");
+        assert_eq!(counter.count_text("hello world"), 2);
+    }
+
+    #[test]
+    fn test_family_priors() {
+        let registry = TokenizerRegistry::new(&HashMap::new());
+        let claude = registry.counter_for("anthropic/claude-test-prior").info();
+        assert_eq!(claude.name, "cl100k_base");
+        assert!((claude.calibration - 1.15).abs() < f64::EPSILON);
+
+        let mistral = registry.counter_for("ollama/mistral-prior-test").info();
+        assert!((mistral.calibration - 1.2).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_reconcile_moves_calibration_toward_reported_usage() {
+        let registry = TokenizerRegistry::new(&HashMap::new());
+        let counter = registry.counter_for("ollama/reconcile-test");
+        let usage = |input_tokens| Usage {
+            input_tokens,
+            output_tokens: 10,
+            ..Default::default()
+        };
+
+        // First sample replaces the prior
+        counter.reconcile(1000, &usage(1300));
+        assert!((counter.info().calibration - 1.3).abs() < 1e-9);
+        assert_eq!(counter.calibrate(100), 130);
+
+        // Later samples are smoothed
+        counter.reconcile(1000, &usage(1000));
+        let info = counter.info();
+        assert!(info.calibration > 1.0 && info.calibration < 1.3);
+        assert_eq!(info.samples, 2);
+
+        // Tiny prompts and absurd ratios are ignored or clamped
+        counter.reconcile(10, &usage(5000));
+        assert_eq!(counter.info().samples, 2);
+        counter.reconcile(1000, &usage(100_000));
+        assert!(counter.info().calibration <= CALIBRATION_RANGE.1);
+    }
+
+    /// Provider that answers every request with a fixed prompt token count
+    struct UsageProvider;
+
+    #[async_trait]
+    impl LLMProvider for UsageProvider {
+        fn name(&self) -> String {
+            "usage".to_string()
+        }
+
+        async fn chat(
+            &self,
+            _messages: &[Message],
+            _tools: Option<&[ToolSchema]>,
+        ) -> Result<LLMResponse> {
+            let mut response = LLMResponse::text("ok".to_string());
+            response.usage = Some(Usage {
+                input_tokens: 2000,
+                output_tokens: 1,
+                ..Default::default()
+            });
+            Ok(response)
+        }
+
+        async fn summarize(&self, _text: &str) -> Result<String> {
+            Ok(String::new())
+        }
+
+        async fn chat_stream(
+            &self,
+            messages: &[Message],
+            tools: Option<&[ToolSchema]>,
+        ) -> Result<StreamResult> {
+            let mut first = StreamChunk::from_response(LLMResponse::text("o".to_string()));
+            first.done = false;
+            let last = StreamChunk::from_response(self.chat(messages, tools).await?);
+            Ok(Box::pin(futures::stream::iter([Ok(first), Ok(last)])))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamed_usage_is_reconciled() {
+        let registry = TokenizerRegistry::new(&HashMap::new());
+        let counter = registry.counter_for("ollama/stream-reconcile-test");
+        let provider = UsageReconcilingProvider::new(Box::new(UsageProvider), counter.clone());
+        let messages = [user(&"word ".repeat(1000))];
+
+        let stream = provider.chat_stream(&messages, None).await.unwrap();
+        let chunks: Vec<_> = stream.collect().await;
+        assert_eq!(chunks.len(), 2);
+
+        let info = counter.info();
+        assert_eq!(info.samples, 1);
+        assert!(info.calibration > 1.0);
+    }
+
+    #[test]
+    fn test_cli_providers_are_not_reconciled() {
+        let registry = TokenizerRegistry::new(&HashMap::new());
+        let counter = registry.counter_for("claude-cli/reconcile-test");
+        counter.reconcile(
+            1000,
+            &Usage {
+                input_tokens: 20_000,
+                output_tokens: 10,
+                ..Default::default()
+            },
+        );
+        assert_eq!(counter.info().samples, 0);
+    }
+
+    #[test]
+    fn test_count_message_includes_overhead_and_tool_calls() {
+        let counter = TokenizerRegistry::new(&HashMap::new()).counter_for("openai/gpt-4o");
+        let plain = counter.count_message(&user("hello world"));
+        assert_eq!(plain, 2 + MESSAGE_OVERHEAD_TOKENS);
+
+        let mut with_call = user("hello world");
+        with_call.tool_calls = Some(vec![super::super::providers::ToolCall {
+            id: "call_1".to_string(),
+            name: "bash".to_string(),
+            arguments: r#"{"command":"ls"}"#.to_string(),
+            signature: None,
+        }]);
+        assert!(counter.count_message(&with_call) > plain);
+    }
+
+    #[test]
+    fn test_override_loads_tiktoken_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tiny.tiktoken");
+        let b64 = |s: &str| base64::engine::general_purpose::STANDARD.encode(s);
+        let mut lines: Vec<String> = (0u8..=255)
+            .map(|b| {
+                format!(
+                    "{} {}",
+                    base64::engine::general_purpose::STANDARD.encode([b]),
+                    b
+                )
+            })
+            .collect();
+        lines.push(format!("{} 256", b64("ab")));
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let overrides = HashMap::from([(
+            "ollama/tiny".to_string(),
+            path.to_string_lossy().to_string(),
+        )]);
+        let counter = TokenizerRegistry::new(&overrides).counter_for("ollama/tiny-model");
+        assert_eq!(counter.info().name, "tiny");
+        assert_eq!(counter.count_text("abab"), 2);
+        assert_eq!(counter.count_text("abc"), 2);
+    }
+
+    #[test]
+    fn test_bad_override_falls_back_to_builtin() {
+        let overrides = HashMap::from([(
+            "ollama/".to_string(),
+            "/nonexistent/tokenizer.tiktoken".to_string(),
+        )]);
+        let counter = TokenizerRegistry::new(&overrides).counter_for("ollama/llama3");
+        assert_eq!(counter.info().name, "cl100k_base");
+    }
+}
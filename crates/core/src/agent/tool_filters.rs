@@ -134,6 +134,22 @@ impl CompiledToolFilter {
         Ok(())
     }
 
+    /// Whether `value` trips a deny substring or deny pattern. Unlike `check`
+    /// this does not log, for callers that use the rules for something other
+    /// than blocking.
+    pub fn is_denied(&self, value: &str) -> bool {
+        let value_lower = value.to_lowercase();
+        self.deny_substrings
+            .iter()
+            .any(|s| value_lower.contains(&s.to_lowercase()))
+            || self.deny_patterns.iter().any(|(_, re)| re.is_match(value))
+    }
+
+    /// Whether `value` matches one of the allow patterns (false when there are none)
+    pub fn is_allowed(&self, value: &str) -> bool {
+        self.allow_patterns.iter().any(|(_, re)| re.is_match(value))
+    }
+
     /// Merge hardcoded deny defaults into this filter.
     /// Deduplicates entries: hardcoded values that already exist are skipped.
     pub fn merge_hardcoded(
@@ -10,13 +10,14 @@ use serde_json::{Value, json};
 use std::fs;
 use std::io::Cursor;
 use std::net::IpAddr;
+use std::ops::Range;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::debug;
 
-use super::providers::ToolSchema;
+use super::providers::{ToolCall, ToolSchema};
 use crate::config::{Config, SearchProviderType};
-use crate::memory::MemoryManager;
+use crate::memory::{MemoryManager, MemorySource, SearchFilter, file_date, parse_filter_date};
 
 use spawn_agent::{SpawnAgentTool, SpawnContext};
 use web_search::{SearchRouter, WebSearchTool};
@@ -32,6 +33,69 @@ pub trait Tool: Send + Sync {
     fn name(&self) -> &str;
     fn schema(&self) -> ToolSchema;
     async fn execute(&self, arguments: &str) -> Result<String>;
+
+    /// Whether this tool may run concurrently with other tool calls from the
+    /// same assistant turn. Tools with side effects on shared state (files,
+    /// shell, subagents, the gen scene) return false and run on their own, in
+    /// call order.
+    fn parallel_safe(&self) -> bool {
+        true
+    }
+}
+
+/// Split one turn's tool calls into batches that may run concurrently: runs of
+/// parallel-safe tools, with every other tool in a batch of its own.
+pub fn parallel_batches(tools: &[Box<dyn Tool>], calls: &[ToolCall]) -> Vec<Range<usize>> {
+    // Unknown tools fail immediately, so they never need to wait their turn
+    let parallel_safe = |call: &ToolCall| {
+        tools
+            .iter()
+            .find(|t| t.name() == call.name)
+            .is_none_or(|t| t.parallel_safe())
+    };
+
+    let mut batches: Vec<Range<usize>> = Vec::new();
+    for (i, call) in calls.iter().enumerate() {
+        match batches.last_mut() {
+            Some(batch) if parallel_safe(call) && parallel_safe(&calls[batch.start]) => {
+                batch.end = i + 1;
+            }
+            _ => batches.push(i..i + 1),
+        }
+    }
+    batches
+}
+
+/// Execute a batch of tool calls with at most `limit` in flight.
+/// Outputs are returned in call order.
+pub async fn run_tool_batch(
+    tools: &[Box<dyn Tool>],
+    calls: &[ToolCall],
+    limit: usize,
+) -> Vec<Result<String>> {
+    use futures::StreamExt;
+    use futures::future::{BoxFuture, FutureExt};
+
+    // Boxed so the agent's futures stay `Send` (closures returning async
+    // blocks trip up higher-ranked lifetime inference)
+    let executions: Vec<BoxFuture<'_, Result<String>>> = calls
+        .iter()
+        .map(|call| {
+            async move {
+                let tool = tools
+                    .iter()
+                    .find(|tool| tool.name() == call.name)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown tool: {}", call.name))?;
+                tool.execute(&call.arguments).await
+            }
+            .boxed()
+        })
+        .collect();
+
+    futures::stream::iter(executions)
+        .buffered(limit.max(1))
+        .collect()
+        .await
 }
 
 /// Create the safe (mobile-compatible) tools: memory search, memory get, web fetch, web search.
@@ -140,6 +204,82 @@ impl MemorySearchTool {
     pub fn new(workspace: PathBuf) -> Self {
         Self { workspace }
     }
+
+    /// Whether a workspace file passes the search filter
+    fn passes(&self, filter: &SearchFilter, relative_path: &str) -> bool {
+        if filter.is_empty() {
+            return true;
+        }
+        let mtime = fs::metadata(self.workspace.join(relative_path))
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs() as i64);
+        filter.matches(
+            relative_path,
+            MemorySource::for_path(relative_path),
+            file_date(relative_path, mtime),
+        )
+    }
+}
+
+/// Parameters shared by both `memory_search` tools
+fn memory_search_properties() -> Value {
+    json!({
+        "query": {
+            "type": "string",
+            "description": "The search query"
+        },
+        "limit": {
+            "type": "integer",
+            "description": "Maximum number of results (default: 5)"
+        },
+        "paths": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "Only search these workspace paths: prefixes (knowledge/finance/) or globs (knowledge/finance/**)"
+        },
+        "after": {
+            "type": "string",
+            "description": "Only files dated on or after this day (YYYY-MM-DD). Daily logs and sessions are dated by name, other files by last change"
+        },
+        "before": {
+            "type": "string",
+            "description": "Only files dated on or before this day (YYYY-MM-DD)"
+        },
+        "sources": {
+            "type": "array",
+            "items": { "type": "string", "enum": ["memory", "session", "external"] },
+            "description": "Only these sources: memory (workspace files), session (saved conversations), external (indexed paths outside the workspace)"
+        }
+    })
+}
+
+/// Read the optional filter arguments of `memory_search`. A single string
+/// is accepted where a list is expected.
+fn parse_search_filter(args: &Value) -> Result<SearchFilter> {
+    fn strings(value: &Value) -> Vec<String> {
+        match value {
+            Value::String(s) => vec![s.clone()],
+            Value::Array(items) => items
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    let filter = SearchFilter {
+        paths: strings(&args["paths"]),
+        after: args["after"].as_str().map(parse_filter_date).transpose()?,
+        before: args["before"].as_str().map(parse_filter_date).transpose()?,
+        sources: strings(&args["sources"])
+            .iter()
+            .map(|s| s.parse())
+            .collect::<Result<_>>()?,
+    };
+    filter.validate()?;
+    Ok(filter)
 }
 
 #[async_trait]
@@ -154,16 +294,7 @@ impl Tool for MemorySearchTool {
             description: "Search the memory index for relevant information".to_string(),
             parameters: json!({
                 "type": "object",
-                "properties": {
-                    "query": {
-                        "type": "string",
-                        "description": "The search query"
-                    },
-                    "limit": {
-                        "type": "integer",
-                        "description": "Maximum number of results (default: 5)"
-                    }
-                },
+                "properties": memory_search_properties(),
                 "required": ["query"]
             }),
         }
@@ -175,6 +306,7 @@ impl Tool for MemorySearchTool {
             .as_str()
             .ok_or_else(|| anyhow::anyhow!("Missing query"))?;
         let limit = args["limit"].as_u64().unwrap_or(5) as usize;
+        let filter = parse_search_filter(&args)?;
 
         debug!("Memory search: {} (limit: {})", query, limit);
 
@@ -184,6 +316,7 @@ impl Tool for MemorySearchTool {
 
         let memory_file = self.workspace.join("MEMORY.md");
         if memory_file.exists()
+            && self.passes(&filter, "MEMORY.md")
             && let Ok(content) = fs::read_to_string(&memory_file)
         {
             for (i, line) in content.lines().enumerate() {
@@ -207,10 +340,11 @@ impl Tool for MemorySearchTool {
                 }
 
                 let path = entry.path();
+                let filename = path.file_name().unwrap().to_string_lossy();
                 if path.extension().map(|e| e == "md").unwrap_or(false)
+                    && self.passes(&filter, &format!("memory/{}", filename))
                     && let Ok(content) = fs::read_to_string(&path)
                 {
-                    let filename = path.file_name().unwrap().to_string_lossy();
                     for (i, line) in content.lines().enumerate() {
                         if line.to_lowercase().contains(&query.to_lowercase()) {
                             results.push(format!("memory/{}:{}: {}", filename, i + 1, line));
@@ -260,16 +394,7 @@ impl Tool for MemorySearchToolWithIndex {
             description: description.to_string(),
             parameters: json!({
                 "type": "object",
-                "properties": {
-                    "query": {
-                        "type": "string",
-                        "description": "The search query"
-                    },
-                    "limit": {
-                        "type": "integer",
-                        "description": "Maximum number of results (default: 5)"
-                    }
-                },
+                "properties": memory_search_properties(),
                 "required": ["query"]
             }),
         }
@@ -281,6 +406,7 @@ impl Tool for MemorySearchToolWithIndex {
             .as_str()
             .ok_or_else(|| anyhow::anyhow!("Missing query"))?;
         let limit = args["limit"].as_u64().unwrap_or(5) as usize;
+        let filter = parse_search_filter(&args)?;
 
         let search_type = if self.memory.has_embeddings() {
             "hybrid"
@@ -292,7 +418,7 @@ impl Tool for MemorySearchToolWithIndex {
             search_type, query, limit
         );
 
-        let results = self.memory.search(query, limit)?;
+        let results = self.memory.search_filtered(query, limit, &filter)?;
 
         if results.is_empty() {
             return Ok("No results found".to_string());
@@ -305,10 +431,16 @@ impl Tool for MemorySearchToolWithIndex {
             .map(|(i, chunk)| {
                 let preview: String = chunk.content.chars().take(200).collect();
                 let preview = preview.replace('\n', " ");
+                let section = if chunk.breadcrumb.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", chunk.breadcrumb)
+                };
                 format!(
-                    "{}. {} (lines {}-{}, score: {:.3})\n   {}{}",
+                    "{}. {}{} (lines {}-{}, score: {:.3})\n   {}{}",
                     i + 1,
                     chunk.file,
+                    section,
                     chunk.line_start,
                     chunk.line_end,
                     chunk.score,
@@ -794,4 +926,118 @@ mod tests {
         let msg = err.unwrap_err().to_string();
         assert!(msg.contains("Only http/https"));
     }
+
+    /// Sleeps, then echoes its arguments, tracking how many run at once
+    struct SlowTool {
+        name: &'static str,
+        parallel_safe: bool,
+        in_flight: Arc<std::sync::atomic::AtomicUsize>,
+        max_in_flight: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Tool for SlowTool {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn schema(&self) -> ToolSchema {
+            ToolSchema {
+                name: self.name.to_string(),
+                description: String::new(),
+                parameters: json!({ "type": "object" }),
+            }
+        }
+
+        async fn execute(&self, arguments: &str) -> Result<String> {
+            use std::sync::atomic::Ordering;
+
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+            // Later calls finish first, so ordering comes from the executor
+            let delay = 30u64.saturating_sub(arguments.len() as u64 * 5);
+            tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(arguments.to_string())
+        }
+
+        fn parallel_safe(&self) -> bool {
+            self.parallel_safe
+        }
+    }
+
+    fn slow_tools() -> (Vec<Box<dyn Tool>>, Arc<std::sync::atomic::AtomicUsize>) {
+        let in_flight = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let max_in_flight = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let tools: Vec<Box<dyn Tool>> = vec![
+            Box::new(SlowTool {
+                name: "fetch",
+                parallel_safe: true,
+                in_flight: Arc::clone(&in_flight),
+                max_in_flight: Arc::clone(&max_in_flight),
+            }),
+            Box::new(SlowTool {
+                name: "write",
+                parallel_safe: false,
+                in_flight,
+                max_in_flight: Arc::clone(&max_in_flight),
+            }),
+        ];
+        (tools, max_in_flight)
+    }
+
+    fn call(name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: format!("call_{}", arguments),
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+            signature: None,
+        }
+    }
+
+    #[test]
+    fn test_parallel_batches_isolate_unsafe_tools() {
+        let (tools, _) = slow_tools();
+        let calls = vec![
+            call("fetch", "a"),
+            call("fetch", "b"),
+            call("write", "c"),
+            call("write", "d"),
+            call("fetch", "e"),
+            call("missing", "f"),
+        ];
+        assert_eq!(
+            parallel_batches(&tools, &calls),
+            vec![0..2, 2..3, 3..4, 4..6]
+        );
+        assert!(parallel_batches(&tools, &[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_tool_batch_is_concurrent_and_ordered() {
+        let (tools, max_in_flight) = slow_tools();
+        let calls = vec![
+            call("fetch", "a"),
+            call("fetch", "bb"),
+            call("fetch", "ccc"),
+            call("missing", "d"),
+        ];
+
+        let outputs = run_tool_batch(&tools, &calls, 2).await;
+        assert_eq!(outputs[0].as_ref().unwrap(), "a");
+        assert_eq!(outputs[1].as_ref().unwrap(), "bb");
+        assert_eq!(outputs[2].as_ref().unwrap(), "ccc");
+        assert!(
+            outputs[3]
+                .as_ref()
+                .unwrap_err()
+                .to_string()
+                .contains("Unknown tool")
+        );
+        assert_eq!(max_in_flight.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        let (tools, max_in_flight) = slow_tools();
+        run_tool_batch(&tools, &calls[..2], 1).await;
+        assert_eq!(max_in_flight.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
 }
@@ -19,6 +19,7 @@ use tracing::{debug, info, warn};
 use super::Tool;
 use crate::agent::providers::ToolSchema;
 use crate::agent::session::Session;
+use crate::agent::structured::ResponseSchema;
 use crate::config::Config;
 use crate::memory::MemoryManager;
 
@@ -73,6 +74,55 @@ pub struct SubAgentResult {
     pub tokens_used: Option<u64>,
 }
 
+/// Outcome the subagent reports once it has finished
+#[derive(Debug, Deserialize)]
+struct SubAgentReport {
+    success: bool,
+    summary: String,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Schema for `SubAgentReport`, requested with `chat_structured`
+fn report_schema() -> ResponseSchema {
+    ResponseSchema::new(
+        "subagent_report",
+        json!({
+            "type": "object",
+            "properties": {
+                "success": {
+                    "type": "boolean",
+                    "description": "Whether the task was completed"
+                },
+                "summary": {
+                    "type": "string",
+                    "description": "One or two sentences on what was accomplished"
+                },
+                "error": {
+                    "type": ["string", "null"],
+                    "description": "What went wrong, if the task was not completed"
+                }
+            },
+            "required": ["success", "summary", "error"],
+            "additionalProperties": false
+        }),
+    )
+    .with_description("Report the outcome of the task you were given.")
+}
+
+fn record_subagent_usage(
+    ledger: Option<&crate::agent::UsageLedger>,
+    model_spec: &crate::agent::ModelSpec,
+    usage: &crate::agent::Usage,
+) {
+    let entry = crate::agent::UsageEntry::for_model("subagent", "spawn_agent", model_spec, usage);
+    if let Some(ledger) = ledger
+        && let Err(e) = ledger.record(&entry)
+    {
+        warn!("Failed to record subagent usage: {}", e);
+    }
+}
+
 /// Context passed to the spawn_agent tool
 pub struct SpawnContext {
     /// Current spawn depth (0 = root agent)
@@ -215,6 +265,10 @@ impl SpawnAgentTool {
 
         // Create provider for subagent
         let provider = crate::agent::providers::create_provider(model, &self.context.config)?;
+        let model_spec = crate::agent::ModelCatalog::from_config(&self.context.config)
+            .resolve(model)
+            .spec;
+        let usage_ledger = crate::agent::UsageLedger::open_default().ok();
 
         // Build system prompt for subagent
         let system_prompt = self.build_subagent_prompt(params);
@@ -257,6 +311,7 @@ impl SpawnAgentTool {
             tool_calls: None,
             tool_call_id: None,
             images: Vec::new(),
+            thinking: Vec::new(),
         });
 
         // Run the agent loop
@@ -286,6 +341,7 @@ impl SpawnAgentTool {
             // Track token usage
             if let Some(usage) = response.usage {
                 total_tokens += usage.total();
+                record_subagent_usage(usage_ledger.as_ref(), &model_spec, &usage);
             }
 
             match response.content {
@@ -293,14 +349,52 @@ impl SpawnAgentTool {
                     // Subagent completed - return result
                     debug!("Subagent completed with text response");
 
-                    // Parse the response for structured result
-                    let (summary, _details) = self.parse_subagent_response(&text);
+                    // Ask for a structured report, falling back to the reply text
+                    let mut report_messages = messages;
+                    report_messages.push(Message {
+                        role: Role::Assistant,
+                        content: text.clone(),
+                        tool_calls: None,
+                        tool_call_id: None,
+                        images: Vec::new(),
+                        thinking: Vec::new(),
+                    });
+                    report_messages.push(Message {
+                        role: Role::User,
+                        content: "Report the outcome of the task.".to_string(),
+                        tool_calls: None,
+                        tool_call_id: None,
+                        images: Vec::new(),
+                        thinking: Vec::new(),
+                    });
 
-                    return Ok(SubAgentResult {
+                    let report = match provider
+                        .chat_structured(&report_messages, &report_schema())
+                        .await
+                    {
+                        Ok(response) => {
+                            if let Some(ref usage) = response.usage {
+                                total_tokens += usage.total();
+                                record_subagent_usage(usage_ledger.as_ref(), &model_spec, usage);
+                            }
+                            response.into_typed::<SubAgentReport>().ok()
+                        }
+                        Err(e) => {
+                            debug!("Structured subagent report failed: {}", e);
+                            None
+                        }
+                    };
+                    let report = report.unwrap_or_else(|| SubAgentReport {
                         success: true,
-                        summary,
-                        details: Some(text),
+                        summary: self.parse_subagent_response(&text).0,
                         error: None,
+                    });
+
+                    return Ok(SubAgentResult {
+                        success: report.success,
+                        summary: report.summary,
+                        details: Some(text),
+                        error: report.error,
                         tokens_used: Some(total_tokens),
                     });
                 }
@@ -316,6 +410,7 @@ impl SpawnAgentTool {
                         tool_calls: Some(calls.clone()),
                         tool_call_id: None,
                         images: Vec::new(),
+                        thinking: Vec::new(),
                     });
 
                     // Execute each tool
@@ -337,6 +432,7 @@ impl SpawnAgentTool {
                             tool_calls: None,
                             tool_call_id: Some(call.id.clone()),
                             images: Vec::new(),
+                            thinking: Vec::new(),
                         });
                     }
 
@@ -392,6 +488,11 @@ impl Tool for SpawnAgentTool {
         "spawn_agent"
     }
 
+    // Subagents run their own tools against the same workspace
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "spawn_agent".to_string(),
@@ -617,6 +718,23 @@ mod tests {
         assert_eq!(summary, Some("Found 3 issues in the code.".to_string()));
     }
 
+    #[test]
+    fn test_report_schema_accepts_report() {
+        let schema = report_schema();
+        let reply =
+            r#"{"success": false, "summary": "Could not build", "error": "missing toolchain"}"#;
+        let value = schema.parse(reply).unwrap();
+        let report: SubAgentReport = serde_json::from_value(value).unwrap();
+        assert!(!report.success);
+        assert_eq!(report.error.as_deref(), Some("missing toolchain"));
+
+        assert!(
+            schema
+                .parse(r#"{"success": "yes", "summary": "Done"}"#)
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_parse_subagent_response_without_summary() {
         // Test without explicit summary - takes first non-empty line
@@ -0,0 +1,454 @@
+//! Persistent usage ledger and spend budgets
+//!
+//! Every LLM call (and every paid web search) is appended to a JSONL ledger in
+//! the state directory, one file per month (`usage/2026-03.jsonl`), tagged with
+//! the agent, channel, provider and model that incurred it. Costs come from the
+//! model catalog's pricing, so models without pricing (local, CLI subscriptions)
+//! are recorded with their token counts at $0.
+//!
+//! `[budget]` limits are checked against the ledger before each turn; see
+//! [`UsageLedger::check_budget`]. `localgpt usage` reports from the same files.
+
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::model_catalog::ModelSpec;
+use super::providers::Usage;
+use crate::config::BudgetConfig;
+
+/// One billed request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageEntry {
+    pub timestamp: DateTime<Utc>,
+    pub agent_id: String,
+    /// Where the request came from: "cli", "telegram", "http", "cron", "heartbeat", ...
+    pub channel: String,
+    pub provider: String,
+    pub model: String,
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_read_tokens: u64,
+    #[serde(default)]
+    pub cache_write_tokens: u64,
+    #[serde(default)]
+    pub cost_usd: f64,
+}
+
+impl UsageEntry {
+    /// Entry for an LLM response, priced from the model's catalog entry
+    pub fn for_model(agent_id: &str, channel: &str, spec: &ModelSpec, usage: &Usage) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            agent_id: agent_id.to_string(),
+            channel: channel.to_string(),
+            provider: spec.provider.clone(),
+            model: spec.id.clone(),
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_read_tokens: usage.cache_read_tokens,
+            cache_write_tokens: usage.cache_write_tokens,
+            cost_usd: spec.pricing.map(|p| p.cost(usage)).unwrap_or(0.0),
+        }
+    }
+}
+
+/// Append-only ledger directory (`state_dir/usage`)
+#[derive(Debug, Clone)]
+pub struct UsageLedger {
+    dir: PathBuf,
+}
+
+impl UsageLedger {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Ledger in the resolved state directory
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::new(crate::paths::Paths::resolve()?.usage_dir()))
+    }
+
+    fn month_file(&self, year: i32, month: u32) -> PathBuf {
+        self.dir.join(format!("{:04}-{:02}.jsonl", year, month))
+    }
+
+    /// Append an entry to the file for its (UTC) month
+    pub fn record(&self, entry: &UsageEntry) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.month_file(entry.timestamp.year(), entry.timestamp.month());
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        // A single short append is atomic enough for concurrent writers
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Entries with `since <= timestamp < until`, oldest first
+    pub fn entries(&self, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<UsageEntry>> {
+        let mut entries = Vec::new();
+        if since >= until {
+            return Ok(entries);
+        }
+
+        let (mut year, mut month) = (since.year(), since.month());
+        let (last_year, last_month) = (until.year(), until.month());
+        while (year, month) <= (last_year, last_month) {
+            let path = self.month_file(year, month);
+            if path.exists() {
+                let reader = BufReader::new(fs::File::open(&path)?);
+                for line in reader.lines() {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_str::<UsageEntry>(&line) {
+                        Ok(entry) if entry.timestamp >= since && entry.timestamp < until => {
+                            entries.push(entry)
+                        }
+                        Ok(_) => {}
+                        // A torn write or hand edit shouldn't hide the rest of the month
+                        Err(e) => debug!("Skipping bad ledger line in {}: {}", path.display(), e),
+                    }
+                }
+            }
+            (year, month) = if month == 12 {
+                (year + 1, 1)
+            } else {
+                (year, month + 1)
+            };
+        }
+
+        entries.sort_by_key(|e| e.timestamp);
+        Ok(entries)
+    }
+
+    /// Check `[budget]` limits for `agent_id` as of `now`.
+    ///
+    /// Returns the first exceeded limit: global daily, global monthly, then the
+    /// agent's own daily and monthly limits.
+    pub fn check_budget(
+        &self,
+        budget: &BudgetConfig,
+        agent_id: &str,
+        now: DateTime<Local>,
+    ) -> Result<Option<BudgetExceeded>> {
+        if !budget.is_enabled() {
+            return Ok(None);
+        }
+
+        let day_start = BudgetPeriod::Day.start(now);
+        let until = now.with_timezone(&Utc) + chrono::Duration::days(1);
+        let entries = self.entries(BudgetPeriod::Month.start(now), until)?;
+
+        let spend = |period: BudgetPeriod, agent: Option<&str>| -> f64 {
+            entries
+                .iter()
+                .filter(|e| period == BudgetPeriod::Month || e.timestamp >= day_start)
+                .filter(|e| agent.is_none_or(|a| e.agent_id == a))
+                .map(|e| e.cost_usd)
+                .sum()
+        };
+
+        let agent_limits = budget.agents.get(agent_id);
+        let checks = [
+            (BudgetPeriod::Day, None, budget.daily_usd),
+            (BudgetPeriod::Month, None, budget.monthly_usd),
+            (
+                BudgetPeriod::Day,
+                Some(agent_id),
+                agent_limits.and_then(|l| l.daily_usd),
+            ),
+            (
+                BudgetPeriod::Month,
+                Some(agent_id),
+                agent_limits.and_then(|l| l.monthly_usd),
+            ),
+        ];
+
+        for (period, agent, limit) in checks {
+            let Some(limit) = limit else { continue };
+            let spent = spend(period, agent);
+            if spent >= limit {
+                return Ok(Some(BudgetExceeded {
+                    period,
+                    agent_id: agent.map(str::to_string),
+                    spent_usd: spent,
+                    limit_usd: limit,
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Budget period, in local time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetPeriod {
+    Day,
+    Month,
+}
+
+impl BudgetPeriod {
+    /// Start of the period containing `now`
+    pub fn start(self, now: DateTime<Local>) -> DateTime<Utc> {
+        let date = match self {
+            BudgetPeriod::Day => now.date_naive(),
+            BudgetPeriod::Month => {
+                NaiveDate::from_ymd_opt(now.year(), now.month(), 1).unwrap_or(now.date_naive())
+            }
+        };
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+        Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|t| t.with_timezone(&Utc))
+            // Midnight skipped by a DST change: fall back to UTC midnight
+            .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+    }
+}
+
+/// A `[budget]` limit that has been reached
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetExceeded {
+    pub period: BudgetPeriod,
+    /// Agent the limit belongs to (None for the global limit)
+    pub agent_id: Option<String>,
+    pub spent_usd: f64,
+    pub limit_usd: f64,
+}
+
+impl std::fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let period = match self.period {
+            BudgetPeriod::Day => "Daily",
+            BudgetPeriod::Month => "Monthly",
+        };
+        write!(f, "{} budget", period)?;
+        if let Some(agent) = &self.agent_id {
+            write!(f, " for agent '{}'", agent)?;
+        }
+        write!(
+            f,
+            " reached: ${:.2} spent of ${:.2}",
+            self.spent_usd, self.limit_usd
+        )
+    }
+}
+
+/// Dimension to aggregate ledger entries by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageGroup {
+    Agent,
+    Channel,
+    Provider,
+    Model,
+    Day,
+}
+
+/// Aggregated usage for one group key
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageSummary {
+    pub key: String,
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// Aggregate entries by `group`. Days are listed chronologically, everything
+/// else by descending cost.
+pub fn summarize(entries: &[UsageEntry], group: UsageGroup) -> Vec<UsageSummary> {
+    let mut groups: HashMap<String, UsageSummary> = HashMap::new();
+    for entry in entries {
+        let key = match group {
+            UsageGroup::Agent => entry.agent_id.clone(),
+            UsageGroup::Channel => entry.channel.clone(),
+            UsageGroup::Provider => entry.provider.clone(),
+            UsageGroup::Model => format!("{}/{}", entry.provider, entry.model),
+            UsageGroup::Day => entry
+                .timestamp
+                .with_timezone(&Local)
+                .format("%Y-%m-%d")
+                .to_string(),
+        };
+        let summary = groups.entry(key.clone()).or_insert_with(|| UsageSummary {
+            key,
+            ..Default::default()
+        });
+        summary.requests += 1;
+        summary.input_tokens += entry.input_tokens;
+        summary.output_tokens += entry.output_tokens;
+        summary.cache_read_tokens += entry.cache_read_tokens;
+        summary.cost_usd += entry.cost_usd;
+    }
+
+    let mut summaries: Vec<UsageSummary> = groups.into_values().collect();
+    if group == UsageGroup::Day {
+        summaries.sort_by(|a, b| a.key.cmp(&b.key));
+    } else {
+        summaries.sort_by(|a, b| {
+            b.cost_usd
+                .total_cmp(&a.cost_usd)
+                .then_with(|| a.key.cmp(&b.key))
+        });
+    }
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BudgetConfig, BudgetLimits};
+    use chrono::Duration;
+    use tempfile::TempDir;
+
+    fn entry(agent: &str, channel: &str, at: DateTime<Utc>, cost: f64) -> UsageEntry {
+        UsageEntry {
+            timestamp: at,
+            agent_id: agent.to_string(),
+            channel: channel.to_string(),
+            provider: "anthropic".to_string(),
+            model: "claude-sonnet-4-6".to_string(),
+            input_tokens: 1000,
+            output_tokens: 100,
+            cache_read_tokens: 0,
+            cache_write_tokens: 0,
+            cost_usd: cost,
+        }
+    }
+
+    #[test]
+    fn test_entries_span_month_files() {
+        let tmp = TempDir::new().unwrap();
+        let ledger = UsageLedger::new(tmp.path());
+        let jan = Utc.with_ymd_and_hms(2026, 1, 31, 12, 0, 0).unwrap();
+        let feb = Utc.with_ymd_and_hms(2026, 2, 1, 12, 0, 0).unwrap();
+        ledger.record(&entry("main", "cli", feb, 0.2)).unwrap();
+        ledger.record(&entry("main", "cli", jan, 0.1)).unwrap();
+
+        assert!(tmp.path().join("2026-01.jsonl").exists());
+        assert!(tmp.path().join("2026-02.jsonl").exists());
+
+        let all = ledger
+            .entries(jan - Duration::days(1), feb + Duration::days(1))
+            .unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].timestamp, jan);
+
+        let only_feb = ledger.entries(feb, feb + Duration::days(1)).unwrap();
+        assert_eq!(only_feb.len(), 1);
+    }
+
+    #[test]
+    fn test_entries_skip_bad_lines() {
+        let tmp = TempDir::new().unwrap();
+        let ledger = UsageLedger::new(tmp.path());
+        let at = Utc.with_ymd_and_hms(2026, 3, 5, 9, 0, 0).unwrap();
+        ledger.record(&entry("main", "cli", at, 0.1)).unwrap();
+        let path = tmp.path().join("2026-03.jsonl");
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"timestamp\": \"trunc").unwrap();
+
+        let entries = ledger.entries(at, at + Duration::hours(1)).unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn test_entry_cost_from_pricing() {
+        let spec = crate::agent::ModelCatalog::builtin()
+            .resolve("anthropic/claude-sonnet-4-6")
+            .spec;
+        let usage = Usage {
+            input_tokens: 1_000_000,
+            output_tokens: 0,
+            ..Default::default()
+        };
+        let e = UsageEntry::for_model("main", "cli", &spec, &usage);
+        assert_eq!(e.provider, "anthropic");
+        assert!((e.cost_usd - spec.pricing.unwrap().input).abs() < 1e-9);
+
+        let local = crate::agent::ModelCatalog::builtin()
+            .resolve("ollama/llama3")
+            .spec;
+        assert_eq!(
+            UsageEntry::for_model("main", "cli", &local, &usage).cost_usd,
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_check_budget_global_and_per_agent() {
+        let tmp = TempDir::new().unwrap();
+        let ledger = UsageLedger::new(tmp.path());
+        let now = Local::now();
+        let today = BudgetPeriod::Day.start(now);
+        ledger.record(&entry("main", "cli", today, 0.6)).unwrap();
+        ledger.record(&entry("gen", "cron", today, 0.3)).unwrap();
+
+        let mut budget = BudgetConfig::default();
+        assert_eq!(ledger.check_budget(&budget, "main", now).unwrap(), None);
+
+        budget.daily_usd = Some(1.0);
+        assert_eq!(ledger.check_budget(&budget, "main", now).unwrap(), None);
+
+        budget.agents.insert(
+            "gen".to_string(),
+            BudgetLimits {
+                daily_usd: Some(0.25),
+                monthly_usd: None,
+            },
+        );
+        let exceeded = ledger.check_budget(&budget, "gen", now).unwrap().unwrap();
+        assert_eq!(exceeded.agent_id.as_deref(), Some("gen"));
+        assert_eq!(exceeded.period, BudgetPeriod::Day);
+        assert!((exceeded.spent_usd - 0.3).abs() < 1e-9);
+        // Another agent's limit doesn't apply to main
+        assert_eq!(ledger.check_budget(&budget, "main", now).unwrap(), None);
+
+        budget.daily_usd = Some(0.5);
+        let exceeded = ledger.check_budget(&budget, "main", now).unwrap().unwrap();
+        assert_eq!(exceeded.agent_id, None);
+        assert_eq!(
+            exceeded.to_string(),
+            "Daily budget reached: $0.90 spent of $0.50"
+        );
+    }
+
+    #[test]
+    fn test_summarize_groups() {
+        let at = Utc.with_ymd_and_hms(2026, 3, 5, 12, 0, 0).unwrap();
+        let entries = vec![
+            entry("main", "cli", at, 0.1),
+            entry("main", "telegram", at, 0.5),
+            entry("gen", "cli", at, 0.2),
+        ];
+
+        let by_channel = summarize(&entries, UsageGroup::Channel);
+        assert_eq!(by_channel.len(), 2);
+        assert_eq!(by_channel[0].key, "telegram");
+        assert_eq!(by_channel[1].key, "cli");
+        assert_eq!(by_channel[1].requests, 2);
+        assert_eq!(by_channel[1].input_tokens, 2000);
+
+        let by_agent = summarize(&entries, UsageGroup::Agent);
+        assert_eq!(by_agent[0].key, "main");
+        assert!((by_agent[0].cost_usd - 0.6).abs() < 1e-9);
+
+        let by_model = summarize(&entries, UsageGroup::Model);
+        assert_eq!(by_model[0].key, "anthropic/claude-sonnet-4-6");
+    }
+}
@@ -92,6 +92,13 @@ pub const COMMANDS: &[SlashCommand] = &[
         usage: "[name]",
         interfaces: &[Interface::Cli, Interface::Telegram, Interface::Discord],
     },
+    SlashCommand {
+        name: "think",
+        description: "Show or set reasoning effort",
+        aliases: &[],
+        usage: "[off|low|medium|high]",
+        interfaces: &[Interface::Cli],
+    },
     SlashCommand {
         name: "models",
         description: "List model prefixes",
@@ -141,6 +148,55 @@ pub const COMMANDS: &[SlashCommand] = &[
         usage: "",
         interfaces: &[Interface::Cli, Interface::Telegram, Interface::Discord],
     },
+    SlashCommand {
+        name: "stop",
+        description: "Stop the reply in progress",
+        aliases: &[],
+        usage: "",
+        interfaces: &[Interface::Telegram, Interface::Discord],
+    },
+    SlashCommand {
+        name: "retry",
+        description: "Regenerate the last reply",
+        aliases: &[],
+        usage: "",
+        interfaces: &[Interface::Cli],
+    },
+    SlashCommand {
+        name: "edit",
+        description: "Edit the last message and resend",
+        aliases: &[],
+        usage: "<text>",
+        interfaces: &[Interface::Cli],
+    },
+    SlashCommand {
+        name: "branch",
+        description: "List branches or switch to one",
+        aliases: &[],
+        usage: "[n|id]",
+        interfaces: &[Interface::Cli],
+    },
+    SlashCommand {
+        name: "pin",
+        description: "Pin a note, or the last message, for this session",
+        aliases: &[],
+        usage: "[text]",
+        interfaces: &[Interface::Cli, Interface::Telegram, Interface::Discord],
+    },
+    SlashCommand {
+        name: "unpin",
+        description: "List pins, or remove one",
+        aliases: &[],
+        usage: "[n]",
+        interfaces: &[Interface::Cli, Interface::Telegram, Interface::Discord],
+    },
+    SlashCommand {
+        name: "instructions",
+        description: "Show, set or clear session instructions",
+        aliases: &[],
+        usage: "[text|clear]",
+        interfaces: &[Interface::Cli, Interface::Telegram, Interface::Discord],
+    },
     SlashCommand {
         name: "memory",
         description: "Search memory files",
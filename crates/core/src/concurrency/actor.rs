@@ -42,7 +42,7 @@ use tokio::sync::{mpsc, oneshot};
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info};
 
-use crate::agent::{Agent, AgentConfig};
+use crate::agent::{Agent, AgentConfig, TurnCanceller};
 use crate::config::Config;
 use crate::memory::MemoryManager;
 
@@ -144,6 +144,8 @@ pub struct MemorySearchResult {
     pub score: f64,
     pub line_start: usize,
     pub line_end: usize,
+    /// Headings the chunk sits under (empty before the first heading)
+    pub breadcrumb: String,
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -154,12 +156,23 @@ pub struct MemorySearchResult {
 #[derive(Clone)]
 pub struct AgentRef {
     sender: mpsc::Sender<AgentMessage>,
+    /// Shared with the actor's agent so a running turn can be stopped
+    canceller: TurnCanceller,
 }
 
 impl AgentRef {
     /// Create a new agent reference
     fn new(sender: mpsc::Sender<AgentMessage>) -> Self {
-        Self { sender }
+        Self {
+            sender,
+            canceller: TurnCanceller::default(),
+        }
+    }
+
+    /// Cancel the turn the actor is running, if any. This bypasses the
+    /// mailbox, which is not read again until the turn ends.
+    pub fn cancel(&self) {
+        self.canceller.cancel();
     }
 
     /// Send a chat message and wait for response
@@ -356,6 +369,7 @@ impl AgentActor {
     ) -> Result<ActorHandle> {
         let (sender, mut receiver) = mpsc::channel::<AgentMessage>(actor_config.mailbox_size);
         let reference = AgentRef::new(sender);
+        let canceller = reference.canceller.clone();
 
         let agent_id = agent_id.to_string();
 
@@ -383,6 +397,8 @@ impl AgentActor {
                     return;
                 }
             };
+            agent.set_usage_scope(&agent_id, "actor");
+            agent.set_canceller(canceller);
 
             // Start a new session
             if let Err(e) = agent.new_session().await {
@@ -468,6 +484,7 @@ impl AgentActor {
                                     score: c.score,
                                     line_start: c.line_start as usize,
                                     line_end: c.line_end as usize,
+                                    breadcrumb: c.breadcrumb,
                                 })
                                 .collect()
                         });
@@ -632,6 +649,7 @@ mod tests {
             score: 0.95,
             line_start: 10,
             line_end: 20,
+            breadcrumb: "notes > testing".to_string(),
         };
 
         assert_eq!(result.file, "test.md");
@@ -57,6 +57,14 @@ pub struct Config {
 
     #[serde(default)]
     pub mcp: McpConfig,
+
+    /// Model catalog overrides keyed by "provider/model" (see `agent::model_catalog`)
+    #[serde(default)]
+    pub models: std::collections::HashMap<String, ModelOverride>,
+
+    /// Spend limits checked against the usage ledger
+    #[serde(default)]
+    pub budget: BudgetConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,13 +72,15 @@ pub struct AgentConfig {
     #[serde(default = "default_model")]
     pub default_model: String,
 
-    #[serde(default = "default_context_window")]
+    /// Context window override in tokens. 0 (default) uses the model catalog,
+    /// so switching models adjusts the window automatically.
+    #[serde(default)]
     pub context_window: usize,
 
     #[serde(default = "default_reserve_tokens")]
     pub reserve_tokens: usize,
 
-    /// Maximum tokens for LLM response
+    /// Maximum tokens for LLM response (capped at the model's output limit)
     #[serde(default = "default_max_tokens")]
     pub max_tokens: usize,
 
@@ -87,6 +97,7 @@ pub struct AgentConfig {
 
     /// Fallback models to try if primary provider fails with retryable errors
     /// (rate limits, server errors, timeouts). Providers are tried in order.
+    /// A failing provider is skipped until its Retry-After / backoff cooldown expires.
     /// Example: ["openai/gpt-4o", "ollama/llama3"]
     #[serde(default)]
     pub fallback_models: Vec<String>,
@@ -105,6 +116,103 @@ pub struct AgentConfig {
     /// 0 = unlimited. Default: 500.
     #[serde(default = "default_session_max_count")]
     pub session_max_count: usize,
+
+    /// Tokenizer overrides keyed by model prefix ("provider/model").
+    /// Values are a built-in encoding ("o200k_base", "cl100k_base") or a path
+    /// to a `.tiktoken` rank file. Longest matching prefix wins.
+    /// Example: { "ollama/llama3" = "~/.local/share/localgpt/tokenizers/llama3.tiktoken" }
+    #[serde(default)]
+    pub tokenizers: std::collections::HashMap<String, String>,
+
+    /// Prompt cache lifetime for providers with explicit cache breakpoints (Anthropic):
+    /// "5m" (default), "1h" (worth it when heartbeats run less often than every 5m),
+    /// or "off". OpenAI caches automatically and ignores this.
+    #[serde(default)]
+    pub prompt_cache: PromptCacheTtl,
+
+    /// Default reasoning effort for models that support extended thinking:
+    /// "off" (default), "low", "medium" or "high". Override per model with
+    /// `[models."provider/model"] reasoning`, or per session with `/think`.
+    #[serde(default)]
+    pub reasoning: ReasoningEffort,
+
+    /// Append every LLM exchange to this JSONL fixture file. Replay it offline
+    /// with the model `replay/<file>`. Usually set per run with `--record <file>`.
+    #[serde(default)]
+    pub record_fixture: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PromptCacheTtl {
+    #[serde(rename = "off")]
+    Off,
+    #[default]
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+}
+
+/// How much a model should reason before answering.
+/// Maps to Anthropic thinking budgets, OpenAI/xAI `reasoning_effort` and
+/// Ollama's `think` flag.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    #[default]
+    Off,
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReasoningEffort::Off => "off",
+            ReasoningEffort::Low => "low",
+            ReasoningEffort::Medium => "medium",
+            ReasoningEffort::High => "high",
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        *self != ReasoningEffort::Off
+    }
+
+    /// Thinking token budget for this effort (Anthropic `budget_tokens`,
+    /// Gemini `thinkingBudget`)
+    pub fn thinking_budget(&self) -> Option<usize> {
+        match self {
+            ReasoningEffort::Off => None,
+            ReasoningEffort::Low => Some(2_048),
+            ReasoningEffort::Medium => Some(8_192),
+            ReasoningEffort::High => Some(24_576),
+        }
+    }
+}
+
+impl std::fmt::Display for ReasoningEffort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ReasoningEffort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "off" | "none" => Ok(ReasoningEffort::Off),
+            "low" | "minimal" => Ok(ReasoningEffort::Low),
+            "medium" => Ok(ReasoningEffort::Medium),
+            "high" => Ok(ReasoningEffort::High),
+            other => anyhow::bail!(
+                "Unknown reasoning effort '{}' (expected off, low, medium or high)",
+                other
+            ),
+        }
+    }
 }
 
 fn default_max_tool_repeats() -> usize {
@@ -134,10 +242,25 @@ pub struct ToolsConfig {
     #[serde(default)]
     pub require_approval: Vec<String>,
 
+    /// Per-tool approval rules (auto-approve and always-ask patterns).
+    /// Keys are tool names (e.g. "bash").
+    #[serde(default)]
+    pub approval: std::collections::HashMap<String, crate::agent::approval::ApprovalPolicy>,
+
+    /// Seconds a remote approval request (HTTP, WebSocket, chat bridges)
+    /// waits for a decision before the call is denied
+    #[serde(default = "default_approval_timeout_secs")]
+    pub approval_timeout_secs: u64,
+
     /// Maximum characters for tool output (0 = unlimited)
     #[serde(default = "default_tool_output_max_chars")]
     pub tool_output_max_chars: usize,
 
+    /// Maximum tool calls from one assistant turn executed concurrently
+    /// (1 = sequential). Tools that are not parallel-safe always run alone.
+    #[serde(default = "default_max_parallel_tools")]
+    pub max_parallel: usize,
+
     /// Log warnings for suspicious injection patterns detected in tool outputs
     #[serde(default = "default_true")]
     pub log_injection_warnings: bool,
@@ -387,6 +510,9 @@ pub struct ProvidersConfig {
     #[serde(default)]
     pub anthropic_oauth: Option<AnthropicOAuthConfig>,
 
+    #[serde(default)]
+    pub gemini: Option<GeminiConfig>,
+
     #[serde(default)]
     pub gemini_oauth: Option<GeminiOAuthConfig>,
 
@@ -396,12 +522,47 @@ pub struct ProvidersConfig {
     #[serde(default)]
     pub github_copilot: Option<GitHubOAuthConfig>,
 
+    /// Azure OpenAI deployments (`azure/<model>` models)
+    #[serde(default)]
+    pub azure_openai: Option<AzureOpenAIConfig>,
+
+    /// AWS Bedrock Converse API (`bedrock/<model-id>` models)
+    #[serde(default)]
+    pub bedrock: Option<BedrockConfig>,
+
+    /// In-process GGUF chat models via llama.cpp (`llamacpp/<model>` models)
+    #[serde(default)]
+    pub llamacpp: Option<LlamaCppConfig>,
+
     /// Generic OpenAI-compatible provider for any endpoint speaking the OpenAI Chat Completions API
     /// (OpenRouter, DeepSeek, Groq, vLLM, LiteLLM, Together AI, Fireworks, etc.)
     #[serde(default)]
     pub openai_compatible: Option<OpenAICompatibleConfig>,
 }
 
+impl ProvidersConfig {
+    /// Whether credentials exist for a model provider prefix (e.g. "anthropic")
+    pub fn is_configured(&self, provider: &str) -> bool {
+        match provider {
+            "anthropic" => self.anthropic.is_some() || self.anthropic_oauth.is_some(),
+            "openai" => self.openai.is_some() || self.openai_oauth.is_some(),
+            "xai" => self.xai.is_some(),
+            "ollama" => self.ollama.is_some(),
+            "glm" => self.glm.is_some(),
+            "gemini" => self.gemini.is_some() || self.gemini_oauth.is_some(),
+            "github" => self.github_copilot.is_some(),
+            "azure" => self.azure_openai.is_some(),
+            "bedrock" => self.bedrock.is_some(),
+            "llamacpp" => self.llamacpp.is_some(),
+            "openai-compat" | "openai_compat" => self.openai_compatible.is_some(),
+            "claude-cli" => self.claude_cli.is_some(),
+            "gemini-cli" => self.gemini_cli.is_some(),
+            "codex-cli" => self.codex_cli.is_some(),
+            _ => false,
+        }
+    }
+}
+
 /// Configuration for OpenAI-compatible providers (OpenRouter, DeepSeek, Groq, etc.)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenAICompatibleConfig {
@@ -416,6 +577,101 @@ pub struct OpenAICompatibleConfig {
     pub extra_headers: std::collections::HashMap<String, String>,
 }
 
+/// Configuration for Azure OpenAI resources
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AzureOpenAIConfig {
+    /// Resource endpoint (e.g., "https://my-resource.openai.azure.com")
+    pub endpoint: String,
+
+    /// Resource key, sent in the `api-key` header (supports ${ENV_VAR} expansion)
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    /// Microsoft Entra ID access token, used instead of `api_key`
+    #[serde(default)]
+    pub ad_token: Option<String>,
+
+    #[serde(default = "default_azure_api_version")]
+    pub api_version: String,
+
+    /// Deployment name per model (e.g., "gpt-4o" = "prod-gpt4o").
+    /// Models without an entry use the model name as the deployment name.
+    #[serde(default)]
+    pub deployments: std::collections::HashMap<String, String>,
+}
+
+impl AzureOpenAIConfig {
+    /// Deployment serving `model`
+    pub fn deployment_for<'a>(&'a self, model: &'a str) -> &'a str {
+        self.deployments
+            .get(model)
+            .map(String::as_str)
+            .unwrap_or(model)
+    }
+}
+
+/// Configuration for AWS Bedrock. Unset credentials and region fall back to the
+/// standard AWS environment variables.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BedrockConfig {
+    /// AWS region (default: AWS_REGION, then AWS_DEFAULT_REGION)
+    #[serde(default)]
+    pub region: Option<String>,
+
+    /// Access key ID (default: AWS_ACCESS_KEY_ID)
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+
+    /// Secret access key (default: AWS_SECRET_ACCESS_KEY)
+    #[serde(default)]
+    pub secret_access_key: Option<String>,
+
+    /// Session token for temporary credentials (default: AWS_SESSION_TOKEN)
+    #[serde(default)]
+    pub session_token: Option<String>,
+
+    /// Override the runtime endpoint (VPC endpoints, local mocks)
+    #[serde(default)]
+    pub endpoint: Option<String>,
+}
+
+/// Configuration for local GGUF chat models run in-process by llama.cpp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlamaCppConfig {
+    /// Directory searched for `llamacpp/<model>` IDs that are not paths
+    #[serde(default = "default_llamacpp_models_dir")]
+    pub models_dir: String,
+
+    /// Context size in tokens (KV cache memory grows with it). Set
+    /// `agent.context_window` to match so compaction triggers in time.
+    #[serde(default = "default_llamacpp_context_size")]
+    pub context_size: u32,
+
+    /// Layers to offload to the GPU (0 = CPU only)
+    #[serde(default)]
+    pub gpu_layers: u32,
+
+    /// CPU threads (default: llama.cpp's choice)
+    #[serde(default)]
+    pub threads: Option<i32>,
+
+    /// Sampling temperature (0 = greedy)
+    #[serde(default = "default_llamacpp_temperature")]
+    pub temperature: f32,
+}
+
+impl Default for LlamaCppConfig {
+    fn default() -> Self {
+        Self {
+            models_dir: default_llamacpp_models_dir(),
+            context_size: default_llamacpp_context_size(),
+            gpu_layers: 0,
+            threads: None,
+            temperature: default_llamacpp_temperature(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitHubOAuthConfig {
     /// OAuth access token (Bearer token)
@@ -462,6 +718,19 @@ pub struct AnthropicConfig {
     pub base_url: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiConfig {
+    pub api_key: String,
+
+    #[serde(default = "default_gemini_base_url")]
+    pub base_url: String,
+
+    /// Ground answers with Google Search (the model's native web search) on
+    /// requests without function tools; the API rejects the combination
+    #[serde(default = "default_true")]
+    pub google_search: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OllamaConfig {
     #[serde(default = "default_ollama_endpoint")]
@@ -653,6 +922,44 @@ pub struct MemoryConfig {
     /// Set to 0 to preserve full message content like OpenClaw
     #[serde(default)]
     pub session_max_chars: usize,
+
+    /// Ranking of memory search results
+    #[serde(default)]
+    pub search: MemorySearchConfig,
+}
+
+/// Ranking of memory search results (`[memory.search]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemorySearchConfig {
+    /// Weight of keyword (FTS) ranks in reciprocal-rank fusion
+    #[serde(default = "default_search_text_weight")]
+    pub text_weight: f32,
+
+    /// Weight of vector ranks in reciprocal-rank fusion
+    #[serde(default = "default_search_vector_weight")]
+    pub vector_weight: f32,
+
+    /// RRF constant k: each result scores weight / (k + rank). Larger values
+    /// flatten the gap between top and lower ranks (default: 60)
+    #[serde(default = "default_rrf_k")]
+    pub rrf_k: f32,
+
+    /// Local cross-encoder that re-scores fused candidates (requires local
+    /// embeddings): "bge-reranker-base", "bge-reranker-v2-m3",
+    /// "jina-reranker-v1-turbo-en", "jina-reranker-v2-base-multilingual".
+    /// Empty disables reranking (default)
+    #[serde(default)]
+    pub reranker: String,
+
+    /// Maximal-marginal-relevance trade-off between relevance (1.0) and
+    /// diversity (0.0). 1.0 disables deduplication (default: 0.7)
+    #[serde(default = "default_mmr_lambda")]
+    pub mmr_lambda: f32,
+
+    /// Days after which a daily log (memory/YYYY-MM-DD.md) scores half as
+    /// much. 0 disables recency decay (default: 30)
+    #[serde(default = "default_recency_half_life_days")]
+    pub recency_half_life_days: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -757,6 +1064,93 @@ pub struct CronJob {
     pub timeout: String,
 }
 
+/// Per-model capability overrides (`[models."provider/model"]`).
+/// Unset fields keep the built-in catalog value; unknown models are added.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelOverride {
+    #[serde(default)]
+    pub context_window: Option<usize>,
+    #[serde(default)]
+    pub max_output_tokens: Option<usize>,
+    #[serde(default)]
+    pub supports_tools: Option<bool>,
+    #[serde(default)]
+    pub supports_images: Option<bool>,
+    /// Model id sent to the provider API, if different from the key
+    #[serde(default)]
+    pub api_id: Option<String>,
+    /// Extra names that resolve to this model (e.g. "qwen")
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// USD per million input tokens
+    #[serde(default)]
+    pub input_price: Option<f64>,
+    /// USD per million output tokens
+    #[serde(default)]
+    pub output_price: Option<f64>,
+    /// USD per million cached input tokens read
+    #[serde(default)]
+    pub cache_read_price: Option<f64>,
+    /// USD per million input tokens written to the prompt cache
+    #[serde(default)]
+    pub cache_write_price: Option<f64>,
+    /// Whether the model accepts a reasoning effort / thinking budget
+    #[serde(default)]
+    pub supports_reasoning: Option<bool>,
+    /// Reasoning effort for this model, overriding `agent.reasoning`
+    #[serde(default)]
+    pub reasoning: Option<ReasoningEffort>,
+}
+
+/// Daily and monthly spend limits (`[budget]`), checked before each turn.
+/// Days and months follow local time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetConfig {
+    /// USD limit per day across all agents
+    #[serde(default)]
+    pub daily_usd: Option<f64>,
+
+    /// USD limit per calendar month across all agents
+    #[serde(default)]
+    pub monthly_usd: Option<f64>,
+
+    /// What to do once a limit is reached
+    #[serde(default)]
+    pub action: BudgetAction,
+
+    /// Model to switch to when `action = "downgrade"` (e.g. "ollama/qwen3:8b")
+    #[serde(default)]
+    pub downgrade_model: Option<String>,
+
+    /// Per-agent limits keyed by agent id, checked in addition to the global ones
+    #[serde(default)]
+    pub agents: std::collections::HashMap<String, BudgetLimits>,
+}
+
+impl BudgetConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.daily_usd.is_some() || self.monthly_usd.is_some() || !self.agents.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetLimits {
+    #[serde(default)]
+    pub daily_usd: Option<f64>,
+    #[serde(default)]
+    pub monthly_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetAction {
+    /// Refuse new turns until the period rolls over
+    #[default]
+    Block,
+    /// Switch to `downgrade_model` (blocks if none is configured)
+    Downgrade,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct McpConfig {
     #[serde(default)]
@@ -796,9 +1190,6 @@ fn default_model() -> String {
     // Default to Claude CLI (uses existing Claude Code auth, no API key needed)
     "claude-cli/opus".to_string()
 }
-fn default_context_window() -> usize {
-    128000
-}
 fn default_reserve_tokens() -> usize {
     8000
 }
@@ -814,6 +1205,12 @@ fn default_web_fetch_max_bytes() -> usize {
 fn default_tool_output_max_chars() -> usize {
     50000 // 50k characters max for tool output by default
 }
+fn default_max_parallel_tools() -> usize {
+    4
+}
+fn default_approval_timeout_secs() -> u64 {
+    300 // 5 minutes
+}
 fn default_openai_base_url() -> String {
     "https://api.openai.com/v1".to_string()
 }
@@ -850,6 +1247,9 @@ fn default_codex_cli_model() -> String {
 fn default_glm_base_url() -> String {
     "https://api.z.ai/api/coding/paas/v4".to_string()
 }
+fn default_azure_api_version() -> String {
+    "2024-10-21".to_string()
+}
 fn default_gemini_base_url() -> String {
     "https://generativelanguage.googleapis.com".to_string()
 }
@@ -872,6 +1272,15 @@ fn default_embedding_provider() -> String {
 fn default_embedding_model() -> String {
     "all-MiniLM-L6-v2".to_string() // Local model via fastembed (no API key needed)
 }
+fn default_llamacpp_models_dir() -> String {
+    format!("{}/models", DEFAULT_DATA_DIR_STR)
+}
+fn default_llamacpp_context_size() -> u32 {
+    8192
+}
+fn default_llamacpp_temperature() -> f32 {
+    0.7
+}
 fn default_embedding_cache_dir() -> String {
     crate::paths::DEFAULT_CACHE_DIR_STR.to_string() + "/embeddings"
 }
@@ -890,6 +1299,21 @@ fn default_index_paths() -> Vec<MemoryIndexPath> {
 fn default_pattern() -> String {
     "**/*.md".to_string()
 }
+fn default_search_text_weight() -> f32 {
+    0.3
+}
+fn default_search_vector_weight() -> f32 {
+    0.7
+}
+fn default_rrf_k() -> f32 {
+    60.0
+}
+fn default_mmr_lambda() -> f32 {
+    0.7
+}
+fn default_recency_half_life_days() -> f32 {
+    30.0
+}
 fn default_session_max_messages() -> usize {
     15 // Match OpenClaw's default
 }
@@ -949,7 +1373,7 @@ impl Default for AgentConfig {
     fn default() -> Self {
         Self {
             default_model: default_model(),
-            context_window: default_context_window(),
+            context_window: 0, // From the model catalog
             reserve_tokens: default_reserve_tokens(),
             max_tokens: default_max_tokens(),
             max_spawn_depth: Some(1),    // Single-level spawning by default
@@ -958,6 +1382,10 @@ impl Default for AgentConfig {
             max_tool_repeats: default_max_tool_repeats(), // Loop detection threshold
             session_max_age: default_session_max_age(), // 30 days
             session_max_count: default_session_max_count(), // 500 sessions
+            tokenizers: std::collections::HashMap::new(),
+            prompt_cache: PromptCacheTtl::default(),
+            reasoning: ReasoningEffort::default(),
+            record_fixture: None,
         }
     }
 }
@@ -968,7 +1396,10 @@ impl Default for ToolsConfig {
             bash_timeout_ms: default_bash_timeout(),
             web_fetch_max_bytes: default_web_fetch_max_bytes(),
             require_approval: Vec::new(),
+            approval: std::collections::HashMap::new(),
+            approval_timeout_secs: default_approval_timeout_secs(),
             tool_output_max_chars: default_tool_output_max_chars(),
+            max_parallel: default_max_parallel_tools(),
             log_injection_warnings: default_true(),
             use_content_delimiters: default_true(),
             web_search: None,
@@ -1002,6 +1433,20 @@ impl Default for MemoryConfig {
             paths: default_index_paths(),
             session_max_messages: default_session_max_messages(),
             session_max_chars: 0, // 0 = unlimited (preserve full content like OpenClaw)
+            search: MemorySearchConfig::default(),
+        }
+    }
+}
+
+impl Default for MemorySearchConfig {
+    fn default() -> Self {
+        Self {
+            text_weight: default_search_text_weight(),
+            vector_weight: default_search_vector_weight(),
+            rrf_k: default_rrf_k(),
+            reranker: String::new(),
+            mmr_lambda: default_mmr_lambda(),
+            recency_half_life_days: default_recency_half_life_days(),
         }
     }
 }
@@ -1144,6 +1589,9 @@ impl Config {
         if let Some(ref mut anthropic) = self.providers.anthropic {
             anthropic.api_key = expand_env(&anthropic.api_key);
         }
+        if let Some(ref mut gemini) = self.providers.gemini {
+            gemini.api_key = expand_env(&gemini.api_key);
+        }
         if let Some(ref mut telegram) = self.telegram {
             telegram.api_token = expand_env(&telegram.api_token);
         }
@@ -1198,6 +1646,29 @@ impl Config {
                 *client_secret = expand_env(client_secret);
             }
         }
+        if let Some(ref mut azure) = self.providers.azure_openai {
+            azure.endpoint = expand_env(&azure.endpoint);
+            if let Some(ref mut api_key) = azure.api_key {
+                *api_key = expand_env(api_key);
+            }
+            if let Some(ref mut ad_token) = azure.ad_token {
+                *ad_token = expand_env(ad_token);
+            }
+        }
+        if let Some(ref mut bedrock) = self.providers.bedrock {
+            for value in [
+                &mut bedrock.region,
+                &mut bedrock.access_key_id,
+                &mut bedrock.secret_access_key,
+                &mut bedrock.session_token,
+                &mut bedrock.endpoint,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                *value = expand_env(value);
+            }
+        }
         if let Some(ref mut openai_compat) = self.providers.openai_compatible {
             openai_compat.api_key = expand_env(&openai_compat.api_key);
             openai_compat.base_url = expand_env(&openai_compat.base_url);
@@ -1352,7 +1823,7 @@ const DEFAULT_CONFIG_TEMPLATE: &str = r#"# LocalGPT Configuration
 [agent]
 # Default model: claude-cli/opus, anthropic/claude-sonnet-4-5, openai/gpt-4o, xai/grok-3-mini, etc.
 default_model = "claude-cli/opus"
-context_window = 128000
+# context_window = 128000  # Override the model's context window (default: from model catalog)
 reserve_tokens = 8000
 
 # Spawn agent (subagent) configuration
@@ -1362,6 +1833,8 @@ reserve_tokens = 8000
 # Failover configuration (optional)
 # Automatically try fallback models if primary fails with retryable errors
 # (rate limits, server errors, timeouts). Providers tried in order.
+# A failing provider is skipped until its cooldown (Retry-After or exponential
+# backoff) expires; see `localgpt doctor` and /api/status for circuit state.
 # fallback_models = ["openai/gpt-4o", "ollama/llama3"]
 
 # Loop detection (optional)
@@ -1369,6 +1842,50 @@ reserve_tokens = 8000
 # before detection triggers. Default: 3. Set to 0 to disable.
 # max_tool_repeats = 3
 
+# Prompt caching (Anthropic): the system prompt and conversation history are
+# marked as cacheable, so repeated turns are billed at the cache read rate.
+# Use "1h" when heartbeats run less often than every 5 minutes, or "off".
+# prompt_cache = "5m"
+
+# Extended thinking / reasoning effort (optional)
+# "off" (default), "low", "medium" or "high". Applies to models that support it
+# (Claude 4.x, OpenAI o-series and gpt-5, grok-3-mini); change it per session
+# with /think, or per model with `reasoning` in a [models."..."] table.
+# reasoning = "medium"
+
+# Record/replay (optional)
+# Append every LLM exchange to a JSONL fixture; replay it offline with
+# the model "replay/<file>". Usually set per run with `--record <file>`.
+# record_fixture = "~/fixtures/session.jsonl"
+
+# Token counting (optional)
+# Context usage is counted with the model's tokenizer (tiktoken encodings for
+# OpenAI, calibrated counters elsewhere) and reconciled against API usage.
+# Override per model prefix with a built-in encoding or a .tiktoken rank file:
+# [agent.tokenizers]
+# "ollama/llama3" = "~/.local/share/localgpt/tokenizers/llama3.tiktoken"
+# "glm/" = "o200k_base"
+
+# Model catalog overrides (optional)
+# Context window, output limit, tool/image support and pricing are built in for
+# common models. Override them or describe models the catalog doesn't know:
+# [models."ollama/qwen3:8b"]
+# context_window = 40960       # Match the num_ctx Ollama serves with
+# supports_images = false
+# aliases = ["qwen"]           # Enables `/model qwen`
+# supports_reasoning = true    # Send Ollama's `think` flag
+# reasoning = "low"            # Overrides agent.reasoning for this model
+
+# Spend limits (optional)
+# Every API call is recorded in the usage ledger (see `localgpt usage`).
+# [budget]
+# daily_usd = 5.0
+# monthly_usd = 50.0
+# action = "downgrade"         # "block" (default) or "downgrade"
+# downgrade_model = "ollama/qwen3:8b"
+# [budget.agents.gen]
+# daily_usd = 1.0
+
 # Anthropic API (for anthropic/* models)
 # [providers.anthropic]
 # api_key = "${ANTHROPIC_API_KEY}"
@@ -1382,6 +1899,31 @@ reserve_tokens = 8000
 # api_key = "${XAI_API_KEY}"
 # base_url = "https://api.x.ai/v1"
 
+# Google Gemini API (for gemini/* models)
+# [providers.gemini]
+# api_key = "${GEMINI_API_KEY}"
+# google_search = true         # Ground tool-less answers with Google Search
+
+# Azure OpenAI (for azure/* models)
+# [providers.azure_openai]
+# endpoint = "https://my-resource.openai.azure.com"
+# api_key = "${AZURE_OPENAI_API_KEY}"   # or ad_token = "${AZURE_OPENAI_AD_TOKEN}"
+# api_version = "2024-10-21"
+# deployments = { "gpt-4o" = "prod-gpt4o" }   # Use with: --model azure/gpt-4o
+
+# AWS Bedrock (for bedrock/* models, e.g. bedrock/anthropic.claude-sonnet-4-5-20250929-v1:0)
+# Credentials default to AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY, AWS_SESSION_TOKEN
+# and AWS_REGION when omitted.
+# [providers.bedrock]
+# region = "us-east-1"
+
+# Local GGUF chat models run in-process by llama.cpp (for llamacpp/* models,
+# requires building with --features llamacpp), e.g. llamacpp/qwen2.5-7b-instruct-q4_k_m
+# [providers.llamacpp]
+# models_dir = "~/.local/share/localgpt/models"
+# context_size = 8192          # Also set agent.context_window to match
+# gpu_layers = 0               # Layers to offload to the GPU
+
 # OpenAI-Compatible provider (OpenRouter, DeepSeek, Groq, vLLM, LiteLLM, etc.)
 # [providers.openai_compatible]
 # base_url = "https://openrouter.ai/api/v1"
@@ -1445,6 +1987,13 @@ level = "info"
 # [sandbox.network]
 # policy = "deny"                       # deny | proxy
 
+# Tool approval rules (optional); tools listed in tools.require_approval
+# ask before every call unless a rule approves it
+# [tools.approval.bash]
+# allow_patterns = ["^git (status|diff|log)"]   # approved without asking
+# ask_patterns = ['\brm\b']                     # always ask, even if allowed for the session
+# ask_substrings = ["sudo"]                      # case-insensitive
+
 # Web search (optional)
 # [tools.web_search]
 # provider = "searxng"            # searxng | brave | tavily | perplexity | none
@@ -29,6 +29,7 @@ pub async fn run_job(
     };
 
     let mut agent = Agent::new(agent_config, config, memory).await?;
+    agent.set_usage_scope(&agent_id, "cron");
 
     if let Some(tools) = extra_tools {
         agent.extend_tools(tools);
@@ -399,6 +399,7 @@ impl HeartbeatRunner {
         // Wrap cloned memory in Arc for sharing with spawn_agent tool
         let memory = Arc::new(self.memory.clone());
         let mut agent = Agent::new(agent_config, &self.config, Arc::clone(&memory)).await?;
+        agent.set_usage_scope(&self.agent_id, "heartbeat");
 
         // Extend agent with additional tools from factory if provided (e.g., CLI tools from daemon)
         if let Some(ref factory) = self.tool_factory {
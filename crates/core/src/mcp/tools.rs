@@ -48,6 +48,11 @@ impl Tool for McpTool {
         &self.namespaced_name
     }
 
+    // MCP tools can have arbitrary side effects
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: self.namespaced_name.clone(),
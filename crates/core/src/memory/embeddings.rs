@@ -326,12 +326,11 @@ impl LlamaCppProvider {
     /// - nomic-embed-text-v1.5.Q8_0.gguf (~270MB, 768 dims)
     /// - mxbai-embed-large-v1-q8_0.gguf (~670MB, 1024 dims)
     pub fn new(model_path: &str, cache_dir: Option<&str>) -> Result<Self> {
-        use llama_cpp_2::llama_backend::LlamaBackend;
         use llama_cpp_2::model::LlamaModel;
         use llama_cpp_2::model::params::LlamaModelParams;
 
-        // Initialize backend
-        let backend = LlamaBackend::init()?;
+        // Initialize backend (shared with GGUF chat models)
+        let backend = crate::agent::llama_cpp::shared_backend()?;
 
         // Resolve model path - check if it's a file or needs downloading
         let resolved_path = Self::resolve_model_path(model_path, cache_dir)?;
@@ -354,7 +353,7 @@ impl LlamaCppProvider {
 
         Ok(Self {
             model: Arc::new(StdMutex::new(model)),
-            backend: Arc::new(backend),
+            backend,
             model_name,
             dimensions,
             cache_dir: cache_dir.map(|s| s.to_string()),
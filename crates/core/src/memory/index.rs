@@ -9,15 +9,17 @@ use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 use super::embeddings::{cosine_similarity, deserialize_embedding, serialize_embedding};
-use super::search::MemoryChunk;
+use super::ranking::reciprocal_rank_fusion;
+use super::search::{MemoryChunk, MemorySource, SearchFilter, file_date};
+use crate::config::MemorySearchConfig;
 
 #[derive(Clone)]
 pub struct MemoryIndex {
     conn: Arc<Mutex<Connection>>,
     workspace: PathBuf,
     db_path: PathBuf,
-    /// Whether sqlite-vec extension is loaded for fast vector search
-    has_vec_extension: bool,
+    /// sqlite-vec version when it is compiled in for fast vector search
+    vec_version: Option<String>,
     /// Token count per chunk (default: 400)
     chunk_size: usize,
     /// Token overlap between chunks (default: 80)
@@ -32,6 +34,15 @@ pub struct ReindexStats {
     pub duration: Duration,
 }
 
+/// Embedding coverage of one model
+#[derive(Debug, Clone)]
+pub struct EmbeddingModelStats {
+    pub model: String,
+    /// Vector dimensions (0 if nothing has been embedded with the model yet)
+    pub dims: usize,
+    pub embedded_chunks: usize,
+}
+
 impl MemoryIndex {
     /// Create a new memory index with database at the specified path
     pub fn new_with_db_path(workspace: &Path, db_path: &Path) -> Result<Self> {
@@ -40,6 +51,9 @@ impl MemoryIndex {
             fs::create_dir_all(parent)?;
         }
 
+        #[cfg(feature = "sqlite-vec")]
+        register_sqlite_vec();
+
         let conn = Connection::open(db_path)?;
 
         // Check if we need to migrate from old schema
@@ -78,7 +92,8 @@ impl MemoryIndex {
                 model TEXT NOT NULL DEFAULT '',
                 text TEXT NOT NULL,
                 embedding TEXT NOT NULL DEFAULT '',
-                updated_at INTEGER NOT NULL
+                updated_at INTEGER NOT NULL,
+                breadcrumb TEXT NOT NULL DEFAULT ''
             );
 
             -- Embedding cache (OpenClaw-compatible)
@@ -93,6 +108,15 @@ impl MemoryIndex {
                 PRIMARY KEY (provider, model, provider_key, hash)
             );
 
+            -- Embedding models that have stored vectors, one sqlite-vec
+            -- table each (see vec_table_name)
+            CREATE TABLE IF NOT EXISTS embedding_models (
+                model TEXT NOT NULL,
+                dims INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY (model, dims)
+            );
+
             -- Indexes
             CREATE INDEX IF NOT EXISTS idx_chunks_path ON chunks(path);
             CREATE INDEX IF NOT EXISTS idx_chunks_source ON chunks(source);
@@ -100,27 +124,42 @@ impl MemoryIndex {
             "#,
         )?;
 
-        // Create FTS5 table (OpenClaw-compatible with UNINDEXED columns)
-        Self::ensure_fts_table(&conn)?;
-
         // Ensure source column exists on older tables
         Self::ensure_column(&conn, "files", "source", "TEXT NOT NULL DEFAULT 'memory'")?;
         Self::ensure_column(&conn, "chunks", "source", "TEXT NOT NULL DEFAULT 'memory'")?;
 
-        // Try to load sqlite-vec extension for fast vector search
-        let has_vec_extension = Self::try_load_sqlite_vec(&conn);
-        if has_vec_extension {
-            debug!("sqlite-vec extension loaded successfully");
-            Self::ensure_vec_table(&conn)?;
-        } else {
-            debug!("sqlite-vec extension not available, using in-memory vector search");
+        // Chunks from before heading breadcrumbs were cut by line count; forget
+        // the file hashes so the next reindex re-chunks every file
+        if Self::ensure_column(&conn, "chunks", "breadcrumb", "TEXT NOT NULL DEFAULT ''")? {
+            info!("Memory index predates markdown chunking, files will be re-chunked");
+            conn.execute("UPDATE files SET hash = ''", [])?;
+        }
+
+        // Create FTS5 table (OpenClaw-compatible with UNINDEXED columns)
+        Self::ensure_fts_table(&conn)?;
+
+        // Older indexes filed everything under 'memory'
+        Self::backfill_sources(&conn)?;
+
+        let vec_version = Self::query_vec_version(&conn);
+        match &vec_version {
+            Some(version) => {
+                debug!("sqlite-vec {} registered", version);
+                // Superseded by per-model tables, which are refilled from the
+                // embeddings stored on chunks
+                let _ = conn.execute("DROP TABLE IF EXISTS chunks_vec", []);
+            }
+            None if cfg!(feature = "sqlite-vec") => {
+                warn!("sqlite-vec failed to register, using in-memory vector search")
+            }
+            None => debug!("Built without sqlite-vec, using in-memory vector search"),
         }
 
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
             workspace: workspace.to_path_buf(),
             db_path: db_path.to_path_buf(),
-            has_vec_extension,
+            vec_version,
             chunk_size: 400,
             chunk_overlap: 80,
         })
@@ -133,56 +172,59 @@ impl MemoryIndex {
         self
     }
 
-    /// Try to load sqlite-vec extension
-    #[cfg(feature = "sqlite-vec")]
-    #[allow(unsafe_code)]
-    fn try_load_sqlite_vec(conn: &Connection) -> bool {
-        // sqlite-vec provides the extension as a loadable module
-        // Try to load it - this requires the extension to be installed on the system
-
-        // SAFETY: load_extension is unsafe because it loads arbitrary native code
-        // We only load from known, trusted paths (sqlite-vec)
-        if unsafe { conn.load_extension_enable() }.is_err() {
-            return false;
-        }
-
-        // Try loading from common locations
-        let ext_paths = [
-            "vec0", // If in LD_LIBRARY_PATH
-            "./vec0",
-            "/usr/local/lib/vec0",
-            "/usr/lib/vec0",
-        ];
+    /// Version of sqlite-vec on this connection, if it is registered
+    fn query_vec_version(conn: &Connection) -> Option<String> {
+        conn.query_row("SELECT vec_version()", [], |row| row.get(0))
+            .ok()
+    }
 
-        for path in ext_paths {
-            // SAFETY: Loading sqlite-vec extension from trusted path
-            if unsafe { conn.load_extension(path, None::<&str>) }.is_ok() {
-                let _ = conn.load_extension_disable();
-                return true;
-            }
+    /// Get the virtual table for vector search of `model` (requires
+    /// sqlite-vec), creating it and filling it from the embeddings stored on
+    /// chunks if it does not exist yet
+    fn ensure_vec_table(conn: &Connection, model: &str, dims: usize) -> Result<String> {
+        let table = vec_table_name(model, dims);
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = ?1)",
+            params![&table],
+            |row| row.get(0),
+        )?;
+        if exists {
+            return Ok(table);
         }
 
-        let _ = conn.load_extension_disable();
-        false
-    }
+        conn.execute(
+            &format!(
+                "CREATE VIRTUAL TABLE {} USING vec0(id TEXT PRIMARY KEY, embedding float[{}] distance_metric=cosine)",
+                table, dims
+            ),
+            [],
+        )?;
 
-    #[cfg(not(feature = "sqlite-vec"))]
-    fn try_load_sqlite_vec(_conn: &Connection) -> bool {
-        false
-    }
+        let mut stmt =
+            conn.prepare("SELECT id, embedding FROM chunks WHERE model = ?1 AND embedding != ''")?;
+        let rows = stmt.query_map(params![model], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
 
-    /// Create virtual table for vector search (requires sqlite-vec)
-    fn ensure_vec_table(conn: &Connection) -> Result<()> {
-        // Create chunks_vec virtual table if sqlite-vec is available
-        let result = conn.execute(
-            "CREATE VIRTUAL TABLE IF NOT EXISTS chunks_vec USING vec0(id TEXT PRIMARY KEY, embedding float[1536])",
-            [],
+        let insert = format!(
+            "INSERT OR REPLACE INTO {} (id, embedding) VALUES (?1, ?2)",
+            table
         );
-        match result {
-            Ok(_) => debug!("chunks_vec table created/verified"),
-            Err(e) => debug!("chunks_vec table creation skipped: {}", e),
+        let mut filled = 0;
+        for row in rows {
+            let (chunk_id, embedding_json) = row?;
+            let embedding = deserialize_embedding(&embedding_json);
+            if embedding.len() == dims {
+                conn.execute(&insert, params![chunk_id, embedding_to_blob(&embedding)])?;
+                filled += 1;
+            }
         }
-        Ok(())
+
+        debug!(
+            "Created {} for {} ({} dims) with {} vectors",
+            table, model, dims, filled
+        );
+        Ok(table)
     }
 
     /// Create a new memory index with database in workspace (legacy path)
@@ -231,18 +273,19 @@ impl MemoryIndex {
 
         debug!("Indexing file: {}", relative_path);
 
+        let source = MemorySource::for_path(&relative_path).as_str();
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)?
             .as_secs() as i64;
 
         // Update file record (OpenClaw-compatible columns)
         conn.execute(
-            "INSERT OR REPLACE INTO files (path, source, hash, mtime, size) VALUES (?1, 'memory', ?2, ?3, ?4)",
-            params![&relative_path, &file_hash, mtime, size],
+            "INSERT OR REPLACE INTO files (path, source, hash, mtime, size) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![&relative_path, source, &file_hash, mtime, size],
         )?;
 
         // Delete existing chunks and their FTS entries
-        Self::delete_chunks_for_path(&conn, &relative_path)?;
+        self.delete_chunks_for_path(&conn, &relative_path)?;
 
         // Create new chunks (OpenClaw-compatible)
         let chunks = chunk_text(&content, self.chunk_size, self.chunk_overlap);
@@ -252,9 +295,9 @@ impl MemoryIndex {
             let chunk_hash = hash_content(&chunk.content);
 
             conn.execute(
-                r#"INSERT INTO chunks (id, path, source, start_line, end_line, hash, model, text, embedding, updated_at)
-                   VALUES (?1, ?2, 'memory', ?3, ?4, ?5, '', ?6, '', ?7)"#,
-                params![&chunk_id, &relative_path, chunk.line_start, chunk.line_end, &chunk_hash, &chunk.content, now],
+                r#"INSERT INTO chunks (id, path, source, start_line, end_line, hash, model, text, embedding, updated_at, breadcrumb)
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6, '', ?7, '', ?8, ?9)"#,
+                params![&chunk_id, &relative_path, source, chunk.line_start, chunk.line_end, &chunk_hash, &chunk.content, now, &chunk.breadcrumb],
             )?;
 
             // Insert into FTS
@@ -262,19 +305,20 @@ impl MemoryIndex {
                 &conn,
                 &chunk_id,
                 &relative_path,
-                "memory",
+                source,
                 "",
                 chunk.line_start,
                 chunk.line_end,
                 &chunk.content,
+                &chunk.breadcrumb,
             )?;
         }
 
         Ok(true)
     }
 
-    /// Delete chunks for a path and their FTS entries
-    fn delete_chunks_for_path(conn: &Connection, path: &str) -> Result<()> {
+    /// Delete chunks for a path and their FTS and vector entries
+    fn delete_chunks_for_path(&self, conn: &Connection, path: &str) -> Result<()> {
         // Delete from FTS first (get chunk IDs)
         let mut stmt = conn.prepare("SELECT id FROM chunks WHERE path = ?1")?;
         let chunk_ids: Vec<String> = stmt
@@ -282,8 +326,20 @@ impl MemoryIndex {
             .filter_map(|r| r.ok())
             .collect();
 
+        let vec_tables = if self.has_vec_extension() {
+            Self::vec_tables(conn)?
+        } else {
+            Vec::new()
+        };
+
         for chunk_id in chunk_ids {
             let _ = conn.execute("DELETE FROM chunks_fts WHERE id = ?1", params![&chunk_id]);
+            for table in &vec_tables {
+                let _ = conn.execute(
+                    &format!("DELETE FROM {} WHERE id = ?1", table),
+                    params![&chunk_id],
+                );
+            }
         }
 
         // Delete chunks
@@ -298,7 +354,7 @@ impl MemoryIndex {
             .lock()
             .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
 
-        Self::delete_chunks_for_path(&conn, relative_path)?;
+        self.delete_chunks_for_path(&conn, relative_path)?;
         conn.execute("DELETE FROM files WHERE path = ?1", params![relative_path])?;
 
         debug!("Removed deleted file from index: {}", relative_path);
@@ -333,16 +389,55 @@ impl MemoryIndex {
         start_line: i32,
         end_line: i32,
         text: &str,
+        breadcrumb: &str,
     ) -> Result<()> {
         let _ = conn.execute(
-            "INSERT INTO chunks_fts (text, id, path, source, model, start_line, end_line) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![text, id, path, source, model, start_line, end_line],
+            "INSERT INTO chunks_fts (text, breadcrumb, id, path, source, model, start_line, end_line) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![text, breadcrumb, id, path, source, model, start_line, end_line],
         );
         Ok(())
     }
 
+    /// Paths of the indexed files that pass `filter`, as a JSON array for
+    /// `json_each`, or None if the filter lets everything through
+    fn matching_paths(conn: &Connection, filter: &SearchFilter) -> Result<Option<String>> {
+        if filter.is_empty() {
+            return Ok(None);
+        }
+        filter.validate()?;
+
+        let mut stmt = conn.prepare("SELECT path, source, mtime FROM files")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })?;
+
+        let mut paths = Vec::new();
+        for row in rows {
+            let (path, source, mtime) = row?;
+            let source = source.parse().unwrap_or(MemorySource::Memory);
+            if filter.matches(&path, source, file_date(&path, mtime)) {
+                paths.push(path);
+            }
+        }
+        Ok(Some(serde_json::to_string(&paths)?))
+    }
+
     /// Search using FTS5
     pub fn search(&self, query: &str, limit: usize) -> Result<Vec<MemoryChunk>> {
+        self.search_filtered(query, limit, &SearchFilter::default())
+    }
+
+    /// Search using FTS5, only in files that pass `filter`
+    pub fn search_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<MemoryChunk>> {
         let fts_query = match build_fts_query(query) {
             Some(q) => q,
             None => return Ok(Vec::new()),
@@ -353,24 +448,29 @@ impl MemoryIndex {
             .lock()
             .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
 
-        // OpenClaw-compatible: use 'path', 'start_line', 'end_line', 'text' columns
+        // OpenClaw-compatible: use 'path', 'start_line', 'end_line', 'text' columns.
+        // Terms found in the heading breadcrumb weigh more than body matches.
         let mut stmt = conn.prepare(
             r#"
-            SELECT fts.path, fts.start_line, fts.end_line, fts.text, bm25(chunks_fts) as score
+            SELECT fts.path, fts.start_line, fts.end_line, fts.text,
+                   bm25(chunks_fts, 1.0, 3.0) as score, fts.breadcrumb
             FROM chunks_fts fts
             WHERE chunks_fts MATCH ?1
+              AND (?3 IS NULL OR fts.path IN (SELECT value FROM json_each(?3)))
             ORDER BY score
             LIMIT ?2
             "#,
         )?;
 
-        let rows = stmt.query_map(params![&fts_query, limit as i64], |row| {
+        let paths = Self::matching_paths(&conn, filter)?;
+        let rows = stmt.query_map(params![&fts_query, limit as i64, paths], |row| {
             Ok(MemoryChunk {
                 file: row.get(0)?,
                 line_start: row.get(1)?,
                 line_end: row.get(2)?,
                 content: row.get(3)?,
                 score: row.get::<_, f64>(4)?.abs(), // BM25 returns negative scores
+                breadcrumb: row.get(5)?,
             })
         })?;
 
@@ -489,7 +589,8 @@ impl MemoryIndex {
                 model TEXT NOT NULL DEFAULT '',
                 text TEXT NOT NULL,
                 embedding TEXT NOT NULL DEFAULT '',
-                updated_at INTEGER NOT NULL
+                updated_at INTEGER NOT NULL,
+                breadcrumb TEXT NOT NULL DEFAULT ''
             )
             "#,
             [],
@@ -585,13 +686,61 @@ impl MemoryIndex {
         Ok(())
     }
 
+    /// Re-file indexed paths whose source differs from what
+    /// [`MemorySource::for_path`] gives them now
+    fn backfill_sources(conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare("SELECT path, source FROM files")?;
+        let stale: Vec<(String, &str)> = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .filter_map(|r| r.ok())
+            .filter_map(|(path, source)| {
+                let expected = MemorySource::for_path(&path).as_str();
+                (source != expected).then_some((path, expected))
+            })
+            .collect();
+
+        for (path, source) in &stale {
+            conn.execute(
+                "UPDATE files SET source = ?2 WHERE path = ?1",
+                params![path, source],
+            )?;
+            conn.execute(
+                "UPDATE chunks SET source = ?2 WHERE path = ?1",
+                params![path, source],
+            )?;
+            conn.execute(
+                "UPDATE chunks_fts SET source = ?2 WHERE path = ?1",
+                params![path, source],
+            )?;
+        }
+        if !stale.is_empty() {
+            debug!("Updated the source of {} indexed files", stale.len());
+        }
+        Ok(())
+    }
+
     /// Create FTS5 table with OpenClaw-compatible structure
     fn ensure_fts_table(conn: &Connection) -> Result<()> {
+        // FTS5 tables cannot gain columns, so rebuild one that predates the
+        // breadcrumb column from the chunks table
+        let table_exists = conn.prepare("SELECT id FROM chunks_fts LIMIT 0").is_ok();
+        let rebuild = table_exists
+            && conn
+                .prepare("SELECT breadcrumb FROM chunks_fts LIMIT 0")
+                .is_err();
+        if rebuild {
+            debug!("Rebuilding chunks_fts with breadcrumb column");
+            conn.execute("DROP TABLE chunks_fts", [])?;
+        }
+
         // OpenClaw uses UNINDEXED columns for metadata
         let result = conn.execute(
             r#"
             CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts USING fts5(
                 text,
+                breadcrumb,
                 id UNINDEXED,
                 path UNINDEXED,
                 source UNINDEXED,
@@ -608,32 +757,71 @@ impl MemoryIndex {
             Err(e) => debug!("FTS5 table creation skipped: {}", e),
         }
 
+        if rebuild {
+            conn.execute(
+                r#"
+                INSERT INTO chunks_fts (text, breadcrumb, id, path, source, model, start_line, end_line)
+                SELECT text, breadcrumb, id, path, source, model, start_line, end_line FROM chunks
+                "#,
+                [],
+            )?;
+        }
+
         Ok(())
     }
 
-    /// Ensure a column exists on a table (for migrations)
-    fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    /// Ensure a column exists on a table (for migrations), returning true if
+    /// it had to be added
+    fn ensure_column(
+        conn: &Connection,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> Result<bool> {
         let sql = format!("SELECT {} FROM {} LIMIT 0", column, table);
         if conn.prepare(&sql).is_err() {
             let alter = format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition);
             conn.execute(&alter, [])?;
             debug!("Added column {} to table {}", column, table);
+            return Ok(true);
         }
-        Ok(())
+        Ok(false)
     }
 
-    /// Get chunks that need embeddings (OpenClaw-compatible: id is TEXT, text column)
-    pub fn chunks_without_embeddings(&self, limit: usize) -> Result<Vec<(String, String)>> {
+    /// Names of the sqlite-vec tables of every model that stored vectors
+    fn vec_tables(conn: &Connection) -> Result<Vec<String>> {
+        let mut stmt = conn.prepare("SELECT model, dims FROM embedding_models")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(vec_table_name(
+                &row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)? as usize,
+            ))
+        })?;
+
+        let mut tables = Vec::new();
+        for row in rows {
+            tables.push(row?);
+        }
+        Ok(tables)
+    }
+
+    /// Get chunks that need embeddings from `model`: never embedded, or
+    /// embedded by a different model (OpenClaw-compatible: id is TEXT, text column)
+    pub fn chunks_without_embeddings(
+        &self,
+        model: &str,
+        limit: usize,
+    ) -> Result<Vec<(String, String)>> {
         let conn = self
             .conn
             .lock()
             .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
 
         let mut stmt = conn.prepare(
-            "SELECT id, text FROM chunks WHERE embedding = '' OR embedding IS NULL LIMIT ?1",
+            "SELECT id, text FROM chunks WHERE embedding = '' OR embedding IS NULL OR model != ?1 LIMIT ?2",
         )?;
 
-        let rows = stmt.query_map(params![limit as i64], |row| {
+        let rows = stmt.query_map(params![model, limit as i64], |row| {
             Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
         })?;
 
@@ -661,19 +849,73 @@ impl MemoryIndex {
             "UPDATE chunks SET embedding = ?1, model = ?2, updated_at = ?3 WHERE id = ?4",
             params![&embedding_json, model, now, chunk_id],
         )?;
+        conn.execute(
+            "INSERT INTO embedding_models (model, dims, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (model, dims) DO UPDATE SET updated_at = excluded.updated_at",
+            params![model, embedding.len() as i64, now],
+        )?;
 
-        // Also store in vec table if sqlite-vec is available
-        if self.has_vec_extension {
-            let embedding_blob = embedding_to_blob(embedding);
-            let _ = conn.execute(
-                "INSERT OR REPLACE INTO chunks_vec (id, embedding) VALUES (?1, ?2)",
-                params![chunk_id, &embedding_blob],
-            );
+        // Also store in the model's vec table if sqlite-vec is available,
+        // moving the chunk out of the table of the model it had before
+        if self.has_vec_extension() {
+            match Self::ensure_vec_table(&conn, model, embedding.len()) {
+                Ok(table) => {
+                    for other in Self::vec_tables(&conn)? {
+                        if other != table {
+                            let _ = conn.execute(
+                                &format!("DELETE FROM {} WHERE id = ?1", other),
+                                params![chunk_id],
+                            );
+                        }
+                    }
+                    let embedding_blob = embedding_to_blob(embedding);
+                    let _ = conn.execute(
+                        &format!(
+                            "INSERT OR REPLACE INTO {} (id, embedding) VALUES (?1, ?2)",
+                            table
+                        ),
+                        params![chunk_id, &embedding_blob],
+                    );
+                }
+                Err(e) => debug!("Vector table for {} unavailable: {}", model, e),
+            }
         }
 
         Ok(())
     }
 
+    /// Embedded chunk counts per model, with the dimensions each model stored
+    pub fn embedding_stats(&self) -> Result<Vec<EmbeddingModelStats>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
+
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT c.model, COUNT(*),
+                   (SELECT MAX(m.dims) FROM embedding_models m WHERE m.model = c.model)
+            FROM chunks c
+            WHERE c.embedding != '' AND c.embedding IS NOT NULL
+            GROUP BY c.model
+            ORDER BY c.model
+            "#,
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(EmbeddingModelStats {
+                model: row.get(0)?,
+                embedded_chunks: row.get::<_, i64>(1)? as usize,
+                dims: row.get::<_, Option<i64>>(2)?.unwrap_or(0) as usize,
+            })
+        })?;
+
+        let mut stats = Vec::new();
+        for row in rows {
+            stats.push(row?);
+        }
+        Ok(stats)
+    }
+
     // ========================================================================
     // Embedding Cache (OpenClaw-compatible)
     // ========================================================================
@@ -732,32 +974,38 @@ impl MemoryIndex {
 
     /// Check if sqlite-vec is available
     pub fn has_vec_extension(&self) -> bool {
-        self.has_vec_extension
+        self.vec_version.is_some()
+    }
+
+    /// Version of the bundled sqlite-vec, or None when vector search falls
+    /// back to scanning every embedding
+    pub fn sqlite_vec_version(&self) -> Option<&str> {
+        self.vec_version.as_deref()
     }
 
     /// Vector search using embeddings (OpenClaw-compatible columns)
-    /// Uses sqlite-vec if available for fast search, otherwise falls back to in-memory scan
+    /// Uses sqlite-vec when it is compiled in, otherwise an in-memory scan
     pub fn search_vector(
         &self,
         query_embedding: &[f32],
         model: &str,
         limit: usize,
+        filter: &SearchFilter,
     ) -> Result<Vec<MemoryChunk>> {
         let conn = self
             .conn
             .lock()
             .map_err(|e| anyhow!("Lock poisoned: {}", e))?;
 
-        // Try sqlite-vec fast path if available
-        if self.has_vec_extension {
-            if let Ok(results) = self.search_vector_fast(&conn, query_embedding, model, limit) {
-                return Ok(results);
-            }
-            warn!("sqlite-vec search failed, falling back to in-memory scan");
+        let paths = Self::matching_paths(&conn, filter)?;
+        if self.has_vec_extension() && paths.is_none() {
+            self.search_vector_fast(&conn, query_embedding, model, limit)
+        } else {
+            // Builds without sqlite-vec (e.g. `embeddings-none`) scan in
+            // memory. So do filtered searches: the KNN would pick its k
+            // nearest before the filter drops most of them.
+            self.search_vector_scan(&conn, query_embedding, model, limit, paths)
         }
-
-        // Fallback: in-memory scan (slower but always works)
-        self.search_vector_scan(&conn, query_embedding, model, limit)
     }
 
     /// Fast vector search using sqlite-vec extension
@@ -768,28 +1016,34 @@ impl MemoryIndex {
         model: &str,
         limit: usize,
     ) -> Result<Vec<MemoryChunk>> {
+        let table = Self::ensure_vec_table(conn, model, query_embedding.len())?;
         let query_blob = embedding_to_blob(query_embedding);
 
-        // sqlite-vec uses vec_distance_cosine for cosine distance (1 - similarity)
-        let mut stmt = conn.prepare(
+        // KNN over the model's table; its cosine distance is 1 - similarity
+        let mut stmt = conn.prepare(&format!(
             r#"
+            WITH knn AS (
+                SELECT id, distance FROM {}
+                WHERE embedding MATCH ?1 AND k = ?2
+            )
             SELECT c.path, c.start_line, c.end_line, c.text,
-                   1.0 - vec_distance_cosine(v.embedding, ?1) AS score
-            FROM chunks_vec v
-            JOIN chunks c ON c.id = v.id
-            WHERE c.model = ?2
-            ORDER BY score DESC
-            LIMIT ?3
+                   1.0 - knn.distance AS score, c.breadcrumb
+            FROM knn
+            JOIN chunks c ON c.id = knn.id
+            WHERE c.model = ?3
+            ORDER BY knn.distance
             "#,
-        )?;
+            table
+        ))?;
 
-        let rows = stmt.query_map(params![&query_blob, model, limit as i64], |row| {
+        let rows = stmt.query_map(params![&query_blob, limit as i64, model], |row| {
             Ok(MemoryChunk {
                 file: row.get(0)?,
                 line_start: row.get(1)?,
                 line_end: row.get(2)?,
                 content: row.get(3)?,
                 score: row.get(4)?,
+                breadcrumb: row.get(5)?,
             })
         })?;
 
@@ -807,14 +1061,16 @@ impl MemoryIndex {
         query_embedding: &[f32],
         model: &str,
         limit: usize,
+        paths: Option<String>,
     ) -> Result<Vec<MemoryChunk>> {
         let mut stmt = conn.prepare(
-            "SELECT id, path, start_line, end_line, text, embedding
+            "SELECT id, path, start_line, end_line, text, embedding, breadcrumb
              FROM chunks
-             WHERE embedding != '' AND embedding IS NOT NULL AND model = ?1",
+             WHERE embedding != '' AND embedding IS NOT NULL AND model = ?1
+               AND (?2 IS NULL OR path IN (SELECT value FROM json_each(?2)))",
         )?;
 
-        let rows = stmt.query_map(params![model], |row| {
+        let rows = stmt.query_map(params![model, paths], |row| {
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, String>(1)?,
@@ -822,6 +1078,7 @@ impl MemoryIndex {
                 row.get::<_, i32>(3)?,
                 row.get::<_, String>(4)?,
                 row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
             ))
         })?;
 
@@ -829,7 +1086,7 @@ impl MemoryIndex {
         let mut scored: Vec<(f32, MemoryChunk)> = Vec::new();
 
         for row in rows {
-            let (_, path, start_line, end_line, text, embedding_json) = row?;
+            let (_, path, start_line, end_line, text, embedding_json, breadcrumb) = row?;
             let embedding = deserialize_embedding(&embedding_json);
 
             if embedding.len() == query_embedding.len() {
@@ -842,6 +1099,7 @@ impl MemoryIndex {
                         line_end: end_line,
                         content: text,
                         score: similarity as f64,
+                        breadcrumb,
                     },
                 ));
             }
@@ -858,64 +1116,35 @@ impl MemoryIndex {
             .collect())
     }
 
-    /// Hybrid search: combine FTS and vector results
+    /// Hybrid search: fuse FTS and vector results with reciprocal-rank fusion
     pub fn search_hybrid(
         &self,
         query: &str,
         query_embedding: Option<&[f32]>,
         model: &str,
         limit: usize,
-        text_weight: f32,
-        vector_weight: f32,
+        config: &MemorySearchConfig,
+        filter: &SearchFilter,
     ) -> Result<Vec<MemoryChunk>> {
         // Get FTS results
-        let fts_results = self.search(query, limit * 2)?;
+        let fts_results = self.search_filtered(query, limit * 2, filter)?;
 
         // Get vector results if embedding provided
         let vector_results = if let Some(embedding) = query_embedding {
-            self.search_vector(embedding, model, limit * 2)?
+            self.search_vector(embedding, model, limit * 2, filter)?
         } else {
             Vec::new()
         };
 
-        // Merge results using rank-based scoring (OpenClaw-compatible)
-        let mut merged: std::collections::HashMap<String, (f32, MemoryChunk)> =
-            std::collections::HashMap::new();
-
-        // Add FTS results using rank-based scoring (OpenClaw-compatible)
-        // BM25 results are already ordered by relevance (best first)
-        for (rank, result) in fts_results.into_iter().enumerate() {
-            let key = format!("{}:{}:{}", result.file, result.line_start, result.line_end);
-            let rank_score = 1.0 / (1.0 + rank as f32); // rank 0 → 1.0, rank 1 → 0.5, rank 9 → 0.1
-            let weighted_score = rank_score * text_weight;
-            merged.insert(key, (weighted_score, result));
-        }
-
-        // Add/merge vector results using rank-based scoring
-        for (rank, result) in vector_results.into_iter().enumerate() {
-            let key = format!("{}:{}:{}", result.file, result.line_start, result.line_end);
-            let rank_score = 1.0 / (1.0 + rank as f32);
-            let weighted_score = rank_score * vector_weight;
-
-            if let Some((existing_score, existing_chunk)) = merged.get_mut(&key) {
-                *existing_score += weighted_score;
-                existing_chunk.score = *existing_score as f64;
-            } else {
-                let mut chunk = result;
-                chunk.score = weighted_score as f64;
-                merged.insert(key, (weighted_score, chunk));
-            }
-        }
-
-        // Sort by combined score and take top results
-        let mut results: Vec<_> = merged.into_values().collect();
-        results.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
-
-        Ok(results
-            .into_iter()
-            .take(limit)
-            .map(|(_, chunk)| chunk)
-            .collect())
+        let mut fused = reciprocal_rank_fusion(
+            vec![
+                (fts_results, config.text_weight),
+                (vector_results, config.vector_weight),
+            ],
+            config.rrf_k,
+        );
+        fused.truncate(limit);
+        Ok(fused)
     }
 
     /// Count chunks with embeddings (OpenClaw-compatible: model column)
@@ -941,6 +1170,36 @@ fn hash_content(content: &str) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+/// Register the statically linked sqlite-vec with every connection opened
+/// from now on
+#[cfg(feature = "sqlite-vec")]
+#[allow(unsafe_code)]
+fn register_sqlite_vec() {
+    type ExtensionInit = unsafe extern "C" fn(
+        *mut rusqlite::ffi::sqlite3,
+        *mut *mut std::ffi::c_char,
+        *const rusqlite::ffi::sqlite3_api_routines,
+    ) -> std::ffi::c_int;
+
+    static REGISTER: std::sync::Once = std::sync::Once::new();
+    REGISTER.call_once(|| {
+        // SAFETY: sqlite3_vec_init is the extension entry point, which has the
+        // signature sqlite3_auto_extension expects despite its Rust declaration
+        unsafe {
+            let init = std::mem::transmute::<*const (), ExtensionInit>(
+                sqlite_vec::sqlite3_vec_init as *const (),
+            );
+            rusqlite::ffi::sqlite3_auto_extension(Some(init));
+        }
+    });
+}
+
+/// Name of the sqlite-vec table holding `model`'s vectors. Model names can
+/// contain any character, so the name uses a hash of it.
+fn vec_table_name(model: &str, dims: usize) -> String {
+    format!("chunks_vec_{}_{}", &hash_content(model)[..12], dims)
+}
+
 /// Convert f32 embedding to binary blob for sqlite-vec
 fn embedding_to_blob(embedding: &[f32]) -> Vec<u8> {
     let mut blob = Vec::with_capacity(embedding.len() * 4);
@@ -976,67 +1235,245 @@ struct ChunkInfo {
     line_start: i32,
     line_end: i32,
     content: String,
+    /// Headings the chunk sits under, e.g. "tech > rust > async"
+    breadcrumb: String,
 }
 
+/// A run of lines that stays in one chunk: a heading, paragraph, table, list
+/// or fenced code block
+struct Block {
+    start: usize,
+    end: usize,
+    chars: usize,
+    /// Level and title when the block is an ATX heading
+    heading: Option<(usize, String)>,
+}
+
+/// Split markdown into chunks that follow its structure.
+///
+/// Every heading starts a new chunk, and a section longer than the target is
+/// split between blocks instead of inside them, carrying trailing blocks that
+/// fit in the overlap into the next chunk. Only blocks over twice the target
+/// are cut by line count.
 fn chunk_text(text: &str, target_tokens: usize, overlap_tokens: usize) -> Vec<ChunkInfo> {
     let lines: Vec<&str> = text.lines().collect();
     let mut chunks = Vec::new();
 
-    if lines.is_empty() {
-        return chunks;
-    }
-
     // Rough estimate: 4 chars per token
-    let target_chars = target_tokens * 4;
+    let target_chars = (target_tokens * 4).max(1);
     let overlap_chars = overlap_tokens * 4;
 
-    let mut start_line = 0;
-    let mut current_chars = 0;
-    let mut chunk_lines = Vec::new();
+    let mut headings: Vec<(usize, String)> = Vec::new();
+    let mut current: Vec<Block> = Vec::new();
+    let mut has_body = false;
+
+    for block in markdown_blocks(&lines, target_chars) {
+        if let Some((level, title)) = &block.heading {
+            // Consecutive headings share a chunk with the first body below them
+            if has_body {
+                chunks.push(build_chunk(&lines, &current, &headings));
+                current.clear();
+                has_body = false;
+            }
+            headings.retain(|(l, _)| l < level);
+            headings.push((*level, title.clone()));
+            current.push(block);
+            continue;
+        }
 
-    for (i, line) in lines.iter().enumerate() {
-        chunk_lines.push(*line);
-        current_chars += line.len() + 1; // +1 for newline
+        let size: usize = current.iter().map(|b| b.chars).sum();
+        if has_body && size + block.chars > target_chars {
+            chunks.push(build_chunk(&lines, &current, &headings));
 
-        if current_chars >= target_chars || i == lines.len() - 1 {
-            // Create chunk
-            chunks.push(ChunkInfo {
-                line_start: (start_line + 1) as i32,
-                line_end: (i + 1) as i32,
-                content: chunk_lines.join("\n"),
-            });
+            // Keep trailing body blocks as overlap for the next chunk
+            let mut keep = 0;
+            let mut kept_chars = 0;
+            for b in current.iter().rev() {
+                if b.heading.is_some() || kept_chars + b.chars > overlap_chars {
+                    break;
+                }
+                kept_chars += b.chars;
+                keep += 1;
+            }
+            let dropped = current.len() - keep;
+            current.drain(..dropped);
+        }
 
-            // Calculate overlap for next chunk
-            let mut overlap_len = 0;
-            let mut overlap_start = chunk_lines.len();
+        current.push(block);
+        has_body = true;
+    }
+
+    if !current.is_empty() {
+        chunks.push(build_chunk(&lines, &current, &headings));
+    }
+
+    chunks
+}
 
-            for (j, line) in chunk_lines.iter().enumerate().rev() {
-                overlap_len += line.len() + 1;
-                if overlap_len >= overlap_chars {
-                    overlap_start = j;
+fn build_chunk(lines: &[&str], blocks: &[Block], headings: &[(usize, String)]) -> ChunkInfo {
+    let start = blocks[0].start;
+    let end = blocks[blocks.len() - 1].end;
+    let breadcrumb: Vec<&str> = headings
+        .iter()
+        .map(|(_, title)| title.as_str())
+        .filter(|title| !title.is_empty())
+        .collect();
+
+    ChunkInfo {
+        line_start: (start + 1) as i32,
+        line_end: (end + 1) as i32,
+        content: lines[start..=end].join("\n"),
+        breadcrumb: breadcrumb.join(" > "),
+    }
+}
+
+/// Group lines into blocks, skipping blank lines between them. Blocks over
+/// twice `target_chars` are cut into pieces of about `target_chars`.
+fn markdown_blocks(lines: &[&str], target_chars: usize) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if line.trim().is_empty() {
+            i += 1;
+            continue;
+        }
+
+        if let Some(heading) = parse_heading(line) {
+            blocks.push(Block {
+                start: i,
+                end: i,
+                chars: line.len() + 1,
+                heading: Some(heading),
+            });
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        if let Some(fence) = fence_marker(line) {
+            i += 1;
+            while i < lines.len() && !closes_fence(lines[i], fence) {
+                i += 1;
+            }
+            // Include the closing fence (an unclosed fence runs to the end)
+            i = (i + 1).min(lines.len());
+        } else if is_list_item(line) {
+            loop {
+                i += 1;
+                while i < lines.len() && !lines[i].trim().is_empty() && !interrupts_block(lines[i])
+                {
+                    i += 1;
+                }
+                // A blank line ends the list unless another item or an
+                // indented continuation follows it
+                let mut next = i;
+                while next < lines.len() && lines[next].trim().is_empty() {
+                    next += 1;
+                }
+                let continues = next > i
+                    && next < lines.len()
+                    && (is_list_item(lines[next]) || lines[next].starts_with(char::is_whitespace));
+                if !continues {
                     break;
                 }
+                i = next;
             }
+        } else {
+            // Paragraphs and tables run until a blank line
+            i += 1;
+            while i < lines.len() && !lines[i].trim().is_empty() && !interrupts_block(lines[i]) {
+                i += 1;
+            }
+        }
+        let end = i - 1;
+
+        let chars: usize = lines[start..=end].iter().map(|l| l.len() + 1).sum();
+        if chars <= target_chars * 2 {
+            blocks.push(Block {
+                start,
+                end,
+                chars,
+                heading: None,
+            });
+            continue;
+        }
 
-            // Prepare for next chunk
-            if overlap_start < chunk_lines.len() {
-                start_line += overlap_start;
-                chunk_lines = chunk_lines[overlap_start..].to_vec();
-                current_chars = chunk_lines.iter().map(|l| l.len() + 1).sum();
-            } else {
-                start_line = i + 1;
-                chunk_lines.clear();
-                current_chars = 0;
+        let mut piece_start = start;
+        let mut piece_chars = 0;
+        for (j, line) in lines.iter().enumerate().take(end + 1).skip(start) {
+            piece_chars += line.len() + 1;
+            if piece_chars >= target_chars || j == end {
+                blocks.push(Block {
+                    start: piece_start,
+                    end: j,
+                    chars: piece_chars,
+                    heading: None,
+                });
+                piece_start = j + 1;
+                piece_chars = 0;
             }
         }
     }
 
-    chunks
+    blocks
+}
+
+/// Parse an ATX heading ("## Title") into its level and title
+fn parse_heading(line: &str) -> Option<(usize, String)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    Some((
+        level,
+        rest.trim().trim_end_matches('#').trim_end().to_string(),
+    ))
+}
+
+/// Fence character and length of a line opening a fenced code block
+fn fence_marker(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start();
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|&c| c == marker).count();
+    (len >= 3).then_some((marker, len))
+}
+
+fn closes_fence(line: &str, (marker, len): (char, usize)) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() >= len && trimmed.chars().all(|c| c == marker)
+}
+
+fn is_list_item(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let rest = match trimmed.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+            match trimmed[digits..].strip_prefix(['.', ')']) {
+                Some(rest) if digits > 0 => rest,
+                _ => return false,
+            }
+        }
+    };
+    rest.is_empty() || rest.starts_with([' ', '\t'])
+}
+
+/// Whether an unindented line starts a new block even without a blank line
+fn interrupts_block(line: &str) -> bool {
+    !line.starts_with(char::is_whitespace)
+        && (parse_heading(line).is_some() || fence_marker(line).is_some())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::NaiveDate;
     use tempfile::TempDir;
 
     #[test]
@@ -1070,4 +1507,276 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_chunk_text_follows_headings() {
+        let text = "# tech\n\n## rust\n\nOwnership rules.\n\n### async\n\nFutures are lazy.\n\n## go\n\nGoroutines.";
+        let chunks = chunk_text(text, 400, 80);
+
+        let crumbs: Vec<&str> = chunks.iter().map(|c| c.breadcrumb.as_str()).collect();
+        assert_eq!(crumbs, ["tech > rust", "tech > rust > async", "tech > go"]);
+        assert_eq!((chunks[0].line_start, chunks[0].line_end), (1, 5));
+        assert!(chunks[0].content.starts_with("# tech\n\n## rust"));
+        assert_eq!(chunks[1].content, "### async\n\nFutures are lazy.");
+    }
+
+    #[test]
+    fn test_chunk_text_keeps_fences_and_lists_whole() {
+        let text = "Intro paragraph here.\n\n```sh\n# not a heading\n\necho hi\n```\n\n- one\n\n- two\n  more\n\nAfter.";
+        let chunks = chunk_text(text, 5, 0);
+
+        let contents: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            [
+                "Intro paragraph here.",
+                "```sh\n# not a heading\n\necho hi\n```",
+                "- one\n\n- two\n  more",
+                "After.",
+            ]
+        );
+        assert!(chunks.iter().all(|c| c.breadcrumb.is_empty()));
+    }
+
+    #[test]
+    fn test_chunk_text_splits_oversized_blocks() {
+        let text = (1..=40)
+            .map(|i| format!("line number {i}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let chunks = chunk_text(&text, 10, 0);
+
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks[0].line_start, 1);
+        assert_eq!(chunks.last().unwrap().line_end, 40);
+    }
+
+    #[test]
+    fn test_search_matches_breadcrumb() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+        let test_file = workspace.join("tech.md");
+        fs::write(
+            &test_file,
+            "# tech\n\n## rust\n\nBorrowing rules.\n\n## cooking\n\nRust on a pan means it needs oil.",
+        )?;
+
+        let index = MemoryIndex::new(workspace)?;
+        index.index_file(&test_file, false)?;
+
+        let results = index.search("tech borrowing", 10)?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].breadcrumb, "tech > rust");
+
+        // The heading match ranks above the body mention
+        let results = index.search("rust", 10)?;
+        assert_eq!(results[0].breadcrumb, "tech > rust");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_model_switch_reembeds_chunks() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+        let test_file = workspace.join("notes.md");
+        fs::write(&test_file, "# one\n\nFirst.\n\n# two\n\nSecond.")?;
+
+        let index = MemoryIndex::new(workspace)?;
+        index.index_file(&test_file, false)?;
+        for (id, _) in index.chunks_without_embeddings("small", 10)? {
+            index.store_embedding(&id, &[1.0, 0.0], "small")?;
+        }
+        assert!(index.chunks_without_embeddings("small", 10)?.is_empty());
+
+        // Switching models queues every chunk again
+        let pending = index.chunks_without_embeddings("large", 10)?;
+        assert_eq!(pending.len(), 2);
+        index.store_embedding(&pending[0].0, &[0.0, 0.0, 1.0], "large")?;
+
+        let stats = index.embedding_stats()?;
+        let summary: Vec<(&str, usize, usize)> = stats
+            .iter()
+            .map(|s| (s.model.as_str(), s.dims, s.embedded_chunks))
+            .collect();
+        assert_eq!(summary, [("large", 3, 1), ("small", 2, 1)]);
+
+        assert_eq!(
+            index
+                .search_vector(&[0.0, 0.0, 1.0], "large", 10, &SearchFilter::default())?
+                .len(),
+            1
+        );
+        assert_eq!(
+            index
+                .search_vector(&[1.0, 0.0], "small", 10, &SearchFilter::default())?
+                .len(),
+            1
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_filters() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+        fs::create_dir_all(workspace.join("knowledge/finance"))?;
+        fs::create_dir_all(workspace.join("memory"))?;
+        let files = [
+            "knowledge/finance/budget.md",
+            "knowledge/travel.md",
+            "memory/2026-03-01.md",
+            "memory/2026-03-20-budget-review.md",
+        ];
+
+        let index = MemoryIndex::new(workspace)?;
+        for file in files {
+            fs::write(workspace.join(file), "Budget notes for the quarter.")?;
+            index.index_file(&workspace.join(file), false)?;
+        }
+        for (id, _) in index.chunks_without_embeddings("test", 10)? {
+            index.store_embedding(&id, &[1.0, 0.0], "test")?;
+        }
+
+        let search = |filter: SearchFilter| -> Result<Vec<String>> {
+            let mut fts: Vec<String> = index
+                .search_filtered("budget", 10, &filter)?
+                .into_iter()
+                .map(|c| c.file)
+                .collect();
+            let mut vector: Vec<String> = index
+                .search_vector(&[1.0, 0.0], "test", 10, &filter)?
+                .into_iter()
+                .map(|c| c.file)
+                .collect();
+            fts.sort();
+            vector.sort();
+            assert_eq!(fts, vector);
+            Ok(fts)
+        };
+
+        assert_eq!(search(SearchFilter::default())?.len(), 4);
+        assert_eq!(
+            search(SearchFilter {
+                paths: vec!["knowledge/finance/**".to_string()],
+                ..Default::default()
+            })?,
+            ["knowledge/finance/budget.md"]
+        );
+        assert_eq!(
+            search(SearchFilter {
+                sources: vec![MemorySource::Session],
+                ..Default::default()
+            })?,
+            ["memory/2026-03-20-budget-review.md"]
+        );
+        assert_eq!(
+            search(SearchFilter {
+                paths: vec!["memory/".to_string()],
+                before: NaiveDate::from_ymd_opt(2026, 3, 10),
+                ..Default::default()
+            })?,
+            ["memory/2026-03-01.md"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sources_are_backfilled() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+        fs::create_dir_all(workspace.join("memory"))?;
+        let session = workspace.join("memory/2026-03-20-budget-review.md");
+        fs::write(&session, "Budget review.")?;
+
+        MemoryIndex::new(workspace)?.index_file(&session, false)?;
+        {
+            // Indexes from before sources filed everything under 'memory'
+            let conn = Connection::open(workspace.join("memory.sqlite"))?;
+            conn.execute("UPDATE files SET source = 'memory'", [])?;
+            conn.execute("UPDATE chunks SET source = 'memory'", [])?;
+            conn.execute("UPDATE chunks_fts SET source = 'memory'", [])?;
+        }
+
+        let index = MemoryIndex::new(workspace)?;
+        let filter = SearchFilter {
+            sources: vec![MemorySource::Session],
+            ..Default::default()
+        };
+        assert_eq!(index.search_filtered("budget", 10, &filter)?.len(), 1);
+        Ok(())
+    }
+
+    #[cfg(feature = "sqlite-vec")]
+    #[test]
+    fn test_sqlite_vec_is_bundled() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+        let test_file = workspace.join("notes.md");
+        fs::write(&test_file, "# one\n\nFirst.\n\n# two\n\nSecond.")?;
+
+        let index = MemoryIndex::new(workspace)?;
+        assert!(
+            index
+                .sqlite_vec_version()
+                .is_some_and(|v| v.starts_with('v'))
+        );
+
+        index.index_file(&test_file, false)?;
+        for (id, text) in index.chunks_without_embeddings("small", 10)? {
+            let embedding = if text.contains("Second") {
+                [0.0, 1.0]
+            } else {
+                [1.0, 0.0]
+            };
+            index.store_embedding(&id, &embedding, "small")?;
+        }
+
+        // Nearest neighbour of the second chunk's own vector is that chunk
+        let results = index.search_vector(&[0.0, 1.0], "small", 1, &SearchFilter::default())?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content, "# two\n\nSecond.");
+        assert!((results[0].score - 1.0).abs() < 1e-6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_vec_table_name_per_model_and_dims() {
+        let name = vec_table_name("text-embedding-3-small", 1536);
+        assert!(name.starts_with("chunks_vec_") && name.ends_with("_1536"));
+        assert!(name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+        assert_ne!(name, vec_table_name("text-embedding-3-small", 512));
+        assert_ne!(name, vec_table_name("all-MiniLM-L6-v2", 1536));
+    }
+
+    #[test]
+    fn test_old_index_is_rechunked() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+        let db_path = workspace.join("memory.sqlite");
+        let test_file = workspace.join("notes.md");
+        fs::write(&test_file, "# notes\n\nSomething to remember.")?;
+
+        MemoryIndex::new_with_db_path(workspace, &db_path)?.index_file(&test_file, false)?;
+        {
+            // Roll the schema back to before breadcrumbs
+            let conn = Connection::open(&db_path)?;
+            conn.execute_batch(
+                "ALTER TABLE chunks DROP COLUMN breadcrumb;
+                 DROP TABLE chunks_fts;
+                 CREATE VIRTUAL TABLE chunks_fts USING fts5(text, id UNINDEXED, path UNINDEXED, source UNINDEXED, model UNINDEXED, start_line UNINDEXED, end_line UNINDEXED);
+                 INSERT INTO chunks_fts (text, id, path, source, model, start_line, end_line)
+                 SELECT text, id, path, source, model, start_line, end_line FROM chunks;",
+            )?;
+        }
+
+        let index = MemoryIndex::new_with_db_path(workspace, &db_path)?;
+        assert_eq!(index.search("remember", 10)?.len(), 1);
+        assert!(index.index_file(&test_file, false)?);
+        assert_eq!(index.search("remember", 10)?[0].breadcrumb, "notes");
+
+        Ok(())
+    }
 }
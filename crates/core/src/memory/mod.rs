@@ -1,5 +1,6 @@
 mod embeddings;
 mod index;
+mod ranking;
 mod search;
 mod watcher;
 mod workspace;
@@ -9,8 +10,11 @@ pub use embeddings::FastEmbedProvider;
 #[cfg(feature = "gguf")]
 pub use embeddings::LlamaCppProvider;
 pub use embeddings::{EmbeddingProvider, OpenAIEmbeddingProvider, hash_text};
-pub use index::{MemoryIndex, ReindexStats};
-pub use search::MemoryChunk;
+pub use index::{EmbeddingModelStats, MemoryIndex, ReindexStats};
+#[cfg(feature = "embeddings-local")]
+pub use ranking::FastEmbedReranker;
+pub use ranking::Reranker;
+pub use search::{MemoryChunk, MemorySource, SearchFilter, file_date, parse_filter_date};
 pub use watcher::MemoryWatcher;
 pub use workspace::{init_state_dir, init_workspace};
 
@@ -20,11 +24,15 @@ use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::runtime::Handle;
+use tokio::runtime::{Handle, RuntimeFlavor};
 use tracing::{debug, info, warn};
 
 use crate::config::{Config, MemoryConfig};
 
+/// Candidates ranked per requested search result before reranking and
+/// diversity selection narrow them down
+const SEARCH_CANDIDATES_PER_RESULT: usize = 4;
+
 #[derive(Clone)]
 pub struct MemoryManager {
     workspace: PathBuf,
@@ -33,6 +41,8 @@ pub struct MemoryManager {
     config: MemoryConfig,
     /// Optional embedding provider for semantic search
     embedding_provider: Option<Arc<dyn EmbeddingProvider>>,
+    /// Optional cross-encoder that re-scores search candidates
+    reranker: Option<Arc<dyn Reranker>>,
     /// True if this was a brand new workspace (first run)
     is_brand_new: bool,
 }
@@ -44,6 +54,12 @@ pub struct MemoryStats {
     pub total_chunks: usize,
     pub index_size_kb: u64,
     pub files: Vec<FileStats>,
+    /// Model of the configured embedding provider, if any
+    pub embedding_model: Option<String>,
+    /// Embedding coverage per model, including the configured one
+    pub embeddings: Vec<EmbeddingModelStats>,
+    /// sqlite-vec version, or None if vector search scans every embedding
+    pub sqlite_vec_version: Option<String>,
 }
 
 #[derive(Debug)]
@@ -215,16 +231,57 @@ impl MemoryManager {
             }
         };
 
+        let reranker = Self::create_reranker(memory_config);
+
         Ok(Self {
             workspace,
             db_path,
             index,
             config: memory_config.clone(),
             embedding_provider,
+            reranker,
             is_brand_new,
         })
     }
 
+    /// Load the `[memory.search]` reranker, if one is configured
+    fn create_reranker(memory_config: &MemoryConfig) -> Option<Arc<dyn Reranker>> {
+        let model = memory_config.search.reranker.as_str();
+        if model.is_empty() {
+            return None;
+        }
+
+        #[cfg(feature = "embeddings-local")]
+        {
+            let cache_dir = if memory_config.embedding_cache_dir.is_empty() {
+                None
+            } else {
+                Some(memory_config.embedding_cache_dir.as_str())
+            };
+            match FastEmbedReranker::new(model, cache_dir) {
+                Ok(reranker) => {
+                    info!("Using local reranker: {}", reranker.model());
+                    Some(Arc::new(reranker))
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to initialize reranker: {}. Search results will not be reranked.",
+                        e
+                    );
+                    None
+                }
+            }
+        }
+        #[cfg(not(feature = "embeddings-local"))]
+        {
+            warn!(
+                "Reranker '{}' requested but `embeddings-local` feature is disabled. Search results will not be reranked.",
+                model
+            );
+            None
+        }
+    }
+
     /// Set embedding provider for semantic search (requires OpenAI API key)
     pub fn with_embedding_provider(mut self, provider: Arc<dyn EmbeddingProvider>) -> Self {
         self.embedding_provider = Some(provider);
@@ -344,39 +401,94 @@ impl MemoryManager {
         Ok(content)
     }
 
-    /// Search memory using hybrid search (FTS + semantic if available)
+    /// Search memory using hybrid search (FTS + semantic if available),
+    /// ranked as configured under `[memory.search]`
     pub fn search(&self, query: &str, limit: usize) -> Result<Vec<MemoryChunk>> {
-        // If we have an embedding provider, try hybrid search
-        if let Some(ref provider) = self.embedding_provider {
-            // Try to get query embedding (may fail if no API key, rate limited, etc.)
-            if let Ok(handle) = Handle::try_current() {
-                let provider = provider.clone();
-                let query_string = query.to_string();
-                let model = provider.model().to_string();
-
-                // Run embedding in blocking context
-                let embedding_result = std::thread::spawn(move || {
-                    handle.block_on(async { provider.embed(&query_string).await })
-                })
-                .join()
-                .map_err(|_| anyhow::anyhow!("Thread panicked"))?;
-
-                if let Ok(embedding) = embedding_result {
-                    debug!("Using hybrid search with {} dimensions", embedding.len());
-                    return self.index.search_hybrid(
-                        query,
-                        Some(&embedding),
-                        &model,
-                        limit,
-                        0.3, // FTS weight
-                        0.7, // Vector weight
-                    );
-                }
+        self.search_filtered(query, limit, &SearchFilter::default())
+    }
+
+    /// Search memory like [`MemoryManager::search`], only in files that
+    /// pass `filter`
+    pub fn search_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<MemoryChunk>> {
+        filter.validate()?;
+        let search_config = &self.config.search;
+        let query_embedding = self.embed_query(query)?;
+        let model = self
+            .embedding_provider
+            .as_ref()
+            .map(|p| p.model().to_string())
+            .unwrap_or_default();
+
+        // Rank a larger pool so reranking and deduplication have room to work
+        let mut candidates = self.index.search_hybrid(
+            query,
+            query_embedding.as_deref(),
+            &model,
+            limit * SEARCH_CANDIDATES_PER_RESULT,
+            search_config,
+            filter,
+        )?;
+
+        if let Some(reranker) = &self.reranker
+            && !candidates.is_empty()
+            && let Err(e) = Self::rerank_candidates(reranker.as_ref(), query, &mut candidates)
+        {
+            warn!("Reranking failed, keeping fused ranking: {}", e);
+        }
+
+        ranking::apply_recency_decay(
+            &mut candidates,
+            search_config.recency_half_life_days,
+            Local::now().date_naive(),
+        );
+
+        Ok(ranking::maximal_marginal_relevance(
+            candidates,
+            search_config.mmr_lambda,
+            limit,
+        ))
+    }
+
+    /// Rerank candidates with the cross-encoder. Inference is CPU-bound, so on
+    /// a multi-threaded runtime the worker's other tasks are handed off first.
+    fn rerank_candidates(
+        reranker: &dyn Reranker,
+        query: &str,
+        candidates: &mut [MemoryChunk],
+    ) -> Result<()> {
+        let mut rerank = || ranking::rerank(reranker, query, candidates);
+        match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(rerank)
             }
+            _ => rerank(),
         }
+    }
 
-        // Fallback to FTS-only search
-        self.index.search(query, limit)
+    /// Embed a search query, or None without a provider or if embedding
+    /// fails (no API key, rate limited, etc.)
+    fn embed_query(&self, query: &str) -> Result<Option<Vec<f32>>> {
+        let (Some(provider), Ok(handle)) = (&self.embedding_provider, Handle::try_current()) else {
+            return Ok(None);
+        };
+        let provider = provider.clone();
+        let query_string = query.to_string();
+
+        // Run embedding in blocking context
+        let embedding_result = std::thread::spawn(move || {
+            handle.block_on(async { provider.embed(&query_string).await })
+        })
+        .join()
+        .map_err(|_| anyhow::anyhow!("Thread panicked"))?;
+
+        Ok(embedding_result
+            .inspect(|embedding| debug!("Using hybrid search with {} dimensions", embedding.len()))
+            .ok())
     }
 
     /// Search memory using FTS only (faster, no API calls)
@@ -559,12 +671,30 @@ impl MemoryManager {
 
         let index_size = self.index.size_bytes()? / 1024;
 
+        let embedding_model = self
+            .embedding_provider
+            .as_ref()
+            .map(|p| p.model().to_string());
+        let mut embeddings = self.index.embedding_stats()?;
+        if let Some(provider) = &self.embedding_provider
+            && !embeddings.iter().any(|e| e.model == provider.model())
+        {
+            embeddings.push(EmbeddingModelStats {
+                model: provider.model().to_string(),
+                dims: provider.dimensions(),
+                embedded_chunks: 0,
+            });
+        }
+
         Ok(MemoryStats {
             workspace: self.workspace.display().to_string(),
             total_files: files.len(),
             total_chunks,
             index_size_kb: index_size,
             files,
+            embedding_model,
+            embeddings,
+            sqlite_vec_version: self.index.sqlite_vec_version().map(str::to_string),
         })
     }
 
@@ -639,8 +769,9 @@ impl MemoryManager {
         let mut cache_hits = 0;
 
         loop {
-            // Get chunks without embeddings
-            let chunks = self.index.chunks_without_embeddings(batch_size)?;
+            // Get chunks without embeddings from this model (re-embeds chunks
+            // left over from a previously configured model)
+            let chunks = self.index.chunks_without_embeddings(&model, batch_size)?;
             if chunks.is_empty() {
                 break;
             }
@@ -743,3 +874,43 @@ impl MemoryManager {
         self.index.embedded_chunk_count(&model)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct LengthReranker;
+
+    impl Reranker for LengthReranker {
+        fn model(&self) -> &str {
+            "length"
+        }
+
+        fn rerank(&self, _query: &str, documents: &[String]) -> Result<Vec<f32>> {
+            Ok(documents.iter().map(|d| d.len() as f32).collect())
+        }
+    }
+
+    fn candidates() -> Vec<MemoryChunk> {
+        vec![
+            MemoryChunk::new("a.md".to_string(), 1, 1, "a".to_string(), 0.9),
+            MemoryChunk::new("b.md".to_string(), 1, 1, "bbb".to_string(), 0.1),
+        ]
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rerank_candidates_on_multi_thread_runtime() -> Result<()> {
+        let mut chunks = candidates();
+        MemoryManager::rerank_candidates(&LengthReranker, "query", &mut chunks)?;
+        assert_eq!(chunks[0].file, "b.md");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rerank_candidates_on_current_thread_runtime() -> Result<()> {
+        let mut chunks = candidates();
+        MemoryManager::rerank_candidates(&LengthReranker, "query", &mut chunks)?;
+        assert_eq!(chunks[0].file, "b.md");
+        Ok(())
+    }
+}
@@ -0,0 +1,304 @@
+//! Ranking of memory search results
+//!
+//! Hybrid search fuses the keyword and vector result lists with reciprocal-rank
+//! fusion, optionally re-scores the fused candidates with a local cross-encoder,
+//! decays old daily logs and finally picks a diverse top-N with maximal marginal
+//! relevance. Everything is tuned under `[memory.search]`.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::collections::{HashMap, HashSet};
+
+use super::search::{MemoryChunk, memory_file_date};
+
+// ============================================================================
+// Reranking
+// ============================================================================
+
+/// Cross-encoder that scores how well each document answers a query
+pub trait Reranker: Send + Sync {
+    /// Model name
+    fn model(&self) -> &str;
+
+    /// Relevance score per document, in input order (higher is better)
+    fn rerank(&self, query: &str, documents: &[String]) -> Result<Vec<f32>>;
+}
+
+/// Re-score `chunks` with `reranker`, mapping its logits to 0..1, and sort
+pub fn rerank(reranker: &dyn Reranker, query: &str, chunks: &mut [MemoryChunk]) -> Result<()> {
+    let documents: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+    let scores = reranker.rerank(query, &documents)?;
+    for (chunk, score) in chunks.iter_mut().zip(scores) {
+        chunk.score = 1.0 / (1.0 + (-score as f64).exp());
+    }
+    sort_by_score(chunks);
+    Ok(())
+}
+
+#[cfg(feature = "embeddings-local")]
+pub struct FastEmbedReranker {
+    model: std::sync::Mutex<fastembed::TextRerank>,
+    model_name: String,
+}
+
+#[cfg(feature = "embeddings-local")]
+impl FastEmbedReranker {
+    pub fn new(model_name: &str, cache_dir: Option<&str>) -> Result<Self> {
+        use fastembed::{RerankInitOptions, RerankerModel, TextRerank};
+
+        let model = match model_name {
+            "bge-reranker-base" => RerankerModel::BGERerankerBase,
+            "bge-reranker-v2-m3" => RerankerModel::BGERerankerV2M3,
+            "jina-reranker-v1-turbo-en" => RerankerModel::JINARerankerV1TurboEn,
+            "jina-reranker-v2-base-multilingual" => RerankerModel::JINARerankerV2BaseMultiligual,
+            other => anyhow::bail!(
+                "Unknown reranker model: '{}'. Supported models: bge-reranker-base, \
+                 bge-reranker-v2-m3, jina-reranker-v1-turbo-en, jina-reranker-v2-base-multilingual",
+                other
+            ),
+        };
+
+        let mut options = RerankInitOptions::new(model);
+        if let Some(dir) = cache_dir {
+            options = options.with_cache_dir(shellexpand::tilde(dir).to_string().into());
+        }
+
+        Ok(Self {
+            model: std::sync::Mutex::new(TextRerank::try_new(options)?),
+            model_name: model_name.to_string(),
+        })
+    }
+}
+
+#[cfg(feature = "embeddings-local")]
+impl Reranker for FastEmbedReranker {
+    fn model(&self) -> &str {
+        &self.model_name
+    }
+
+    fn rerank(&self, query: &str, documents: &[String]) -> Result<Vec<f32>> {
+        let mut model = self
+            .model
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock poisoned: {}", e))?;
+        let documents: Vec<&str> = documents.iter().map(String::as_str).collect();
+        let results = model.rerank(query, documents, false, None)?;
+
+        // Results come back sorted by score; put them back in input order
+        let mut scores = vec![0.0; results.len()];
+        for result in results {
+            scores[result.index] = result.score;
+        }
+        Ok(scores)
+    }
+}
+
+// ============================================================================
+// Fusion, recency and diversity
+// ============================================================================
+
+/// Merge ranked result lists with reciprocal-rank fusion: a chunk scores
+/// `weight / (k + rank)` (rank starting at 1) summed over every list it is in
+pub fn reciprocal_rank_fusion(lists: Vec<(Vec<MemoryChunk>, f32)>, k: f32) -> Vec<MemoryChunk> {
+    let mut merged: HashMap<String, MemoryChunk> = HashMap::new();
+
+    for (results, weight) in lists {
+        for (rank, mut chunk) in results.into_iter().enumerate() {
+            let score = (weight / (k + rank as f32 + 1.0)) as f64;
+            let key = format!("{}:{}:{}", chunk.file, chunk.line_start, chunk.line_end);
+            merged
+                .entry(key)
+                .and_modify(|existing| existing.score += score)
+                .or_insert_with(|| {
+                    chunk.score = score;
+                    chunk
+                });
+        }
+    }
+
+    let mut fused: Vec<MemoryChunk> = merged.into_values().collect();
+    sort_by_score(&mut fused);
+    fused
+}
+
+/// Halve the score of daily logs (`memory/YYYY-MM-DD.md`) every
+/// `half_life_days` of age, then re-sort. Other files are left alone.
+pub fn apply_recency_decay(chunks: &mut [MemoryChunk], half_life_days: f32, today: NaiveDate) {
+    if half_life_days <= 0.0 {
+        return;
+    }
+
+    for chunk in chunks.iter_mut() {
+        if let Some(date) = daily_log_date(&chunk.file) {
+            let age_days = (today - date).num_days().max(0) as f64;
+            chunk.score *= 0.5f64.powf(age_days / half_life_days as f64);
+        }
+    }
+    sort_by_score(chunks);
+}
+
+/// Date of a daily log path such as `memory/2026-01-31.md`
+fn daily_log_date(path: &str) -> Option<NaiveDate> {
+    memory_file_date(path)
+        .filter(|(_, rest)| rest.is_empty())
+        .map(|(date, _)| date)
+}
+
+/// Pick `limit` chunks from relevance-sorted `chunks`, each maximizing
+/// `lambda * relevance - (1 - lambda) * similarity to those already picked`.
+/// Similarity is the word overlap (Jaccard) of the chunk texts, so a lower
+/// lambda pushes out near-duplicates such as repeated daily log entries.
+pub fn maximal_marginal_relevance(
+    chunks: Vec<MemoryChunk>,
+    lambda: f32,
+    limit: usize,
+) -> Vec<MemoryChunk> {
+    if lambda >= 1.0 || chunks.len() <= 1 {
+        return chunks.into_iter().take(limit).collect();
+    }
+
+    let lambda = lambda.max(0.0) as f64;
+    let max_score = chunks
+        .iter()
+        .map(|c| c.score)
+        .fold(f64::MIN, f64::max)
+        .max(f64::EPSILON);
+    let words: Vec<HashSet<String>> = chunks.iter().map(|c| word_set(&c.content)).collect();
+
+    let mut remaining: Vec<usize> = (0..chunks.len()).collect();
+    let mut picked: Vec<usize> = Vec::new();
+
+    while picked.len() < limit && !remaining.is_empty() {
+        let mut best = 0;
+        let mut best_value = f64::MIN;
+        for (i, &candidate) in remaining.iter().enumerate() {
+            let redundancy = picked
+                .iter()
+                .map(|&p| jaccard(&words[candidate], &words[p]))
+                .fold(0.0, f64::max);
+            let value = lambda * chunks[candidate].score / max_score - (1.0 - lambda) * redundancy;
+            if value > best_value {
+                best = i;
+                best_value = value;
+            }
+        }
+        picked.push(remaining.remove(best));
+    }
+
+    let mut chunks: Vec<Option<MemoryChunk>> = chunks.into_iter().map(Some).collect();
+    picked
+        .into_iter()
+        .filter_map(|i| chunks[i].take())
+        .collect()
+}
+
+fn word_set(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+fn sort_by_score(chunks: &mut [MemoryChunk]) {
+    chunks.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(file: &str, line: i32, content: &str, score: f64) -> MemoryChunk {
+        MemoryChunk::new(file.to_string(), line, line, content.to_string(), score)
+    }
+
+    #[test]
+    fn test_rrf_rewards_agreement() {
+        let fts = vec![chunk("a.md", 1, "a", 9.0), chunk("b.md", 1, "b", 5.0)];
+        let vector = vec![chunk("b.md", 1, "b", 0.9), chunk("c.md", 1, "c", 0.8)];
+
+        let fused = reciprocal_rank_fusion(vec![(fts, 1.0), (vector, 1.0)], 60.0);
+        let files: Vec<&str> = fused.iter().map(|c| c.file.as_str()).collect();
+        assert_eq!(files, ["b.md", "a.md", "c.md"]);
+        assert!((fused[0].score - (1.0 / 62.0 + 1.0 / 61.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_recency_decay_only_touches_daily_logs() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 31).unwrap();
+        let mut chunks = vec![
+            chunk("memory/2026-03-01.md", 1, "old", 1.0),
+            chunk("MEMORY.md", 1, "facts", 0.8),
+            chunk("memory/2026-03-31.md", 1, "new", 0.7),
+        ];
+
+        apply_recency_decay(&mut chunks, 30.0, today);
+        let files: Vec<&str> = chunks.iter().map(|c| c.file.as_str()).collect();
+        assert_eq!(
+            files,
+            ["MEMORY.md", "memory/2026-03-31.md", "memory/2026-03-01.md"]
+        );
+        assert!((chunks[2].score - 0.5).abs() < 1e-6);
+        assert_eq!(daily_log_date("knowledge/2026-03-01.md"), None);
+    }
+
+    #[test]
+    fn test_mmr_skips_near_duplicates() {
+        let chunks = vec![
+            chunk(
+                "memory/2026-03-30.md",
+                1,
+                "deployed the api server today",
+                1.0,
+            ),
+            chunk(
+                "memory/2026-03-31.md",
+                1,
+                "deployed the api server today",
+                0.95,
+            ),
+            chunk("MEMORY.md", 1, "api keys live in the vault", 0.6),
+        ];
+
+        let picked = maximal_marginal_relevance(chunks.clone(), 0.5, 2);
+        let files: Vec<&str> = picked.iter().map(|c| c.file.as_str()).collect();
+        assert_eq!(files, ["memory/2026-03-30.md", "MEMORY.md"]);
+
+        // lambda 1.0 keeps the relevance order
+        let picked = maximal_marginal_relevance(chunks, 1.0, 2);
+        assert_eq!(picked[1].file, "memory/2026-03-31.md");
+    }
+
+    struct ReverseReranker;
+
+    impl Reranker for ReverseReranker {
+        fn model(&self) -> &str {
+            "reverse"
+        }
+
+        fn rerank(&self, _query: &str, documents: &[String]) -> Result<Vec<f32>> {
+            Ok((0..documents.len()).map(|i| i as f32).collect())
+        }
+    }
+
+    #[test]
+    fn test_rerank_replaces_scores() -> Result<()> {
+        let mut chunks = vec![chunk("a.md", 1, "a", 0.9), chunk("b.md", 1, "b", 0.1)];
+        rerank(&ReverseReranker, "query", &mut chunks)?;
+
+        assert_eq!(chunks[0].file, "b.md");
+        assert!(chunks.iter().all(|c| c.score > 0.0 && c.score < 1.0));
+        Ok(())
+    }
+}
@@ -1,6 +1,11 @@
 //! Memory search types and utilities
 
+use anyhow::{Result, bail};
+use chrono::{DateTime, Local, NaiveDate};
+use glob::Pattern;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
 /// A chunk of memory content returned from search
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +24,11 @@ pub struct MemoryChunk {
 
     /// Relevance score (higher is better)
     pub score: f64,
+
+    /// Headings the chunk sits under, e.g. "tech > rust > async" (empty
+    /// before the first heading)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub breadcrumb: String,
 }
 
 impl MemoryChunk {
@@ -30,9 +40,16 @@ impl MemoryChunk {
             line_end,
             content,
             score,
+            breadcrumb: String::new(),
         }
     }
 
+    /// Set the heading breadcrumb (builder pattern)
+    pub fn with_breadcrumb(mut self, breadcrumb: impl Into<String>) -> Self {
+        self.breadcrumb = breadcrumb.into();
+        self
+    }
+
     /// Get a preview of the content (first N characters)
     pub fn preview(&self, max_len: usize) -> String {
         if self.content.len() <= max_len {
@@ -55,6 +72,165 @@ impl MemoryChunk {
     }
 }
 
+/// Where an indexed file comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemorySource {
+    /// Curated workspace files (MEMORY.md, daily logs, knowledge/...)
+    Memory,
+    /// Transcripts saved on `/new` as `memory/YYYY-MM-DD-slug.md`
+    Session,
+    /// Files under `[memory] paths` outside the workspace
+    External,
+}
+
+impl MemorySource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MemorySource::Memory => "memory",
+            MemorySource::Session => "session",
+            MemorySource::External => "external",
+        }
+    }
+
+    /// Source of an indexed path (relative to the workspace, or absolute
+    /// for files outside it)
+    pub fn for_path(path: &str) -> Self {
+        if std::path::Path::new(path).is_absolute() {
+            return MemorySource::External;
+        }
+        match memory_file_date(path) {
+            Some((_, rest)) if !rest.is_empty() => MemorySource::Session,
+            _ => MemorySource::Memory,
+        }
+    }
+}
+
+impl fmt::Display for MemorySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MemorySource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "memory" => Ok(MemorySource::Memory),
+            "session" => Ok(MemorySource::Session),
+            "external" => Ok(MemorySource::External),
+            other => bail!(
+                "Unknown memory source: '{}'. Expected memory, session or external",
+                other
+            ),
+        }
+    }
+}
+
+/// Narrows a memory search to some files. Empty fields don't filter.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchFilter {
+    /// Path prefixes (`knowledge/finance`) or globs (`knowledge/finance/**`),
+    /// relative to the workspace
+    #[serde(default)]
+    pub paths: Vec<String>,
+
+    /// Only files dated on or after this day
+    #[serde(default)]
+    pub after: Option<NaiveDate>,
+
+    /// Only files dated on or before this day
+    #[serde(default)]
+    pub before: Option<NaiveDate>,
+
+    /// Only files from these sources
+    #[serde(default)]
+    pub sources: Vec<MemorySource>,
+}
+
+impl SearchFilter {
+    /// True if the filter lets every file through
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+            && self.after.is_none()
+            && self.before.is_none()
+            && self.sources.is_empty()
+    }
+
+    /// Check that every path glob compiles and the date range is not inverted
+    pub fn validate(&self) -> Result<()> {
+        for path in &self.paths {
+            if is_glob(path)
+                && let Err(e) = Pattern::new(path)
+            {
+                bail!("Invalid path pattern '{}': {}", path, e);
+            }
+        }
+        if let (Some(after), Some(before)) = (self.after, self.before)
+            && after > before
+        {
+            bail!("Date range is empty: {} is after {}", after, before);
+        }
+        Ok(())
+    }
+
+    /// Whether an indexed file passes the filter. `date` is the file's date
+    /// as given by [`file_date`].
+    pub fn matches(&self, path: &str, source: MemorySource, date: NaiveDate) -> bool {
+        (self.paths.is_empty() || self.paths.iter().any(|p| path_matches(p, path)))
+            && (self.sources.is_empty() || self.sources.contains(&source))
+            && self.after.is_none_or(|after| date >= after)
+            && self.before.is_none_or(|before| date <= before)
+    }
+}
+
+/// Parse a `YYYY-MM-DD` date given to a search filter
+pub fn parse_filter_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("Invalid date '{}', expected YYYY-MM-DD", value))
+}
+
+/// Date a file is filed under: the day in its name for daily logs and saved
+/// sessions (`memory/2026-01-31.md`, `memory/2026-01-31-slug.md`), otherwise
+/// the local day it was last modified (`mtime` in Unix seconds)
+pub fn file_date(path: &str, mtime: i64) -> NaiveDate {
+    memory_file_date(path)
+        .map(|(date, _)| date)
+        .or_else(|| {
+            DateTime::from_timestamp(mtime, 0).map(|t| t.with_timezone(&Local).date_naive())
+        })
+        .unwrap_or_default()
+}
+
+/// Split a dated file in a memory directory into its date and the rest of
+/// its name (empty for daily logs, `-slug` for saved sessions)
+pub(super) fn memory_file_date(path: &str) -> Option<(NaiveDate, &str)> {
+    let (dir, name) = path.rsplit_once('/')?;
+    if dir != "memory" && !dir.ends_with("/memory") {
+        return None;
+    }
+    let stem = name.strip_suffix(".md")?;
+    let date = NaiveDate::parse_from_str(stem.get(..10)?, "%Y-%m-%d").ok()?;
+    let rest = &stem[10..];
+    (rest.is_empty() || rest.starts_with('-')).then_some((date, rest))
+}
+
+fn is_glob(spec: &str) -> bool {
+    spec.contains(['*', '?', '['])
+}
+
+/// Match a path against a glob, or a plain prefix such as `knowledge/` or
+/// `memory/2026-03`
+fn path_matches(spec: &str, path: &str) -> bool {
+    let spec = spec.trim().trim_start_matches("./");
+    if is_glob(spec) {
+        Pattern::new(spec).is_ok_and(|pattern| pattern.matches(path))
+    } else {
+        path.starts_with(spec)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,4 +296,74 @@ mod tests {
         assert!(preview.ends_with("..."));
         assert_eq!(preview, "one...");
     }
+
+    #[test]
+    fn test_source_for_path() {
+        assert_eq!(MemorySource::for_path("MEMORY.md"), MemorySource::Memory);
+        assert_eq!(
+            MemorySource::for_path("memory/2026-03-01.md"),
+            MemorySource::Memory
+        );
+        assert_eq!(
+            MemorySource::for_path("memory/2026-03-01-deploy-plan.md"),
+            MemorySource::Session
+        );
+        assert_eq!(
+            MemorySource::for_path("/home/me/notes/todo.md"),
+            MemorySource::External
+        );
+        assert_eq!(
+            "Session".parse::<MemorySource>().unwrap(),
+            MemorySource::Session
+        );
+        assert!("web".parse::<MemorySource>().is_err());
+    }
+
+    #[test]
+    fn test_search_filter_matches() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 15).unwrap();
+        let filter = SearchFilter {
+            paths: vec![
+                "knowledge/finance/**".to_string(),
+                "memory/2026-03".to_string(),
+            ],
+            ..Default::default()
+        };
+        assert!(filter.matches("knowledge/finance/tax/2025.md", MemorySource::Memory, date));
+        assert!(filter.matches("memory/2026-03-15.md", MemorySource::Memory, date));
+        assert!(!filter.matches("knowledge/health.md", MemorySource::Memory, date));
+
+        let filter = SearchFilter {
+            after: NaiveDate::from_ymd_opt(2026, 3, 1),
+            before: NaiveDate::from_ymd_opt(2026, 3, 31),
+            sources: vec![MemorySource::Session],
+            ..Default::default()
+        };
+        assert!(filter.matches("memory/2026-03-15-plan.md", MemorySource::Session, date));
+        assert!(!filter.matches("MEMORY.md", MemorySource::Memory, date));
+        let april = NaiveDate::from_ymd_opt(2026, 4, 1).unwrap();
+        assert!(!filter.matches("memory/2026-04-01-plan.md", MemorySource::Session, april));
+
+        assert!(SearchFilter::default().is_empty());
+        assert!(
+            SearchFilter {
+                paths: vec!["knowledge/[".to_string()],
+                ..Default::default()
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_file_date_prefers_name() {
+        assert_eq!(
+            file_date("memory/2026-01-31-slug.md", 0),
+            NaiveDate::from_ymd_opt(2026, 1, 31).unwrap()
+        );
+        // Noon UTC, the same day in every timezone within 12 hours
+        let march_15 = NaiveDate::from_ymd_opt(2026, 3, 15).unwrap();
+        assert_eq!(file_date("MEMORY.md", 1773576000), march_15);
+        assert_eq!(file_date("memory/2026-01-31x.md", 1773576000), march_15);
+    }
 }
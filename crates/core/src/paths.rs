@@ -137,6 +137,16 @@ impl Paths {
         self.state_dir.join("last_heartbeat")
     }
 
+    /// Provider circuit-breaker state: state_dir/provider_health.json
+    pub fn provider_health(&self) -> PathBuf {
+        self.state_dir.join("provider_health.json")
+    }
+
+    /// Usage ledger: state_dir/usage/YYYY-MM.jsonl
+    pub fn usage_dir(&self) -> PathBuf {
+        self.state_dir.join("usage")
+    }
+
     /// Search index for a specific agent: cache_dir/memory/{agent_id}.sqlite
     pub fn search_index(&self, agent_id: &str) -> PathBuf {
         self.cache_dir
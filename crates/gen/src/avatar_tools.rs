@@ -80,6 +80,11 @@ impl Tool for MoveAvatarTool {
         "move_avatar"
     }
 
+    // Avatar moves are relative to where the previous call left it
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "move_avatar".to_string(),
@@ -153,6 +158,10 @@ impl Tool for LookAvatarTool {
         "look_avatar"
     }
 
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "look_avatar".to_string(),
@@ -214,6 +223,10 @@ impl Tool for TeleportAvatarTool {
         "teleport_avatar"
     }
 
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "teleport_avatar".to_string(),
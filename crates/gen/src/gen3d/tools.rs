@@ -222,6 +222,11 @@ impl Tool for GenSpawnPrimitiveTool {
         "gen_spawn_primitive"
     }
 
+    // Scene edits in one turn may build on each other, so they run in call order
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "gen_spawn_primitive".into(),
@@ -352,6 +357,10 @@ impl Tool for GenModifyEntityTool {
         "gen_modify_entity"
     }
 
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "gen_modify_entity".into(),
@@ -450,6 +459,10 @@ impl Tool for GenDeleteEntityTool {
         "gen_delete_entity"
     }
 
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "gen_delete_entity".into(),
@@ -502,6 +515,10 @@ impl Tool for GenSetCameraTool {
         "gen_set_camera"
     }
 
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "gen_set_camera".into(),
@@ -568,6 +585,10 @@ impl Tool for GenSetLightTool {
         "gen_set_light"
     }
 
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "gen_set_light".into(),
@@ -661,6 +682,10 @@ impl Tool for GenSetEnvironmentTool {
         "gen_set_environment"
     }
 
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "gen_set_environment".into(),
@@ -724,6 +749,10 @@ impl Tool for GenSpawnMeshTool {
         "gen_spawn_mesh"
     }
 
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "gen_spawn_mesh".into(),
@@ -844,6 +873,10 @@ impl Tool for GenLoadGltfTool {
         "gen_load_gltf"
     }
 
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "gen_load_gltf".into(),
@@ -1021,6 +1054,10 @@ impl Tool for GenSetAmbienceTool {
         "gen_set_ambience"
     }
 
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "gen_set_ambience".into(),
@@ -1114,6 +1151,10 @@ impl Tool for GenAudioEmitterTool {
         "gen_audio_emitter"
     }
 
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "gen_audio_emitter".into(),
@@ -1208,6 +1249,10 @@ impl Tool for GenModifyAudioTool {
         "gen_modify_audio"
     }
 
+    fn parallel_safe(&self) -> bool {
+        false
+    }
+
     fn schema(&self) -> ToolSchema {
         ToolSchema {
             name: "gen_modify_audio".into(),
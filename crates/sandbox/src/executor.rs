@@ -24,21 +24,16 @@ pub async fn run_sandboxed(
     // argv[0] = "localgpt-sandbox" (sentinel for dispatch)
     // argv[1] = policy JSON
     // argv[2] = shell command to execute
-    let timeout_duration = Duration::from_millis(timeout_ms);
-
-    let output = tokio::time::timeout(
-        timeout_duration,
-        tokio::process::Command::new(&exe_path)
-            .arg0("localgpt-sandbox")
-            .arg(&policy_json)
-            .arg(command)
-            .current_dir(&policy.workspace_path)
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .output(),
-    )
-    .await
-    .map_err(|_| anyhow::anyhow!("Sandboxed command timed out after {}ms", timeout_ms))??;
+    let mut child = tokio::process::Command::new(&exe_path);
+    child
+        .arg0("localgpt-sandbox")
+        .arg(&policy_json)
+        .arg(command)
+        .current_dir(&policy.workspace_path);
+
+    let output = output_with_timeout(child, Duration::from_millis(timeout_ms))
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Sandboxed command timed out after {}ms", timeout_ms))?;
 
     let stdout = String::from_utf8_lossy(&output.stdout);
     let stderr = String::from_utf8_lossy(&output.stderr);
@@ -77,6 +72,54 @@ pub async fn run_sandboxed(
     Ok((result, exit_code))
 }
 
+/// Run `command` in its own process group and collect its output.
+///
+/// Returns `None` if `timeout` expires first. When the command times out or
+/// the future is dropped before it exits (e.g. the agent turn is cancelled),
+/// the whole process group is killed, so children of the shell die with it.
+pub async fn output_with_timeout(
+    mut command: tokio::process::Command,
+    timeout: Duration,
+) -> std::io::Result<Option<std::process::Output>> {
+    command
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    command.process_group(0);
+
+    let child = command.spawn()?;
+    let group = ProcessGroup(child.id());
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => {
+            // Exited normally: leave anything it deliberately backgrounded alone
+            group.disarm();
+            output.map(Some)
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Kills a process group when dropped, unless disarmed
+struct ProcessGroup(Option<u32>);
+
+impl ProcessGroup {
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.0 {
+            use nix::sys::signal::{Signal, killpg};
+            let _ = killpg(nix::unistd::Pid::from_raw(pid as i32), Signal::SIGKILL);
+        }
+    }
+}
+
 /// Trait extension for Command to set argv[0].
 #[allow(dead_code)]
 trait CommandExt {
@@ -99,3 +142,38 @@ impl CommandExt for tokio::process::Command {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn timeout_kills_background_children() {
+        let dir = std::env::temp_dir().join(format!("localgpt-pgid-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pid_file = dir.join("pid");
+
+        let mut command = tokio::process::Command::new("bash");
+        command
+            .arg("-c")
+            .arg(format!("sleep 30 & echo $! > {}; wait", pid_file.display()));
+        let output = output_with_timeout(command, Duration::from_millis(500))
+            .await
+            .unwrap();
+        assert!(output.is_none());
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        // Gone, or killed and waiting to be reaped
+        if let Ok(stat) = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim())) {
+            assert!(
+                stat.contains(") Z "),
+                "background child still running: {}",
+                stat
+            );
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -32,11 +32,15 @@ use tower_http::cors::{Any, CorsLayer};
 use tower_http::limit::RequestBodyLimitLayer;
 use tracing::{debug, info};
 
-use localgpt_core::agent::{Agent, AgentConfig, StreamEvent, extract_tool_detail};
+use localgpt_core::agent::failover::{ProviderHealth, ProviderHealthRegistry};
+use localgpt_core::agent::{
+    Agent, AgentConfig, ApprovalBroker, ApprovalDecision, ApprovalEvent, ApprovalRequest,
+    Cancelled, ModelCatalog, StreamEvent, TurnCanceller, extract_tool_detail,
+};
 use localgpt_core::concurrency::{TurnGate, WorkspaceLock};
-use localgpt_core::config::Config;
+use localgpt_core::config::{Config, ReasoningEffort};
 use localgpt_core::heartbeat::{HeartbeatStatus, get_last_heartbeat_event};
-use localgpt_core::memory::MemoryManager;
+use localgpt_core::memory::{MemoryManager, SearchFilter, parse_filter_date};
 
 /// Embedded UI assets
 #[derive(RustEmbed)]
@@ -50,12 +54,18 @@ const SESSION_TIMEOUT: Duration = Duration::from_secs(30 * 60);
 const MAX_SESSIONS: usize = 100;
 
 /// Agent ID for HTTP sessions
-const HTTP_AGENT_ID: &str = "http";
+pub(crate) const HTTP_AGENT_ID: &str = "http";
+
+/// Approval origin for an HTTP session
+fn http_origin(session_id: &str) -> String {
+    format!("http:{}", session_id)
+}
 
 pub struct Server {
     config: Config,
     turn_gate: TurnGate,
     bridge_manager: crate::security::BridgeManager,
+    approvals: ApprovalBroker,
 }
 
 pub(crate) struct SessionEntry {
@@ -78,6 +88,42 @@ pub(crate) struct AppState {
     rate_limiter: Arc<crate::rate_limiter::RateLimiter>,
     /// Bridge manager for tracking active connections
     pub(crate) bridge_manager: crate::security::BridgeManager,
+    /// Tool approvals waiting on a decision from any channel
+    pub(crate) approvals: ApprovalBroker,
+    /// Sessions with a turn in progress. Kept apart from `sessions`, which
+    /// the turn holds until it ends.
+    running: std::sync::Mutex<HashMap<String, TurnCanceller>>,
+}
+
+/// Lets `POST /api/sessions/{id}/cancel` stop a session's turn until dropped
+struct RunningTurn {
+    state: Arc<AppState>,
+    session_id: String,
+}
+
+impl RunningTurn {
+    fn start(state: &Arc<AppState>, session_id: &str, canceller: TurnCanceller) -> Self {
+        canceller.prepare_turn();
+        state
+            .running
+            .lock()
+            .expect("running turns lock poisoned")
+            .insert(session_id.to_string(), canceller);
+        Self {
+            state: state.clone(),
+            session_id: session_id.to_string(),
+        }
+    }
+}
+
+impl Drop for RunningTurn {
+    fn drop(&mut self) {
+        self.state
+            .running
+            .lock()
+            .expect("running turns lock poisoned")
+            .remove(&self.session_id);
+    }
 }
 
 impl Server {
@@ -86,6 +132,7 @@ impl Server {
             config: config.clone(),
             turn_gate: TurnGate::new(),
             bridge_manager: crate::security::BridgeManager::new(),
+            approvals: ApprovalBroker::from_config(&config.tools),
         })
     }
 
@@ -96,19 +143,23 @@ impl Server {
             config: config.clone(),
             turn_gate,
             bridge_manager: crate::security::BridgeManager::new(),
+            approvals: ApprovalBroker::from_config(&config.tools),
         })
     }
 
-    /// Create a server with both a shared TurnGate and a shared BridgeManager.
+    /// Create a server with a shared TurnGate, BridgeManager and ApprovalBroker
+    /// (so approvals requested by the Telegram bot can be answered over HTTP).
     pub fn new_daemon(
         config: &Config,
         turn_gate: TurnGate,
         bridge_manager: crate::security::BridgeManager,
+        approvals: ApprovalBroker,
     ) -> Result<Self> {
         Ok(Self {
             config: config.clone(),
             turn_gate,
             bridge_manager,
+            approvals,
         })
     }
 
@@ -128,6 +179,8 @@ impl Server {
             workspace_lock,
             rate_limiter,
             bridge_manager: self.bridge_manager.clone(),
+            approvals: self.approvals.clone(),
+            running: std::sync::Mutex::new(HashMap::new()),
         });
 
         // Load persisted sessions on startup
@@ -196,6 +249,26 @@ impl Server {
             .route("/api/sessions/{session_id}/compact", post(compact_session))
             .route("/api/sessions/{session_id}/clear", post(clear_session))
             .route("/api/sessions/{session_id}/model", post(set_session_model))
+            .route(
+                "/api/sessions/{session_id}/branches",
+                get(get_session_branches),
+            )
+            .route(
+                "/api/sessions/{session_id}/branch",
+                post(switch_session_branch),
+            )
+            .route("/api/sessions/{session_id}/fork", post(fork_session))
+            .route("/api/sessions/{session_id}/retry", post(retry_session))
+            .route(
+                "/api/sessions/{session_id}/cancel",
+                post(cancel_session_turn),
+            )
+            .route(
+                "/api/sessions/{session_id}/edit",
+                post(edit_session_message),
+            )
+            .route("/api/approvals", get(list_approvals))
+            .route("/api/approvals/{approval_id}", post(resolve_approval))
             .route("/api/chat", post(chat))
             .route("/api/chat/stream", post(chat_stream))
             .route("/api/ws", get(websocket_handler))
@@ -348,6 +421,8 @@ async fn load_persisted_sessions(state: &Arc<AppState>) -> Result<(), anyhow::Er
 
         let memory = StdArc::new(state.memory.clone());
         let mut agent = Agent::new(agent_config, &state.config, memory).await?;
+        agent.set_usage_scope(HTTP_AGENT_ID, "http");
+        agent.set_approval_handler(state.approvals.handler(http_origin(&session_info.id)));
 
         // Try to resume the session
         if agent.resume_session(&session_info.id).await.is_ok() {
@@ -442,6 +517,8 @@ async fn get_or_create_session(
     let mut agent = Agent::new(agent_config, &state.config, memory)
         .await
         .map_err(|e| AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    agent.set_usage_scope(HTTP_AGENT_ID, "http");
+    agent.set_approval_handler(state.approvals.handler(http_origin(&new_id)));
 
     agent
         .new_session()
@@ -501,6 +578,7 @@ struct StatusResponse {
     memory_chunks: usize,
     active_sessions: usize,
     is_brand_new: bool,
+    providers: Vec<ProviderHealth>,
 }
 
 async fn status(State(state): State<Arc<AppState>>) -> Json<StatusResponse> {
@@ -512,6 +590,7 @@ async fn status(State(state): State<Arc<AppState>>) -> Json<StatusResponse> {
         memory_chunks: state.memory.chunk_count().unwrap_or(0),
         active_sessions: sessions.len(),
         is_brand_new: state.memory.is_brand_new(),
+        providers: ProviderHealthRegistry::global().snapshot(),
     })
 }
 
@@ -634,6 +713,8 @@ async fn get_session_status(
 // Get session messages - returns message history for an active session
 #[derive(Serialize)]
 struct ActiveSessionMessage {
+    id: String,
+    parent_id: Option<String>,
     role: String,
     content: Option<String>,
     tool_calls: Option<Vec<serde_json::Value>>,
@@ -683,6 +764,8 @@ async fn get_session_messages(
                     });
 
                     ActiveSessionMessage {
+                        id: sm.id.clone(),
+                        parent_id: sm.parent_id.clone(),
                         role: role.to_string(),
                         content: if sm.message.content.is_empty() {
                             None
@@ -780,6 +863,218 @@ async fn set_session_model(
     }
 }
 
+// List the branches of a session tree
+#[derive(Serialize)]
+struct SessionBranch {
+    leaf_id: String,
+    message_count: usize,
+    preview: String,
+    timestamp: u64,
+    active: bool,
+}
+
+async fn get_session_branches(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Response {
+    let mut sessions = state.sessions.lock().await;
+
+    match sessions.get_mut(&session_id) {
+        Some(entry) => {
+            entry.last_accessed = Instant::now();
+
+            let branches: Vec<SessionBranch> = entry
+                .agent
+                .branches()
+                .into_iter()
+                .map(|b| SessionBranch {
+                    leaf_id: b.leaf_id,
+                    message_count: b.message_count,
+                    preview: b.preview,
+                    timestamp: b.timestamp,
+                    active: b.active,
+                })
+                .collect();
+
+            Json(json!({"session_id": session_id, "branches": branches})).into_response()
+        }
+        None => AppError(StatusCode::NOT_FOUND, "Session not found".to_string()).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct MessageIdRequest {
+    message_id: String,
+}
+
+// Switch to the branch through a message
+async fn switch_session_branch(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Json(request): Json<MessageIdRequest>,
+) -> Response {
+    let mut sessions = state.sessions.lock().await;
+
+    match sessions.get_mut(&session_id) {
+        Some(entry) => {
+            entry.last_accessed = Instant::now();
+
+            match entry.agent.switch_branch(&request.message_id) {
+                Ok(()) => {
+                    entry.dirty = true;
+                    Json(json!({
+                        "session_id": session_id,
+                        "message_count": entry.agent.raw_session_messages().len(),
+                    }))
+                    .into_response()
+                }
+                Err(e) => AppError(StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+            }
+        }
+        None => AppError(StatusCode::NOT_FOUND, "Session not found".to_string()).into_response(),
+    }
+}
+
+// Cut the active branch after a message; the next chat message starts a new branch
+async fn fork_session(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Json(request): Json<MessageIdRequest>,
+) -> Response {
+    let mut sessions = state.sessions.lock().await;
+
+    match sessions.get_mut(&session_id) {
+        Some(entry) => {
+            entry.last_accessed = Instant::now();
+
+            match entry.agent.fork_at(&request.message_id) {
+                Ok(()) => {
+                    entry.dirty = true;
+                    Json(json!({
+                        "session_id": session_id,
+                        "message_count": entry.agent.raw_session_messages().len(),
+                    }))
+                    .into_response()
+                }
+                Err(e) => AppError(StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+            }
+        }
+        None => AppError(StatusCode::NOT_FOUND, "Session not found".to_string()).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct EditMessageRequest {
+    message_id: String,
+    message: String,
+}
+
+/// A turn that rewrites the end of a session's active branch
+enum BranchTurn {
+    Retry,
+    Edit(EditMessageRequest),
+}
+
+// Regenerate the last reply
+async fn retry_session(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Response {
+    run_branch_turn(&state, session_id, BranchTurn::Retry).await
+}
+
+// Edit a user message and answer it on a new branch
+async fn edit_session_message(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Json(request): Json<EditMessageRequest>,
+) -> Response {
+    run_branch_turn(&state, session_id, BranchTurn::Edit(request)).await
+}
+
+async fn run_branch_turn(state: &Arc<AppState>, session_id: String, turn: BranchTurn) -> Response {
+    let _gate_permit = state.turn_gate.acquire().await;
+
+    let ws_lock_path = state.workspace_lock.clone();
+    let ws_guard = match tokio::task::spawn_blocking(move || ws_lock_path.acquire()).await {
+        Ok(Ok(guard)) => guard,
+        Ok(Err(e)) => {
+            return AppError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to acquire workspace lock: {}", e),
+            )
+            .into_response();
+        }
+        Err(e) => {
+            return AppError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Lock task error: {}", e),
+            )
+            .into_response();
+        }
+    };
+
+    let mut sessions = state.sessions.lock().await;
+    let Some(entry) = sessions.get_mut(&session_id) else {
+        return AppError(StatusCode::NOT_FOUND, "Session not found".to_string()).into_response();
+    };
+
+    entry.last_accessed = Instant::now();
+    let _running = RunningTurn::start(state, &session_id, entry.agent.canceller());
+
+    let result = match turn {
+        BranchTurn::Retry => entry.agent.regenerate().await,
+        BranchTurn::Edit(request) => {
+            entry
+                .agent
+                .edit_message(&request.message_id, &request.message)
+                .await
+        }
+    };
+
+    drop(ws_guard);
+
+    // The branch changes even when the new reply fails
+    entry.dirty = true;
+
+    match result {
+        Ok(response) => Json(ChatResponse {
+            response,
+            session_id,
+            model: entry.agent.model().to_string(),
+        })
+        .into_response(),
+        Err(e) if Cancelled::is(&e) => {
+            AppError(StatusCode::CONFLICT, e.to_string()).into_response()
+        }
+        Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// Cancel the turn in progress in a session
+async fn cancel_session_turn(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Response {
+    let canceller = state
+        .running
+        .lock()
+        .expect("running turns lock poisoned")
+        .get(&session_id)
+        .cloned();
+    match canceller {
+        Some(canceller) => {
+            canceller.cancel();
+            Json(json!({"session_id": session_id, "cancelled": true})).into_response()
+        }
+        None => AppError(
+            StatusCode::CONFLICT,
+            "No turn in progress for this session".to_string(),
+        )
+        .into_response(),
+    }
+}
+
 // Chat endpoint
 #[derive(Deserialize)]
 struct ChatRequest {
@@ -787,6 +1082,8 @@ struct ChatRequest {
     session_id: Option<String>,
     /// Optional model to use for this request (switches session model)
     model: Option<String>,
+    /// Optional reasoning effort (switches session reasoning)
+    reasoning: Option<ReasoningEffort>,
 }
 
 #[derive(Serialize)]
@@ -845,7 +1142,15 @@ async fn chat(State(state): State<Arc<AppState>>, Json(request): Json<ChatReques
         return AppError(StatusCode::BAD_REQUEST, format!("Invalid model: {}", e)).into_response();
     }
 
+    if request.reasoning.is_some()
+        && let Err(e) = entry.agent.set_reasoning(request.reasoning)
+    {
+        return AppError(StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+
+    let running = RunningTurn::start(&state, &session_id, entry.agent.canceller());
     let result = entry.agent.chat(&request.message).await;
+    drop(running);
 
     // Release workspace lock explicitly before returning
     drop(ws_guard);
@@ -860,6 +1165,9 @@ async fn chat(State(state): State<Arc<AppState>>, Json(request): Json<ChatReques
             })
             .into_response()
         }
+        Err(e) if Cancelled::is(&e) => {
+            AppError(StatusCode::CONFLICT, e.to_string()).into_response()
+        }
         Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
@@ -877,6 +1185,7 @@ async fn chat_stream(
 
     let state_clone = state.clone();
     let message = request.message.clone();
+    let reasoning = request.reasoning;
 
     let stream = async_stream::stream! {
         // Send session_id first
@@ -911,7 +1220,15 @@ async fn chat_stream(
         entry.last_accessed = Instant::now();
         entry.dirty = true;
 
+        if reasoning.is_some()
+            && let Err(e) = entry.agent.set_reasoning(reasoning)
+        {
+            yield Ok(Event::default().data(json!({"error": e.to_string()}).to_string()));
+            return;
+        }
+
         // Use streaming with tools
+        let _running = RunningTurn::start(&state_clone, &session_id, entry.agent.canceller());
         match entry.agent.chat_stream_with_tools(&message, Vec::new()).await {
             Ok(event_stream) => {
                 use futures::StreamExt;
@@ -919,8 +1236,30 @@ async fn chat_stream(
                 // Pin the stream to iterate over it
                 let mut pinned_stream = std::pin::pin!(event_stream);
 
-                while let Some(event) = pinned_stream.next().await {
+                // A tool call can wait on approval mid-stream; forward this session's requests
+                let origin = http_origin(&session_id);
+                let mut approvals = state_clone.approvals.subscribe();
+
+                loop {
+                    let next = tokio::select! {
+                        event = pinned_stream.next() => futures::future::Either::Left(event),
+                        Ok(approval) = approvals.recv() => futures::future::Either::Right(approval),
+                    };
+                    let event = match next {
+                        futures::future::Either::Left(Some(event)) => event,
+                        futures::future::Either::Left(None) => break,
+                        futures::future::Either::Right(approval) => {
+                            if approval.origin() == origin {
+                                yield Ok(Event::default().data(approval_event_json(&approval).to_string()));
+                            }
+                            continue;
+                        }
+                    };
                     match event {
+                        Ok(StreamEvent::Thinking(thinking)) => {
+                            let data = json!({"type": "thinking", "delta": thinking});
+                            yield Ok(Event::default().data(data.to_string()));
+                        }
                         Ok(StreamEvent::Content(content)) => {
                             let data = json!({"type": "content", "delta": content});
                             yield Ok(Event::default().data(data.to_string()));
@@ -944,6 +1283,10 @@ async fn chat_stream(
                             let data = json!({"type": "done"});
                             yield Ok(Event::default().data(data.to_string()));
                         }
+                        Err(e) if Cancelled::is(&e) => {
+                            yield Ok(Event::default().data(json!({"type": "cancelled"}).to_string()));
+                            break;
+                        }
                         Err(e) => {
                             yield Ok(Event::default().data(json!({"error": e.to_string()}).to_string()));
                             break;
@@ -962,11 +1305,100 @@ async fn chat_stream(
     Sse::new(stream).into_response()
 }
 
+fn approval_event_json(event: &ApprovalEvent) -> serde_json::Value {
+    match event {
+        ApprovalEvent::Requested(request) => json!({
+            "type": "approval_request",
+            "id": request.id,
+            "name": request.tool_name,
+            "detail": request.detail,
+            "arguments": request.arguments,
+        }),
+        ApprovalEvent::Resolved {
+            id,
+            decision,
+            timed_out,
+            ..
+        } => json!({
+            "type": "approval_resolved",
+            "id": id,
+            "decision": decision,
+            "timed_out": timed_out,
+        }),
+    }
+}
+
+// Tool calls waiting for approval, from every channel
+#[derive(Serialize)]
+struct ApprovalsResponse {
+    approvals: Vec<ApprovalRequest>,
+}
+
+async fn list_approvals(State(state): State<Arc<AppState>>) -> Json<ApprovalsResponse> {
+    Json(ApprovalsResponse {
+        approvals: state.approvals.pending(),
+    })
+}
+
+#[derive(Deserialize)]
+struct ApprovalDecisionRequest {
+    decision: ApprovalDecision,
+}
+
+// Approve or deny a pending tool call; the waiting turn resumes
+async fn resolve_approval(
+    State(state): State<Arc<AppState>>,
+    Path(approval_id): Path<String>,
+    Json(request): Json<ApprovalDecisionRequest>,
+) -> Response {
+    if state.approvals.resolve(&approval_id, request.decision) {
+        Json(json!({"id": approval_id, "decision": request.decision})).into_response()
+    } else {
+        AppError(
+            StatusCode::NOT_FOUND,
+            "No pending approval with that id".to_string(),
+        )
+        .into_response()
+    }
+}
+
 // Memory search endpoint
 #[derive(Deserialize)]
 struct SearchQuery {
     q: String,
     limit: Option<usize>,
+    /// Comma-separated path prefixes or globs
+    path: Option<String>,
+    after: Option<String>,
+    before: Option<String>,
+    /// Comma-separated sources (memory, session, external)
+    source: Option<String>,
+}
+
+impl SearchQuery {
+    fn filter(&self) -> Result<SearchFilter, anyhow::Error> {
+        let list = |value: &Option<String>| -> Vec<String> {
+            value
+                .iter()
+                .flat_map(|v| v.split(','))
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(String::from)
+                .collect()
+        };
+
+        let filter = SearchFilter {
+            paths: list(&self.path),
+            after: self.after.as_deref().map(parse_filter_date).transpose()?,
+            before: self.before.as_deref().map(parse_filter_date).transpose()?,
+            sources: list(&self.source)
+                .iter()
+                .map(|s| s.parse())
+                .collect::<Result<_, _>>()?,
+        };
+        filter.validate()?;
+        Ok(filter)
+    }
 }
 
 #[derive(Serialize)]
@@ -976,6 +1408,8 @@ struct SearchResult {
     line_end: i32,
     content: String,
     score: f64,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    breadcrumb: String,
 }
 
 #[derive(Serialize)]
@@ -988,7 +1422,11 @@ async fn memory_search(
     State(state): State<Arc<AppState>>,
     Query(query): Query<SearchQuery>,
 ) -> Response {
-    match memory_search_inner(&state.memory, &query.q, query.limit) {
+    let filter = match query.filter() {
+        Ok(filter) => filter,
+        Err(e) => return AppError(StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    match memory_search_inner(&state.memory, &query.q, query.limit, &filter) {
         Ok(response) => Json(response).into_response(),
         Err(e) => AppError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
@@ -998,9 +1436,10 @@ fn memory_search_inner(
     memory: &MemoryManager,
     query: &str,
     limit: Option<usize>,
+    filter: &SearchFilter,
 ) -> Result<SearchResponse, anyhow::Error> {
     let limit = limit.unwrap_or(10);
-    let results = memory.search(query, limit)?;
+    let results = memory.search_filtered(query, limit, filter)?;
 
     let results: Vec<SearchResult> = results
         .into_iter()
@@ -1010,6 +1449,7 @@ fn memory_search_inner(
             line_end: r.line_end,
             content: r.content,
             score: r.score,
+            breadcrumb: r.breadcrumb,
         })
         .collect();
 
@@ -1026,6 +1466,16 @@ struct StatsResponse {
     total_files: usize,
     total_chunks: usize,
     index_size_kb: u64,
+    embedding_model: Option<String>,
+    embeddings: Vec<EmbeddingStats>,
+    sqlite_vec_version: Option<String>,
+}
+
+#[derive(Serialize)]
+struct EmbeddingStats {
+    model: String,
+    dims: usize,
+    embedded_chunks: usize,
 }
 
 async fn memory_stats(State(state): State<Arc<AppState>>) -> Response {
@@ -1043,6 +1493,17 @@ fn memory_stats_inner(memory: &MemoryManager) -> Result<StatsResponse, anyhow::E
         total_files: stats.total_files,
         total_chunks: stats.total_chunks,
         index_size_kb: stats.index_size_kb,
+        embedding_model: stats.embedding_model,
+        embeddings: stats
+            .embeddings
+            .into_iter()
+            .map(|e| EmbeddingStats {
+                model: e.model,
+                dims: e.dims,
+                embedded_chunks: e.embedded_chunks,
+            })
+            .collect(),
+        sqlite_vec_version: stats.sqlite_vec_version,
     })
 }
 
@@ -1134,7 +1595,10 @@ async fn get_config(State(state): State<Arc<AppState>>) -> Json<ConfigResponse>
     Json(ConfigResponse {
         agent: AgentConfigInfo {
             default_model: state.config.agent.default_model.clone(),
-            context_window: state.config.agent.context_window,
+            context_window: ModelCatalog::from_config(&state.config).context_window(
+                &state.config.agent.default_model,
+                state.config.agent.context_window,
+            ),
             reserve_tokens: state.config.agent.reserve_tokens,
         },
         server: ServerConfigInfo {
@@ -1454,6 +1918,15 @@ enum WsIncoming {
     /// For streaming, use the SSE endpoint at /api/chat/stream
     #[serde(rename = "chat")]
     Chat { message: String },
+    /// Approve or deny a pending tool call (from any session)
+    #[serde(rename = "approval")]
+    Approval {
+        id: String,
+        decision: ApprovalDecision,
+    },
+    /// Stop the chat turn in progress
+    #[serde(rename = "cancel")]
+    Cancel,
     /// Ping for keepalive
     #[serde(rename = "ping")]
     Ping,
@@ -1479,9 +1952,27 @@ enum WsOutgoing {
         id: String,
         output: String,
     },
+    /// A tool call in this session is waiting for approval
+    #[serde(rename = "approval_request")]
+    ApprovalRequest {
+        id: String,
+        name: String,
+        detail: Option<String>,
+        arguments: String,
+    },
+    /// A pending tool call was approved, denied or timed out
+    #[serde(rename = "approval_resolved")]
+    ApprovalResolved {
+        id: String,
+        decision: ApprovalDecision,
+        timed_out: bool,
+    },
     /// Message complete
     #[serde(rename = "done")]
     Done,
+    /// The chat turn was cancelled before it finished
+    #[serde(rename = "cancelled")]
+    Cancelled,
     /// Pong response
     #[serde(rename = "pong")]
     Pong,
@@ -1490,6 +1981,27 @@ enum WsOutgoing {
     Error { message: String },
 }
 
+fn ws_approval_event(event: ApprovalEvent) -> WsOutgoing {
+    match event {
+        ApprovalEvent::Requested(request) => WsOutgoing::ApprovalRequest {
+            id: request.id,
+            name: request.tool_name,
+            detail: request.detail,
+            arguments: request.arguments,
+        },
+        ApprovalEvent::Resolved {
+            id,
+            decision,
+            timed_out,
+            ..
+        } => WsOutgoing::ApprovalResolved {
+            id,
+            decision,
+            timed_out,
+        },
+    }
+}
+
 async fn handle_websocket(socket: WebSocket, state: Arc<AppState>) {
     let (mut sender, mut receiver) = socket.split();
 
@@ -1603,7 +2115,54 @@ async fn handle_websocket(socket: WebSocket, state: Arc<AppState>) {
 
                         entry.last_accessed = Instant::now();
 
-                        match entry.agent.chat(&message).await {
+                        // Keep reading the socket while the turn runs so approval
+                        // decisions can reach a tool call waiting on one
+                        let origin = http_origin(&session_id);
+                        let mut approvals = state.approvals.subscribe();
+                        let canceller = entry.agent.canceller();
+                        let _running = RunningTurn::start(&state, &session_id, canceller.clone());
+                        let chat = entry.agent.chat(&message);
+                        tokio::pin!(chat);
+                        let result = loop {
+                            tokio::select! {
+                                result = &mut chat => break result,
+                                Ok(approval) = approvals.recv() => {
+                                    if approval.origin() == origin
+                                        && let Ok(json) = serde_json::to_string(&ws_approval_event(approval))
+                                    {
+                                        let _ = sender.send(WsMessage::Text(json.into())).await;
+                                    }
+                                }
+                                Some(Ok(WsMessage::Text(text))) = receiver.next() => {
+                                    let reply = match serde_json::from_str::<WsIncoming>(&text) {
+                                        Ok(WsIncoming::Approval { id, decision }) => {
+                                            // Success is reported by the resolved event
+                                            (!state.approvals.resolve(&id, decision)).then(|| {
+                                                WsOutgoing::Error {
+                                                    message: "No pending approval with that id"
+                                                        .to_string(),
+                                                }
+                                            })
+                                        }
+                                        Ok(WsIncoming::Cancel) => {
+                                            canceller.cancel();
+                                            None
+                                        }
+                                        Ok(WsIncoming::Ping) => Some(WsOutgoing::Pong),
+                                        _ => Some(WsOutgoing::Error {
+                                            message: "A chat turn is in progress".to_string(),
+                                        }),
+                                    };
+                                    if let Some(reply) = reply
+                                        && let Ok(json) = serde_json::to_string(&reply)
+                                    {
+                                        let _ = sender.send(WsMessage::Text(json.into())).await;
+                                    }
+                                }
+                            }
+                        };
+
+                        match result {
                             Ok(response) => {
                                 // Send response as content
                                 let content = WsOutgoing::Content { delta: response };
@@ -1618,15 +2177,43 @@ async fn handle_websocket(socket: WebSocket, state: Arc<AppState>) {
                                 }
                             }
                             Err(e) => {
-                                let error = WsOutgoing::Error {
-                                    message: e.to_string(),
+                                let reply = if Cancelled::is(&e) {
+                                    WsOutgoing::Cancelled
+                                } else {
+                                    WsOutgoing::Error {
+                                        message: e.to_string(),
+                                    }
                                 };
-                                if let Ok(json) = serde_json::to_string(&error) {
+                                if let Ok(json) = serde_json::to_string(&reply) {
                                     let _ = sender.send(WsMessage::Text(json.into())).await;
                                 }
                             }
                         }
                     }
+                    Ok(WsIncoming::Cancel) => {
+                        let error = WsOutgoing::Error {
+                            message: "No chat turn in progress".to_string(),
+                        };
+                        if let Ok(json) = serde_json::to_string(&error) {
+                            let _ = sender.send(WsMessage::Text(json.into())).await;
+                        }
+                    }
+                    Ok(WsIncoming::Approval { id, decision }) => {
+                        let reply = if state.approvals.resolve(&id, decision) {
+                            WsOutgoing::ApprovalResolved {
+                                id,
+                                decision,
+                                timed_out: false,
+                            }
+                        } else {
+                            WsOutgoing::Error {
+                                message: "No pending approval with that id".to_string(),
+                            }
+                        };
+                        if let Ok(json) = serde_json::to_string(&reply) {
+                            let _ = sender.send(WsMessage::Text(json.into())).await;
+                        }
+                    }
                     Ok(WsIncoming::Ping) => {
                         let pong = WsOutgoing::Pong;
                         if let Ok(json) = serde_json::to_string(&pong) {
@@ -23,12 +23,12 @@ use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 use localgpt_core::agent::{
-    Agent, AgentConfig, LLMResponse, LLMResponseContent, Message, Role, StreamEvent, ToolCall,
-    ToolSchema,
+    Agent, AgentConfig, LLMResponse, LLMResponseContent, Message, ModelCatalog, ProviderError,
+    ResponseSchema, Role, StreamEvent, ToolCall, ToolSchema,
 };
-use localgpt_core::config::Config;
+use localgpt_core::config::{Config, ReasoningEffort};
 
-use crate::http::AppState;
+use crate::http::{AppState, HTTP_AGENT_ID};
 
 // ============================================================================
 // Request/Response Types (OpenAI Wire Format)
@@ -46,6 +46,26 @@ pub struct ChatCompletionRequest {
     pub tools: Option<Vec<OaiToolDef>>,
     /// Map of tool_choice options: "auto", "none", or {"type": "function", "function": {"name": "..."}}
     pub tool_choice: Option<Value>,
+    /// Reasoning effort: "off", "low", "medium" or "high"
+    pub reasoning_effort: Option<String>,
+    /// Constrain the reply to JSON ("json_object") or a JSON schema ("json_schema")
+    pub response_format: Option<OaiResponseFormat>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OaiResponseFormat {
+    Text,
+    JsonObject,
+    JsonSchema { json_schema: OaiJsonSchema },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OaiJsonSchema {
+    pub name: String,
+    pub description: Option<String>,
+    pub schema: Option<Value>,
+    pub strict: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -115,6 +135,8 @@ pub struct OaiResponseMessage {
     pub content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<OaiToolCallResponse>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_content: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -148,6 +170,8 @@ pub struct ChunkDelta {
     pub content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<OaiToolCallChunk>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_content: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -182,6 +206,27 @@ pub struct ModelInfo {
     pub object: &'static str,
     pub created: u64,
     pub owned_by: String,
+    /// LocalGPT extensions from the model catalog
+    pub context_window: usize,
+    pub max_output_tokens: usize,
+    pub supports_tools: bool,
+    pub supports_images: bool,
+}
+
+impl ModelInfo {
+    fn from_catalog(catalog: &ModelCatalog, id: String, owned_by: String) -> Self {
+        let spec = catalog.resolve(&id).spec;
+        Self {
+            id,
+            object: "model",
+            created: 0,
+            owned_by,
+            context_window: spec.context_window,
+            max_output_tokens: spec.max_output_tokens,
+            supports_tools: spec.supports_tools,
+            supports_images: spec.supports_images,
+        }
+    }
 }
 
 // ============================================================================
@@ -213,6 +258,7 @@ fn convert_messages(oai_messages: &[OaiMessage]) -> Result<Vec<Message>> {
                     id: tc.id.clone(),
                     name: tc.function.name.clone(),
                     arguments: tc.function.arguments.clone(),
+                    signature: None,
                 })
                 .collect()
         });
@@ -223,6 +269,7 @@ fn convert_messages(oai_messages: &[OaiMessage]) -> Result<Vec<Message>> {
             tool_calls,
             tool_call_id: msg.tool_call_id.clone(),
             images: Vec::new(),
+            thinking: Vec::new(),
         });
     }
 
@@ -249,6 +296,47 @@ fn unix_timestamp() -> u64 {
         .as_secs()
 }
 
+/// Map an upstream provider failure to the HTTP status returned to the client
+fn llm_error_status(err: &anyhow::Error) -> StatusCode {
+    match ProviderError::classify(err) {
+        Some(ProviderError::RateLimited { .. }) => StatusCode::TOO_MANY_REQUESTS,
+        Some(ProviderError::BadRequest { .. } | ProviderError::ContextOverflow { .. }) => {
+            StatusCode::BAD_REQUEST
+        }
+        Some(ProviderError::Auth { .. } | ProviderError::Server { .. }) => StatusCode::BAD_GATEWAY,
+        Some(ProviderError::Transport { .. }) => StatusCode::GATEWAY_TIMEOUT,
+        None => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Parse the optional `reasoning_effort` request field
+fn parse_reasoning_effort(
+    req: &ChatCompletionRequest,
+) -> Result<Option<ReasoningEffort>, (StatusCode, String)> {
+    req.reasoning_effort
+        .as_deref()
+        .map(|s| s.parse::<ReasoningEffort>())
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+/// The schema requested by `response_format`, if the reply must be JSON
+fn response_schema(req: &ChatCompletionRequest) -> Option<ResponseSchema> {
+    match req.response_format.as_ref()? {
+        OaiResponseFormat::Text => None,
+        OaiResponseFormat::JsonObject => Some(ResponseSchema::new(
+            "json_object",
+            json!({ "type": "object" }),
+        )),
+        OaiResponseFormat::JsonSchema { json_schema } => Some(ResponseSchema {
+            name: json_schema.name.clone(),
+            description: json_schema.description.clone(),
+            schema: json_schema.schema.clone().unwrap_or_else(|| json!({})),
+            strict: json_schema.strict.unwrap_or(false),
+        }),
+    }
+}
+
 /// Generate a unique completion ID
 fn generate_completion_id() -> String {
     format!("chatcmpl-{}", Uuid::new_v4().simple())
@@ -264,6 +352,12 @@ pub async fn chat_completions(
     Json(req): Json<ChatCompletionRequest>,
 ) -> Result<Response, (StatusCode, String)> {
     if req.stream {
+        if response_schema(&req).is_some() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "response_format is not supported with stream: true".to_string(),
+            ));
+        }
         return chat_completions_stream(state, req)
             .await
             .map(|r| r.into_response());
@@ -283,6 +377,7 @@ async fn chat_completions_non_stream(
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid messages: {}", e)))?;
 
     let tools = req.tools.as_ref().map(|t| convert_tools(t));
+    let reasoning = parse_reasoning_effort(&req)?;
 
     // Create a fresh agent for this request
     let agent_config = AgentConfig {
@@ -300,19 +395,33 @@ async fn chat_completions_non_stream(
                 format!("Failed to create agent: {}", e),
             )
         })?;
+    agent.set_usage_scope(HTTP_AGENT_ID, "openai-api");
+    if reasoning.is_some() {
+        agent
+            .set_reasoning(reasoning)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    }
 
     info!("OpenAI API: non-streaming request for model {}", req.model);
 
+    if let Some(schema) = response_schema(&req) {
+        let structured = agent
+            .chat_structured(&messages, &schema)
+            .await
+            .map_err(|e| (llm_error_status(&e), format!("LLM error: {}", e)))?;
+        let response = LLMResponse {
+            content: LLMResponseContent::Text(structured.value.to_string()),
+            usage: structured.usage,
+            thinking: Vec::new(),
+        };
+        return Ok(Json(to_completion_response(response, &req.model)));
+    }
+
     // Call the provider
     let response = agent
         .chat_with_messages(&messages, tools.as_deref())
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("LLM error: {}", e),
-            )
-        })?;
+        .map_err(|e| (llm_error_status(&e), format!("LLM error: {}", e)))?;
 
     // Convert response
     let completion = to_completion_response(response, &req.model);
@@ -335,6 +444,7 @@ async fn chat_completions_stream(
         .unwrap_or_default();
 
     let model = req.model.clone();
+    let reasoning = parse_reasoning_effort(&req)?;
     let completion_id = generate_completion_id();
     let created = unix_timestamp();
 
@@ -351,15 +461,14 @@ async fn chat_completions_stream(
 
     // The agent must live for the duration of the stream, so we create the stream
     // in an async_stream that owns both the agent and the inner event stream.
-    let event_stream = create_sse_stream_owned(
-        agent_config,
-        state.config.clone(),
-        memory,
-        last_message,
+    let turn = StreamTurn {
+        message: last_message,
+        reasoning,
         completion_id,
         created,
         model,
-    );
+    };
+    let event_stream = create_sse_stream_owned(agent_config, state.config.clone(), memory, turn);
 
     Ok(Sse::new(event_stream).keep_alive(
         axum::response::sse::KeepAlive::new()
@@ -368,16 +477,30 @@ async fn chat_completions_stream(
     ))
 }
 
+/// Per-request values for a streamed completion
+struct StreamTurn {
+    /// Last user message, sent as the turn's input
+    message: String,
+    reasoning: Option<ReasoningEffort>,
+    completion_id: String,
+    created: u64,
+    model: String,
+}
+
 /// Create an SSE stream that owns its agent and handles the full lifecycle.
 fn create_sse_stream_owned(
     agent_config: AgentConfig,
     config: Config,
     memory: Arc<localgpt_core::memory::MemoryManager>,
-    message: String,
-    completion_id: String,
-    created: u64,
-    model: String,
+    turn: StreamTurn,
 ) -> impl Stream<Item = Result<Event, Infallible>> {
+    let StreamTurn {
+        message,
+        reasoning,
+        completion_id,
+        created,
+        model,
+    } = turn;
     async_stream::try_stream! {
         // Create agent inside the stream so it lives for the stream's duration
         let mut agent = match Agent::new(agent_config, &config, memory).await {
@@ -388,6 +511,14 @@ fn create_sse_stream_owned(
                 return;
             }
         };
+        agent.set_usage_scope(HTTP_AGENT_ID, "openai-api");
+        if reasoning.is_some()
+            && let Err(e) = agent.set_reasoning(reasoning)
+        {
+            warn!("Failed to set reasoning effort: {}", e);
+            yield Event::default().data("[DONE]");
+            return;
+        }
 
         let event_stream = match agent.chat_stream_with_tools(&message, Vec::new()).await {
             Ok(s) => s,
@@ -412,6 +543,7 @@ fn create_sse_stream_owned(
                     role: Some("assistant".to_string()),
                     content: None,
                     tool_calls: None,
+                    reasoning_content: None,
                 },
                 finish_reason: None,
             }],
@@ -422,6 +554,25 @@ fn create_sse_stream_owned(
 
         while let Some(event) = stream.next().await {
             match event {
+                Ok(StreamEvent::Thinking(text)) => {
+                    let chunk = ChatCompletionChunk {
+                        id: completion_id.clone(),
+                        object: "chat.completion.chunk",
+                        created,
+                        model: model.clone(),
+                        choices: vec![ChunkChoice {
+                            index: 0,
+                            delta: ChunkDelta {
+                                role: None,
+                                content: None,
+                                tool_calls: None,
+                                reasoning_content: Some(text),
+                            },
+                            finish_reason: None,
+                        }],
+                    };
+                    yield Event::default().json_data(chunk).unwrap();
+                }
                 Ok(StreamEvent::Content(text)) => {
                     let chunk = ChatCompletionChunk {
                         id: completion_id.clone(),
@@ -434,6 +585,7 @@ fn create_sse_stream_owned(
                                 role: None,
                                 content: Some(text),
                                 tool_calls: None,
+                                reasoning_content: None,
                             },
                             finish_reason: None,
                         }],
@@ -460,6 +612,7 @@ fn create_sse_stream_owned(
                                         arguments: None,
                                     }),
                                 }]),
+                                reasoning_content: None,
                             },
                             finish_reason: None,
                         }],
@@ -503,35 +656,41 @@ fn create_sse_stream_owned(
 pub async fn list_models(
     State(state): State<Arc<AppState>>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let config = &state.config;
+    let catalog = ModelCatalog::from_config(config);
     let mut models = Vec::new();
 
     // Add the default model
-    let default_model = &state.config.agent.default_model;
-    models.push(ModelInfo {
-        id: default_model.clone(),
-        object: "model",
-        created: 0,
-        owned_by: "localgpt".to_string(),
-    });
+    models.push(ModelInfo::from_catalog(
+        &catalog,
+        config.agent.default_model.clone(),
+        "localgpt".to_string(),
+    ));
 
     // Add fallback models
-    for model in &state.config.agent.fallback_models {
-        models.push(ModelInfo {
-            id: model.clone(),
-            object: "model",
-            created: 0,
-            owned_by: "localgpt".to_string(),
-        });
+    for model in &config.agent.fallback_models {
+        models.push(ModelInfo::from_catalog(
+            &catalog,
+            model.clone(),
+            "localgpt".to_string(),
+        ));
     }
 
     // Add configured provider models
-    if let Some(ollama) = &state.config.providers.ollama {
-        models.push(ModelInfo {
-            id: format!("ollama/{}", ollama.model),
-            object: "model",
-            created: 0,
-            owned_by: "ollama".to_string(),
-        });
+    if let Some(ollama) = &config.providers.ollama {
+        models.push(ModelInfo::from_catalog(
+            &catalog,
+            format!("ollama/{}", ollama.model),
+            "ollama".to_string(),
+        ));
+    }
+
+    // Add catalog models whose provider has credentials
+    for spec in catalog.models() {
+        let id = spec.full_id();
+        if config.providers.is_configured(&spec.provider) && !models.iter().any(|m| m.id == id) {
+            models.push(ModelInfo::from_catalog(&catalog, id, spec.provider.clone()));
+        }
     }
 
     Ok(Json(ModelsResponse {
@@ -546,6 +705,7 @@ pub async fn list_models(
 
 /// Convert LocalGPT LLMResponse to OpenAI ChatCompletionResponse
 fn to_completion_response(response: LLMResponse, model: &str) -> ChatCompletionResponse {
+    let reasoning_content = response.thinking_text();
     let (content, tool_calls, finish_reason) = match response.content {
         LLMResponseContent::Text(text) => (Some(text), None, "stop"),
         LLMResponseContent::ToolCalls(calls) => {
@@ -581,6 +741,7 @@ fn to_completion_response(response: LLMResponse, model: &str) -> ChatCompletionR
                 role: "assistant".to_string(),
                 content,
                 tool_calls,
+                reasoning_content,
             },
             finish_reason: Some(finish_reason.to_string()),
         }],
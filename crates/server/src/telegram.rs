@@ -10,11 +10,16 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
 use teloxide::prelude::*;
-use teloxide::types::{MessageId, ParseMode};
-use tokio::sync::Mutex;
+use teloxide::types::{
+    InlineKeyboardButton, InlineKeyboardMarkup, MessageId, ParseMode, UpdateKind,
+};
+use tokio::sync::{Mutex, broadcast};
 use tracing::{debug, error, info, warn};
 
-use localgpt_core::agent::{Agent, AgentConfig, StreamEvent, extract_tool_detail, tools::Tool};
+use localgpt_core::agent::{
+    Agent, AgentConfig, ApprovalBroker, ApprovalDecision, ApprovalEvent, Cancelled, StreamEvent,
+    TurnCanceller, extract_tool_detail, tools::Tool,
+};
 use localgpt_core::concurrency::TurnGate;
 use localgpt_core::config::Config;
 use localgpt_core::memory::MemoryManager;
@@ -52,6 +57,9 @@ struct BotState {
     paired_user: Mutex<Option<PairedUser>>,
     pending_pairing_code: Mutex<Option<String>>,
     tool_factory: Option<ToolFactory>,
+    approvals: ApprovalBroker,
+    /// Turns in progress by chat, for /stop (a turn holds `sessions`)
+    running: Mutex<HashMap<i64, TurnCanceller>>,
 }
 
 fn pairing_file_path() -> Result<PathBuf> {
@@ -86,6 +94,7 @@ fn generate_pairing_code() -> String {
 pub async fn run_telegram_bot(
     config: &Config,
     turn_gate: TurnGate,
+    approvals: ApprovalBroker,
     tool_factory: Option<ToolFactory>,
 ) -> Result<()> {
     let telegram_config = config
@@ -126,6 +135,8 @@ pub async fn run_telegram_bot(
         paired_user: Mutex::new(paired_user),
         pending_pairing_code: Mutex::new(None),
         tool_factory,
+        approvals: approvals.clone(),
+        running: Mutex::new(HashMap::new()),
     });
 
     // Register bot commands so Telegram clients show the "/" menu
@@ -138,11 +149,23 @@ pub async fn run_telegram_bot(
         warn!("Failed to set bot commands: {}", e);
     }
 
+    tokio::spawn(forward_approvals(bot.clone(), approvals));
+
     info!("Starting Telegram bot...");
 
-    let handler = Update::filter_message().endpoint(handle_message);
+    let handler = dptree::entry()
+        .branch(Update::filter_message().endpoint(handle_message))
+        .branch(Update::filter_callback_query().endpoint(handle_callback));
 
     Dispatcher::builder(bot, handler)
+        // Button presses and /stop must not queue behind their chat: its
+        // turn may be the one waiting on the approval they carry, or the one
+        // to stop
+        .distribution_function(|upd| match upd.kind {
+            UpdateKind::CallbackQuery(_) => None,
+            UpdateKind::Message(ref msg) if msg.text().map(str::trim) == Some("/stop") => None,
+            _ => upd.chat().map(|chat| chat.id),
+        })
         .default_handler(|_upd| async {})
         .dependencies(dptree::deps![state])
         .enable_ctrlc_handler()
@@ -195,6 +218,118 @@ async fn handle_message(bot: Bot, msg: Message, state: Arc<BotState>) -> Respons
     handle_chat(&bot, chat_id, &state, &text).await
 }
 
+/// Approve/deny buttons on an approval request
+async fn handle_callback(
+    bot: Bot,
+    query: CallbackQuery,
+    state: Arc<BotState>,
+) -> ResponseResult<()> {
+    let authorized = state
+        .paired_user
+        .lock()
+        .await
+        .as_ref()
+        .is_some_and(|pu| pu.user_id == query.from.id.0);
+
+    let answer = if !authorized {
+        "Not authorized."
+    } else if let Some((id, decision)) = query.data.as_deref().and_then(parse_approval_callback) {
+        if state.approvals.resolve(id, decision) {
+            "Done."
+        } else {
+            "This request is no longer pending."
+        }
+    } else {
+        "Unknown action."
+    };
+
+    bot.answer_callback_query(query.id).text(answer).await?;
+    Ok(())
+}
+
+/// Approval origin for a Telegram chat
+fn telegram_origin(chat_id: ChatId) -> String {
+    format!("telegram:{}", chat_id.0)
+}
+
+fn parse_approval_callback(data: &str) -> Option<(&str, ApprovalDecision)> {
+    let (action, id) = data.split_once(':')?;
+    let decision = match action {
+        "approve" => ApprovalDecision::Approve,
+        "always" => ApprovalDecision::AlwaysAllow,
+        "deny" => ApprovalDecision::Deny,
+        _ => return None,
+    };
+    Some((id, decision))
+}
+
+/// Post approval requests from Telegram chats with inline buttons, and
+/// record the outcome on the message once the request is settled.
+async fn forward_approvals(bot: Bot, approvals: ApprovalBroker) {
+    let mut events = approvals.subscribe();
+    let mut prompts: HashMap<String, (MessageId, String)> = HashMap::new();
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        let Some(chat_id) = event
+            .origin()
+            .strip_prefix("telegram:")
+            .and_then(|id| id.parse().ok())
+            .map(ChatId)
+        else {
+            continue;
+        };
+
+        match event {
+            ApprovalEvent::Requested(request) => {
+                let text = match &request.detail {
+                    Some(d) => format!("🔐 Run {}({})?", request.tool_name, d),
+                    None => format!("🔐 Run {}?", request.tool_name),
+                };
+                let keyboard = InlineKeyboardMarkup::new([[
+                    InlineKeyboardButton::callback("Approve", format!("approve:{}", request.id)),
+                    InlineKeyboardButton::callback("Always", format!("always:{}", request.id)),
+                    InlineKeyboardButton::callback("Deny", format!("deny:{}", request.id)),
+                ]]);
+                match bot
+                    .send_message(chat_id, &text)
+                    .reply_markup(keyboard)
+                    .await
+                {
+                    Ok(msg) => {
+                        prompts.insert(request.id, (msg.id, text));
+                    }
+                    Err(e) => warn!("Failed to send approval request: {}", e),
+                }
+            }
+            ApprovalEvent::Resolved {
+                id,
+                decision,
+                timed_out,
+                ..
+            } => {
+                let Some((msg_id, text)) = prompts.remove(&id) else {
+                    continue;
+                };
+                let outcome = match decision {
+                    _ if timed_out => "⌛ No answer, denied",
+                    ApprovalDecision::Approve => "✅ Approved",
+                    ApprovalDecision::AlwaysAllow => "✅ Allowed for this session",
+                    ApprovalDecision::Deny => "❌ Denied",
+                };
+                // Editing the text drops the buttons
+                let _ = bot
+                    .edit_message_text(chat_id, msg_id, format!("{}\n{}", text, outcome))
+                    .await;
+            }
+        }
+    }
+}
+
 async fn handle_pairing(
     bot: Bot,
     msg: Message,
@@ -286,6 +421,16 @@ async fn handle_command(
             );
             bot.send_message(chat_id, &help).await?;
         }
+        "/stop" => {
+            let reply = match state.running.lock().await.get(&chat_id.0) {
+                Some(canceller) => {
+                    canceller.cancel();
+                    "Stopping the current reply."
+                }
+                None => "Nothing to stop.",
+            };
+            bot.send_message(chat_id, reply).await?;
+        }
         "/new" => {
             let mut sessions = state.sessions.lock().await;
             sessions.remove(&chat_id.0);
@@ -367,6 +512,79 @@ async fn handle_command(
                 bot.send_message(chat_id, "No active session.").await?;
             }
         }
+        "/pin" => {
+            let mut sessions = state.sessions.lock().await;
+            if let Some(entry) = sessions.get_mut(&chat_id.0) {
+                entry.last_accessed = Instant::now();
+                let reply = match entry.agent.pin((!args.is_empty()).then_some(args)) {
+                    Ok(pin) => format!("Pinned: {}", truncate_str(&pin.content, 200)),
+                    Err(e) => format!("Pin failed: {}", e),
+                };
+                bot.send_message(chat_id, reply).await?;
+            } else {
+                bot.send_message(chat_id, "No active session.").await?;
+            }
+        }
+        "/unpin" => {
+            let mut sessions = state.sessions.lock().await;
+            if let Some(entry) = sessions.get_mut(&chat_id.0) {
+                entry.last_accessed = Instant::now();
+                let pins = entry.agent.pinned_messages().to_vec();
+                let reply = if args.is_empty() {
+                    if pins.is_empty() {
+                        "Nothing pinned. Use /pin [text] to pin something.".to_string()
+                    } else {
+                        let mut text = "Pinned:\n".to_string();
+                        for (i, pin) in pins.iter().enumerate() {
+                            text.push_str(&format!(
+                                "{}. {}\n",
+                                i + 1,
+                                truncate_str(&pin.content, 200)
+                            ));
+                        }
+                        text.push_str("\nUse /unpin <n> to remove one.");
+                        text
+                    }
+                } else {
+                    match args.parse::<usize>() {
+                        Ok(n) if n >= 1 && n <= pins.len() => {
+                            match entry.agent.unpin(&pins[n - 1].id) {
+                                Ok(()) => "Unpinned.".to_string(),
+                                Err(e) => format!("Unpin failed: {}", e),
+                            }
+                        }
+                        _ => "Usage: /unpin <n> (send /unpin for the list)".to_string(),
+                    }
+                };
+                bot.send_message(chat_id, reply).await?;
+            } else {
+                bot.send_message(chat_id, "No active session.").await?;
+            }
+        }
+        "/instructions" => {
+            let mut sessions = state.sessions.lock().await;
+            if let Some(entry) = sessions.get_mut(&chat_id.0) {
+                entry.last_accessed = Instant::now();
+                let reply = match args {
+                    "" => match entry.agent.session_instructions() {
+                        Some(instructions) => format!("Session instructions:\n{}", instructions),
+                        None => "No session instructions. Use /instructions <text> to set them."
+                            .to_string(),
+                    },
+                    "clear" => {
+                        entry.agent.set_session_instructions(None);
+                        "Session instructions cleared.".to_string()
+                    }
+                    _ => {
+                        entry.agent.set_session_instructions(Some(args.to_string()));
+                        "Session instructions set.".to_string()
+                    }
+                };
+                bot.send_message(chat_id, reply).await?;
+            } else {
+                bot.send_message(chat_id, "No active session.").await?;
+            }
+        }
         "/memory" => {
             if args.is_empty() {
                 bot.send_message(chat_id, "Usage: /memory <search query>")
@@ -678,6 +896,8 @@ async fn handle_chat(
         let memory = std::sync::Arc::new(state.memory.clone());
         match Agent::new(agent_config, &state.config, memory).await {
             Ok(mut agent) => {
+                agent.set_usage_scope(TELEGRAM_AGENT_ID, "telegram");
+                agent.set_approval_handler(state.approvals.handler(telegram_origin(chat_id)));
                 // Extend agent with additional tools from factory if provided (e.g., CLI tools from daemon)
                 if let Some(ref factory) = state.tool_factory {
                     match factory(&state.config) {
@@ -725,6 +945,9 @@ async fn handle_chat(
 
     let entry = sessions.get_mut(&chat_id.0).unwrap();
     entry.last_accessed = Instant::now();
+    let canceller = entry.agent.canceller();
+    canceller.prepare_turn();
+    state.running.lock().await.insert(chat_id.0, canceller);
 
     // Use streaming with tools
     let response = match entry.agent.chat_stream_with_tools(text, Vec::new()).await {
@@ -738,6 +961,8 @@ async fn handle_chat(
 
             while let Some(event) = pinned_stream.next().await {
                 match event {
+                    // Reasoning is not shown in chat messages
+                    Ok(StreamEvent::Thinking(_)) => {}
                     Ok(StreamEvent::Content(delta)) => {
                         full_response.push_str(&delta);
 
@@ -777,6 +1002,10 @@ async fn handle_chat(
                         }
                     }
                     Ok(StreamEvent::Done) => break,
+                    Err(e) if Cancelled::is(&e) => {
+                        full_response.push_str("\n\n⏹ Stopped");
+                        break;
+                    }
                     Err(e) => {
                         error!("Stream error: {}", e);
                         full_response.push_str(&format!("\n\nError: {}", e));
@@ -793,6 +1022,7 @@ async fn handle_chat(
         }
         Err(e) => format!("Error: {}", e),
     };
+    state.running.lock().await.remove(&chat_id.0);
 
     // Save session before releasing lock
     if let Err(e) = entry.agent.save_session_for_agent(TELEGRAM_AGENT_ID).await {
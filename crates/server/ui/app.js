@@ -14,7 +14,7 @@ document.addEventListener('DOMContentLoaded', () => {
 });
 
 function setupEventListeners() {
-    document.getElementById('send').onclick = sendMessage;
+    document.getElementById('send').onclick = () => isStreaming ? stopTurn() : sendMessage();
     document.getElementById('new-session').onclick = newSession;
 
     const input = document.getElementById('input');
@@ -223,8 +223,9 @@ async function sendMessage() {
     const assistantDiv = appendMessage('assistant', '');
     assistantDiv.classList.add('loading');
 
+    // The send button stops the reply while it streams
     const sendBtn = document.getElementById('send');
-    sendBtn.disabled = true;
+    sendBtn.textContent = 'Stop';
     isStreaming = true;
 
     try {
@@ -269,7 +270,7 @@ async function sendMessage() {
         assistantDiv.textContent = `Error: ${err.message}`;
     } finally {
         assistantDiv.classList.remove('loading');
-        sendBtn.disabled = false;
+        sendBtn.textContent = 'Send';
         isStreaming = false;
         scrollToBottom();
     }
@@ -282,6 +283,18 @@ function handleEvent(event, assistantDiv) {
             updateSessionSelect(sessionId);
             break;
 
+        case 'thinking':
+            if (!assistantDiv.thinkingEl) {
+                const details = document.createElement('details');
+                details.className = 'message thinking';
+                details.innerHTML = '<summary>Thinking</summary><div class="thinking-text"></div>';
+                assistantDiv.before(details);
+                assistantDiv.thinkingEl = details.querySelector('.thinking-text');
+            }
+            assistantDiv.thinkingEl.textContent += event.delta;
+            scrollToBottom();
+            break;
+
         case 'content':
             assistantDiv.textContent += event.delta;
             scrollToBottom();
@@ -308,16 +321,69 @@ function handleEvent(event, assistantDiv) {
             scrollToBottom();
             break;
 
+        case 'approval_request':
+            const approvalDiv = document.createElement('div');
+            approvalDiv.className = 'message tool';
+            approvalDiv.id = `approval-${event.id}`;
+            const approvalLabel = event.detail
+                ? `[${event.name}: ${escapeHtml(event.detail)}]`
+                : `[${event.name}]`;
+            approvalDiv.innerHTML = `<span class="tool-name">${approvalLabel}</span> Needs approval `;
+            for (const [decision, label] of [['approve', 'Approve'], ['always_allow', 'Always'], ['deny', 'Deny']]) {
+                const button = document.createElement('button');
+                button.textContent = label;
+                button.onclick = () => decideApproval(event.id, decision);
+                approvalDiv.appendChild(button);
+            }
+            assistantDiv.after(approvalDiv);
+            scrollToBottom();
+            break;
+
+        case 'approval_resolved':
+            const resolvedEl = document.getElementById(`approval-${event.id}`);
+            if (resolvedEl) {
+                const outcome = event.timed_out ? 'No answer, denied'
+                    : event.decision === 'deny' ? 'Denied' : 'Approved';
+                resolvedEl.querySelectorAll('button').forEach(b => b.remove());
+                resolvedEl.append(`— ${outcome}`);
+            }
+            break;
+
         case 'error':
             assistantDiv.classList.add('error');
             assistantDiv.textContent = `Error: ${event.message}`;
             break;
 
+        case 'cancelled':
+            assistantDiv.append(' [Stopped]');
+            break;
+
         case 'done':
             break;
     }
 }
 
+async function stopTurn() {
+    if (!sessionId) return;
+    try {
+        await fetch(`${API}/sessions/${encodeURIComponent(sessionId)}/cancel`, { method: 'POST' });
+    } catch (err) {
+        console.error('Failed to stop the reply:', err);
+    }
+}
+
+async function decideApproval(id, decision) {
+    try {
+        await fetch(`${API}/approvals/${encodeURIComponent(id)}`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ decision })
+        });
+    } catch (err) {
+        console.error('Failed to send approval decision:', err);
+    }
+}
+
 function updateSessionSelect(newSessionId) {
     const select = document.getElementById('session-select');
 